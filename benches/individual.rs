@@ -1,13 +1,48 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 
-#[allow(clippy::redundant_closure)]
+/// Benchmarks each day's solutions in a group named after the day, like `seven/seven_b`,
+/// so that a single solution can be run with e.g. `cargo bench --bench individual -- seven_b`.
+macro_rules! bench_days {
+    ($c:expr, $($day:ident => [$($part:ident),+]),+ $(,)?) => {
+        $(
+            let mut group = $c.benchmark_group(stringify!($day));
+            group.sample_size(10);
+            $(
+                group.bench_function(stringify!($part), |b| b.iter(advent_2019::$day::$part));
+            )+
+            group.finish();
+        )+
+    };
+}
+
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let mut group = c.benchmark_group("individual");
-    group.sample_size(10);
-    group.bench_function("individual", |b| {
-        b.iter(|| advent_2019::twenty_three::twenty_three_a());
-    });
-    group.finish();
+    bench_days!(c,
+        one => [one_a, one_b],
+        two => [two_a, two_b],
+        three => [three_a, three_b],
+        four => [four_a, four_b],
+        five => [five_a, five_b],
+        six => [six_a, six_b],
+        seven => [seven_a, seven_b],
+        eight => [eight_a, eight_b],
+        nine => [nine_a, nine_b],
+        ten => [ten_a, ten_b],
+        eleven => [eleven_a, eleven_b],
+        twelve => [twelve_a, twelve_b],
+        thirteen => [thirteen_a, thirteen_b],
+        fourteen => [fourteen_a, fourteen_b],
+        fifteen => [fifteen_a, fifteen_b],
+        sixteen => [sixteen_a, sixteen_b],
+        seventeen => [seventeen_a, seventeen_b],
+        eighteen => [eighteen_a, eighteen_b],
+        nineteen => [nineteen_a, nineteen_b],
+        twenty => [twenty_a, twenty_b],
+        twenty_one => [twenty_one_a, twenty_one_b],
+        twenty_two => [twenty_two_a, twenty_two_b],
+        twenty_three => [twenty_three_a, twenty_three_b],
+        twenty_four => [twenty_four_a, twenty_four_b],
+        twenty_five => [twenty_five_a],
+    );
 }
 
 criterion_group!(benches, criterion_benchmark);
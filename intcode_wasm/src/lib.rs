@@ -0,0 +1,284 @@
+//! The Intcode computer from advent_2019, for JavaScript.
+//!
+//! `wasm-pack build intcode_wasm` produces an npm package, TypeScript definitions included.
+//! Intcode values are 64 bits wide, so they go in and out of JavaScript as BigInts.
+
+use advent_2019::computer::{self, Computer, HaltReason, Memory, StepError};
+use std::convert::TryFrom;
+use wasm_bindgen::prelude::*;
+
+/// Why `run()` stopped; mirrors advent_2019's HaltReason.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Halt {
+    Exit,
+    Output,
+    NeedsInput,
+}
+
+impl From<HaltReason> for Halt {
+    fn from(reason: HaltReason) -> Self {
+        match reason {
+            HaltReason::Exit => Halt::Exit,
+            HaltReason::Output => Halt::Output,
+            HaltReason::NeedsInput => Halt::NeedsInput,
+        }
+    }
+}
+
+impl From<Halt> for HaltReason {
+    fn from(halt: Halt) -> Self {
+        match halt {
+            Halt::Exit => HaltReason::Exit,
+            Halt::Output => HaltReason::Output,
+            Halt::NeedsInput => HaltReason::NeedsInput,
+        }
+    }
+}
+
+/// Parses a comma-separated program like `1,0,0,0,99`, without panicking on bad input:
+/// a panic inside WebAssembly can't be caught from JavaScript.
+pub fn parse_program(contents: &str) -> Result<Memory, String> {
+    computer::try_parse_program(contents).map_err(|e| e.to_string())
+}
+
+/// An Intcode computer.
+///
+/// Unlike advent_2019's Computer, a program that runs out of input is left waiting on its input
+/// instruction rather than being given -1, so it picks up where it left off once more input is pushed.
+#[wasm_bindgen]
+pub struct Intcode {
+    computer: Computer,
+}
+
+#[wasm_bindgen]
+impl Intcode {
+    /// Loads a comma-separated program like `1,0,0,0,99`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &str) -> Result<Intcode, JsValue> {
+        let memory = parse_program(program).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Intcode {
+            computer: Computer::new(memory),
+        })
+    }
+
+    /// Runs the program until it exits or needs input that it hasn't been given.
+    /// Everything it outputs along the way waits in `takeOutput()`.
+    /// Throws if the program hits an instruction that can't be run, like one with an unknown opcode.
+    pub fn run(&mut self) -> Result<Halt, JsValue> {
+        self.run_until(Halt::NeedsInput)
+    }
+
+    /// Like `run()`, but also stops at the first output if `until` is Halt.Output.
+    /// If `until` is Halt.Exit, the program runs until it exits, being given -1 whenever it's out of input.
+    #[wasm_bindgen(js_name = runUntil)]
+    pub fn run_until(&mut self, until: Halt) -> Result<Halt, JsValue> {
+        self.try_run_until(until).map_err(to_js_error)
+    }
+
+    /// Runs a single instruction, and returns the event it caused, if any.
+    pub fn step(&mut self) -> Result<Option<Halt>, JsValue> {
+        self.try_step().map_err(to_js_error)
+    }
+
+    #[wasm_bindgen(js_name = pushInput)]
+    pub fn push_input(&mut self, value: i64) {
+        self.computer.push_input(value);
+    }
+
+    /// Pushes each character of `line` and then a newline, for the programs that speak ASCII.
+    #[wasm_bindgen(js_name = pushLine)]
+    pub fn push_line(&mut self, line: &str) {
+        for c in line.chars().chain(Some('\n')) {
+            self.computer.push_input(c as i64);
+        }
+    }
+
+    /// Removes and returns everything the program has output so far, oldest first.
+    #[wasm_bindgen(js_name = takeOutput)]
+    pub fn take_output(&mut self) -> Vec<i64> {
+        std::iter::from_fn(|| self.computer.pop_output()).collect()
+    }
+
+    /// Like `takeOutput()`, but decodes the output as ASCII text.
+    /// Stops at the first value that isn't ASCII, like day 17's dust count, leaving it to be taken next.
+    #[wasm_bindgen(js_name = takeText)]
+    pub fn take_text(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(value) = self
+            .computer
+            .peek_output()
+            .filter(|value| (0..128).contains(value))
+        {
+            self.computer.pop_output();
+            text.push(value as u8 as char);
+        }
+
+        text
+    }
+
+    #[wasm_bindgen(js_name = memorySize)]
+    pub fn memory_size(&self) -> usize {
+        self.computer.memory_size()
+    }
+
+    #[wasm_bindgen(js_name = readMemory)]
+    pub fn read_memory(&self, address: usize) -> Result<i64, JsValue> {
+        self.check_address(address)?;
+        Ok(self.computer.read_memory(address))
+    }
+
+    #[wasm_bindgen(js_name = writeMemory)]
+    pub fn write_memory(&mut self, address: usize, value: i64) -> Result<(), JsValue> {
+        self.check_address(address)?;
+        self.computer.write_memory(address, value);
+        Ok(())
+    }
+
+    /// Runs the program until it exits, calling `input()` whenever it needs a value
+    /// and `output(value)` with each value it outputs.
+    /// `input` can return a number or a BigInt; `output` is always given a BigInt.
+    #[wasm_bindgen(js_name = runWithHooks)]
+    pub fn run_with_hooks(
+        &mut self,
+        input: &js_sys::Function,
+        output: &js_sys::Function,
+    ) -> Result<(), JsValue> {
+        loop {
+            let reason = self.run_until(Halt::Output)?;
+            for value in self.take_output() {
+                output.call1(&JsValue::NULL, &JsValue::from(value))?;
+            }
+
+            match reason {
+                Halt::Exit => return Ok(()),
+                Halt::Output => (),
+                Halt::NeedsInput => {
+                    let value = input.call0(&JsValue::NULL)?;
+                    self.computer.push_input(to_i64(value)?);
+                }
+            }
+        }
+    }
+}
+
+impl Intcode {
+    fn try_run_until(&mut self, until: Halt) -> Result<Halt, StepError> {
+        let reason = loop {
+            match self.computer.try_run(until.into())? {
+                HaltReason::Output if until != Halt::Output => (),
+                reason => break reason,
+            }
+        };
+        if reason == HaltReason::NeedsInput {
+            self.computer.retry_input();
+        }
+
+        Ok(reason.into())
+    }
+
+    fn try_step(&mut self) -> Result<Option<Halt>, StepError> {
+        let reason = self.computer.try_step()?;
+        if reason == Some(HaltReason::NeedsInput) {
+            self.computer.retry_input();
+        }
+
+        Ok(reason.map(Halt::from))
+    }
+
+    fn check_address(&self, address: usize) -> Result<(), JsValue> {
+        if address < self.computer.memory_size() {
+            Ok(())
+        } else {
+            Err(JsValue::from_str(&format!(
+                "address {} is out of range; memory is {} values long",
+                address,
+                self.computer.memory_size()
+            )))
+        }
+    }
+}
+
+fn to_js_error(error: StepError) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+fn to_i64(value: JsValue) -> Result<i64, JsValue> {
+    if let Some(number) = value.as_f64() {
+        if number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER {
+            return Ok(number as i64);
+        }
+    } else if value.is_bigint() {
+        return i64::try_from(value);
+    }
+
+    Err(JsValue::from_str("input() has to return an integer"))
+}
+
+/// Number.MAX_SAFE_INTEGER; a number any bigger than this may not be the integer it appears to be.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_program() {
+        assert_eq!(parse_program("1,0,0,0,99\n"), Ok(vec![1, 0, 0, 0, 99]));
+        assert_eq!(
+            parse_program("1,0,x,99"),
+            Err("line 1: \"x\": value 2 of the program isn't an integer".to_string())
+        );
+    }
+
+    #[test]
+    fn test_input_and_output() {
+        // Reads two values and outputs their sum, then the ASCII string "ok", then 1000.
+        let mut intcode =
+            Intcode::new("3,100,3,101,1,100,101,102,4,102,104,111,104,107,104,1000,99").unwrap();
+
+        assert_eq!(
+            intcode.try_run_until(Halt::NeedsInput),
+            Ok(Halt::NeedsInput)
+        );
+        intcode.push_input(40);
+        assert_eq!(intcode.try_step(), Ok(None));
+        assert_eq!(intcode.try_step(), Ok(Some(Halt::NeedsInput)));
+
+        intcode.push_input(2);
+        assert_eq!(intcode.try_run_until(Halt::Output), Ok(Halt::Output));
+        assert_eq!(intcode.take_output(), vec![42]);
+
+        assert_eq!(intcode.try_run_until(Halt::NeedsInput), Ok(Halt::Exit));
+        assert_eq!(intcode.take_text(), "ok");
+        assert_eq!(intcode.take_output(), vec![1000]);
+        assert_eq!(intcode.read_memory(102).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_push_line() {
+        // Echoes three characters of input.
+        let mut intcode = Intcode::new("3,100,4,100,3,100,4,100,3,100,4,100,99").unwrap();
+        intcode.push_line("hi");
+        assert_eq!(intcode.try_run_until(Halt::NeedsInput), Ok(Halt::Exit));
+        assert_eq!(intcode.take_text(), "hi\n");
+    }
+
+    #[test]
+    fn test_bad_opcode() {
+        // Outputs 7, then hits opcode 42.
+        let mut intcode = Intcode::new("104,7,42,99").unwrap();
+        let error = StepError::UnknownOpcode {
+            instruction_pointer: 2,
+            instruction: 42,
+        };
+
+        assert_eq!(intcode.try_run_until(Halt::Exit), Err(error));
+        assert_eq!(intcode.take_output(), vec![7]);
+        assert_eq!(intcode.try_step(), Err(error));
+        assert_eq!(
+            error.to_string(),
+            "instruction 42 at instruction pointer 2 has an unknown opcode"
+        );
+    }
+}
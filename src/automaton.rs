@@ -0,0 +1,243 @@
+use crate::util::{Direction, Point};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Decides which cells count as each other's neighbors.
+pub trait Topology {
+    /// Identifies a single cell.
+    type Address: Copy + Eq + Hash;
+
+    fn neighbors(&self, address: Self::Address) -> Vec<Self::Address>;
+}
+
+/// Given whether a cell is alive and how many of its neighbors are alive, returns whether it'll be alive next tick.
+pub type Rule = fn(bool, usize) -> bool;
+
+/// A cellular automaton that only keeps track of its living cells,
+/// so it works just as well on topologies that go on forever.
+pub struct Automaton<T: Topology> {
+    topology: T,
+    alive: HashSet<T::Address>,
+    rule: Rule,
+}
+
+impl<T: Topology + Clone> Automaton<T> {
+    /// Panics if `rule` would bring a cell to life with no living neighbors,
+    /// because that would fill up every cell in the topology.
+    pub fn new(topology: T, alive: impl IntoIterator<Item = T::Address>, rule: Rule) -> Self {
+        assert!(!rule(false, 0), "rule brings isolated cells to life");
+
+        Automaton {
+            topology,
+            alive: alive.into_iter().collect(),
+            rule,
+        }
+    }
+
+    pub fn topology(&self) -> &T {
+        &self.topology
+    }
+
+    pub fn is_alive(&self, address: T::Address) -> bool {
+        self.alive.contains(&address)
+    }
+
+    pub fn alive(&self) -> impl Iterator<Item = &T::Address> {
+        self.alive.iter()
+    }
+
+    pub fn num_alive(&self) -> usize {
+        self.alive.len()
+    }
+
+    pub fn tick(&self) -> Self {
+        // Only living cells and their neighbors can be alive next tick.
+        let mut num_alive_neighbors: HashMap<T::Address, usize> =
+            self.alive.iter().map(|&address| (address, 0)).collect();
+
+        for &address in &self.alive {
+            for neighbor in self.topology.neighbors(address) {
+                *num_alive_neighbors.entry(neighbor).or_insert(0) += 1;
+            }
+        }
+
+        let alive = num_alive_neighbors
+            .into_iter()
+            .filter(|&(address, count)| (self.rule)(self.is_alive(address), count))
+            .map(|(address, _)| address)
+            .collect();
+
+        Automaton {
+            topology: self.topology.clone(),
+            alive,
+            rule: self.rule,
+        }
+    }
+}
+
+/// A `width` by `height` grid, where each cell's neighbors are the cells above, below, and to either side of it.
+/// Addresses are (x, y).
+#[derive(Clone, Copy, Debug)]
+pub struct FlatGrid {
+    pub width: usize,
+    pub height: usize,
+    /// If true, cells on one edge of the grid are neighbors with the cells on the opposite edge.
+    /// If false, cells on the edge just have fewer neighbors.
+    pub wraps: bool,
+}
+
+impl Topology for FlatGrid {
+    type Address = (usize, usize);
+
+    fn neighbors(&self, address: (usize, usize)) -> Vec<(usize, usize)> {
+        let point = Point::from(address);
+
+        if self.wraps {
+            let (width, height) = (self.width as i32, self.height as i32);
+            point
+                .neighbors4()
+                .map(|neighbor| {
+                    Point::new(neighbor.x.rem_euclid(width), neighbor.y.rem_euclid(height))
+                        .to_usize()
+                })
+                .collect()
+        } else {
+            point
+                .neighbors4_within(self.width, self.height)
+                .map(Point::to_usize)
+                .collect()
+        }
+    }
+}
+
+/// A cell on one level of a `RecursiveGrid`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LevelPosition {
+    /// Higher depths are further in.
+    pub depth: i32,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Plutonian recursive levels: "the middle tile of the grid is actually another grid",
+/// and the grid is itself the middle tile of a larger grid, forever in both directions.
+#[derive(Clone, Copy, Debug)]
+pub struct RecursiveGrid {
+    /// Each level is `size` by `size`. Must be odd, so that there's a middle tile.
+    pub size: usize,
+}
+
+impl RecursiveGrid {
+    fn middle(&self) -> usize {
+        self.size / 2
+    }
+}
+
+impl Topology for RecursiveGrid {
+    type Address = LevelPosition;
+
+    fn neighbors(&self, position: LevelPosition) -> Vec<LevelPosition> {
+        let (size, middle) = (self.size as i64, self.middle() as i64);
+        let at = |depth, x: i64, y: i64| LevelPosition {
+            depth,
+            x: x as usize,
+            y: y as usize,
+        };
+
+        let mut neighbors = vec![];
+
+        for &direction in Direction::ALL.iter() {
+            let (dx, dy) = direction.offset();
+            let (dx, dy) = (dx as i64, dy as i64);
+            let (x, y) = (position.x as i64 + dx, position.y as i64 + dy);
+
+            if x < 0 || x >= size || y < 0 || y >= size {
+                // Stepping off the edge of this level puts us next to the middle tile on the level outside this one.
+                neighbors.push(at(position.depth - 1, middle + dx, middle + dy));
+            } else if x == middle && y == middle {
+                // Stepping into the middle tile puts us next to a whole edge of the level inside this one.
+                let inner_depth = position.depth + 1;
+                for i in 0..size {
+                    neighbors.push(match (dx, dy) {
+                        (1, _) => at(inner_depth, 0, i),
+                        (-1, _) => at(inner_depth, size - 1, i),
+                        (_, 1) => at(inner_depth, i, 0),
+                        _ => at(inner_depth, i, size - 1),
+                    });
+                }
+            } else {
+                neighbors.push(at(position.depth, x, y));
+            }
+        }
+
+        neighbors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spread(alive: bool, num_alive_neighbors: usize) -> bool {
+        num_alive_neighbors == 1 || (alive && num_alive_neighbors == 2)
+    }
+
+    #[test]
+    fn test_flat_grid() {
+        let grid = FlatGrid {
+            width: 5,
+            height: 4,
+            wraps: false,
+        };
+        assert_eq!(grid.neighbors((0, 0)), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors((2, 2)).len(), 4);
+
+        let grid = FlatGrid {
+            wraps: true,
+            ..grid
+        };
+        assert_eq!(grid.neighbors((0, 0)), vec![(0, 3), (1, 0), (0, 1), (4, 0)]);
+    }
+
+    #[test]
+    fn test_recursive_grid() {
+        let grid = RecursiveGrid { size: 5 };
+        let at = |depth, x, y| LevelPosition { depth, x, y };
+
+        // "Tile 19 has four adjacent tiles: 14, 18, 20, and 24."
+        assert_eq!(grid.neighbors(at(0, 3, 3)).len(), 4);
+
+        // "Tile G has four adjacent tiles: B, F, H, and L."
+        assert_eq!(
+            grid.neighbors(at(1, 1, 1)),
+            vec![at(1, 1, 0), at(1, 2, 1), at(1, 1, 2), at(1, 0, 1)]
+        );
+
+        // "Tile E has four adjacent tiles: 8, D, 14, and J."
+        assert_eq!(
+            grid.neighbors(at(1, 4, 0)),
+            vec![at(0, 2, 1), at(0, 3, 2), at(1, 4, 1), at(1, 3, 0)]
+        );
+
+        // "Tile 14 has eight adjacent tiles: 9, E, J, O, T, Y, 15, and 19."
+        let neighbors = grid.neighbors(at(0, 3, 2));
+        assert_eq!(neighbors.len(), 8);
+        assert!((0..5).all(|y| neighbors.contains(&at(1, 4, y))));
+    }
+
+    #[test]
+    fn test_automaton() {
+        let topology = FlatGrid {
+            width: 3,
+            height: 3,
+            wraps: false,
+        };
+        let automaton = Automaton::new(topology, vec![(1, 1)], spread);
+        assert_eq!(automaton.num_alive(), 1);
+
+        let automaton = automaton.tick();
+        assert!(!automaton.is_alive((1, 1)));
+        assert_eq!(automaton.num_alive(), 4);
+        assert!(automaton.alive().all(|&(x, y)| (x + y) % 2 == 1));
+    }
+}
@@ -1,8 +1,22 @@
+use crate::util::{self, LoadError, ParseError};
+use std::path::Path;
+mod batch;
+mod compare;
 mod operations;
+mod trace;
 
+pub use batch::{batch_map, batch_run, Outputs};
+pub use compare::{compare, Divergence};
 use operations::Operation;
+pub use operations::DEBUG_OPCODE;
+use smallvec::SmallVec;
 use std::collections::VecDeque;
-use std::fs;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::io;
+pub use trace::{load_trace, parse_trace, replay, IoEvent, ReplayMismatch, Trace};
+use tracing::instrument;
 
 pub type Memory = Vec<i64>;
 
@@ -24,23 +38,131 @@ pub enum HaltReason {
     NeedsInput,
 }
 
+/// A value that a program printed with the debug operation; see `Computer::enable_debug_opcode()`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DebugPrint {
+    /// The address of the debug instruction that printed `value`.
+    pub instruction_pointer: usize,
+    pub value: i64,
+}
+
+impl fmt::Display for DebugPrint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[ip {}] {}", self.instruction_pointer, self.value)
+    }
+}
+
+/// A copy of a Computer's memory, input, output, and registers, which can be restored later.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot(State);
+
+/// How a program used a memory address.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum AddressUse {
+    Untouched,
+    /// Read or written by an instruction's parameters, but never run as part of an instruction.
+    Data,
+    /// Run as an opcode or one of an opcode's parameters.
+    Executed,
+}
+
+/// Records how each memory address was used by one or more runs of a program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Coverage {
+    uses: Vec<AddressUse>,
+    // The length of the program that was loaded, not counting the extra memory that the computer provides.
+    program_len: usize,
+}
+
+impl Coverage {
+    fn new(memory_len: usize, program_len: usize) -> Self {
+        Coverage {
+            uses: vec![AddressUse::Untouched; memory_len],
+            program_len,
+        }
+    }
+
+    fn mark(&mut self, address: usize, address_use: AddressUse) {
+        if let Some(existing) = self.uses.get_mut(address) {
+            *existing = (*existing).max(address_use);
+        }
+    }
+
+    pub fn get(&self, address: usize) -> AddressUse {
+        *self.uses.get(address).unwrap_or(&AddressUse::Untouched)
+    }
+
+    /// Folds `other` into `self`, so that `self` covers every address that either of them covered.
+    /// Both must be from runs of the same program.
+    pub fn merge(&mut self, other: &Coverage) {
+        assert_eq!(self.program_len, other.program_len);
+
+        for (address, &address_use) in other.uses.iter().enumerate() {
+            self.mark(address, address_use);
+        }
+    }
+
+    /// Returns how many of the program's addresses were used in the way `address_use` describes.
+    pub fn count(&self, address_use: AddressUse) -> usize {
+        self.uses[..self.program_len]
+            .iter()
+            .filter(|&&x| x == address_use)
+            .count()
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent =
+            |address_use| 100.0 * self.count(address_use) as f64 / self.program_len as f64;
+
+        write!(
+            f,
+            "{} addresses: {} executed ({:.1}%), {} data ({:.1}%), {} untouched ({:.1}%)",
+            self.program_len,
+            self.count(AddressUse::Executed),
+            percent(AddressUse::Executed),
+            self.count(AddressUse::Data),
+            percent(AddressUse::Data),
+            self.count(AddressUse::Untouched),
+            percent(AddressUse::Untouched),
+        )
+    }
+}
+
 /// A Computer.
 pub struct Computer {
     pub(crate) state: State,
     operations: Vec<Option<Operation>>,
+    program_len: usize,
+    coverage: Option<Coverage>,
+    ascii_sink: Option<Box<dyn io::Write>>,
 }
 
 /// A computer's mutable state.
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct State {
     pub memory: Memory,
     pub input: Vec<i64>,
     pub output: VecDeque<i64>,
     pub(crate) instruction_pointer: usize,
     pub(crate) relative_base: i64,
+    pub debug_prints: Vec<DebugPrint>,
+    pub trace: Option<Trace>,
+}
+
+impl State {
+    fn record(&mut self, event: IoEvent) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(event);
+        }
+    }
 }
 
 impl Computer {
     pub fn new(mut memory: Memory) -> Self {
+        let program_len = memory.len();
+
         // "The computer's available memory should be much larger than the
         // initial program. Memory beyond the initial program starts with
         // the value 0 and can be read or written like any other memory."
@@ -56,41 +178,72 @@ impl Computer {
                 output: VecDeque::new(),
                 instruction_pointer: 0,
                 relative_base: 0,
+                debug_prints: vec![],
+                trace: None,
             },
             operations,
+            program_len,
+            coverage: None,
+            ascii_sink: None,
+        }
+    }
+
+    /// Starts recording which memory addresses the program executes and which it uses as data.
+    pub fn track_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Coverage::new(self.state.memory.len(), self.program_len));
         }
     }
 
+    /// Returns None if `track_coverage()` hasn't been called.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Starts recording every input the program consumes and every output it produces; see `trace()`.
+    /// The trace is part of the computer's state, so restoring a snapshot rewinds it too.
+    pub fn record_io(&mut self) {
+        if self.state.trace.is_none() {
+            self.state.trace = Some(Trace::default());
+        }
+    }
+
+    /// Returns None if `record_io()` hasn't been called.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.state.trace.as_ref()
+    }
+
+    /// Teaches the computer opcode `DEBUG_OPCODE`, which programs can use like printf to report a value,
+    /// e.g. `198, 5` reports 5 and `98, 5` reports whatever's at address 5.
+    /// The values are logged at debug level and kept in `debug_prints()`.
+    pub fn enable_debug_opcode(&mut self) {
+        self.operations[DEBUG_OPCODE] = Some(operations::debug_operation());
+    }
+
+    /// Everything the program has printed with the debug opcode, oldest first.
+    pub fn debug_prints(&self) -> &[DebugPrint] {
+        &self.state.debug_prints
+    }
+
+    /// Writes each ASCII character the program outputs to `sink` as soon as it's output.
+    /// Every output, ASCII or not, still goes on the queue that `pop_output()` reads from.
+    pub fn stream_ascii_output(&mut self, sink: impl io::Write + 'static) {
+        self.ascii_sink = Some(Box::new(sink));
+    }
+
     /// Runs the program in `self` until the event specified by `halt_level`.
     /// Returns a HaltReason indicating the event that caused the program to halt.
+    /// Panics if the program hits an instruction that can't be run; see `try_run()`.
     pub fn run(&mut self, halt_level: HaltReason) -> HaltReason {
-        let mut parameter_mode_buffer = [ParameterMode::Position; operations::MAX_NUM_ARGUMENTS];
-        let mut argument_buffer = [0; operations::MAX_NUM_ARGUMENTS];
-
-        loop {
-            // Decode the instruction.
-            let instruction = self.state.memory[self.state.instruction_pointer];
-            let opcode = parse_instruction(instruction, &mut parameter_mode_buffer);
-            let operation = self.operations[opcode as usize].as_ref().unwrap();
-
-            write_arguments(
-                &self.state.memory,
-                self.state.instruction_pointer,
-                self.state.relative_base,
-                &operation,
-                opcode,
-                &parameter_mode_buffer[0..operation.num_arguments],
-                &mut argument_buffer,
-            );
-
-            // Run the instruction.
-            let outcome = (operation.run)(
-                &mut self.state,
-                &argument_buffer[0..operation.num_arguments],
-            );
+        self.try_run(halt_level).unwrap_or_else(|e| panic!("{}", e))
+    }
 
+    /// Like `run()`, but returns a StepError instead of panicking if the program hits an instruction
+    /// that can't be run. The computer is left on that instruction.
+    pub fn try_run(&mut self, halt_level: HaltReason) -> Result<HaltReason, StepError> {
+        Ok(loop {
             // Halt if we're supposed to, otherwise carry on.
-            match outcome.halt_reason {
+            match self.try_step()? {
                 Some(HaltReason::NeedsInput) if halt_level == HaltReason::NeedsInput => {
                     break HaltReason::NeedsInput
                 }
@@ -102,11 +255,128 @@ impl Computer {
                 Some(HaltReason::Exit) => break HaltReason::Exit,
                 _ => (),
             }
+        })
+    }
+
+    /// Runs a single instruction, and returns the event it caused, if any.
+    /// Once the program has exited, every step returns HaltReason::Exit without doing anything else.
+    /// Panics if the instruction can't be run; see `try_step()`.
+    pub fn step(&mut self) -> Option<HaltReason> {
+        self.try_step().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `step()`, but returns a StepError instead of panicking if the instruction can't be run,
+    /// in which case the computer is left just as it was.
+    pub fn try_step(&mut self) -> Result<Option<HaltReason>, StepError> {
+        let mut parameter_mode_buffer = [ParameterMode::Position; operations::MAX_NUM_ARGUMENTS];
+        let mut argument_buffer = [0; operations::MAX_NUM_ARGUMENTS];
+
+        // Decode the instruction.
+        let instruction_pointer = self.state.instruction_pointer;
+        let instruction = *self.state.memory.get(instruction_pointer).ok_or(
+            StepError::InstructionPointerOutOfRange {
+                instruction_pointer,
+            },
+        )?;
+        let opcode = parse_instruction(instruction, &mut parameter_mode_buffer).ok_or(
+            StepError::BadParameterMode {
+                instruction_pointer,
+                instruction,
+            },
+        )?;
+        let operations = &self.operations;
+        let operation = usize::try_from(opcode)
+            .ok()
+            .and_then(|opcode| operations.get(opcode))
+            .and_then(Option::as_ref)
+            .ok_or(StepError::UnknownOpcode {
+                instruction_pointer,
+                instruction,
+            })?;
+        if instruction_pointer + operation.num_arguments >= self.state.memory.len() {
+            return Err(StepError::InstructionPointerOutOfRange {
+                instruction_pointer,
+            });
+        }
 
-            if !outcome.manipulated_instruction_pointer {
-                self.state.instruction_pointer += operation.num_arguments + 1;
+        write_arguments(
+            &self.state.memory,
+            instruction_pointer,
+            self.state.relative_base,
+            operation,
+            opcode,
+            &parameter_mode_buffer[0..operation.num_arguments],
+            &mut argument_buffer,
+        )?;
+
+        if let Some(coverage) = self.coverage.as_mut() {
+            record_coverage(
+                coverage,
+                &self.state,
+                operation,
+                &parameter_mode_buffer[0..operation.num_arguments],
+            );
+        }
+
+        // Run the instruction.
+        let outcome = (operation.run)(
+            &mut self.state,
+            &argument_buffer[0..operation.num_arguments],
+        );
+
+        if let (Some(HaltReason::Output), Some(sink)) =
+            (outcome.halt_reason, self.ascii_sink.as_mut())
+        {
+            let output = *self.state.output.back().unwrap();
+            if (0..128).contains(&output) {
+                // A failed write shouldn't stop the program; the output is still on the queue.
+                let _ = sink.write_all(&[output as u8]).and_then(|_| sink.flush());
             }
         }
+
+        // Leave the instruction pointer on EXIT, so that the program stays exited.
+        if !outcome.manipulated_instruction_pointer && outcome.halt_reason != Some(HaltReason::Exit)
+        {
+            self.state.instruction_pointer += operation.num_arguments + 1;
+        }
+
+        Ok(outcome.halt_reason)
+    }
+
+    /// Rewinds the computer to just before the POP_INPUT instruction that most recently halted it
+    /// with HaltReason::NeedsInput, so that the instruction will try again to read input next time it runs.
+    pub fn retry_input(&mut self) {
+        self.state.instruction_pointer -= 2;
+
+        // The program never really got the -1 it was given, so it doesn't belong in the trace.
+        if let Some(trace) = self.state.trace.as_mut() {
+            trace.pop();
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.state.clone())
+    }
+
+    /// Puts the computer back exactly how it was when `snapshot` was taken.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.state = snapshot.0.clone();
+    }
+
+    /// How many addresses the program can use: the program itself, plus the extra memory past its end.
+    pub fn memory_size(&self) -> usize {
+        self.state.memory.len()
+    }
+
+    /// Returns the value at `address`. Panics if there's no such address.
+    pub fn read_memory(&self, address: usize) -> i64 {
+        self.state.memory[address]
+    }
+
+    /// Overwrites the value at `address`, the way day 2 fills in a noun and verb before running.
+    /// Panics if there's no such address.
+    pub fn write_memory(&mut self, address: usize, value: i64) {
+        self.state.memory[address] = value;
     }
 
     pub fn push_input(&mut self, input: i64) {
@@ -117,6 +387,31 @@ impl Computer {
         self.state.output.pop_front()
     }
 
+    /// Returns the oldest output that hasn't been popped yet, without popping it.
+    pub fn peek_output(&self) -> Option<i64> {
+        self.state.output.front().copied()
+    }
+
+    /// Returns the oldest output that hasn't been popped yet, running the program until it emits one if necessary.
+    /// Returns None if the program exits first, and keeps returning None on every call after that.
+    pub fn next_output(&mut self) -> Option<i64> {
+        if self.state.output.is_empty() && self.run(HaltReason::Output) == HaltReason::Exit {
+            return None;
+        }
+
+        self.pop_output()
+    }
+
+    /// Runs the program until `n` outputs are waiting to be popped, and pops them, oldest first.
+    /// Handy for programs that output fixed-size tuples, like day 13's (x, y, tile_id) triples.
+    /// If the program exits first, returns however many outputs it did produce.
+    pub fn run_until_n_outputs(&mut self, n: usize) -> SmallVec<[i64; 3]> {
+        while self.state.output.len() < n && self.run(HaltReason::Output) != HaltReason::Exit {}
+
+        let n = n.min(self.state.output.len());
+        self.state.output.drain(..n).collect()
+    }
+
     /// Private function, useful for testing.
     fn _memory_starts_with(&self, expected: Vec<i64>) -> bool {
         Iterator::eq(
@@ -127,21 +422,45 @@ impl Computer {
 }
 
 /// Reads the file at `filename` into a Memory.
-pub fn load_program(filename: &str) -> Memory {
-    let contents = fs::read_to_string(filename).unwrap();
+pub fn load_program(filename: impl AsRef<Path>) -> Memory {
+    util::load(filename, try_parse_program)
+}
+
+/// Like `load_program()`, but returns an error naming the file and the bad value instead of panicking.
+pub fn try_load_program(filename: impl AsRef<Path>) -> Result<Memory, LoadError> {
+    util::try_load(filename, try_parse_program)
+}
 
+/// Parses a comma-separated program like `1,0,0,0,99`.
+pub fn parse_program(contents: &str) -> Memory {
+    try_parse_program(contents).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Like `parse_program()`, but returns an error naming the first value that isn't an integer.
+#[instrument(name = "parse", level = "debug", skip_all)]
+pub fn try_parse_program(contents: &str) -> Result<Memory, ParseError> {
     contents
         .trim()
         .split(',')
-        .map(|x| x.parse::<i64>().unwrap())
+        .enumerate()
+        .map(|(i, value)| {
+            value.trim().parse::<i64>().map_err(|_| {
+                ParseError::new(
+                    1,
+                    value,
+                    format!("value {} of the program isn't an integer", i),
+                )
+            })
+        })
         .collect()
 }
 
 /// Parses an instruction like `1102`.
 ///
-/// Returns an i64 opcode like `02`.
+/// Returns an i64 opcode like `02`, or None if the instruction's parameter modes aren't valid,
+/// or there are more of them than will fit in `parameter_mode_buffer`.
 /// Writes the instruction's encoded parameter modes to `parameter_mode_buffer`.
-fn parse_instruction(instruction: i64, parameter_mode_buffer: &mut [ParameterMode]) -> i64 {
+fn parse_instruction(instruction: i64, parameter_mode_buffer: &mut [ParameterMode]) -> Option<i64> {
     for item in &mut parameter_mode_buffer.iter_mut() {
         *item = ParameterMode::Position;
     }
@@ -150,21 +469,140 @@ fn parse_instruction(instruction: i64, parameter_mode_buffer: &mut [ParameterMod
     let mut index = 0;
 
     while parameter_modes != 0 {
-        parameter_mode_buffer[index] = match parameter_modes % 10 {
+        *parameter_mode_buffer.get_mut(index)? = match parameter_modes % 10 {
             0 => ParameterMode::Position,
             1 => ParameterMode::Immediate,
             2 => ParameterMode::Relative,
-            _ => panic!("unexpected parameter mode {}", parameter_modes % 10),
+            _ => return None,
         };
 
         parameter_modes /= 10;
         index += 1;
     }
 
-    instruction % 100
+    Some(instruction % 100)
 }
 
+/// Marks the instruction at the state's instruction pointer as executed,
+/// and the addresses that its Position and Relative parameters refer to as data.
+fn record_coverage(
+    coverage: &mut Coverage,
+    state: &State,
+    operation: &Operation,
+    parameter_modes: &[ParameterMode],
+) {
+    let instruction_pointer = state.instruction_pointer;
+
+    for address in instruction_pointer..=instruction_pointer + operation.num_arguments {
+        coverage.mark(address, AddressUse::Executed);
+    }
+
+    for (i, mode) in parameter_modes.iter().enumerate() {
+        let value = state.memory[instruction_pointer + 1 + i];
+        let address = match mode {
+            ParameterMode::Position => value,
+            ParameterMode::Immediate => continue,
+            ParameterMode::Relative => value + state.relative_base,
+        };
+
+        if address >= 0 {
+            coverage.mark(address as usize, AddressUse::Data);
+        }
+    }
+}
+
+/// Describes a parameter that refers to a memory address that doesn't exist.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InvalidAddress {
+    pub instruction_pointer: usize,
+    pub opcode: i64,
+    /// Which of the instruction's parameters it was, starting from 0.
+    pub parameter_index: usize,
+    pub relative_base: i64,
+    pub address: i64,
+}
+
+impl fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "parameter {} of opcode {} at instruction pointer {} refers to invalid address {} (relative base {})",
+            self.parameter_index, self.opcode, self.instruction_pointer, self.address, self.relative_base
+        )
+    }
+}
+
+/// Everything that can stop a program from running an instruction; see `Computer::try_step()`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum StepError {
+    /// The instruction pointer went past the end of memory, or the instruction it points to does.
+    InstructionPointerOutOfRange {
+        instruction_pointer: usize,
+    },
+    /// The instruction's last two digits aren't an opcode that the computer knows.
+    UnknownOpcode {
+        instruction_pointer: usize,
+        instruction: i64,
+    },
+    /// The instruction has a parameter mode other than 0, 1, or 2, or more parameter modes than
+    /// any operation has parameters.
+    BadParameterMode {
+        instruction_pointer: usize,
+        instruction: i64,
+    },
+    /// The parameter that an instruction writes its result to is in immediate mode.
+    ImmediateTarget {
+        instruction_pointer: usize,
+        opcode: i64,
+        parameter_index: usize,
+    },
+    InvalidAddress(InvalidAddress),
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StepError::InstructionPointerOutOfRange {
+                instruction_pointer,
+            } => write!(
+                f,
+                "instruction pointer {} runs off the end of memory",
+                instruction_pointer
+            ),
+            StepError::UnknownOpcode {
+                instruction_pointer,
+                instruction,
+            } => write!(
+                f,
+                "instruction {} at instruction pointer {} has an unknown opcode",
+                instruction, instruction_pointer
+            ),
+            StepError::BadParameterMode {
+                instruction_pointer,
+                instruction,
+            } => write!(
+                f,
+                "instruction {} at instruction pointer {} has invalid parameter modes",
+                instruction, instruction_pointer
+            ),
+            StepError::ImmediateTarget {
+                instruction_pointer,
+                opcode,
+                parameter_index,
+            } => write!(
+                f,
+                "Operation {} at instruction pointer {} got an immediate parameter mode for argument {}",
+                opcode, instruction_pointer, parameter_index
+            ),
+            StepError::InvalidAddress(invalid_address) => write!(f, "{}", invalid_address),
+        }
+    }
+}
+
+impl error::Error for StepError {}
+
 /// Writes `num_arguments` arguments to `argument_buffer`, based on `memory`, `instruction_pointer`, and `parameter_modes`.
+/// Returns a StepError if a parameter refers to an address outside of `memory`.
 fn write_arguments(
     memory: &[i64],
     instruction_pointer: usize,
@@ -173,33 +611,55 @@ fn write_arguments(
     opcode: i64,
     parameter_modes: &[ParameterMode],
     argument_buffer: &mut [i64],
-) {
+) -> Result<(), StepError> {
+    let check_address = |parameter_index, address: i64| {
+        if address < 0 || address as usize >= memory.len() {
+            Err(StepError::InvalidAddress(InvalidAddress {
+                instruction_pointer,
+                opcode,
+                parameter_index,
+                relative_base,
+                address,
+            }))
+        } else {
+            Ok(address)
+        }
+    };
+
     for i in 0..operation.num_arguments {
         let value_in_memory_at_i = memory[instruction_pointer + 1 + i];
 
         if Some(i) == operation.target_memory_location_arg {
             argument_buffer[i] = match parameter_modes[i] {
-                ParameterMode::Position => value_in_memory_at_i,
-                ParameterMode::Immediate => panic!(
-                    "Operation {} got a relative parameter mode for argument {}",
-                    opcode,
-                    operation.target_memory_location_arg.unwrap()
-                ),
-                ParameterMode::Relative => value_in_memory_at_i + relative_base,
+                ParameterMode::Position => check_address(i, value_in_memory_at_i)?,
+                ParameterMode::Immediate => {
+                    return Err(StepError::ImmediateTarget {
+                        instruction_pointer,
+                        opcode,
+                        parameter_index: i,
+                    })
+                }
+                ParameterMode::Relative => check_address(i, value_in_memory_at_i + relative_base)?,
             };
         } else {
             argument_buffer[i] = match parameter_modes[i] {
-                ParameterMode::Position => memory[value_in_memory_at_i as usize],
+                ParameterMode::Position => memory[check_address(i, value_in_memory_at_i)? as usize],
                 ParameterMode::Immediate => value_in_memory_at_i,
-                ParameterMode::Relative => memory[(value_in_memory_at_i + relative_base) as usize],
+                ParameterMode::Relative => {
+                    memory[check_address(i, value_in_memory_at_i + relative_base)? as usize]
+                }
             };
         }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
     #[test]
     fn test_run_program() {
@@ -227,7 +687,7 @@ mod tests {
     #[test]
     fn test_load_program() {
         assert_eq!(
-            load_program("src/inputs/2.txt"),
+            load_program(util::input(2)),
             vec![
                 1, 0, 0, 3, 1, 1, 2, 3, 1, 3, 4, 3, 1, 5, 0, 3, 2, 13, 1, 19, 1, 5, 19, 23, 2, 10,
                 23, 27, 1, 27, 5, 31, 2, 9, 31, 35, 1, 35, 5, 39, 2, 6, 39, 43, 1, 43, 5, 47, 2,
@@ -239,6 +699,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(try_parse_program("1, 0,0,0,99\n"), Ok(vec![1, 0, 0, 0, 99]));
+        assert_eq!(
+            try_parse_program("1,0,x,99").unwrap_err().to_string(),
+            "line 1: \"x\": value 2 of the program isn't an integer"
+        );
+
+        let error = try_load_program(util::input(3)).unwrap_err();
+        assert!(matches!(error, LoadError::Parse { .. }));
+        assert!(error.to_string().contains(": line 1: \"R"));
+    }
+
     #[test]
     fn test_parse_instruction() {
         let mut buffer = vec![
@@ -246,7 +719,7 @@ mod tests {
             ParameterMode::Position,
             ParameterMode::Position,
         ];
-        assert_eq!(parse_instruction(1002, &mut buffer), 2);
+        assert_eq!(parse_instruction(1002, &mut buffer), Some(2));
         assert_eq!(
             buffer,
             vec![
@@ -261,7 +734,7 @@ mod tests {
             ParameterMode::Immediate,
             ParameterMode::Immediate,
         ];
-        assert_eq!(parse_instruction(1002, &mut buffer), 2);
+        assert_eq!(parse_instruction(1002, &mut buffer), Some(2));
         assert_eq!(
             buffer,
             vec![
@@ -275,7 +748,7 @@ mod tests {
             ParameterMode::Position,
             ParameterMode::Position,
         ];
-        assert_eq!(parse_instruction(11004, &mut buffer), 4);
+        assert_eq!(parse_instruction(11004, &mut buffer), Some(4));
         assert_eq!(
             buffer,
             vec![
@@ -293,7 +766,7 @@ mod tests {
             ParameterMode::Position,
             ParameterMode::Position,
         ];
-        assert_eq!(parse_instruction(101099, &mut buffer), 99);
+        assert_eq!(parse_instruction(101099, &mut buffer), Some(99));
         assert_eq!(
             buffer,
             vec![
@@ -328,7 +801,8 @@ mod tests {
             5,
             &vec![ParameterMode::Position, ParameterMode::Immediate][..],
             &mut argument_buffer,
-        );
+        )
+        .unwrap();
 
         assert_eq!(argument_buffer, vec![2, 2, 0, 0, 0]);
     }
@@ -462,6 +936,254 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_retry_input() {
+        // Reads a value and outputs it.
+        let mut computer = Computer::new(vec![3, 5, 4, 5, 99, 0]);
+        assert_eq!(computer.run(HaltReason::NeedsInput), HaltReason::NeedsInput);
+
+        computer.retry_input();
+        computer.push_input(42);
+        computer.run(HaltReason::Exit);
+        assert_eq!(computer.pop_output(), Some(42));
+    }
+
+    #[test]
+    fn test_coverage() {
+        // "Using position mode, consider whether the input is equal to 8; output 1 (if it is) or 0 (if it is not)."
+        let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+        let mut computer = Computer::new(program.clone());
+        assert_eq!(computer.coverage(), None);
+
+        computer.track_coverage();
+        computer.push_input(8);
+        computer.run(HaltReason::Exit);
+
+        let coverage = computer.coverage().unwrap();
+        assert!((0..=8).all(|address| coverage.get(address) == AddressUse::Executed));
+        assert_eq!(coverage.get(9), AddressUse::Data);
+        assert_eq!(coverage.get(10), AddressUse::Data);
+        assert_eq!(coverage.get(11), AddressUse::Untouched);
+        assert_eq!(
+            coverage.to_string(),
+            "11 addresses: 9 executed (81.8%), 2 data (18.2%), 0 untouched (0.0%)"
+        );
+
+        // "Using immediate mode, consider whether the input is less than 8", except that this one jumps
+        // over an output of 1 if it isn't.
+        let program = vec![3, 12, 1007, 12, 8, 13, 1006, 13, 11, 104, 1, 99, 0, 0];
+        let coverage_for_input = |input| {
+            let mut computer = Computer::new(program.clone());
+            computer.track_coverage();
+            computer.push_input(input);
+            computer.run(HaltReason::Exit);
+            computer.coverage().unwrap().clone()
+        };
+
+        let mut coverage = coverage_for_input(100);
+        assert_eq!(coverage.get(9), AddressUse::Untouched);
+        assert_eq!(coverage.count(AddressUse::Untouched), 2);
+
+        coverage.merge(&coverage_for_input(5));
+        assert_eq!(coverage.get(9), AddressUse::Executed);
+        assert_eq!(coverage.get(10), AddressUse::Executed);
+        assert_eq!(coverage.count(AddressUse::Untouched), 0);
+        assert_eq!(coverage.count(AddressUse::Data), 2);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "parameter 0 of opcode 4 at instruction pointer 2 refers to invalid address -3 (relative base -5)"
+    )]
+    fn test_negative_relative_address() {
+        // Sets the relative base to -5, then tries to output the value at relative address 2.
+        let mut computer = Computer::new(vec![109, -5, 204, 2, 99]);
+        computer.run(HaltReason::Exit);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "parameter 2 of opcode 1 at instruction pointer 0 refers to invalid address -1 (relative base 0)"
+    )]
+    fn test_negative_target_address() {
+        let mut computer = Computer::new(vec![21101, 1, 1, -1, 99]);
+        computer.run(HaltReason::Exit);
+    }
+
+    #[test]
+    fn test_try_step_errors() {
+        let try_run = |program: Vec<i64>| Computer::new(program).try_run(HaltReason::Exit);
+
+        assert_eq!(
+            try_run(vec![1101, 1, 1, 5, 42, 0, 99]),
+            Err(StepError::UnknownOpcode {
+                instruction_pointer: 4,
+                instruction: 42
+            })
+        );
+        assert_eq!(
+            try_run(vec![-1, 99]),
+            Err(StepError::UnknownOpcode {
+                instruction_pointer: 0,
+                instruction: -1
+            })
+        );
+        assert_eq!(
+            try_run(vec![301, 1, 1, 0, 99]),
+            Err(StepError::BadParameterMode {
+                instruction_pointer: 0,
+                instruction: 301
+            })
+        );
+        assert_eq!(
+            try_run(vec![1111101, 1, 1, 0, 99]),
+            Err(StepError::BadParameterMode {
+                instruction_pointer: 0,
+                instruction: 1111101
+            })
+        );
+        assert_eq!(
+            try_run(vec![11101, 1, 1, 0, 99]),
+            Err(StepError::ImmediateTarget {
+                instruction_pointer: 0,
+                opcode: 1,
+                parameter_index: 2
+            })
+        );
+        assert!(matches!(
+            try_run(vec![4, 20_000, 99]),
+            Err(StepError::InvalidAddress(InvalidAddress {
+                address: 20_000,
+                ..
+            }))
+        ));
+        assert_eq!(
+            try_run(vec![1106, 0, -1]),
+            Err(StepError::InstructionPointerOutOfRange {
+                instruction_pointer: usize::MAX
+            })
+        );
+
+        // The computer stays on the bad instruction, so it can be fixed and run again.
+        let mut computer = Computer::new(vec![104, 7, 42, 99]);
+        assert_eq!(computer.try_step(), Ok(Some(HaltReason::Output)));
+        assert!(computer.try_step().is_err());
+        computer.write_memory(2, 99);
+        assert_eq!(computer.try_run(HaltReason::Exit), Ok(HaltReason::Exit));
+        assert_eq!(computer.pop_output(), Some(7));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        // Reads a value and outputs it, twice.
+        let mut computer = Computer::new(vec![3, 9, 4, 9, 3, 9, 4, 9, 99, 0]);
+        computer.push_input(1);
+        computer.run(HaltReason::Output);
+        assert_eq!(computer.pop_output(), Some(1));
+
+        let snapshot = computer.snapshot();
+        computer.push_input(2);
+        computer.run(HaltReason::Exit);
+        assert_eq!(computer.pop_output(), Some(2));
+
+        computer.restore(&snapshot);
+        assert_eq!(computer.snapshot(), snapshot);
+        computer.push_input(3);
+        computer.run(HaltReason::Exit);
+        assert_eq!(computer.pop_output(), Some(3));
+    }
+
+    #[test]
+    fn test_next_output() {
+        // Outputs 1 and 2, then exits.
+        let mut computer = Computer::new(vec![104, 1, 104, 2, 99]);
+        assert_eq!(computer.next_output(), Some(1));
+        assert_eq!(computer.next_output(), Some(2));
+        assert_eq!(computer.next_output(), None);
+        assert_eq!(computer.next_output(), None);
+
+        // Outputs that are already waiting come first.
+        let mut computer = Computer::new(vec![104, 1, 104, 2, 99]);
+        computer.run(HaltReason::Output);
+        computer.run(HaltReason::Output);
+        assert_eq!(computer.next_output(), Some(1));
+        assert_eq!(computer.next_output(), Some(2));
+        assert_eq!(computer.next_output(), None);
+    }
+
+    #[test]
+    fn test_run_until_n_outputs() {
+        // Outputs 1, 2, and 3, then exits.
+        let mut computer = Computer::new(vec![104, 1, 104, 2, 104, 3, 99]);
+        computer.run(HaltReason::Output);
+        assert_eq!(computer.run_until_n_outputs(2)[..], [1, 2]);
+        assert_eq!(computer.run_until_n_outputs(2)[..], [3]);
+        assert!(computer.run_until_n_outputs(2).is_empty());
+    }
+
+    /// A sink that can still be read after it's been handed to a Computer.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stream_ascii_output() {
+        // Outputs "hi", then 1000, then a newline.
+        let mut computer = Computer::new(vec![104, 104, 104, 105, 104, 1000, 104, 10, 99]);
+        let sink = SharedBuffer::default();
+        computer.stream_ascii_output(sink.clone());
+
+        computer.run(HaltReason::Output);
+        assert_eq!(&sink.0.borrow()[..], b"h");
+
+        computer.run(HaltReason::Exit);
+        assert_eq!(&sink.0.borrow()[..], b"hi\n");
+
+        let outputs: Vec<_> = std::iter::from_fn(|| computer.pop_output()).collect();
+        assert_eq!(outputs, vec![104, 105, 1000, 10]);
+    }
+
+    #[test]
+    fn test_debug_opcode() {
+        // Adds 2 and 3, debug-prints the sum and then the constant 7, and exits.
+        let program = vec![1101, 2, 3, 9, 98, 9, 198, 7, 99, 0];
+
+        let mut computer = Computer::new(program.clone());
+        computer.enable_debug_opcode();
+        computer.run(HaltReason::Exit);
+        assert_eq!(
+            computer.debug_prints(),
+            &[
+                DebugPrint {
+                    instruction_pointer: 4,
+                    value: 5
+                },
+                DebugPrint {
+                    instruction_pointer: 6,
+                    value: 7
+                }
+            ]
+        );
+        assert_eq!(computer.debug_prints()[0].to_string(), "[ip 4] 5");
+        assert_eq!(computer.pop_output(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_debug_opcode_disabled() {
+        let mut computer = Computer::new(vec![198, 7, 99]);
+        computer.run(HaltReason::Exit);
+    }
+
     #[test]
     fn test_relative_base_programs() {
         let quine_program = vec![
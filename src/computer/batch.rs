@@ -0,0 +1,63 @@
+use crate::computer::{Computer, HaltReason};
+use rayon::prelude::*;
+
+/// Everything a program output during a single run.
+pub type Outputs = Vec<i64>;
+
+/// Calls `f` once for each of `items`, in parallel, with a fresh Computer running `program`,
+/// and returns the results in the same order as `items`.
+pub fn batch_map<T, R, F>(program: &[i64], items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(&mut Computer, T) -> R + Sync,
+{
+    items
+        .into_par_iter()
+        .map(|item| {
+            // Computers can't be shared between threads, so each work item gets its own.
+            let mut computer = Computer::new(program.to_vec());
+            f(&mut computer, item)
+        })
+        .collect()
+}
+
+/// Runs `program` to completion once for each set of `inputs`, in parallel,
+/// and returns the outputs from each run in the same order as `inputs`.
+pub fn batch_run(program: &[i64], inputs: impl IntoIterator<Item = Vec<i64>>) -> Vec<Outputs> {
+    batch_map(program, inputs.into_iter().collect(), |computer, inputs| {
+        for input in inputs {
+            computer.push_input(input);
+        }
+        computer.run(HaltReason::Exit);
+
+        std::iter::from_fn(|| computer.pop_output()).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_run() {
+        // Outputs its input plus 1, then exits.
+        let add_one = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        assert_eq!(
+            batch_run(&add_one, (0..100).map(|x| vec![x])),
+            (1..101).map(|x| vec![x]).collect::<Vec<_>>()
+        );
+        assert!(batch_run(&add_one, vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_batch_map() {
+        // Adds the value at the address in position 1 to the value at address 0.
+        let results = batch_map(&[1, 0, 0, 0, 99], vec![1, 2, 4], |computer, address| {
+            computer.state.memory[1] = address;
+            computer.run(HaltReason::Exit);
+            computer.state.memory[0]
+        });
+        assert_eq!(results, vec![2, 1, 100]);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::computer::{AddressUse, Computer, Coverage, HaltReason, Memory, StepError};
+use std::fmt;
+
+/// The first difference `compare()` found between two programs.
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    /// The programs' `index`th outputs differ. None means that program didn't produce that many outputs.
+    Output {
+        index: usize,
+        a: Option<i64>,
+        b: Option<i64>,
+    },
+    /// The programs produced the same outputs, but didn't both fail on the same instruction in the same way.
+    /// None means that program didn't fail.
+    Error {
+        a: Option<StepError>,
+        b: Option<StepError>,
+    },
+    /// The programs produced the same outputs, but left different values at `address`.
+    Memory { address: usize, a: i64, b: i64 },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |value: &Option<i64>| match value {
+            Some(value) => value.to_string(),
+            None => "nothing".to_string(),
+        };
+
+        match self {
+            Divergence::Output { index, a, b } => write!(
+                f,
+                "output {} was {} from program a but {} from program b",
+                index,
+                describe(a),
+                describe(b)
+            ),
+            Divergence::Error { a, b } => {
+                let describe = |error: &Option<StepError>| match error {
+                    Some(error) => format!("failed with \"{}\"", error),
+                    None => "didn't fail".to_string(),
+                };
+                write!(f, "program a {} but program b {}", describe(a), describe(b))
+            }
+            Divergence::Memory { address, a, b } => write!(
+                f,
+                "address {} was {} in program a but {} in program b",
+                address, a, b
+            ),
+        }
+    }
+}
+
+/// Everything `run_with_budget()` found out about a run of a program.
+struct Run {
+    outputs: Vec<i64>,
+    memory: Memory,
+    coverage: Coverage,
+    error: Option<StepError>,
+}
+
+/// Runs `program` until it exits, runs out of `inputs`, hits an instruction it can't run,
+/// or has run `budget` instructions, and returns everything it output along with its memory at that point.
+fn run_with_budget(program: Memory, inputs: &[i64], budget: usize) -> Run {
+    let mut computer = Computer::new(program);
+    computer.track_coverage();
+    for &input in inputs {
+        computer.push_input(input);
+    }
+
+    let mut error = None;
+    for _ in 0..budget {
+        match computer.try_step() {
+            Ok(Some(HaltReason::Exit)) | Ok(Some(HaltReason::NeedsInput)) => break,
+            Ok(_) => (),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    Run {
+        outputs: std::iter::from_fn(|| computer.pop_output()).collect(),
+        coverage: computer.coverage().unwrap().clone(),
+        memory: computer.state.memory,
+        error,
+    }
+}
+
+/// Runs `program_a` and `program_b` on the same `inputs`, each for at most `budget` instructions,
+/// and returns the first place where their outputs differ. If their outputs match, returns the instructions
+/// they couldn't run if they didn't fail the same way, or else the first data address where their memory differs.
+/// Returns None if the programs are indistinguishable.
+///
+/// Only addresses that at least one program read or wrote as data, and that neither program ran as code,
+/// are compared, so two programs that compute the same thing with different instructions don't diverge.
+pub fn compare(
+    program_a: Memory,
+    program_b: Memory,
+    inputs: &[i64],
+    budget: usize,
+) -> Option<Divergence> {
+    let run_a = run_with_budget(program_a, inputs, budget);
+    let run_b = run_with_budget(program_b, inputs, budget);
+
+    for index in 0..run_a.outputs.len().max(run_b.outputs.len()) {
+        let (a, b) = (
+            run_a.outputs.get(index).copied(),
+            run_b.outputs.get(index).copied(),
+        );
+        if a != b {
+            return Some(Divergence::Output { index, a, b });
+        }
+    }
+
+    if run_a.error != run_b.error {
+        return Some(Divergence::Error {
+            a: run_a.error,
+            b: run_b.error,
+        });
+    }
+
+    // Memory that one computer has and the other doesn't would still be 0 if it existed.
+    (0..run_a.memory.len().max(run_b.memory.len()))
+        .filter(|&address| {
+            let uses = [run_a.coverage.get(address), run_b.coverage.get(address)];
+            uses.contains(&AddressUse::Data) && !uses.contains(&AddressUse::Executed)
+        })
+        .map(|address| {
+            (
+                address,
+                *run_a.memory.get(address).unwrap_or(&0),
+                *run_b.memory.get(address).unwrap_or(&0),
+            )
+        })
+        .find(|(_, a, b)| a != b)
+        .map(|(address, a, b)| Divergence::Memory { address, a, b })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs;
+
+    #[test]
+    fn test_compare() {
+        // Outputs its input plus 1, then exits.
+        let add_one = vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+        // Does the same thing with a multiply and an add.
+        let add_one_slowly = vec![3, 13, 1002, 13, 1, 13, 1001, 13, 1, 13, 4, 13, 99, 0];
+        // Outputs its input plus 2.
+        let add_two = vec![3, 9, 1001, 9, 2, 9, 4, 9, 99, 0];
+
+        assert_eq!(compare(add_one.clone(), add_one.clone(), &[5], 100), None);
+        assert_eq!(
+            compare(add_one.clone(), add_two, &[5], 100),
+            Some(Divergence::Output {
+                index: 0,
+                a: Some(6),
+                b: Some(7)
+            })
+        );
+
+        // Their code differs, but only the address they keep their result in is compared.
+        let divergence = compare(add_one.clone(), add_one_slowly.clone(), &[5], 100).unwrap();
+        assert_eq!(
+            divergence,
+            Divergence::Memory {
+                address: 13,
+                a: 0,
+                b: 6
+            }
+        );
+        assert_eq!(
+            divergence.to_string(),
+            "address 13 was 0 in program a but 6 in program b"
+        );
+
+        // Outputs its input plus 1, but keeps its result at the same address as add_one.
+        let add_one_differently = vec![3, 9, 101, 1, 9, 9, 4, 9, 99, 0];
+        assert_eq!(
+            compare(add_one.clone(), add_one_differently, &[5], 100),
+            None
+        );
+
+        // The slow version hasn't gotten to its output after three instructions.
+        let divergence = compare(add_one, add_one_slowly, &[5], 3).unwrap();
+        assert_eq!(
+            divergence,
+            Divergence::Output {
+                index: 0,
+                a: Some(6),
+                b: None
+            }
+        );
+        assert_eq!(
+            divergence.to_string(),
+            "output 0 was 6 from program a but nothing from program b"
+        );
+    }
+
+    #[test]
+    fn test_compare_errors() {
+        let unknown_opcode = StepError::UnknownOpcode {
+            instruction_pointer: 0,
+            instruction: 42,
+        };
+        let divergence = compare(vec![42, 0, 0, 0], vec![99], &[], 10).unwrap();
+        assert_eq!(
+            divergence,
+            Divergence::Error {
+                a: Some(unknown_opcode),
+                b: None
+            }
+        );
+        assert_eq!(
+            divergence.to_string(),
+            "program a failed with \"instruction 42 at instruction pointer 0 has an unknown opcode\" \
+             but program b didn't fail"
+        );
+
+        // Programs that fail the same way are indistinguishable.
+        assert_eq!(compare(vec![42, 0, 0, 0], vec![42, 0, 0, 0], &[], 10), None);
+
+        // Outputs before the failure are still compared first.
+        assert_eq!(
+            compare(vec![104, 1, 42], vec![104, 2, 99], &[], 10),
+            Some(Divergence::Output {
+                index: 0,
+                a: Some(1),
+                b: Some(2)
+            })
+        );
+    }
+
+    #[test]
+    fn test_compare_real_program() {
+        let boost = programs::load("boost");
+        assert_eq!(compare(boost.clone(), boost.clone(), &[1], 1_000_000), None);
+
+        // A patched program differs from the original, if only at the patched address.
+        let mut patched = boost.clone();
+        let address = patched.iter().position(|&x| x == 203).unwrap();
+        patched[address] = 3;
+        assert!(compare(boost, patched, &[1], 1_000_000).is_some());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::computer::{HaltReason, State};
+use crate::computer::{DebugPrint, HaltReason, IoEvent, State};
+use tracing::debug;
 
 pub const MAX_NUM_ARGUMENTS: usize = 3;
 
@@ -58,6 +59,7 @@ pub(crate) fn load_operations() -> Vec<Option<Operation>> {
         run: Box::new(|state, args| {
             if state.input.is_empty() {
                 state.memory[args[0] as usize] = -1;
+                state.record(IoEvent::Input(-1));
 
                 // Indicate that the program needs input in order to continue.
                 state.instruction_pointer += 2;
@@ -66,7 +68,9 @@ pub(crate) fn load_operations() -> Vec<Option<Operation>> {
                     manipulated_instruction_pointer: true,
                 }
             } else {
-                state.memory[args[0] as usize] = state.input.remove(0);
+                let input = state.input.remove(0);
+                state.memory[args[0] as usize] = input;
+                state.record(IoEvent::Input(input));
                 Default::default()
             }
         }),
@@ -78,6 +82,7 @@ pub(crate) fn load_operations() -> Vec<Option<Operation>> {
         target_memory_location_arg: None,
         run: Box::new(|state, args| {
             state.output.push_back(args[0]);
+            state.record(IoEvent::Output(args[0]));
             state.instruction_pointer += 2;
             Outcome {
                 halt_reason: Some(HaltReason::Output),
@@ -162,3 +167,23 @@ pub(crate) fn load_operations() -> Vec<Option<Operation>> {
 
     operations
 }
+
+/// The opcode of the debug operation, which isn't part of the Intcode spec.
+/// It takes one parameter and records it, along with the instruction pointer, as a `DebugPrint`.
+pub const DEBUG_OPCODE: usize = 98;
+
+pub(crate) fn debug_operation() -> Operation {
+    Operation {
+        num_arguments: 1,
+        target_memory_location_arg: None,
+        run: Box::new(|state, args| {
+            let debug_print = DebugPrint {
+                instruction_pointer: state.instruction_pointer,
+                value: args[0],
+            };
+            debug!("{}", debug_print);
+            state.debug_prints.push(debug_print);
+            Default::default()
+        }),
+    }
+}
@@ -0,0 +1,238 @@
+use crate::computer::{Computer, Memory};
+use crate::util::{self, ParseError};
+use std::fmt;
+
+/// The most values `Trace`'s Display impl puts on a single line.
+const MAX_VALUES_PER_LINE: usize = 20;
+
+/// A value that went into or came out of a running program.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IoEvent {
+    Input(i64),
+    Output(i64),
+}
+
+/// Every value a program consumed and produced, in the order that it happened.
+///
+/// Displays as lines like `in 1,2,3` and `out 4,5`, which `parse_trace()` reads back in.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Trace {
+    events: Vec<IoEvent>,
+}
+
+impl Trace {
+    pub(crate) fn push(&mut self, event: IoEvent) {
+        self.events.push(event);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<IoEvent> {
+        self.events.pop()
+    }
+
+    pub fn events(&self) -> &[IoEvent] {
+        &self.events
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Input(value) => Some(*value),
+            IoEvent::Output(_) => None,
+        })
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = i64> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            IoEvent::Output(value) => Some(*value),
+            IoEvent::Input(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line: Vec<String> = vec![];
+        let mut line_is_input = false;
+
+        for event in &self.events {
+            let (is_input, value) = match event {
+                IoEvent::Input(value) => (true, value),
+                IoEvent::Output(value) => (false, value),
+            };
+
+            if !line.is_empty() && (is_input != line_is_input || line.len() == MAX_VALUES_PER_LINE)
+            {
+                write_line(f, line_is_input, &line)?;
+                line.clear();
+            }
+
+            line_is_input = is_input;
+            line.push(value.to_string());
+        }
+
+        if !line.is_empty() {
+            write_line(f, line_is_input, &line)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_line(f: &mut fmt::Formatter, is_input: bool, values: &[String]) -> fmt::Result {
+    let direction = if is_input { "in" } else { "out" };
+    writeln!(f, "{} {}", direction, values.join(","))
+}
+
+/// Reads a trace in the format that `Trace` displays as.
+pub fn parse_trace(s: &str) -> Result<Trace, ParseError> {
+    let mut trace = Trace::default();
+
+    for (i, line) in s.lines().map(str::trim).enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let (direction, values) = line.split_once(' ').unwrap_or((line, ""));
+        let to_event = match direction {
+            "in" => IoEvent::Input,
+            "out" => IoEvent::Output,
+            _ => return Err(ParseError::new(i + 1, direction, "expected in or out")),
+        };
+
+        for value in values.trim().split(',') {
+            trace.push(to_event(util::parse_token(value, i + 1)?));
+        }
+    }
+
+    Ok(trace)
+}
+
+pub fn load_trace(filename: &str) -> Trace {
+    util::load(filename, parse_trace)
+}
+
+/// Describes the first output where a replay didn't go the way its trace said it would.
+#[derive(Debug, PartialEq)]
+pub struct ReplayMismatch {
+    /// Counts outputs, not events.
+    pub output_index: usize,
+    pub expected: i64,
+    /// None if the program exited instead.
+    pub actual: Option<i64>,
+}
+
+impl fmt::Display for ReplayMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.actual {
+            Some(actual) => write!(
+                f,
+                "output {} was {}, but the trace says it should be {}",
+                self.output_index, actual, self.expected
+            ),
+            None => write!(
+                f,
+                "the program exited before output {}, which the trace says should be {}",
+                self.output_index, self.expected
+            ),
+        }
+    }
+}
+
+/// Runs `memory` on a fresh Computer, feeding it the inputs from `trace`,
+/// and checks that it produces the outputs from `trace`.
+/// The program may keep running after the trace ends; anything it does after that isn't checked.
+pub fn replay(memory: Memory, trace: &Trace) -> Result<(), ReplayMismatch> {
+    let mut computer = Computer::new(memory);
+    for input in trace.inputs() {
+        computer.push_input(input);
+    }
+
+    for (output_index, expected) in trace.outputs().enumerate() {
+        let actual = computer.next_output();
+        if actual != Some(expected) {
+            return Err(ReplayMismatch {
+                output_index,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::HaltReason;
+
+    /// Reads a value, outputs it doubled, and repeats forever.
+    fn doubler() -> Memory {
+        vec![3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0, 0]
+    }
+
+    #[test]
+    fn test_record_io() {
+        let mut computer = Computer::new(doubler());
+        computer.record_io();
+        computer.push_input(3);
+        computer.push_input(5);
+        computer.run(HaltReason::NeedsInput);
+        computer.run(HaltReason::NeedsInput);
+
+        // The program halts on the -1 it reads when it runs out of input, and rereads that instruction next time.
+        assert_eq!(computer.run(HaltReason::NeedsInput), HaltReason::NeedsInput);
+        computer.retry_input();
+
+        let trace = computer.trace().unwrap();
+        assert_eq!(
+            trace.events(),
+            &[
+                IoEvent::Input(3),
+                IoEvent::Output(6),
+                IoEvent::Input(5),
+                IoEvent::Output(10)
+            ]
+        );
+        assert_eq!(trace.to_string(), "in 3\nout 6\nin 5\nout 10\n");
+        assert_eq!(parse_trace(&trace.to_string()).unwrap(), *trace);
+
+        assert_eq!(
+            parse_trace("in 3\n\nout 6,x").unwrap_err().to_string(),
+            "line 3: \"x\": invalid digit found in string"
+        );
+        assert_eq!(
+            parse_trace("sideways 3").unwrap_err().to_string(),
+            "line 1: \"sideways\": expected in or out"
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = parse_trace("in 3,5\nout 6,10").unwrap();
+        assert_eq!(trace.inputs().collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(replay(doubler(), &trace), Ok(()));
+
+        let mismatch = replay(doubler(), &parse_trace("in 3,5\nout 6,11").unwrap()).unwrap_err();
+        assert_eq!(
+            mismatch,
+            ReplayMismatch {
+                output_index: 1,
+                expected: 11,
+                actual: Some(10)
+            }
+        );
+        assert_eq!(
+            mismatch.to_string(),
+            "output 1 was 10, but the trace says it should be 11"
+        );
+
+        assert_eq!(
+            replay(vec![104, 1, 99], &parse_trace("out 1,2").unwrap()),
+            Err(ReplayMismatch {
+                output_index: 1,
+                expected: 2,
+                actual: None
+            })
+        );
+    }
+}
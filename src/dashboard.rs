@@ -0,0 +1,357 @@
+use ratatui::crossterm::event::{self, Event as TerminalEvent, KeyCode, KeyEventKind};
+use ratatui::layout::Constraint;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Row, Table};
+use ratatui::Frame;
+use std::any::Any;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the screen is redrawn while waiting for solutions, so that running timers keep ticking.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Pending,
+    Running { started: Instant },
+    Done { elapsed: Duration },
+    Failed { elapsed: Duration, message: String },
+}
+
+/// Something that happened while solving, sent from the thread that does the solving.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Started {
+        day: u32,
+        at: Instant,
+    },
+    Answer {
+        day: u32,
+        part: char,
+        answer: String,
+    },
+    Finished {
+        day: u32,
+        elapsed: Duration,
+    },
+    Failed {
+        day: u32,
+        elapsed: Duration,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct DayRow {
+    day: u32,
+    status: Status,
+    part_a: Option<String>,
+    part_b: Option<String>,
+}
+
+/// The state of every day's solution, as shown in the dashboard.
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    rows: Vec<DayRow>,
+    started: Instant,
+}
+
+impl Dashboard {
+    pub fn new(days: impl IntoIterator<Item = u32>) -> Self {
+        Dashboard {
+            rows: days
+                .into_iter()
+                .map(|day| DayRow {
+                    day,
+                    status: Status::Pending,
+                    part_a: None,
+                    part_b: None,
+                })
+                .collect(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn apply(&mut self, event: Event) {
+        let day = match &event {
+            Event::Started { day, .. }
+            | Event::Answer { day, .. }
+            | Event::Finished { day, .. }
+            | Event::Failed { day, .. } => *day,
+        };
+        let row = match self.rows.iter_mut().find(|row| row.day == day) {
+            Some(row) => row,
+            None => return,
+        };
+
+        match event {
+            Event::Started { at, .. } => row.status = Status::Running { started: at },
+            Event::Answer {
+                part: 'a', answer, ..
+            } => row.part_a = Some(answer),
+            Event::Answer { answer, .. } => row.part_b = Some(answer),
+            Event::Finished { elapsed, .. } => row.status = Status::Done { elapsed },
+            Event::Failed {
+                elapsed, message, ..
+            } => row.status = Status::Failed { elapsed, message },
+        }
+    }
+
+    /// Returns true once every day has either finished or failed.
+    pub fn is_finished(&self) -> bool {
+        self.rows
+            .iter()
+            .all(|row| matches!(row.status, Status::Done { .. } | Status::Failed { .. }))
+    }
+
+    /// The text of each row of the table, as (day, status, part a, part b, time) columns.
+    pub fn table(&self, now: Instant) -> Vec<[String; 5]> {
+        self.rows
+            .iter()
+            .map(|row| {
+                let (status, time) = match &row.status {
+                    Status::Pending => ("pending".to_string(), String::new()),
+                    Status::Running { started } => (
+                        "running".to_string(),
+                        format!("{:.1?}", now.duration_since(*started)),
+                    ),
+                    Status::Done { elapsed } => ("done".to_string(), format!("{:.2?}", elapsed)),
+                    Status::Failed { elapsed, message } => {
+                        (format!("failed: {}", message), format!("{:.2?}", elapsed))
+                    }
+                };
+
+                [
+                    row.day.to_string(),
+                    status,
+                    answer_cell(&row.part_a),
+                    answer_cell(&row.part_b),
+                    time,
+                ]
+            })
+            .collect()
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let now = Instant::now();
+        let rows = self
+            .table(now)
+            .into_iter()
+            .zip(&self.rows)
+            .map(|(cells, row)| {
+                let color = match row.status {
+                    Status::Pending => Color::DarkGray,
+                    Status::Running { .. } => Color::Yellow,
+                    Status::Done { .. } => Color::Green,
+                    Status::Failed { .. } => Color::Red,
+                };
+                Row::new(cells.to_vec()).style(Style::default().fg(color))
+            });
+
+        let elapsed = if self.is_finished() {
+            "all done".to_string()
+        } else {
+            format!("{:.1?}", now.duration_since(self.started))
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+            ],
+        )
+        .header(
+            Row::new(["Day", "Status", "Part A", "Part B", "Time"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(format!(
+            " Advent of Code 2019 ({}) - press q to quit ",
+            elapsed
+        )));
+
+        frame.render_widget(table, frame.area());
+    }
+}
+
+/// Multi-line answers are drawings, which don't fit in a table cell.
+fn answer_cell(answer: &Option<String>) -> String {
+    match answer {
+        Some(answer) if answer.contains('\n') => {
+            format!("({}-line drawing)", answer.lines().count())
+        }
+        Some(answer) => answer.clone(),
+        None => String::new(),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+/// Solves each of `days` in order on a background thread, reporting progress through the returned channel.
+pub fn solve_in_background(days: Vec<u32>) -> Receiver<Event> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for day in days {
+            let started = Instant::now();
+            // The receiver only goes away if the dashboard has been closed, in which case nobody's listening.
+            let _ = sender.send(Event::Started { day, at: started });
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                crate::solve(day, &mut |part, answer| {
+                    let _ = sender.send(Event::Answer { day, part, answer });
+                })
+            }));
+
+            let elapsed = started.elapsed();
+            let _ = sender.send(match result {
+                Ok(()) => Event::Finished { day, elapsed },
+                Err(payload) => Event::Failed {
+                    day,
+                    elapsed,
+                    message: panic_message(payload.as_ref()),
+                },
+            });
+        }
+    });
+
+    receiver
+}
+
+/// Takes over the terminal to show each of `days` being solved, until the user presses q or escape.
+#[cfg(not(tarpaulin_include))]
+pub fn run(days: Vec<u32>) -> io::Result<()> {
+    let mut dashboard = Dashboard::new(days.iter().copied());
+    let events = solve_in_background(days);
+
+    // Panics are reported in the table, so keep the default hook from scribbling over it.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut terminal = ratatui::init();
+    let result = loop {
+        while let Ok(event) = events.try_recv() {
+            dashboard.apply(event);
+        }
+
+        if let Err(e) = terminal.draw(|frame| dashboard.draw(frame)) {
+            break Err(e);
+        }
+
+        match event::poll(REFRESH_INTERVAL) {
+            Ok(true) => match event::read() {
+                Ok(TerminalEvent::Key(key))
+                    if key.kind == KeyEventKind::Press
+                        && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) =>
+                {
+                    break Ok(())
+                }
+                Ok(_) => (),
+                Err(e) => break Err(e),
+            },
+            Ok(false) => (),
+            Err(e) => break Err(e),
+        }
+    };
+
+    ratatui::restore();
+    panic::set_hook(default_hook);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dashboard() {
+        let start = Instant::now();
+        let mut dashboard = Dashboard::new(vec![1, 8]);
+        assert!(!dashboard.is_finished());
+
+        dashboard.apply(Event::Started { day: 1, at: start });
+        dashboard.apply(Event::Answer {
+            day: 1,
+            part: 'a',
+            answer: "3334297".to_string(),
+        });
+        assert_eq!(
+            dashboard.table(start + Duration::from_millis(1500)),
+            vec![
+                [
+                    "1".to_string(),
+                    "running".to_string(),
+                    "3334297".to_string(),
+                    String::new(),
+                    "1.5s".to_string()
+                ],
+                [
+                    "8".to_string(),
+                    "pending".to_string(),
+                    String::new(),
+                    String::new(),
+                    String::new()
+                ]
+            ]
+        );
+
+        dashboard.apply(Event::Finished {
+            day: 1,
+            elapsed: Duration::from_millis(2),
+        });
+        dashboard.apply(Event::Answer {
+            day: 8,
+            part: 'b',
+            answer: "#..#\n#..#".to_string(),
+        });
+        dashboard.apply(Event::Failed {
+            day: 8,
+            elapsed: Duration::from_secs(1),
+            message: "oops".to_string(),
+        });
+        // Events for days that aren't on the dashboard are ignored.
+        dashboard.apply(Event::Started { day: 2, at: start });
+
+        let table = dashboard.table(Instant::now());
+        assert_eq!(table[0][1], "done");
+        assert_eq!(table[0][4], "2.00ms");
+        assert_eq!(table[1][1], "failed: oops");
+        assert_eq!(table[1][3], "(2-line drawing)");
+        assert!(dashboard.is_finished());
+    }
+
+    #[test]
+    fn test_solve_in_background() {
+        let events: Vec<_> = solve_in_background(vec![1, 4]).iter().collect();
+        assert_eq!(events.len(), 8);
+
+        let answers: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Answer { day, part, answer } => Some((*day, *part, answer.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            answers,
+            vec![
+                (1, 'a', "3334297"),
+                (1, 'b', "4998565"),
+                (4, 'a', "931"),
+                (4, 'b', "609")
+            ]
+        );
+        assert!(matches!(events[7], Event::Finished { day: 4, .. }));
+    }
+}
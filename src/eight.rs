@@ -1,20 +1,56 @@
+use crate::util::{self, ParseError};
 use itertools::Itertools;
-use std::fs;
+use tracing::instrument;
 
 const WIDTH: usize = 25;
 const HEIGHT: usize = 6;
 
 pub fn eight_a() -> usize {
-    let pixels = load_input();
-    let layers = decode_image(pixels, WIDTH, HEIGHT);
-    let relevant_layer = layers
+    checksum(&load_input(), WIDTH, HEIGHT)
+}
+
+pub fn eight_b() -> String {
+    composited_image(&load_input(), WIDTH, HEIGHT)
         .iter()
-        .min_by_key(|&layer| bytecount::count(layer, 0))
-        .unwrap();
+        .map(|row| {
+            row.iter()
+                .map(|&pixel| match pixel {
+                    2 => panic!("unexpected transparent pixel"),
+                    1 => 'X',
+                    0 => ' ',
+                    _ => panic!("invalid pixel"),
+                })
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+/// "To make sure the image wasn't corrupted during transmission, the Elves would like you to find
+/// the layer that contains the fewest 0 digits. On that layer, what is the number of 1 digits
+/// multiplied by the number of 2 digits?"
+pub fn checksum(pixels: &[u8], width: usize, height: usize) -> usize {
+    let layers = decode_image(pixels, width, height);
+    let relevant_layer = layer_with_fewest(&layers, 0);
 
     bytecount::count(relevant_layer, 1) * bytecount::count(relevant_layer, 2)
 }
 
+/// Splits `pixels` into layers of `width` * `height` pixels each.
+pub fn decode_image(pixels: &[u8], width: usize, height: usize) -> Vec<Vec<u8>> {
+    pixels
+        .chunks(width * height)
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+/// Returns the layer that contains the fewest pixels with the value `digit`.
+pub fn layer_with_fewest(layers: &[Vec<u8>], digit: u8) -> &[u8] {
+    layers
+        .iter()
+        .min_by_key(|&layer| bytecount::count(layer, digit))
+        .unwrap()
+}
+
 /// The image is rendered by stacking the layers and aligning the pixels with the
 /// same positions in each layer. The digits indicate the color of the
 /// corresponding pixel: 0 is black, 1 is white, and 2 is transparent.
@@ -22,11 +58,9 @@ pub fn eight_a() -> usize {
 /// a given position has a transparent pixel in the first and second layers, a
 /// black pixel in the third layer, and a white pixel in the fourth layer, the
 /// final image would have a black pixel at that position.
-pub fn eight_b() -> String {
-    let mut buffer = vec![2; WIDTH * HEIGHT];
+pub fn composite(layers: &[Vec<u8>], width: usize, height: usize) -> Vec<u8> {
+    let mut buffer = vec![2; width * height];
 
-    let pixels = load_input();
-    let layers = decode_image(pixels, WIDTH, HEIGHT);
     for layer in layers {
         for (i, &pixel) in layer.iter().enumerate() {
             if buffer[i] == 2 {
@@ -36,33 +70,27 @@ pub fn eight_b() -> String {
     }
 
     buffer
-        .iter()
-        .map(|&pixel| match pixel {
-            2 => panic!("unexpected transparent pixel"),
-            1 => 'X',
-            0 => ' ',
-            _ => panic!("invalid pixel"),
-        })
-        .chunks(WIDTH)
-        .into_iter()
-        .map(|chunk| chunk.collect::<String>())
-        .join("\n")
 }
 
-fn decode_image(pixels: Vec<u8>, width: usize, height: usize) -> Vec<Vec<u8>> {
-    pixels
-        .chunks(width * height)
-        .map(|chunk| chunk.to_vec())
+/// Decodes `pixels` and composites its layers, returning the final image as a Vec of rows.
+pub fn composited_image(pixels: &[u8], width: usize, height: usize) -> Vec<Vec<u8>> {
+    let layers = decode_image(pixels, width, height);
+
+    composite(&layers, width, height)
+        .chunks(width)
+        .map(|row| row.to_vec())
         .collect()
 }
 
-fn load_input() -> Vec<u8> {
-    let contents = fs::read_to_string("src/inputs/8.txt").unwrap();
+/// Parses a string of digits like "123456789012" into a Vec of pixels.
+pub fn parse_pixels(contents: &str) -> Result<Vec<u8>, ParseError> {
+    let digits = util::try_parse_digits(contents.trim())?;
+    Ok(digits.into_iter().map(|digit| digit as u8).collect())
+}
 
-    contents
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect()
+#[instrument(name = "parse", level = "debug", skip_all)]
+fn load_input() -> Vec<u8> {
+    util::load(util::input(8), parse_pixels)
 }
 
 #[cfg(test)]
@@ -72,11 +100,30 @@ mod tests {
     #[test]
     fn test_decode_image() {
         assert_eq!(
-            decode_image(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2], 3, 2),
+            decode_image(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2], 3, 2),
             vec![vec![1, 2, 3, 4, 5, 6], vec![7, 8, 9, 0, 1, 2]]
         )
     }
 
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(&parse_pixels("123456789012\n").unwrap(), 3, 2), 1);
+    }
+
+    #[test]
+    fn test_composited_image() {
+        // "For example, given an image 2 pixels wide and 2 pixels tall, the image data 0222112222120000 ..."
+        let pixels = parse_pixels("0222112222120000").unwrap();
+        assert_eq!(
+            composite(&decode_image(&pixels, 2, 2), 2, 2),
+            vec![0, 1, 1, 0]
+        );
+        assert_eq!(
+            composited_image(&pixels, 2, 2),
+            vec![vec![0, 1], vec![1, 0]]
+        );
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(eight_a(), 2480);
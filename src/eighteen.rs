@@ -1,25 +1,35 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs;
+use crate::grid::Grid;
+use crate::render::{self, RenderGrid, Rgb};
+use crate::solver::Solver;
+use crate::util;
+use crate::util::search::MinScored;
+use crate::util::{FastHashMap, FastHashSet, ParseError, Point};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::collections::{BTreeSet, BinaryHeap, VecDeque};
+use std::fmt::Write;
+use tracing::instrument;
 
 type Position = (usize, usize);
 
 /// A map of {key -> (distance_to_key_from_starting_position, doors_needed, keys_picked_up_on_the_way)}.
-type KeyDistanceMap = HashMap<Key, (u32, Bitfield, Bitfield)>;
+type KeyDistanceMap = FastHashMap<Key, (u32, Bitfield, Bitfield)>;
 
-#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
-struct Key(u32);
-
-static STARTING_KEY: Key = Key(2147483648); // 2^31
+/// For each robot, a KeyDistanceMap as seen from each key it can stand on, including STARTING_KEY for its entrance.
+type DistanceMapsPerRobot = Vec<FastHashMap<Key, KeyDistanceMap>>;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Key {
+    bit: u64,
+    name: char,
 }
 
+// Robots start out standing on an @, which doesn't count as a key.
+static STARTING_KEY: Key = Key { bit: 0, name: '@' };
+
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum Space {
     Wall,
     Empty,
@@ -27,12 +37,28 @@ enum Space {
     Key(char),
 }
 
-#[derive(Debug)]
-struct Vault {
-    keys: HashMap<char, Position>,
-    doors: HashMap<char, Position>,
-    map: Vec<Space>,
-    width: usize,
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vault {
+    keys: FastHashMap<char, Position>,
+    doors: FastHashMap<char, Position>,
+    /// The position of each @, in reading order. There's one robot per entrance.
+    entrances: Vec<Position>,
+    /// Maps each key's name to its bit in a Bitfield.
+    key_bits: FastHashMap<char, u64>,
+    map: Grid<Space>,
+}
+
+impl Space {
+    /// Returns the character that this space is drawn as in a puzzle input.
+    fn to_char(self) -> char {
+        match self {
+            Space::Wall => '#',
+            Space::Empty => '.',
+            Space::Door(c) => c.to_ascii_uppercase(),
+            Space::Key(c) => c,
+        }
+    }
 }
 
 impl Vault {
@@ -45,75 +71,157 @@ impl Vault {
     /// ########################
     ///
     /// into a Vault.
-    pub fn new(vault_contents: String) -> Self {
-        let mut map = vec![];
-        let mut doors = HashMap::new();
-        let mut keys = HashMap::new();
-
-        for (y, line) in vault_contents.lines().enumerate() {
-            for (x, character) in line.chars().enumerate() {
-                map.push(
-                    match (
-                        character,
-                        character.is_ascii_lowercase(),
-                        character.is_ascii_uppercase(),
-                    ) {
-                        ('#', _, _) => Space::Wall,
-                        ('.', _, _) => Space::Empty,
-                        ('@', _, _) => {
-                            keys.insert(character, (x, y));
-                            Space::Key('@')
-                        }
-                        (character, true, _) => {
-                            keys.insert(character, (x, y));
-                            Space::Key(character)
-                        }
-                        (character, _, true) => {
-                            let character = character.to_lowercase().next().unwrap();
-                            doors.insert(character, (x, y));
-                            Space::Door(character)
-                        }
-                        _ => unreachable!(),
-                    },
-                )
+    #[instrument(name = "parse", level = "debug", skip_all)]
+    pub fn new(vault_contents: String) -> Result<Self, ParseError> {
+        Ok(Vault::new_many(vec![vault_contents])?.pop().unwrap())
+    }
+
+    /// Parses several vaults whose keys can open each other's doors.
+    fn new_many(vault_contents: Vec<String>) -> Result<Vec<Self>, ParseError> {
+        let mut vaults = vault_contents
+            .iter()
+            .map(|c| Vault::parse(c))
+            .collect::<Result<Vec<Vault>, _>>()?;
+
+        // Keys and doors share a single Bitfield across all of the vaults.
+        let names: BTreeSet<char> = vaults
+            .iter()
+            .flat_map(|vault| vault.keys.keys().chain(vault.doors.keys()))
+            .cloned()
+            .collect();
+        if let Some(&name) = names.iter().nth(64) {
+            // Point at the first key or door that doesn't get a bit.
+            let (token, (_, y)) = vaults
+                .iter()
+                .find_map(|vault| {
+                    let key = vault.keys.get(&name).map(|&position| (name, position));
+                    let door = vault
+                        .doors
+                        .get(&name)
+                        .map(|&position| (name.to_uppercase().next().unwrap(), position));
+                    key.or(door)
+                })
+                .unwrap();
+            return Err(ParseError::new(
+                y + 1,
+                &token.to_string(),
+                format_args!("vaults can have at most 64 keys, found {}", names.len()),
+            ));
+        }
+
+        let key_bits: FastHashMap<char, u64> = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, 1 << i))
+            .collect();
+        for vault in &mut vaults {
+            vault.key_bits = key_bits.clone();
+        }
+
+        Ok(vaults)
+    }
+
+    fn parse(vault_contents: &str) -> Result<Self, ParseError> {
+        let mut doors = FastHashMap::default();
+        let mut keys = FastHashMap::default();
+        let mut entrances = vec![];
+        let mut unexpected_character = None;
+
+        let map = Grid::parse(vault_contents, |character, position| {
+            match (
+                character,
+                character.is_lowercase(),
+                character.is_uppercase(),
+            ) {
+                ('#', _, _) => Space::Wall,
+                ('.', _, _) => Space::Empty,
+                ('@', _, _) => {
+                    entrances.push(position);
+                    Space::Key('@')
+                }
+                (character, true, _) => {
+                    keys.insert(character, position);
+                    Space::Key(character)
+                }
+                (character, _, true) => {
+                    let character = character.to_lowercase().next().unwrap();
+                    doors.insert(character, position);
+                    Space::Door(character)
+                }
+                (character, _, _) => {
+                    unexpected_character.get_or_insert((character, position));
+                    Space::Wall
+                }
             }
+        })
+        .map_err(|e| e.at_line_of(vault_contents))?;
+
+        if let Some((c, (_, y))) = unexpected_character {
+            return Err(ParseError::new(
+                y + 1,
+                &c.to_string(),
+                "expected a wall, open space, entrance, key, or door",
+            ));
         }
 
-        Vault {
+        Ok(Vault {
             doors,
             keys,
+            entrances,
+            key_bits: FastHashMap::default(),
             map,
-            width: vault_contents.lines().next().unwrap().len(),
-        }
+        })
     }
 
-    /// Returns the Space at (x, y).
-    fn get(&self, x: usize, y: usize) -> Space {
-        self.map[y * self.width + x]
+    /// Returns the bit for the key (or door) named `name`.
+    fn key_bit(&self, name: char) -> u64 {
+        self.key_bits[&name]
     }
-}
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-struct Bitfield(u32);
+    /// Returns this vault as JSON: its map, the position of each key, door, and entrance,
+    /// and the bit that each key is assigned.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
 
-impl Bitfield {
-    fn contains_all(&self, other: Bitfield) -> bool {
-        (other.0 & !self.0) == 0
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
     }
 }
 
-// Maps a to 1 << 0, n to 1 << 13, and so on.
-fn char_to_shifted_bit(c: char) -> u32 {
-    1 << (c as u32 - 97)
+impl RenderGrid for Vault {
+    fn width(&self) -> usize {
+        self.map.width()
+    }
+
+    fn height(&self) -> usize {
+        self.map.height()
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        self.map[(x, y)].to_char()
+    }
+
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.map[(x, y)] {
+            Space::Wall => render::GRAY,
+            Space::Empty => render::BLACK,
+            Space::Door(_) => Rgb(160, 90, 40),
+            Space::Key('@') => render::WHITE,
+            Space::Key(_) => Rgb(240, 220, 60),
+        }
+    }
 }
 
-/// Returns the Position that's one step ahead of `position` in `direction`.
-fn one_position_ahead(direction: &Direction, position: &Position) -> Position {
-    match direction {
-        Direction::North => (position.0, position.1 - 1),
-        Direction::East => (position.0 + 1, position.1),
-        Direction::South => (position.0, position.1 + 1),
-        Direction::West => (position.0 - 1, position.1),
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Bitfield(u64);
+
+impl Bitfield {
+    fn contains_all(&self, other: Bitfield) -> bool {
+        (other.0 & !self.0) == 0
     }
 }
 
@@ -127,14 +235,14 @@ struct BfsNode {
 
 /// Returns a KeyDistanceMap of `vault` as seen from `starting_position`.
 fn populate_key_distances_and_doors(starting_position: Position, vault: &Vault) -> KeyDistanceMap {
-    let self_key = match vault.get(starting_position.0, starting_position.1) {
+    let self_key = match vault.map[starting_position] {
         Space::Key(character) => character,
         _ => unreachable!(),
     };
 
-    let mut distances_and_doors_by_key = HashMap::new();
+    let mut distances_and_doors_by_key = FastHashMap::default();
 
-    let mut seen = HashSet::new();
+    let mut seen = FastHashSet::default();
 
     let mut queue = VecDeque::new();
     queue.push_back(BfsNode {
@@ -158,35 +266,30 @@ fn populate_key_distances_and_doors(starting_position: Position, vault: &Vault)
             seen.insert(position);
         }
 
-        match vault.get(position.0, position.1) {
+        match vault.map[position] {
             Space::Door(character) => {
                 // The player will need to open this door in order to continue down this path.
-                doors_needed = Bitfield(doors_needed.0 | char_to_shifted_bit(character));
+                doors_needed = Bitfield(doors_needed.0 | vault.key_bit(character));
             }
             Space::Key(character) => {
                 // Found a key!
                 if character != '@' && character != self_key {
-                    distances_and_doors_by_key.insert(
-                        Key(char_to_shifted_bit(character)),
-                        (distance, doors_needed, keys_picked_up),
-                    );
-                    keys_picked_up = Bitfield(keys_picked_up.0 | char_to_shifted_bit(character));
+                    let key = Key {
+                        bit: vault.key_bit(character),
+                        name: character,
+                    };
+                    distances_and_doors_by_key
+                        .insert(key, (distance, doors_needed, keys_picked_up));
+                    keys_picked_up = Bitfield(keys_picked_up.0 | key.bit);
                 }
             }
             Space::Wall => continue,
             Space::Empty => {}
         };
 
-        for direction in [
-            Direction::North,
-            Direction::East,
-            Direction::South,
-            Direction::West,
-        ]
-        .iter()
-        {
+        for neighbor in Point::from(position).neighbors4_within(vault.width(), vault.height()) {
             queue.push_back(BfsNode {
-                position: one_position_ahead(direction, &position),
+                position: neighbor.to_usize(),
                 distance: distance + 1,
                 doors_needed,
                 keys_picked_up,
@@ -201,203 +304,801 @@ struct SearchNode {
     distance: u32,
     current_positions: Vec<Key>,
     keys_acquired: Bitfield,
-    keys_left: Bitfield,
+    // An index into the search's list of route steps, pointing at the step that got us here.
+    route_step: Option<usize>,
 }
 
-/// Returns the smallest distance that is necessary to travel while acquiring all of the keys in `keys_to_find`.
-fn find_shortest_path(
-    keys_to_find: Bitfield,
-    key_distances_per_vault: &[HashMap<Key, KeyDistanceMap>],
-) -> u32 {
-    let mut shortest_path = u32::MAX;
-    let mut queue = VecDeque::new();
-    let mut smallest_distance_for_path = HashMap::new();
+/// One leg of a Route: a robot walking from wherever it was standing over to a key.
+#[derive(Debug, Clone)]
+pub struct RouteStep {
+    /// The index of the robot (and vault) that moved.
+    pub robot: usize,
+    /// The key that the robot walked to.
+    pub key: char,
+    /// The total distance traveled by all robots once this step is complete.
+    pub distance: u32,
+    /// Every key that's been picked up once this step is complete, including ones grabbed along the way.
+    keys_acquired: Bitfield,
+}
 
-    let mut current_positions = Vec::new();
-    for _ in 0..key_distances_per_vault.len() {
-        current_positions.push(STARTING_KEY);
-    }
+/// The order in which keys are collected along a shortest path through a vault.
+#[derive(Debug)]
+pub struct Route {
+    pub distance: u32,
+    pub steps: Vec<RouteStep>,
+}
 
-    queue.push_back(SearchNode {
-        distance: 0,
-        current_positions,
-        keys_acquired: Bitfield(0),
-        keys_left: keys_to_find,
-    });
+/// Returns the shortest Route that acquires all of the keys in `keys_to_find`.
+///
+/// This is Dijkstra's algorithm over (robot positions, keys acquired) states, where each edge
+/// is a robot walking over to a key that it can reach with the keys acquired so far.
+/// Every node at the current smallest distance is expanded at once, in parallel.
+fn find_shortest_route(
+    keys_to_find: Bitfield,
+    key_distances_per_robot: &[FastHashMap<Key, KeyDistanceMap>],
+) -> Route {
+    let mut queue = BinaryHeap::new();
+    let mut smallest_distance_for_state = FastHashMap::default();
 
-    while !queue.is_empty() {
-        let SearchNode {
-            distance,
-            current_positions,
-            keys_acquired,
-            keys_left,
-        } = queue.pop_front().expect("queue is non-empty");
+    // Every step taken by every node we've queued, along with the index of the step that preceded it.
+    let mut route_steps: Vec<(Option<usize>, RouteStep)> = Vec::new();
 
-        if distance >= shortest_path {
-            // Bail, this path is known-non-optimal.
-            continue;
+    let starting_positions = vec![STARTING_KEY; key_distances_per_robot.len()];
+    smallest_distance_for_state.insert((starting_positions.clone(), Bitfield(0)), 0);
+    queue.push(MinScored(
+        0,
+        SearchNode {
+            distance: 0,
+            current_positions: starting_positions,
+            keys_acquired: Bitfield(0),
+            route_step: None,
+        },
+    ));
+
+    while let Some(MinScored(distance, node)) = queue.pop() {
+        // Grab every other node that's just as far away as this one.
+        let mut bucket = vec![node];
+        while let Some(&MinScored(next_distance, _)) = queue.peek() {
+            if next_distance != distance {
+                break;
+            }
+            bucket.push(queue.pop().unwrap().1);
         }
 
-        if keys_left.0 == 0 {
-            // We've bottomed out! Hooray!
-            shortest_path = shortest_path.min(distance);
-            continue;
+        if let Some(node) = bucket
+            .iter()
+            .find(|node| node.keys_acquired.contains_all(keys_to_find))
+        {
+            // Nodes come off the queue in order of distance, so this is the shortest path. Hooray!
+            return Route {
+                distance: node.distance,
+                steps: follow_route_steps(&route_steps, node.route_step),
+            };
         }
 
-        for (i, &key) in current_positions.iter().enumerate() {
-            let path_has_been_seen = smallest_distance_for_path.contains_key(&(keys_acquired, key));
-            if path_has_been_seen && smallest_distance_for_path[&(keys_acquired, key)] <= distance {
-                // Bail, this path is known-non-optimal.
-                continue;
-            } else {
-                // Record our best-seen-so-far distance on this path.
-                smallest_distance_for_path.insert((keys_acquired, key), distance);
-            }
+        // Skip any nodes for which we've already found a shorter way to get to their state.
+        bucket.retain(|node| {
+            smallest_distance_for_state[&(node.current_positions.clone(), node.keys_acquired)]
+                == node.distance
+        });
 
-            for (&other_key, (distance_to_other_key, doors_needed, keys_along_the_way)) in
-                &key_distances_per_vault[i][&key]
-            {
-                if distance + distance_to_other_key >= shortest_path {
-                    // Bail, this path is known-non-optimal.
+        let moves_per_node: Vec<_> = bucket
+            .par_iter()
+            .map(|node| (node.route_step, moves_from(node, key_distances_per_robot)))
+            .collect();
+
+        for (route_step, moves) in moves_per_node {
+            for (step, new_positions) in moves {
+                let best_distance = smallest_distance_for_state
+                    .entry((new_positions.clone(), step.keys_acquired))
+                    .or_insert(u32::MAX);
+                if step.distance >= *best_distance {
                     continue;
                 }
+                *best_distance = step.distance;
 
-                if keys_left.0 & other_key.0 == other_key.0
-                    && keys_acquired.contains_all(*doors_needed)
-                {
-                    // We still need this key, and we can open all the doors between us and it, so let's grab it.
-                    let mut new_positions = current_positions.clone();
-                    new_positions[i] = other_key;
-                    queue.push_back(SearchNode {
-                        distance: distance + distance_to_other_key,
+                queue.push(MinScored(
+                    step.distance,
+                    SearchNode {
+                        distance: step.distance,
                         current_positions: new_positions,
-                        keys_acquired: Bitfield(
-                            keys_acquired.0 | keys_along_the_way.0 | other_key.0,
-                        ),
-                        keys_left: Bitfield(
-                            keys_left.0 - (keys_left.0 & keys_along_the_way.0) - other_key.0,
-                        ),
-                    });
-                }
+                        keys_acquired: step.keys_acquired,
+                        route_step: Some(route_steps.len()),
+                    },
+                ));
+                route_steps.push((route_step, step));
             }
         }
     }
 
-    shortest_path
+    panic!("unable to collect every key in the vault")
 }
 
-fn key_distance_maps_for_each_key_in_vault(vault: &Vault) -> HashMap<Key, KeyDistanceMap> {
-    let mut key_distance_maps = HashMap::new();
-    for (&key, &position) in &vault.keys {
-        key_distance_maps.insert(
-            if key == '@' {
-                STARTING_KEY
-            } else {
-                Key(char_to_shifted_bit(key))
-            },
-            populate_key_distances_and_doors(position, &vault),
-        );
+/// Returns each move that can be made from `node` by having one robot walk over to a key
+/// that it still needs, as a (step, new_robot_positions) pair.
+fn moves_from(
+    node: &SearchNode,
+    key_distances_per_robot: &[FastHashMap<Key, KeyDistanceMap>],
+) -> Vec<(RouteStep, Vec<Key>)> {
+    let mut moves = vec![];
+
+    for (i, key) in node.current_positions.iter().enumerate() {
+        for (&other_key, (distance_to_other_key, doors_needed, keys_along_the_way)) in
+            &key_distances_per_robot[i][key]
+        {
+            if node.keys_acquired.contains_all(Bitfield(other_key.bit))
+                || !node.keys_acquired.contains_all(*doors_needed)
+            {
+                // Either we already have this key, or we can't get to it yet.
+                continue;
+            }
+
+            let mut new_positions = node.current_positions.clone();
+            new_positions[i] = other_key;
+
+            moves.push((
+                RouteStep {
+                    robot: i,
+                    key: other_key.name,
+                    distance: node.distance + distance_to_other_key,
+                    keys_acquired: Bitfield(
+                        node.keys_acquired.0 | keys_along_the_way.0 | other_key.bit,
+                    ),
+                },
+                new_positions,
+            ));
+        }
+    }
+
+    moves
+}
+
+/// Follows the parent pointers in `route_steps` from `last_step` back to the start of the search,
+/// and returns the steps in the order they were taken.
+fn follow_route_steps(
+    route_steps: &[(Option<usize>, RouteStep)],
+    last_step: Option<usize>,
+) -> Vec<RouteStep> {
+    let mut steps = vec![];
+    let mut current_step = last_step;
+    while let Some(index) = current_step {
+        let (parent, step) = &route_steps[index];
+        steps.push(step.clone());
+        current_step = *parent;
     }
+    steps.reverse();
+    steps
+}
 
-    key_distance_maps
+fn key_distance_maps_for_each_key_in_vault(vault: &Vault) -> FastHashMap<Key, KeyDistanceMap> {
+    vault
+        .keys
+        .par_iter()
+        .map(|(&name, &position)| {
+            (
+                Key {
+                    bit: vault.key_bit(name),
+                    name,
+                },
+                populate_key_distances_and_doors(position, vault),
+            )
+        })
+        .collect()
 }
 
 fn keys_in_vault(vault: &Vault) -> Bitfield {
-    Bitfield(vault.keys.keys().fold(0, |acc, &key| {
-        if key == '@' {
-            acc
-        } else {
-            acc | char_to_shifted_bit(key)
+    Bitfield(
+        vault
+            .keys
+            .keys()
+            .fold(0, |acc, &name| acc | vault.key_bit(name)),
+    )
+}
+
+/// Returns a (vault_index, entrance) pair for each robot in `vaults`.
+fn robots(vaults: &[Vault]) -> Vec<(usize, Position)> {
+    vaults
+        .iter()
+        .enumerate()
+        .flat_map(|(i, vault)| vault.entrances.iter().map(move |&entrance| (i, entrance)))
+        .collect()
+}
+
+/// "How many steps is the shortest path that collects all of the keys?"
+/// `vault` can contain any number of @s, each of which is a robot that can move independently.
+#[instrument(name = "solve", level = "debug", skip_all)]
+pub fn shortest_path(vault: &str) -> Result<u32, ParseError> {
+    Ok(shortest_route_through_vaults(&[Vault::new(vault.to_string())?]).distance)
+}
+
+/// A Vault as it looks partway through a Route.
+/// Collected keys and the doors they open are drawn as empty space, and the robots are drawn as @s.
+struct VaultSnapshot<'a> {
+    vault: &'a Vault,
+    robot_positions: Vec<Position>,
+    keys_acquired: Bitfield,
+}
+
+impl RenderGrid for VaultSnapshot<'_> {
+    fn width(&self) -> usize {
+        self.vault.width()
+    }
+
+    fn height(&self) -> usize {
+        self.vault.height()
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        if self.robot_positions.contains(&(x, y)) {
+            return '@';
+        }
+
+        match self.vault.map[(x, y)] {
+            Space::Key('@') => '.',
+            Space::Key(c) | Space::Door(c)
+                if self
+                    .keys_acquired
+                    .contains_all(Bitfield(self.vault.key_bit(c))) =>
+            {
+                '.'
+            }
+            _ => self.vault.cell_char(x, y),
+        }
+    }
+
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.cell_char(x, y) {
+            '@' => render::WHITE,
+            '.' => render::BLACK,
+            _ => self.vault.cell_color(x, y),
         }
-    }))
+    }
 }
 
-fn shortest_path_to_get_all_keys(vault_contents: String) -> u32 {
-    let vault = Vault::new(vault_contents);
+/// Draws each of `vaults` as they look once `keys_acquired` have been picked up,
+/// with each vault's robot standing at the corresponding entry in `robot_positions`.
+fn draw_vaults(vaults: &[Vault], robot_positions: &[Position], keys_acquired: Bitfield) -> String {
+    let robots = robots(vaults);
 
-    let key_distance_maps = vec![key_distance_maps_for_each_key_in_vault(&vault)];
-    let keys_to_find = keys_in_vault(&vault);
+    vaults
+        .iter()
+        .enumerate()
+        .map(|(i, vault)| {
+            render::to_text(&VaultSnapshot {
+                vault,
+                robot_positions: robots
+                    .iter()
+                    .zip(robot_positions)
+                    .filter(|((vault_index, _), _)| *vault_index == i)
+                    .map(|(_, &position)| position)
+                    .collect(),
+                keys_acquired,
+            })
+        })
+        .join("\n")
+}
+
+/// Draws `route` as a series of frames, one per step, showing where each robot
+/// is standing and which doors have been unlocked so far.
+fn draw_route(vaults: &[Vault], route: &Route) -> String {
+    let robots = robots(vaults);
+    let mut robot_positions: Vec<Position> = robots.iter().map(|&(_, entrance)| entrance).collect();
+    let mut keys_acquired = Bitfield(0);
+
+    let mut s = String::new();
+    writeln!(&mut s, "Start:").unwrap();
+    s.push_str(&draw_vaults(vaults, &robot_positions, keys_acquired));
+
+    for (i, step) in route.steps.iter().enumerate() {
+        let (vault_index, _) = robots[step.robot];
+        robot_positions[step.robot] = vaults[vault_index].keys[&step.key];
 
-    find_shortest_path(keys_to_find, &key_distance_maps)
+        let unlocked_doors: Vec<String> = vaults
+            .iter()
+            .flat_map(|vault| vault.doors.keys().map(move |&door| (vault, door)))
+            .filter(|&(vault, door)| {
+                let bit = Bitfield(vault.key_bit(door));
+                step.keys_acquired.contains_all(bit) && !keys_acquired.contains_all(bit)
+            })
+            .map(|(_, door)| door.to_uppercase().to_string())
+            .sorted()
+            .collect();
+        keys_acquired = step.keys_acquired;
+
+        writeln!(&mut s).unwrap();
+        write!(
+            &mut s,
+            "Step {}: robot {} picks up key {} (total distance {})",
+            i + 1,
+            step.robot,
+            step.key,
+            step.distance
+        )
+        .unwrap();
+        if !unlocked_doors.is_empty() {
+            write!(&mut s, ", unlocking {}", unlocked_doors.join(", ")).unwrap();
+        }
+        writeln!(&mut s).unwrap();
+
+        s.push_str(&draw_vaults(vaults, &robot_positions, keys_acquired));
+    }
+
+    s
 }
 
-pub fn eighteen_a() -> u32 {
-    let contents = fs::read_to_string("src/inputs/18.txt").unwrap();
-    shortest_path_to_get_all_keys(contents)
+/// Each robot can walk from its entrance to any key in its vault, and from there to any other key.
+fn distance_maps_per_robot(vaults: &[Vault]) -> DistanceMapsPerRobot {
+    let key_distance_maps_per_vault: Vec<_> = vaults
+        .iter()
+        .map(key_distance_maps_for_each_key_in_vault)
+        .collect();
+
+    robots(vaults)
+        .into_par_iter()
+        .map(|(i, entrance)| {
+            let mut distance_maps = key_distance_maps_per_vault[i].clone();
+            distance_maps.insert(
+                STARTING_KEY,
+                populate_key_distances_and_doors(entrance, &vaults[i]),
+            );
+            distance_maps
+        })
+        .collect()
 }
 
-pub fn eighteen_b() -> u32 {
-    let contents = fs::read_to_string("src/inputs/18b.txt").unwrap();
-    let topleft: String = contents
-        .lines()
-        .take(41)
-        .map(|line| line.chars().take(41).collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n");
-    let bottomleft: String = contents
-        .lines()
-        .skip(40)
-        .take(41)
-        .map(|line| line.chars().take(41).collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n");
-    let topright: String = contents
-        .lines()
-        .take(41)
-        .map(|line| line.chars().skip(40).take(41).collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n");
-    let bottomright: String = contents
+/// Saves distance maps between runs, so that working on the search doesn't mean
+/// waiting on the same BFSes every time; see `util::cache_dir()`.
+#[cfg(feature = "serde")]
+mod cache {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Bump this whenever the distance maps change shape or meaning, so that files saved
+    /// by older code get recomputed instead of being trusted.
+    pub const CACHE_VERSION: u32 = 1;
+
+    /// JSON objects can only have strings as keys, so the maps are stored as lists of pairs.
+    type CachedMaps = Vec<Vec<(Key, Vec<(Key, (u32, Bitfield, Bitfield))>)>>;
+
+    /// What a cache file holds.
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub struct CacheFile {
+        pub version: u32,
+        pub maps: CachedMaps,
+    }
+
+    /// Where the distance maps for `vaults` are cached in `dir`. The file is named after a hash of the vaults'
+    /// text, which determines the maps, so editing a vault means a fresh file.
+    pub fn path(dir: &Path, vaults: &[Vault]) -> PathBuf {
+        let text = vaults
+            .iter()
+            .map(|vault| vault.map.display(|space| space.to_char()).to_string())
+            .join("\n");
+        dir.join(format!("18_{:016x}.json", util::fnv1a(text.as_bytes())))
+    }
+
+    /// Reads the distance maps for `vaults` from `dir`, or computes them and saves them there if they aren't cached yet.
+    /// A cache that can't be read or written is no worse than no cache, so it's recomputed or skipped,
+    /// and so is one that was saved with a different CACHE_VERSION.
+    pub fn load_or_compute(dir: &Path, vaults: &[Vault]) -> DistanceMapsPerRobot {
+        let path = path(dir, vaults);
+        let cached = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|file| file.version == CACHE_VERSION)
+            .map(|file| file.maps);
+        if let Some(cached) = cached {
+            tracing::debug!(path = %path.display(), "loaded cached distance maps");
+            return cached
+                .into_iter()
+                .map(|maps| {
+                    maps.into_iter()
+                        .map(|(key, map)| (key, map.into_iter().collect()))
+                        .collect()
+                })
+                .collect();
+        }
+
+        let maps = distance_maps_per_robot(vaults);
+        let cached = CacheFile {
+            version: CACHE_VERSION,
+            maps: maps
+                .iter()
+                .map(|maps| {
+                    maps.iter()
+                        .map(|(&key, map)| (key, map.iter().map(|(&k, &v)| (k, v)).collect()))
+                        .collect()
+                })
+                .collect(),
+        };
+        let saved = fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, serde_json::to_string(&cached).unwrap()));
+        if let Err(e) = saved {
+            tracing::warn!(path = %path.display(), "couldn't cache distance maps: {}", e);
+        }
+
+        maps
+    }
+}
+
+/// Returns the shortest Route that collects every key in `vaults`, one robot per entrance.
+#[instrument(name = "solve", level = "debug", skip_all)]
+fn shortest_route_through_vaults(vaults: &[Vault]) -> Route {
+    let distance_maps_per_robot = match util::cache_dir() {
+        #[cfg(feature = "serde")]
+        Some(dir) => cache::load_or_compute(&dir, vaults),
+        _ => distance_maps_per_robot(vaults),
+    };
+
+    let keys_to_find = Bitfield(
+        vaults
+            .iter()
+            .fold(0, |acc, vault| acc | keys_in_vault(vault).0),
+    );
+
+    find_shortest_route(keys_to_find, &distance_maps_per_robot)
+}
+
+/// "You arrive at the vault only to discover that there is not one vault, but four - each with its own entrance.
+/// On your map, find the area in the middle of the vault that looks like this:
+///
+/// ...
+/// .@.
+/// ...
+///
+/// Update your map to instead use the correct data:
+///
+/// @#@
+/// ###
+/// @#@"
+///
+/// Returns the four resulting vaults, in the order top-left, bottom-left, top-right, bottom-right.
+/// Each vault includes the walls that now separate it from its neighbors.
+/// Returns an error if there's no entrance, or if it isn't surrounded by open space.
+fn split_vault_into_quadrants(contents: &str) -> Result<Vec<String>, ParseError> {
+    let mut grid: Vec<Vec<char>> = contents
         .lines()
-        .skip(40)
-        .take(41)
-        .map(|line| line.chars().skip(40).take(41).collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n");
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
 
-    let distance_maps_per_vault: Vec<_> = [topleft, bottomleft, topright, bottomright]
+    let (x, y) = grid
         .iter()
-        .map(|contents| Vault::new(contents.clone()))
-        .map(|vault| key_distance_maps_for_each_key_in_vault(&vault))
-        .collect();
+        .enumerate()
+        .find_map(|(y, row)| row.iter().position(|&c| c == '@').map(|x| (x, y)))
+        .ok_or_else(|| ParseError::new(1, "", "expected an entrance to split the vault around"))?;
+
+    let is_open = |x: usize, y: usize| grid.get(y).and_then(|row| row.get(x)) == Some(&'.');
+    let surrounded_by_open_space = x > 0
+        && y > 0
+        && (0..3).all(|dy| (0..3).all(|dx| (dx, dy) == (1, 1) || is_open(x + dx - 1, y + dy - 1)));
+    if !surrounded_by_open_space {
+        return Err(ParseError::new(
+            y + 1,
+            "@",
+            "expected open space all around the entrance, so that the vault can be split around it",
+        ));
+    }
+
+    for dy in 0..3 {
+        for dx in 0..3 {
+            grid[y + dy - 1][x + dx - 1] = if dx != 1 && dy != 1 { '@' } else { '#' };
+        }
+    }
+
+    // Rows that are too short to reach the entrance's column make for ragged vaults,
+    // which Vault::new_many() reports.
+    let quadrant = |rows: &[Vec<char>], left: bool| -> String {
+        rows.iter()
+            .map(|row| {
+                if left {
+                    row.iter().take(x + 1).collect::<String>()
+                } else {
+                    row.iter().skip(x).collect::<String>()
+                }
+            })
+            .join("\n")
+    };
+
+    let (top, bottom) = (&grid[..=y], &grid[y..]);
+    Ok(vec![
+        quadrant(top, true),
+        quadrant(bottom, true),
+        quadrant(top, false),
+        quadrant(bottom, false),
+    ])
+}
+
+/// Part A's vault, along with the four vaults that part B splits it into.
+pub struct Vaults {
+    whole: Vec<Vault>,
+    /// Or why the vault couldn't be split, which only stops part B.
+    quadrants: Result<Vec<Vault>, ParseError>,
+}
+
+impl Vaults {
+    /// Panics if the vault couldn't be split into quadrants.
+    fn quadrants(&self) -> &[Vault] {
+        self.quadrants
+            .as_ref()
+            .unwrap_or_else(|e| panic!("can't split the vault for part B: {}", e))
+    }
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vaults;
+    type Error = ParseError;
+    type A = u32;
+    type B = u32;
+
+    const DAY: u32 = 18;
 
-    let keys_to_find = Bitfield(('a'..'{').fold(0, |acc, c| acc | char_to_shifted_bit(c)));
+    fn parse(input: &str) -> Result<Vaults, ParseError> {
+        Ok(Vaults {
+            whole: vec![Vault::new(input.to_string())?],
+            quadrants: split_vault_into_quadrants(input).and_then(Vault::new_many),
+        })
+    }
+
+    fn part_a(vaults: &Vaults) -> u32 {
+        shortest_route_through_vaults(&vaults.whole).distance
+    }
 
-    find_shortest_path(keys_to_find, &distance_maps_per_vault)
+    fn part_b(vaults: &Vaults) -> u32 {
+        shortest_route_through_vaults(vaults.quadrants()).distance
+    }
+}
+
+pub fn eighteen_a() -> u32 {
+    Solution::part_a(&Solution::load())
+}
+
+pub fn eighteen_b() -> u32 {
+    Solution::part_b(&Solution::load())
+}
+
+/// Returns a step-by-step drawing of the shortest route through part A's vault.
+pub fn draw_route_a() -> String {
+    let vaults = Solution::load().whole;
+    draw_route(&vaults, &shortest_route_through_vaults(&vaults))
+}
+
+/// Returns a step-by-step drawing of the shortest route that part B's four robots take through their vaults.
+pub fn draw_route_b() -> String {
+    let vaults = Solution::load();
+    let quadrants = vaults.quadrants();
+    draw_route(quadrants, &shortest_route_through_vaults(quadrants))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     #[test]
     fn test_samples() {
         assert_eq!(
-            shortest_path_to_get_all_keys(
-                fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()
-            ),
-            8
+            shortest_path(&fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()),
+            Ok(8)
         );
         assert_eq!(
-            shortest_path_to_get_all_keys(
-                fs::read_to_string("src/inputs/18_sample_3.txt").unwrap()
-            ),
-            86
+            shortest_path(&fs::read_to_string("src/inputs/18_sample_3.txt").unwrap()),
+            Ok(86)
         );
         assert_eq!(
-            shortest_path_to_get_all_keys(
-                fs::read_to_string("src/inputs/18_sample_2.txt").unwrap()
-            ),
-            136
+            shortest_path(&fs::read_to_string("src/inputs/18_sample_2.txt").unwrap()),
+            Ok(136)
         );
         assert_eq!(
-            shortest_path_to_get_all_keys(
-                fs::read_to_string("src/inputs/18_sample_4.txt").unwrap()
+            shortest_path(&fs::read_to_string("src/inputs/18_sample_4.txt").unwrap()),
+            Ok(81)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            shortest_path("#####\n#@.a#\n#?..#\n#####"),
+            Err(ParseError::new(
+                3,
+                "?",
+                "expected a wall, open space, entrance, key, or door"
+            ))
+        );
+        assert_eq!(shortest_path("#####\n#@.a#\n###").unwrap_err().line, 3);
+
+        // 65 keys: the Latin and Greek alphabets, and the first 14 letters of the Cyrillic one.
+        let keys: String = ('a'..='z').chain('α'..='ω').chain('а'..='н').collect();
+        let wall = "#".repeat(keys.chars().count() + 3);
+        let vault = format!("{}\n#@{}#\n{}", wall, keys, wall);
+        assert_eq!(
+            shortest_path(&vault),
+            Err(ParseError::new(
+                2,
+                "н",
+                "vaults can have at most 64 keys, found 65"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_shortest_path_with_many_robots() {
+        // The part B example, but as a single vault with four entrances.
+        assert_eq!(
+            shortest_path(
+                "#######
+#a.#Cd#
+##@#@##
+#######
+##@#@##
+#cB#Ab#
+#######"
             ),
-            81
+            Ok(8)
+        );
+    }
+
+    #[test]
+    fn test_shortest_path_with_many_keys() {
+        // 28 keys: Greek letters work as keys and doors, too.
+        let vault = "################################
+#βΑ@αabcdefghijklmnopqrstuvwxyz#
+################################";
+        assert_eq!(shortest_path(vault).unwrap(), 33);
+
+        let route = shortest_route_through_vaults(&[Vault::new(vault.to_string()).unwrap()]);
+        assert_eq!(
+            route.steps.iter().map(|step| step.key).collect::<Vec<_>>(),
+            // Walking over to z picks up a through y along the way.
+            vec!['α', 'β', 'z']
+        );
+    }
+
+    #[test]
+    fn test_shortest_route() {
+        let vaults =
+            vec![Vault::new(fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()).unwrap()];
+        let route = shortest_route_through_vaults(&vaults);
+
+        assert_eq!(route.distance, 8);
+        assert_eq!(
+            route
+                .steps
+                .iter()
+                .map(|step| (step.robot, step.key, step.distance))
+                .collect::<Vec<_>>(),
+            vec![(0, 'a', 2), (0, 'b', 8)]
+        );
+    }
+
+    #[test]
+    fn test_draw_route() {
+        let vaults =
+            vec![Vault::new(fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()).unwrap()];
+        let route = shortest_route_through_vaults(&vaults);
+
+        assert_eq!(
+            draw_route(&vaults, &route),
+            "Start:
+#########
+#b.A.@.a#
+#########
+
+Step 1: robot 0 picks up key a (total distance 2), unlocking A
+#########
+#b.....@#
+#########
+
+Step 2: robot 0 picks up key b (total distance 8)
+#########
+#@......#
+#########
+"
         );
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let vault = Vault::new(fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()).unwrap();
+        let json = vault.to_json();
+        assert!(json.contains(r#""keys":{"#));
+        assert!(json.contains(r#""entrances":[[5,1]]"#));
+        assert!(json.contains(r#"{"Door":"a"}"#));
+
+        assert_eq!(Vault::from_json(&json).unwrap(), vault);
+        assert!(Vault::from_json("[]").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("advent_2019_test_cache_{}", std::process::id()));
+        let vaults =
+            vec![Vault::new(fs::read_to_string("src/inputs/18_sample_2.txt").unwrap()).unwrap()];
+
+        let path = cache::path(&dir, &vaults);
+        assert!(!path.exists());
+        let computed = cache::load_or_compute(&dir, &vaults);
+        assert!(path.exists());
+        assert_eq!(cache::load_or_compute(&dir, &vaults), computed);
+        assert_eq!(computed, distance_maps_per_robot(&vaults));
+
+        // A different vault gets its own file.
+        let other =
+            vec![Vault::new(fs::read_to_string("src/inputs/18_sample_1.txt").unwrap()).unwrap()];
+        assert_ne!(cache::path(&dir, &other), path);
+
+        // A file saved with an older CACHE_VERSION is recomputed and replaced, not trusted.
+        let old = cache::CacheFile {
+            version: cache::CACHE_VERSION - 1,
+            maps: vec![],
+        };
+        fs::write(&path, serde_json::to_string(&old).unwrap()).unwrap();
+        assert_eq!(cache::load_or_compute(&dir, &vaults), computed);
+        let saved: cache::CacheFile =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.version, cache::CACHE_VERSION);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_vault_into_quadrants() {
+        let contents = "#######
+#a.#Cd#
+##...##
+##.@.##
+##...##
+#cB#Ab#
+#######
+";
+        assert_eq!(
+            split_vault_into_quadrants(contents).unwrap(),
+            vec![
+                "####\n#a.#\n##@#\n####",
+                "####\n##@#\n#cB#\n####",
+                "####\n#Cd#\n#@##\n####",
+                "####\n#@##\n#Ab#\n####",
+            ]
+        );
+
+        let vaults = Vault::new_many(split_vault_into_quadrants(contents).unwrap()).unwrap();
+        assert_eq!(shortest_route_through_vaults(&vaults).distance, 8);
+    }
+
+    #[test]
+    fn test_split_vault_into_quadrants_errors() {
+        assert_eq!(
+            split_vault_into_quadrants("#####\n#a..#\n#####").unwrap_err(),
+            ParseError::new(1, "", "expected an entrance to split the vault around")
+        );
+
+        let not_open = ParseError::new(
+            2,
+            "@",
+            "expected open space all around the entrance, so that the vault can be split around it",
+        );
+        assert_eq!(
+            split_vault_into_quadrants("#####\n#@..#\n#...#\n#####").unwrap_err(),
+            not_open
+        );
+        assert_eq!(
+            split_vault_into_quadrants("#####\n@...#\n#####").unwrap_err(),
+            not_open
+        );
+        assert_eq!(split_vault_into_quadrants(".@.\n...").unwrap_err().line, 1);
+
+        // Part A doesn't need to split the vault, so it still works.
+        let vaults = Solution::parse("#####\n#@.a#\n#####").unwrap();
+        assert!(vaults.quadrants.is_err());
+        assert_eq!(Solution::part_a(&vaults), 2);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(eighteen_a(), 5102);
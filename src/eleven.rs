@@ -1,33 +1,19 @@
-use crate::computer;
-use crate::computer::{Computer, HaltReason};
+use crate::computer::{Computer, Memory};
+use crate::programs;
+use crate::util::Direction;
 use itertools::Itertools;
 use std::collections::HashMap;
 use std::fmt::Write;
 
-type Position = (i32, i32);
+pub type Position = (i32, i32);
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Color {
+pub enum Color {
     Black,
     White,
 }
 
-#[derive(PartialEq, Clone, Copy, Debug)]
-enum Direction {
-    Up,
-    Left,
-    Down,
-    Right,
-}
-
-static DIRECTION_ORDER: [Direction; 4] = [
-    Direction::Up,
-    Direction::Left,
-    Direction::Down,
-    Direction::Right,
-];
-
-struct Robot {
+pub struct PaintingRobot {
     direction: Direction,
     position: Position,
     computer: Computer,
@@ -39,13 +25,12 @@ struct RobotOutput {
     color: Color,
 }
 
-impl Robot {
-    fn new(filename: &str) -> Self {
-        let memory = computer::load_program(filename);
-        let computer = Computer::new(memory);
+impl PaintingRobot {
+    fn new(program: Memory) -> Self {
+        let computer = Computer::new(program);
 
-        Robot {
-            direction: Direction::Up,
+        PaintingRobot {
+            direction: Direction::North,
             position: (0, 0),
             computer,
         }
@@ -55,23 +40,11 @@ impl Robot {
         self.direction = new_direction;
 
         // "After the robot turns, it should always move forward exactly one panel."
-        match self.direction {
-            Direction::Up => {
-                self.position.1 += 1;
-            }
-            Direction::Right => {
-                self.position.0 += 1;
-            }
-            Direction::Down => {
-                self.position.1 -= 1;
-            }
-            Direction::Left => {
-                self.position.0 -= 1;
-            }
-        }
+        let (dx, dy) = self.direction.offset();
+        self.position = (self.position.0 + dx, self.position.1 + dy);
     }
 
-    pub fn run(&mut self, current_panel_color: Color) -> Option<RobotOutput> {
+    fn run(&mut self, current_panel_color: Color) -> Option<RobotOutput> {
         // "The program uses input instructions to access the robot's camera:
         // provide 0 if the robot is over a black panel or 1 if the robot is over a white panel."
         self.computer.push_input(match current_panel_color {
@@ -80,21 +53,15 @@ impl Robot {
         });
 
         // "Then, the program will output two values..."
-        let halt_reason = self.computer.run(HaltReason::Output);
-        if halt_reason == HaltReason::Exit {
-            // "The robot will continue running for a while like this and halt when it is finished drawing."
-            return None;
-        }
-
-        // Run the computer one more step to allow the program to emit its second output of the pair.
-        self.computer.run(HaltReason::Output);
-
+        // "The robot will continue running for a while like this and halt when it is finished drawing."
         // "First, it will output a value indicating the color to paint the
         // panel the robot is over: 0 means to paint the panel black, and 1 means to paint the panel white."
-        let color_instruction = self.computer.pop_output().unwrap();
-
         // "Second, it will output a value indicating the direction the robot should turn: 0 means it should turn left 90 degrees, and 1 means it should turn right 90 degrees."
-        let turn_instruction = self.computer.pop_output().unwrap();
+        let (color_instruction, turn_instruction) = match self.computer.run_until_n_outputs(2)[..] {
+            [color_instruction, turn_instruction] => (color_instruction, turn_instruction),
+            [] => return None,
+            _ => panic!("the robot halted without saying which way to turn"),
+        };
 
         let color = match color_instruction {
             0 => Color::Black,
@@ -111,10 +78,75 @@ impl Robot {
 
         ret
     }
+
+    /// Runs a robot controlled by `program` until it halts, starting it on a panel of `start_color`.
+    /// Returns the color of every panel it painted, along with its starting panel.
+    pub fn simulate(program: Memory, start_color: Color) -> HashMap<Position, Color> {
+        PaintingRobot::simulate_with_stats(program, start_color).0
+    }
+
+    /// Like `simulate()`, but also returns statistics about the robot's paint job.
+    pub fn simulate_with_stats(
+        program: Memory,
+        start_color: Color,
+    ) -> (HashMap<Position, Color>, PaintStats) {
+        let mut robot = PaintingRobot::new(program);
+
+        let mut panels = HashMap::new();
+        panels.insert((0, 0), start_color);
+        let mut paint_counts = HashMap::new();
+
+        while let Some(RobotOutput { position, color }) =
+            robot.run(*panels.get(&robot.position).unwrap_or(&Color::Black))
+        {
+            panels.insert(position, color);
+            *paint_counts.entry(position).or_insert(0) += 1;
+        }
+
+        (panels, PaintStats::new(paint_counts))
+    }
+}
+
+/// Statistics about the panels a PaintingRobot painted.
+#[derive(Debug, PartialEq)]
+pub struct PaintStats {
+    /// "The number of panels it paints at least once."
+    pub panels_painted: usize,
+    /// The number of times each panel was painted.
+    pub paint_counts: HashMap<Position, usize>,
+    /// The (min_x, min_y) and (max_x, max_y) corners of the area that was painted.
+    pub bounding_box: Option<(Position, Position)>,
+}
+
+impl PaintStats {
+    fn new(paint_counts: HashMap<Position, usize>) -> Self {
+        let x_bounds = paint_counts.keys().map(|&(x, _)| x).minmax().into_option();
+        let y_bounds = paint_counts.keys().map(|&(_, y)| y).minmax().into_option();
+
+        let bounding_box = match (x_bounds, y_bounds) {
+            (Some((min_x, max_x)), Some((min_y, max_y))) => Some(((min_x, min_y), (max_x, max_y))),
+            _ => None,
+        };
+
+        PaintStats {
+            panels_painted: paint_counts.len(),
+            paint_counts,
+            bounding_box,
+        }
+    }
+
+    /// Returns the number of panels that were painted more than once.
+    pub fn num_repainted_panels(&self) -> usize {
+        self.paint_counts
+            .values()
+            .filter(|&&count| count > 1)
+            .count()
+    }
 }
 
 pub fn eleven_a() -> usize {
-    let painted_panels = run_robot_to_completion(Color::Black);
+    let painted_panels =
+        PaintingRobot::simulate(programs::load("hull_painting_robot"), Color::Black);
     painted_panels.len()
 }
 
@@ -123,28 +155,11 @@ pub fn eleven_a() -> usize {
 /// letters. After starting the robot on a single white panel instead, what
 /// registration identifier does it paint on your hull?"
 pub fn eleven_b() -> String {
-    let painted_panels = run_robot_to_completion(Color::White);
+    let painted_panels =
+        PaintingRobot::simulate(programs::load("hull_painting_robot"), Color::White);
     draw_panels(painted_panels)
 }
 
-fn run_robot_to_completion(starting_panel_color: Color) -> HashMap<Position, Color> {
-    let mut robot = Robot::new("src/inputs/11.txt");
-
-    let mut painted_panels = HashMap::new();
-    painted_panels.insert((0, 0), starting_panel_color);
-
-    while let Some(RobotOutput { position, color }) = robot.run(
-        *painted_panels
-            .get(&robot.position)
-            .or(Some(&Color::Black))
-            .unwrap(),
-    ) {
-        painted_panels.insert(position, color);
-    }
-
-    painted_panels
-}
-
 fn draw_panels(painted_panels: HashMap<Position, Color>) -> String {
     let (min_x, max_x) = painted_panels
         .keys()
@@ -161,7 +176,7 @@ fn draw_panels(painted_panels: HashMap<Position, Color>) -> String {
 
     let mut s = String::new();
 
-    for y in (min_y..(max_y + 1)).rev() {
+    for y in min_y..(max_y + 1) {
         for x in min_x..(max_x + 1) {
             if let Some(&Color::White) = painted_panels.get(&(x, y)) {
                 write!(&mut s, "#").unwrap();
@@ -175,27 +190,14 @@ fn draw_panels(painted_panels: HashMap<Position, Color>) -> String {
     s
 }
 
-// Via https://stackoverflow.com/questions/31210357/is-there-a-modulus-not-remainder-function-operation
-fn modulus(a: i32, b: i32) -> i32 {
-    if a > 0 {
-        a % b
-    } else {
-        ((a % b) + b) % b
-    }
-}
-
 /// "Second, it will output a value indicating the direction the robot should
 /// turn: 0 means it should turn left 90 degrees, and 1 means it should turn right 90 degrees."
 fn rotate(direction: Direction, robot_output: i64) -> Direction {
-    assert!(robot_output == 0 || robot_output == 1);
-
-    let index = DIRECTION_ORDER
-        .iter()
-        .position(|&x| x == direction)
-        .unwrap();
-    let index_delta = if robot_output == 0 { 1 } else { -1 };
-
-    DIRECTION_ORDER[modulus(index as i32 + index_delta, 4) as usize]
+    match robot_output {
+        0 => direction.turn_left(),
+        1 => direction.turn_right(),
+        _ => panic!("unknown turn instruction {}", robot_output),
+    }
 }
 
 #[cfg(test)]
@@ -204,15 +206,35 @@ mod tests {
 
     #[test]
     fn test_rotate() {
-        assert_eq!(rotate(Direction::Up, 0), Direction::Left);
-        assert_eq!(rotate(Direction::Left, 0), Direction::Down);
-        assert_eq!(rotate(Direction::Down, 0), Direction::Right);
-        assert_eq!(rotate(Direction::Right, 0), Direction::Up);
-
-        assert_eq!(rotate(Direction::Up, 1), Direction::Right);
-        assert_eq!(rotate(Direction::Right, 1), Direction::Down);
-        assert_eq!(rotate(Direction::Down, 1), Direction::Left);
-        assert_eq!(rotate(Direction::Left, 1), Direction::Up);
+        assert_eq!(rotate(Direction::North, 0), Direction::West);
+        assert_eq!(rotate(Direction::West, 0), Direction::South);
+        assert_eq!(rotate(Direction::South, 0), Direction::East);
+        assert_eq!(rotate(Direction::East, 0), Direction::North);
+
+        assert_eq!(rotate(Direction::North, 1), Direction::East);
+        assert_eq!(rotate(Direction::East, 1), Direction::South);
+        assert_eq!(rotate(Direction::South, 1), Direction::West);
+        assert_eq!(rotate(Direction::West, 1), Direction::North);
+    }
+
+    #[test]
+    fn test_simulate_with_stats() {
+        // Paints its first panel white and turns right, then paints its second panel black and turns right,
+        // then halts.
+        let program = vec![3, 100, 104, 1, 104, 1, 3, 100, 104, 0, 104, 1, 99];
+        let (panels, stats) = PaintingRobot::simulate_with_stats(program, Color::Black);
+
+        assert_eq!(panels.len(), 2);
+        assert_eq!(panels[&(0, 0)], Color::White);
+        assert_eq!(panels[&(1, 0)], Color::Black);
+
+        assert_eq!(stats.panels_painted, 2);
+        assert_eq!(stats.num_repainted_panels(), 0);
+        assert_eq!(stats.bounding_box, Some(((0, 0), (1, 0))));
+
+        let (_, stats) =
+            PaintingRobot::simulate_with_stats(programs::load("hull_painting_robot"), Color::Black);
+        assert_eq!(stats.panels_painted, 1894);
     }
 
     #[test]
@@ -1,226 +1,220 @@
-use crate::computer;
-use crate::computer::{Computer, HaltReason};
+use crate::computer::{self, Computer, Memory};
+use crate::programs;
+use crate::render::{self, RenderGrid, Rgb};
+use crate::solver::Solver;
+use crate::util::{Direction, FastHashMap, ParseError, Point};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::VecDeque;
 
-static ORIGIN: (i32, i32) = (0, 0);
+static ORIGIN: Position = Point::new(0, 0);
 
-type Position = (i32, i32);
-type ShipMap = HashMap<Position, Space>;
+pub type Position = Point;
+pub type ShipMap = FastHashMap<Position, Space>;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Space {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Space {
     Wall,
     Empty,
     Goal,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Direction {
-    North,
-    South,
-    West,
-    East,
-}
-
 /// A remotely-operated repair droid.
 struct Robot {
     position: Position,
     computer: Computer,
-    direction: Direction,
 }
 
 impl Robot {
-    pub fn new(filename: &str) -> Robot {
-        let memory = computer::load_program(filename);
+    pub fn new(memory: Memory) -> Robot {
         let computer = Computer::new(memory);
 
         Robot {
             position: ORIGIN,
-            direction: Direction::North,
             computer,
         }
     }
 
-    /// Turns the robot 90 degrees to the left.
-    pub fn turn_left(&mut self) {
-        self.direction = match self.direction {
-            Direction::North => Direction::West,
-            Direction::West => Direction::South,
-            Direction::South => Direction::East,
-            Direction::East => Direction::North,
-        };
-    }
-
-    /// Turns the robot 90 degrees to the right.
-    pub fn turn_right(&mut self) {
-        self.direction = match self.direction {
-            Direction::North => Direction::East,
-            Direction::West => Direction::North,
-            Direction::South => Direction::West,
-            Direction::East => Direction::South,
-        };
-    }
-
-    /// Attempts to move the robot forward one step in the direction that it's currently facing.
-    pub fn walk_forward(&mut self) -> i64 {
-        self.computer
-            .push_input(direction_to_input_command(self.direction));
-        self.computer.run(HaltReason::Output);
-        let output = self.computer.pop_output().unwrap();
+    /// Attempts to move the robot one step in `direction`, and returns the droid's status code.
+    pub fn walk(&mut self, direction: Direction) -> i64 {
+        self.computer.push_input(direction.movement_command());
+        let output = self.computer.next_output().unwrap();
 
         if output == 1 || output == 2 {
-            self.position = one_position_ahead(&self.direction, &self.position);
+            self.position = self.position.step(direction);
         }
 
         output
     }
 }
 
-/// Returns the Position that's one step ahead of `position` in `direction`.
-fn one_position_ahead(direction: &Direction, position: &Position) -> Position {
-    match direction {
-        Direction::North => (position.0, position.1 + 1),
-        Direction::East => (position.0 + 1, position.1),
-        Direction::South => (position.0, position.1 - 1),
-        Direction::West => (position.0 - 1, position.1),
-    }
-}
-
-/// "Only four movement commands are understood: north (1), south (2), west (3), and east (4)."
-fn direction_to_input_command(direction: Direction) -> i64 {
-    match direction {
-        Direction::North => 1,
-        Direction::South => 2,
-        Direction::West => 3,
-        Direction::East => 4,
-    }
-}
+/// Explores every space reachable from `robot`'s current position, filling out `map` along the way.
+/// Tries each direction that leads somewhere unexplored, explores everything past it, and then
+/// walks back so that the robot ends up where it started.
+/// Returns Some(Position) if the oxygen system was found, None otherwise.
+fn explore_ship(robot: &mut Robot, map: &mut ShipMap) -> Option<Position> {
+    let mut goal_position = None;
 
-/// Moves `robot` one space forward, fills out `map` with the space that the robot encountered, and returns the space.
-fn navigate_one_space_forward(robot: &mut Robot, map: &mut ShipMap) -> Space {
-    let output = robot.walk_forward();
+    for &direction in Direction::ALL.iter() {
+        let position_ahead = robot.position.step(direction);
+        if map.contains_key(&position_ahead) {
+            continue;
+        }
 
-    let (k, v) = match output {
-        0 => (
-            one_position_ahead(&robot.direction, &robot.position),
-            Space::Wall,
-        ),
-        1 => (robot.position, Space::Empty),
-        2 => (robot.position, Space::Goal),
-        _ => unreachable!(),
-    };
+        let space = match robot.walk(direction) {
+            0 => Space::Wall,
+            1 => Space::Empty,
+            2 => Space::Goal,
+            output => panic!("unexpected status code {}", output),
+        };
+        map.insert(position_ahead, space);
 
-    map.insert(k, v);
+        if space == Space::Wall {
+            continue;
+        }
 
-    v
-}
+        if space == Space::Goal {
+            goal_position = Some(position_ahead);
+        }
 
-/// Explores the ship in `robot`'s program, filling out `map` along the way.
-/// Returns Some(Position) if the oxygen tank was found, None otherwise.
-fn explore_ship(robot: &mut Robot, map: &mut ShipMap) -> Option<Position> {
-    let mut directions_unexplored_from_origin = vec![
-        Direction::North,
-        Direction::East,
-        Direction::South,
-        Direction::West,
-    ];
+        goal_position = explore_ship(robot, map).or(goal_position);
+        robot.walk(direction.reverse());
+    }
 
-    let mut goal_position = None;
+    goal_position
+}
 
-    loop {
-        if robot.position == ORIGIN {
-            if directions_unexplored_from_origin.is_empty() {
-                break;
-            }
+/// A ShipMap that's been framed for drawing, with north pointing up.
+struct ShipMapDrawing<'a> {
+    map: &'a ShipMap,
+    robot_position: Option<Position>,
+    min_x: i32,
+    min_y: i32,
+    width: usize,
+    height: usize,
+}
 
-            directions_unexplored_from_origin.retain(|&direction| direction != robot.direction);
+impl<'a> ShipMapDrawing<'a> {
+    /// Frames `map` for drawing. If `robot_position` is Some, the robot is drawn there as an R.
+    fn new(map: &'a ShipMap, robot_position: Option<Position>) -> Self {
+        let (min_x, max_x) = map
+            .keys()
+            .map(|position| position.x)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_y, max_y) = map
+            .keys()
+            .map(|position| position.y)
+            .minmax()
+            .into_option()
+            .unwrap();
+
+        ShipMapDrawing {
+            map,
+            robot_position,
+            min_x,
+            min_y,
+            width: (max_x - min_x + 1) as usize,
+            height: (max_y - min_y + 1) as usize,
         }
+    }
 
-        let encountered_space = navigate_one_space_forward(robot, map);
+    fn position_at(&self, x: usize, y: usize) -> Position {
+        Point::new(self.min_x + x as i32, self.min_y + y as i32)
+    }
+}
 
-        match encountered_space {
-            Space::Wall => {
-                robot.turn_left();
-            }
-            Space::Empty => {
-                robot.turn_right();
-            }
-            Space::Goal => {
-                goal_position = Some(robot.position);
-            }
-        };
+impl RenderGrid for ShipMapDrawing<'_> {
+    fn width(&self) -> usize {
+        self.width
     }
 
-    goal_position
-}
+    fn height(&self) -> usize {
+        self.height
+    }
 
-#[cfg(not(tarpaulin_include))]
-fn _print_map(map: &ShipMap, robot: &Robot) {
-    let (min_x, max_x) = map.keys().map(|&(x, _)| x).minmax().into_option().unwrap();
-    let (min_y, max_y) = map.keys().map(|&(_, y)| y).minmax().into_option().unwrap();
-
-    for y in (min_y..(max_y + 1)).rev() {
-        for x in min_x..(max_x + 1) {
-            if robot.position == (x, y) {
-                print!("R");
-            } else {
-                match map.get(&(x, y)) {
-                    Some(&Space::Wall) => print!("#"),
-                    Some(&Space::Empty) => print!("."),
-                    Some(&Space::Goal) => print!("$"),
-                    None => print!(" "),
-                }
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        let position = self.position_at(x, y);
+
+        if self.robot_position == Some(position) {
+            'R'
+        } else {
+            match self.map.get(&position) {
+                Some(&Space::Wall) => '#',
+                Some(&Space::Empty) => '.',
+                Some(&Space::Goal) => '$',
+                None => ' ',
             }
         }
-        println!();
     }
-}
 
-/// Fills out `distances` by performing a flood fill.
-fn flood_fill(
-    distances: &mut HashMap<Position, u32>,
-    position: Position,
-    distance: u32,
-    map: &ShipMap,
-) {
-    for direction in [
-        Direction::North,
-        Direction::East,
-        Direction::South,
-        Direction::West,
-    ]
-    .iter()
-    {
-        let position_ahead = one_position_ahead(direction, &position);
-
-        if distances.contains_key(&position_ahead) {
-            continue;
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.cell_char(x, y) {
+            'R' => Rgb(240, 220, 60),
+            '$' => Rgb(80, 160, 255),
+            '#' => render::GRAY,
+            _ => render::BLACK,
         }
+    }
+}
+
+/// Returns a map of {Position -> distance_from_starting_point}, for every Position reachable from `position`.
+/// This is a breadth-first search, so each Position is reached by one of its shortest paths first.
+pub fn flood_fill_from(position: Position, map: &ShipMap) -> FastHashMap<Position, u32> {
+    let mut distances: FastHashMap<Position, u32> = FastHashMap::default();
+    distances.insert(position, 0);
+    let mut queue = VecDeque::from(vec![(position, 0)]);
 
-        match map.get(&position_ahead) {
-            None | Some(Space::Wall) => (),
-            _ => {
-                distances.insert(position_ahead, distance + 1);
+    while let Some((position, distance)) = queue.pop_front() {
+        for position_ahead in position.neighbors4() {
+            if distances.contains_key(&position_ahead) {
+                continue;
+            }
 
-                flood_fill(distances, position_ahead, distance + 1, map);
+            match map.get(&position_ahead) {
+                None | Some(Space::Wall) => (),
+                _ => {
+                    distances.insert(position_ahead, distance + 1);
+                    queue.push_back((position_ahead, distance + 1));
+                }
             }
         }
     }
-}
 
-/// Returns a map of {Position -> distance_from_starting_point}.
-fn flood_fill_from(position: Position, map: &ShipMap) -> HashMap<Position, u32> {
-    let mut distances: HashMap<Position, u32> = HashMap::new();
-    distances.insert(position, 0);
-    flood_fill(&mut distances, position, 0, &map);
     distances
 }
 
+/// A ShipMap as it's written out in JSON.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ShipMapJson {
+    /// Where the droid started out.
+    start: Position,
+    #[serde(with = "crate::util::pairs")]
+    spaces: ShipMap,
+}
+
+/// Returns `map` as JSON, with the droid's starting position and a list of [position, space] pairs.
+#[cfg(feature = "serde")]
+pub fn map_to_json(map: &ShipMap) -> String {
+    serde_json::to_string(&ShipMapJson {
+        start: ORIGIN,
+        spaces: map.clone(),
+    })
+    .unwrap()
+}
+
+#[cfg(feature = "serde")]
+pub fn map_from_json(json: &str) -> serde_json::Result<ShipMap> {
+    serde_json::from_str::<ShipMapJson>(json).map(|map| map.spaces)
+}
+
+/// Explores the entire ship.
 /// Returns a tuple of (filled_out_ship_map, oxygen_tank_position).
-fn fill_out_map() -> (ShipMap, Position) {
-    let mut map: ShipMap = HashMap::new();
-    let mut robot = Robot::new("src/inputs/15.txt");
+pub fn fill_out_map(program: Memory) -> (ShipMap, Position) {
+    let mut map: ShipMap = FastHashMap::default();
+    let mut robot = Robot::new(program);
     map.insert(robot.position, Space::Empty);
 
     let goal_position = explore_ship(&mut robot, &mut map).unwrap();
@@ -228,25 +222,299 @@ fn fill_out_map() -> (ShipMap, Position) {
     (map, goal_position)
 }
 
+/// Everything we know about the ship once it's been fully explored.
+pub struct ShipSurvey {
+    pub map: ShipMap,
+    pub oxygen_system: Position,
+    /// The number of steps it takes to get to each reachable position from the droid's starting point.
+    pub distances_from_origin: FastHashMap<Position, u32>,
+    /// The number of steps it takes to get to each reachable position from the oxygen system.
+    pub distances_from_oxygen_system: FastHashMap<Position, u32>,
+}
+
+impl ShipSurvey {
+    /// The spaces that fill with oxygen during each minute, starting from the oxygen system:
+    /// element 0 is just the oxygen system itself, element 1 is the spaces next to it, and so on.
+    /// Each minute's spaces are in reading order.
+    pub fn oxygen_timeline(&self) -> Vec<Vec<Position>> {
+        let mut timeline = vec![vec![]; self.minutes_to_fill() as usize + 1];
+        for (&position, &distance) in &self.distances_from_oxygen_system {
+            timeline[distance as usize].push(position);
+        }
+
+        for positions in &mut timeline {
+            positions.sort_by_key(|position| (position.y, position.x));
+        }
+        timeline
+    }
+
+    /// The number of spaces that are full of oxygen once `minutes` minutes have passed.
+    pub fn num_filled_after(&self, minutes: u32) -> usize {
+        self.distances_from_oxygen_system
+            .values()
+            .filter(|&&distance| distance <= minutes)
+            .count()
+    }
+
+    /// The number of minutes it takes for oxygen to reach every space.
+    pub fn minutes_to_fill(&self) -> u32 {
+        *self.distances_from_oxygen_system.values().max().unwrap()
+    }
+}
+
+/// Explores the entire ship, and measures the distance to every space from both the origin and the oxygen system.
+pub fn survey_ship(program: Memory) -> ShipSurvey {
+    let (map, oxygen_system) = fill_out_map(program);
+    let distances_from_origin = flood_fill_from(ORIGIN, &map);
+    let distances_from_oxygen_system = flood_fill_from(oxygen_system, &map);
+
+    ShipSurvey {
+        map,
+        oxygen_system,
+        distances_from_origin,
+        distances_from_oxygen_system,
+    }
+}
+
 /// "What is the fewest number of movement commands required to move the repair
 /// droid from its starting position to the location of the oxygen system?"
 pub fn fifteen_a() -> u32 {
-    let (map, goal_position) = fill_out_map();
-    let distances = flood_fill_from(ORIGIN, &map);
-    distances[&goal_position]
+    Solution::part_a(&Solution::load())
+}
+
+/// Returns a drawing of the fully-explored ship, with the oxygen system drawn as a $.
+pub fn draw_map() -> String {
+    let (map, _) = fill_out_map(programs::load("repair_droid"));
+    render::to_text(&ShipMapDrawing::new(&map, None))
 }
 
 /// "How many minutes will it take to fill with oxygen?"
 pub fn fifteen_b() -> u32 {
-    let (map, goal_position) = fill_out_map();
-    let distances = flood_fill_from(goal_position, &map);
-    *distances.values().max().unwrap()
+    Solution::part_b(&Solution::load())
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    /// Both parts need the whole ship explored, so that happens here, once.
+    type Parsed = ShipSurvey;
+    type Error = ParseError;
+    type A = u32;
+    type B = u32;
+
+    const DAY: u32 = 15;
+
+    fn parse(input: &str) -> Result<ShipSurvey, ParseError> {
+        Ok(survey_ship(computer::try_parse_program(input)?))
+    }
+
+    fn part_a(survey: &ShipSurvey) -> u32 {
+        survey.distances_from_origin[&survey.oxygen_system]
+    }
+
+    fn part_b(survey: &ShipSurvey) -> u32 {
+        survey.minutes_to_fill()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let mut map = ShipMap::default();
+        map.insert(Point::new(0, 0), Space::Empty);
+        map.insert(Point::new(1, 0), Space::Goal);
+        map.insert(Point::new(0, -1), Space::Wall);
+
+        let json = map_to_json(&map);
+        assert!(json.starts_with(r#"{"start":{"x":0,"y":0},"spaces":["#));
+        assert!(json.contains(r#"[{"x":1,"y":0},"Goal"]"#));
+
+        assert_eq!(map_from_json(&json).unwrap(), map);
+        assert!(map_from_json(r#"{"start":{"x":0,"y":0}}"#).is_err());
+    }
+
+    #[test]
+    fn test_ship_map_drawing() {
+        let mut map = ShipMap::default();
+        map.insert(Point::new(0, 0), Space::Empty);
+        map.insert(Point::new(1, 0), Space::Goal);
+        map.insert(Point::new(0, -1), Space::Wall);
+        map.insert(Point::new(-1, 1), Space::Wall);
+
+        assert_eq!(
+            render::to_text(&ShipMapDrawing::new(&map, None)),
+            " # \n .$\n#  \n"
+        );
+        assert_eq!(
+            render::to_text(&ShipMapDrawing::new(&map, Some(ORIGIN))),
+            " # \n R$\n#  \n"
+        );
+    }
+
+    #[test]
+    fn test_golden_trace() {
+        let memory = programs::load("repair_droid");
+        let mut robot = Robot::new(memory.clone());
+        robot.computer.record_io();
+
+        let mut map: ShipMap = FastHashMap::default();
+        map.insert(robot.position, Space::Empty);
+        explore_ship(&mut robot, &mut map);
+
+        let golden = computer::load_trace("src/inputs/15_trace.txt");
+        assert_eq!(robot.computer.trace(), Some(&golden));
+        assert_eq!(computer::replay(memory, &golden), Ok(()));
+    }
+
+    #[test]
+    fn test_fill_out_map() {
+        let (map, goal_position) = fill_out_map(programs::load("repair_droid"));
+
+        assert_eq!(map[&goal_position], Space::Goal);
+
+        // Every open space we know about has all four of its neighbors explored.
+        for (position, space) in &map {
+            if *space != Space::Wall {
+                assert!(position
+                    .neighbors4()
+                    .all(|neighbor| map.contains_key(&neighbor)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_flood_fill_from() {
+        let mut map = ShipMap::default();
+        map.insert(Point::new(0, 0), Space::Empty);
+        map.insert(Point::new(1, 0), Space::Empty);
+        map.insert(Point::new(2, 0), Space::Goal);
+        map.insert(Point::new(0, 1), Space::Wall);
+        map.insert(Point::new(0, -1), Space::Empty);
+
+        let distances = flood_fill_from(ORIGIN, &map);
+        assert_eq!(distances.len(), 4);
+        assert_eq!(distances[&Point::new(2, 0)], 2);
+        assert_eq!(distances[&Point::new(0, -1)], 1);
+        assert!(!distances.contains_key(&Point::new(0, 1)));
+    }
+
+    #[test]
+    fn test_flood_fill_open_room() {
+        // A 2x2 room has a loop in it, so there are two ways to get to the far corner.
+        let mut map = ShipMap::default();
+        for &(x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+            map.insert(Point::new(x, y), Space::Empty);
+        }
+
+        let distances = flood_fill_from(ORIGIN, &map);
+        assert_eq!(distances[&Point::new(1, 0)], 1);
+        assert_eq!(distances[&Point::new(0, 1)], 1);
+        assert_eq!(distances[&Point::new(1, 1)], 2);
+    }
+
+    #[test]
+    fn test_survey_ship() {
+        let survey = survey_ship(programs::load("repair_droid"));
+        assert_eq!(survey.distances_from_origin[&ORIGIN], 0);
+        assert_eq!(
+            survey.distances_from_oxygen_system[&survey.oxygen_system],
+            0
+        );
+        assert_eq!(
+            survey.distances_from_origin.len(),
+            survey.distances_from_oxygen_system.len()
+        );
+    }
+
+    #[test]
+    fn test_oxygen_timeline() {
+        // "In the example above, suppose you've used the droid to explore the area fully and have the following map"
+        let mut map = ShipMap::default();
+        for (y, line) in [" ##", "#..##", "#.#..#", "#.O.#", " ###"]
+            .iter()
+            .enumerate()
+        {
+            for (x, c) in line.chars().enumerate() {
+                let space = match c {
+                    '#' => Space::Wall,
+                    '.' => Space::Empty,
+                    'O' => Space::Goal,
+                    _ => continue,
+                };
+                map.insert(Point::new(x as i32, y as i32), space);
+            }
+        }
+
+        let oxygen_system = Point::new(2, 3);
+        let survey = ShipSurvey {
+            distances_from_origin: flood_fill_from(ORIGIN, &map),
+            distances_from_oxygen_system: flood_fill_from(oxygen_system, &map),
+            map,
+            oxygen_system,
+        };
+
+        // "So, in this example, all locations contain oxygen after 4 minutes."
+        assert_eq!(survey.minutes_to_fill(), 4);
+        assert_eq!(
+            survey.oxygen_timeline(),
+            vec![
+                vec![Point::new(2, 3)],
+                vec![Point::new(1, 3), Point::new(3, 3)],
+                vec![Point::new(1, 2), Point::new(3, 2)],
+                vec![Point::new(1, 1), Point::new(4, 2)],
+                vec![Point::new(2, 1)],
+            ]
+        );
+        assert_eq!(survey.num_filled_after(0), 1);
+        assert_eq!(survey.num_filled_after(1), 3);
+        assert_eq!(survey.num_filled_after(100), 8);
+    }
+
+    #[test]
+    fn test_oxygen_timeline_with_loop() {
+        // A ring of open spaces around a wall, with the oxygen system in a corner:
+        // oxygen spreads both ways around the ring and meets in the opposite corner.
+        let mut map = ShipMap::default();
+        for y in 0..3 {
+            for x in 0..3 {
+                let space = if (x, y) == (1, 1) {
+                    Space::Wall
+                } else {
+                    Space::Empty
+                };
+                map.insert(Point::new(x, y), space);
+            }
+        }
+
+        let oxygen_system = Point::new(0, 0);
+        map.insert(oxygen_system, Space::Goal);
+        let survey = ShipSurvey {
+            distances_from_origin: flood_fill_from(ORIGIN, &map),
+            distances_from_oxygen_system: flood_fill_from(oxygen_system, &map),
+            map,
+            oxygen_system,
+        };
+
+        assert_eq!(survey.minutes_to_fill(), 4);
+        assert_eq!(
+            survey.oxygen_timeline(),
+            vec![
+                vec![Point::new(0, 0)],
+                vec![Point::new(1, 0), Point::new(0, 1)],
+                vec![Point::new(2, 0), Point::new(0, 2)],
+                vec![Point::new(2, 1), Point::new(1, 2)],
+                vec![Point::new(2, 2)],
+            ]
+        );
+        assert_eq!(survey.num_filled_after(1), 3);
+        assert_eq!(survey.num_filled_after(3), 7);
+        assert_eq!(survey.num_filled_after(4), 8);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(fifteen_a(), 282);
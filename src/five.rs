@@ -1,8 +1,8 @@
-use crate::computer;
 use crate::computer::{Computer, HaltReason};
+use crate::programs;
 
 pub fn five_a() -> i64 {
-    let memory = computer::load_program("src/inputs/5.txt");
+    let memory = programs::load("diagnostic");
     let mut computer = Computer::new(memory);
     computer.push_input(1);
     computer.run(HaltReason::Exit);
@@ -17,7 +17,7 @@ pub fn five_a() -> i64 {
 }
 
 pub fn five_b() -> i64 {
-    let memory = computer::load_program("src/inputs/5.txt");
+    let memory = programs::load("diagnostic");
     let mut computer = Computer::new(memory);
     computer.push_input(5);
     computer.run(HaltReason::Exit);
@@ -1,45 +1,194 @@
 use std::cmp::Ordering;
-
-type Password = Vec<u32>;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 
 const LOWER_BOUND: u32 = 272091;
 const UPPER_BOUND: u32 = 815432;
-const PASSWORD_LENGTH: usize = 6;
 
-fn write_number_to_buffer(mut number: u32, buffer: &mut Password) {
-    let mut digit = 0;
+/// Runs of more than this many matching digits are all treated alike.
+const MAX_TRACKED_RUN: usize = 3;
+
+/// A predicate over a password's digits, most significant digit first.
+pub type Rule = Box<dyn Fn(&[u32]) -> bool>;
+
+/// Searches a range of numbers for passwords whose digits satisfy every one of a set of rules.
+pub struct PasswordSearch {
+    range: RangeInclusive<u32>,
+    rules: Vec<Rule>,
+}
+
+impl PasswordSearch {
+    pub fn new(range: RangeInclusive<u32>) -> Self {
+        PasswordSearch {
+            range,
+            rules: vec![],
+        }
+    }
+
+    /// Adds a rule that every matching password must satisfy.
+    pub fn rule(mut self, rule: impl Fn(&[u32]) -> bool + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
 
-    while number != 0 {
-        buffer[PASSWORD_LENGTH - 1 - digit] = number % 10;
+    /// Returns an iterator over the passwords in the search's range that satisfy all of its rules.
+    pub fn passwords(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut buffer = Vec::with_capacity(10);
 
+        self.range.clone().filter(move |&password| {
+            write_number_to_buffer(password as u64, &mut buffer);
+            self.rules.iter().all(|rule| rule(&buffer))
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.passwords().count()
+    }
+}
+
+/// Overwrites `buffer` with the digits of `number`, most significant digit first.
+fn write_number_to_buffer(mut number: u64, buffer: &mut Vec<u32>) {
+    buffer.clear();
+
+    loop {
+        buffer.push((number % 10) as u32);
         number /= 10;
-        digit += 1;
+
+        if number == 0 {
+            break;
+        }
     }
+
+    buffer.reverse();
 }
 
 pub fn four_a() -> u32 {
-    let mut buffer = vec![0; PASSWORD_LENGTH];
-
-    (LOWER_BOUND..UPPER_BOUND + 1)
-        .filter(|&password| {
-            write_number_to_buffer(password, &mut buffer);
-            digits_are_non_decreasing(&buffer) && has_two_same_adjacent_digits(&buffer)
-        })
-        .count() as u32
+    count_passwords(LOWER_BOUND as u64..=UPPER_BOUND as u64, false) as u32
 }
 
 pub fn four_b() -> u32 {
-    let mut buffer = vec![0; PASSWORD_LENGTH];
+    count_passwords(LOWER_BOUND as u64..=UPPER_BOUND as u64, true) as u32
+}
 
-    (LOWER_BOUND..UPPER_BOUND + 1)
-        .filter(|&password| {
-            write_number_to_buffer(password, &mut buffer);
-            digits_are_non_decreasing(&buffer) && has_two_same_adjacent_digits_strict(&buffer)
-        })
-        .count() as u32
+/// Counts the passwords in `range` whose digits never decrease and which have two same adjacent digits
+/// (which mustn't be part of a larger group of matching digits if `strict_pairs` is true).
+///
+/// Produces the same answers as a `PasswordSearch` with the equivalent rules, but counts the passwords
+/// digit by digit rather than checking each number in the range, so it's fast even for huge ranges.
+pub fn count_passwords(range: RangeInclusive<u64>, strict_pairs: bool) -> u64 {
+    let (start, end) = range.into_inner();
+    if start > end {
+        return 0;
+    }
+
+    let below_start = match start {
+        0 => 0,
+        _ => count_passwords_up_to(start - 1, strict_pairs),
+    };
+
+    count_passwords_up_to(end, strict_pairs) - below_start
+}
+
+/// Counts the valid passwords in 1..=`upper_bound`.
+fn count_passwords_up_to(upper_bound: u64, strict_pairs: bool) -> u64 {
+    let mut digits = vec![];
+    write_number_to_buffer(upper_bound, &mut digits);
+
+    let mut cache = HashMap::new();
+
+    count_from_position(
+        &digits,
+        strict_pairs,
+        &mut cache,
+        CountState {
+            position: 0,
+            last_digit: 0,
+            run_length: 0,
+            has_pair: false,
+            started: false,
+            tight: true,
+        },
+    )
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct CountState {
+    /// The index of the digit we're about to choose.
+    position: usize,
+    last_digit: u32,
+    /// How many times in a row `last_digit` has appeared, up to MAX_TRACKED_RUN.
+    run_length: usize,
+    has_pair: bool,
+    /// False while we're still choosing leading zeros.
+    started: bool,
+    /// True if every digit so far matches the upper bound's, which limits our choice of the next digit.
+    tight: bool,
+}
+
+fn count_from_position(
+    upper_bound: &[u32],
+    strict_pairs: bool,
+    cache: &mut HashMap<CountState, u64>,
+    state: CountState,
+) -> u64 {
+    let run_is_pair = |run_length| {
+        if strict_pairs {
+            run_length == 2
+        } else {
+            run_length >= 2
+        }
+    };
+
+    if state.position == upper_bound.len() {
+        return (state.started && (state.has_pair || run_is_pair(state.run_length))) as u64;
+    }
+
+    if !state.tight {
+        if let Some(&count) = cache.get(&state) {
+            return count;
+        }
+    }
+
+    let max_digit = if state.tight {
+        upper_bound[state.position]
+    } else {
+        9
+    };
+
+    let mut count = 0;
+    for digit in state.last_digit..=max_digit {
+        let mut next = CountState {
+            position: state.position + 1,
+            tight: state.tight && digit == max_digit,
+            ..state
+        };
+
+        if !state.started {
+            if digit != 0 {
+                next.started = true;
+                next.last_digit = digit;
+                next.run_length = 1;
+            }
+        } else if digit == state.last_digit {
+            next.run_length = (state.run_length + 1).min(MAX_TRACKED_RUN);
+        } else {
+            next.has_pair = state.has_pair || run_is_pair(state.run_length);
+            next.last_digit = digit;
+            next.run_length = 1;
+        }
+
+        count += count_from_position(upper_bound, strict_pairs, cache, next);
+    }
+
+    if !state.tight {
+        cache.insert(state, count);
+    }
+
+    count
 }
 
-fn has_two_same_adjacent_digits(password: &[u32]) -> bool {
+/// "Two adjacent digits are the same (like 22 in 122345)."
+pub fn has_two_same_adjacent_digits(password: &[u32]) -> bool {
     for i in password.iter().zip(password.iter().skip(1)) {
         if i.0 == i.1 {
             return true;
@@ -48,7 +197,8 @@ fn has_two_same_adjacent_digits(password: &[u32]) -> bool {
     false
 }
 
-fn has_two_same_adjacent_digits_strict(password: &[u32]) -> bool {
+/// "The two adjacent matching digits are not part of a larger group of matching digits."
+pub fn has_two_same_adjacent_digits_strict(password: &[u32]) -> bool {
     for i in 0..password.len() - 1 {
         if password[i] == password[i + 1] {
             if i > 0 && password[i - 1] == password[i] {
@@ -64,7 +214,8 @@ fn has_two_same_adjacent_digits_strict(password: &[u32]) -> bool {
     false
 }
 
-fn digits_are_non_decreasing(password: &[u32]) -> bool {
+/// "Going from left to right, the digits never decrease."
+pub fn digits_are_non_decreasing(password: &[u32]) -> bool {
     let mut largest_digit_seen = password[0];
 
     for &digit in password {
@@ -118,6 +269,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_number_to_buffer() {
+        let mut buffer = vec![];
+        write_number_to_buffer(1230, &mut buffer);
+        assert_eq!(buffer, vec![1, 2, 3, 0]);
+        write_number_to_buffer(0, &mut buffer);
+        assert_eq!(buffer, vec![0]);
+    }
+
+    #[test]
+    fn test_password_search() {
+        let search = PasswordSearch::new(100..=133)
+            .rule(digits_are_non_decreasing)
+            .rule(|digits| digits.contains(&3));
+        assert_eq!(search.passwords().collect::<Vec<_>>(), vec![113, 123, 133]);
+        assert_eq!(search.count(), 3);
+
+        assert_eq!(PasswordSearch::new(5..=9).count(), 5);
+    }
+
+    #[test]
+    fn test_count_passwords_matches_brute_force() {
+        for &(start, end) in &[
+            (0, 1000),
+            (111, 111),
+            (123, 122),
+            (5000, 23456),
+            (99990, 100100),
+        ] {
+            for &strict_pairs in &[false, true] {
+                let search = PasswordSearch::new(start..=end)
+                    .rule(digits_are_non_decreasing)
+                    .rule(if strict_pairs {
+                        has_two_same_adjacent_digits_strict
+                    } else {
+                        has_two_same_adjacent_digits
+                    });
+
+                assert_eq!(
+                    count_passwords(start as u64..=end as u64, strict_pairs),
+                    search.count() as u64
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_passwords_wide_range() {
+        // A non-decreasing 10-digit number that starts with 1 only uses the digits 1-9, so it always
+        // repeats one of them. There are C(17, 9) ways to choose its other 9 digits.
+        assert_eq!(count_passwords(1_000_000_000..=1_999_999_999, false), 24310);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(four_a(), 931);
@@ -1,28 +1,34 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::collections::{HashMap, VecDeque};
-use std::fs;
-
-static OUTER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(.*) => (.*)").unwrap());
-static COMPONENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"([0-9]*) ([A-Z]*)").unwrap());
+use crate::solver::Solver;
+use crate::util;
+use crate::util::ParseError;
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt::{self, Write};
+use std::path::Path;
+use tracing::{debug, instrument};
 
 static ONE_TRILLION: u64 = 1_000_000_000_000;
 
+/// A reaction, like "7 A, 1 E => 1 FUEL".
 #[derive(PartialEq, Debug, Clone)]
-struct Recipe {
+pub struct Recipe {
     inputs: Vec<RecipeComponent>,
     output: RecipeComponent,
 }
 
 impl Recipe {
-    pub fn new(recipe: &str) -> Recipe {
-        let captures = OUTER_RE.captures(recipe).unwrap();
-        let inputs = captures[1].split(", ").map(RecipeComponent::new).collect();
-
-        Recipe {
+    /// Parses a recipe like "7 A, 1 E => 1 FUEL". Any amount of whitespace is fine between the parts.
+    pub fn new(recipe: &str) -> Result<Recipe, RecipeError> {
+        let (inputs, output) = recipe.split_once("=>").ok_or(RecipeError::MissingArrow)?;
+        let inputs = inputs
+            .split(',')
+            .map(RecipeComponent::new)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Recipe {
             inputs,
-            output: RecipeComponent::new(&captures[2]),
-        }
+            output: RecipeComponent::new(output)?,
+        })
     }
 }
 
@@ -33,108 +39,362 @@ struct RecipeComponent {
 }
 
 impl RecipeComponent {
-    pub fn new(component: &str) -> RecipeComponent {
-        let captures = COMPONENT_RE.captures(component).unwrap();
+    /// Parses a component like "7 A".
+    pub fn new(component: &str) -> Result<RecipeComponent, RecipeError> {
+        let malformed = || RecipeError::MalformedComponent(component.trim().to_string());
+        let mut words = component.split_whitespace();
+        let (quantity, chemical) = match (words.next(), words.next(), words.next()) {
+            (Some(quantity), Some(chemical), None) => (quantity, chemical),
+            _ => return Err(malformed()),
+        };
+
+        Ok(RecipeComponent {
+            chemical: chemical.to_string(),
+            quantity: quantity
+                .parse()
+                .ok()
+                .filter(|&quantity| quantity > 0)
+                .ok_or_else(malformed)?,
+        })
+    }
+}
+
+/// Everything that can go wrong when parsing a recipe.
+#[derive(Debug, PartialEq)]
+pub enum RecipeError {
+    /// There's no "=>" between the recipe's inputs and its output.
+    MissingArrow,
+    /// A component that isn't a positive quantity followed by a chemical, like "7 A".
+    MalformedComponent(String),
+}
 
-        RecipeComponent {
-            chemical: captures[2].to_string(),
-            quantity: captures[1].parse().unwrap(),
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecipeError::MissingArrow => {
+                write!(f, "there's no => between the inputs and the output")
+            }
+            RecipeError::MalformedComponent(component) => write!(
+                f,
+                "{:?} isn't a quantity followed by a chemical, like \"7 A\"",
+                component
+            ),
         }
     }
 }
 
-fn ore_cost_for_fuel(recipes: &HashMap<String, Recipe>, fuel_quantity: u64) -> u64 {
-    let mut shopping_cart: VecDeque<RecipeComponent> = VecDeque::new();
-    shopping_cart.push_back(RecipeComponent {
-        chemical: "FUEL".to_string(),
-        quantity: fuel_quantity,
-    });
+impl error::Error for RecipeError {}
 
-    let mut chemical_bank: HashMap<String, u64> = HashMap::new();
+/// Returns the amount of ORE required to produce `fuel_quantity` FUEL.
+///
+/// Chemicals are processed in dependency order, so by the time we get to a chemical we know the total amount
+/// of it that's needed by everything downstream, and can run exactly as many reactions as that total requires.
+fn ore_cost_for_fuel(recipes: &HashMap<String, Recipe>, fuel_quantity: u64) -> u64 {
+    let mut required: HashMap<&str, u64> = HashMap::with_capacity(recipes.len() + 1);
+    required.insert("FUEL", fuel_quantity);
 
-    let mut ore_spent = 0;
+    for chemical in chemicals_in_dependency_order(recipes) {
+        let recipe = &recipes[chemical];
+        let required_quantity = required.get(chemical).copied().unwrap_or(0);
 
-    while !shopping_cart.is_empty() {
-        let component = shopping_cart.pop_front().unwrap();
+        let required_num_reactions = required_quantity.div_ceil(recipe.output.quantity);
 
-        if component.chemical == "ORE" {
-            ore_spent += component.quantity;
-            continue;
+        for input in &recipe.inputs {
+            *required.entry(&input.chemical).or_insert(0) +=
+                input.quantity * required_num_reactions;
         }
+    }
 
-        let recipe = &recipes[&component.chemical];
-        let desired_output_quantity = component.quantity;
+    required.get("ORE").copied().unwrap_or(0)
+}
 
-        let bank_entry = chemical_bank.entry(component.chemical).or_insert(0);
-        if *bank_entry >= desired_output_quantity {
-            // We have enough of that chemical lying around already.
-            *bank_entry -= desired_output_quantity;
-        } else {
-            // We don't have enough of that chemical stored, let's make some.
+/// Returns every chemical that FUEL is made from (other than ORE), ordered so that each chemical comes
+/// before all of the chemicals it's made from.
+fn chemicals_in_dependency_order(recipes: &HashMap<String, Recipe>) -> Vec<&str> {
+    fn visit<'a>(
+        chemical: &'a str,
+        recipes: &'a HashMap<String, Recipe>,
+        visited: &mut HashSet<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) {
+        if chemical == "ORE" || !visited.insert(chemical) {
+            return;
+        }
 
-            let missing_amount = desired_output_quantity - *bank_entry;
+        for input in &recipes[chemical].inputs {
+            visit(&input.chemical, recipes, visited, order);
+        }
 
-            let required_num_reactions =
-                (missing_amount as f64 / recipe.output.quantity as f64).ceil() as u64;
+        order.push(chemical);
+    }
 
-            for input in &recipe.inputs {
-                shopping_cart.push_back(RecipeComponent {
-                    chemical: input.chemical.clone(),
-                    quantity: input.quantity * required_num_reactions,
-                });
-            }
+    let mut visited = HashSet::with_capacity(recipes.len());
+    let mut order = Vec::with_capacity(recipes.len());
+    visit("FUEL", recipes, &mut visited, &mut order);
 
-            *bank_entry += recipe.output.quantity * required_num_reactions;
-            *bank_entry -= desired_output_quantity;
+    order.reverse();
+    order
+}
+
+/// Returns a Graphviz DOT graph of the reactions that FUEL is made from, with an edge from each chemical
+/// to each chemical it's an input to, labeled with how much of it one reaction consumes.
+/// Each node notes how much one reaction produces, and ORE and FUEL are highlighted.
+pub fn to_dot(recipes: &HashMap<String, Recipe>) -> String {
+    let mut dot = String::from("digraph reactions {\n");
+    dot.push_str("    \"ORE\" [style=filled, fillcolor=gray];\n");
+
+    for chemical in chemicals_in_dependency_order(recipes) {
+        let recipe = &recipes[chemical];
+        let style = if chemical == "FUEL" {
+            ", style=filled, fillcolor=gold"
+        } else {
+            ""
+        };
+        writeln!(
+            &mut dot,
+            "    \"{}\" [label=\"{} (makes {})\"{}];",
+            chemical, chemical, recipe.output.quantity, style
+        )
+        .unwrap();
+
+        for input in &recipe.inputs {
+            writeln!(
+                &mut dot,
+                "    \"{}\" -> \"{}\" [label={}];",
+                input.chemical, chemical, input.quantity
+            )
+            .unwrap();
         }
     }
 
-    ore_spent
+    dot.push_str("}\n");
+    dot
 }
 
 pub fn fourteen_a() -> u64 {
-    let recipes = load_recipes("src/inputs/14.txt");
-    ore_cost_for_fuel(&recipes, 1)
+    Solution::part_a(&Solution::load())
+}
+
+/// One probe made while searching for the amount of FUEL that a given amount of ORE can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStep {
+    /// The most FUEL known to be affordable when the probe was made.
+    pub lower_bound: u64,
+    /// The most FUEL the search was considering when the probe was made.
+    pub upper_bound: u64,
+    /// The amount of FUEL whose cost was checked.
+    pub fuel: u64,
+    /// The amount of ORE it costs to make `fuel` FUEL.
+    pub ore_cost: u64,
 }
 
 fn num_fuel_producible_with_one_trillion_ore(recipes: &HashMap<String, Recipe>) -> u64 {
-    let mut lower_bound = ONE_TRILLION / ore_cost_for_fuel(&recipes, 1);
-    let mut upper_bound = 10 * lower_bound;
+    search_for_max_fuel(recipes, ONE_TRILLION).0
+}
 
-    while ore_cost_for_fuel(&recipes, upper_bound) < ONE_TRILLION {
-        lower_bound = upper_bound;
-        upper_bound *= 10;
+/// Returns the amount of FUEL that `ore_budget` ORE can produce, along with each step of the search that found it.
+#[instrument(name = "solve", level = "debug", skip_all)]
+fn search_for_max_fuel(
+    recipes: &HashMap<String, Recipe>,
+    ore_budget: u64,
+) -> (u64, Vec<SearchStep>) {
+    let mut trace = vec![];
+    let mut probe = |lower_bound, upper_bound, fuel| {
+        let ore_cost = ore_cost_for_fuel(recipes, fuel);
+        let step = SearchStep {
+            lower_bound,
+            upper_bound,
+            fuel,
+            ore_cost,
+        };
+
+        debug!(
+            lower_bound = step.lower_bound,
+            upper_bound = step.upper_bound,
+            fuel = step.fuel,
+            ore_cost = step.ore_cost,
+            "probed fuel cost"
+        );
+        trace.push(step);
+        ore_cost
+    };
+
+    // Making n FUEL never costs more than n times what it costs to make 1 FUEL, since leftovers can only help.
+    let cost_of_one = probe(0, ore_budget, 1);
+    let fuel = ore_budget / cost_of_one;
+    if fuel == 0 {
+        return (0, trace);
     }
 
-    loop {
-        let midpoint = (lower_bound + upper_bound) / 2;
-        let cost = ore_cost_for_fuel(&recipes, midpoint);
-
-        if cost <= ONE_TRILLION && ore_cost_for_fuel(&recipes, midpoint + 1) > ONE_TRILLION {
-            return midpoint;
+    // The search keeps track of the most FUEL known to be affordable and the least FUEL known not to be,
+    // as (fuel, ore_cost) pairs, and stops once they're next to each other.
+    let mut previous = (0, 0);
+    let mut lower = (1, cost_of_one);
+    if fuel > 1 {
+        previous = lower;
+        lower = (fuel, probe(fuel, 2 * fuel, fuel));
+    }
+    let mut upper: Option<(u64, u64)> = None;
+    // Set once a guess made before there's an upper bound turns out to be affordable. The cost line can
+    // be flat for a while (when one reaction makes enough for several FUEL), so after that the search
+    // stops trusting it and doubles until it finds an amount of FUEL it can't afford.
+    let mut doubling = false;
+
+    // Making n FUEL costs about a + b*n ORE, so rather than bisecting, each guess is where the line through
+    // the nearest known costs reaches the budget. A guess is usually right or off by one, so its neighbor
+    // on the other side of the budget gets checked too.
+    let fuel = loop {
+        if let Some((fuel, _)) = upper {
+            if fuel == lower.0 + 1 {
+                break lower.0;
+            }
         }
 
-        if cost < ONE_TRILLION {
-            lower_bound = midpoint;
+        // Until there's an upper bound, guesses are at most twice the most FUEL known to be affordable.
+        let limit = upper.map_or(2 * lower.0, |(fuel, _)| fuel - 1);
+        let guess = match upper {
+            None if doubling => limit,
+            None => extrapolate(previous, lower, ore_budget).saturating_add(1),
+            Some(upper) => extrapolate(lower, upper, ore_budget),
+        }
+        .clamp(lower.0 + 1, limit);
+
+        let cost = probe(lower.0, limit, guess);
+        if cost <= ore_budget {
+            previous = lower;
+            lower = (guess, cost);
+            doubling = upper.is_none();
+            if guess < limit {
+                let next_cost = probe(lower.0, limit, guess + 1);
+                if next_cost <= ore_budget {
+                    previous = lower;
+                    lower = (guess + 1, next_cost);
+                } else {
+                    upper = Some((guess + 1, next_cost));
+                }
+            }
         } else {
-            upper_bound = midpoint;
+            upper = Some((guess, cost));
+            if guess - 1 > lower.0 {
+                let previous_cost = probe(lower.0, limit, guess - 1);
+                if previous_cost <= ore_budget {
+                    previous = lower;
+                    lower = (guess - 1, previous_cost);
+                } else {
+                    upper = Some((guess - 1, previous_cost));
+                }
+            }
         }
-    }
+    };
+
+    (fuel, trace)
+}
+
+/// Returns the amount of FUEL at which the line through the (fuel, ore_cost) points `a` and `b` costs `ore_budget`.
+fn extrapolate(a: (u64, u64), b: (u64, u64), ore_budget: u64) -> u64 {
+    let ore_per_fuel = (b.1 - a.1) as f64 / (b.0 - a.0) as f64;
+    let fuel = b.0 as f64 + (ore_budget as f64 - b.1 as f64) / ore_per_fuel;
+    fuel.max(0.0) as u64
 }
 
 /// "Given 1 trillion ORE, what is the maximum amount of FUEL you can produce?"
 pub fn fourteen_b() -> u64 {
-    let recipes = load_recipes("src/inputs/14.txt");
-    num_fuel_producible_with_one_trillion_ore(&recipes)
+    Solution::part_b(&Solution::load())
 }
 
-fn load_recipes(filename: &str) -> HashMap<String, Recipe> {
-    let contents = fs::read_to_string(filename).unwrap();
-    contents
+/// Like `fourteen_b()`, but also returns each step of the search for the answer.
+pub fn fourteen_b_with_trace() -> (u64, Vec<SearchStep>) {
+    search_for_max_fuel(
+        &load_recipes(util::input(Solution::DAY as u8)),
+        ONE_TRILLION,
+    )
+}
+
+/// Returns the amount of ORE needed to make `fuel` FUEL, given recipes like "7 A, 1 E => 1 FUEL", one per line.
+pub fn ore_for_fuel(recipes_text: &str, fuel: u64) -> Result<u64, ParseError> {
+    Ok(ore_cost_for_fuel(&parse_recipes(recipes_text)?, fuel))
+}
+
+/// Returns the maximum amount of FUEL that can be made with `ore_budget` ORE, given recipes like
+/// "7 A, 1 E => 1 FUEL", one per line.
+pub fn max_fuel_for_ore(recipes_text: &str, ore_budget: u64) -> Result<u64, ParseError> {
+    Ok(search_for_max_fuel(&parse_recipes(recipes_text)?, ore_budget).0)
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = HashMap<String, Recipe>;
+    type Error = ParseError;
+    type A = u64;
+    type B = u64;
+
+    const DAY: u32 = 14;
+
+    fn parse(input: &str) -> Result<Self::Parsed, ParseError> {
+        parse_recipes(input)
+    }
+
+    /// "What is the minimum amount of ORE required to produce exactly 1 FUEL?"
+    fn part_a(recipes: &Self::Parsed) -> u64 {
+        ore_cost_for_fuel(recipes, 1)
+    }
+
+    /// "Given 1 trillion ORE, what is the maximum amount of FUEL you can produce?"
+    fn part_b(recipes: &Self::Parsed) -> u64 {
+        num_fuel_producible_with_one_trillion_ore(recipes)
+    }
+}
+
+fn load_recipes(filename: impl AsRef<Path>) -> HashMap<String, Recipe> {
+    util::load(filename, parse_recipes)
+}
+
+/// Parses recipes like "7 A, 1 E => 1 FUEL", one per line. Blank lines are skipped.
+/// There has to be a recipe for FUEL, and for every chemical other than ORE that a recipe uses.
+#[instrument(name = "parse", level = "debug", skip_all)]
+fn parse_recipes(contents: &str) -> Result<HashMap<String, Recipe>, ParseError> {
+    let lines: Vec<(usize, &str, Recipe)> = contents
         .lines()
-        .map(Recipe::new)
-        .map(|recipe| (recipe.output.chemical.clone(), recipe))
-        .collect()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let recipe = Recipe::new(line).map_err(|e| ParseError::new(i + 1, line, e))?;
+            Ok((i + 1, line, recipe))
+        })
+        .collect::<Result<_, ParseError>>()?;
+
+    let outputs: HashSet<&str> = lines
+        .iter()
+        .map(|(_, _, recipe)| recipe.output.chemical.as_str())
+        .collect();
+
+    for (line_number, line, recipe) in &lines {
+        if let Some(input) = recipe
+            .inputs
+            .iter()
+            .find(|input| input.chemical != "ORE" && !outputs.contains(input.chemical.as_str()))
+        {
+            return Err(ParseError::new(
+                *line_number,
+                line,
+                format_args!("there's no recipe that makes {}", input.chemical),
+            ));
+        }
+    }
+
+    if !outputs.contains("FUEL") {
+        return Err(ParseError::new(
+            contents.lines().count().max(1),
+            "",
+            "there's no recipe that makes FUEL",
+        ));
+    }
+
+    Ok(lines
+        .into_iter()
+        .map(|(_, _, recipe)| (recipe.output.chemical.clone(), recipe))
+        .collect())
 }
 
 #[cfg(test)]
@@ -144,11 +404,57 @@ mod tests {
     #[test]
     fn test_parse_recipe() {
         assert_eq!(
-            Recipe::new("7 LCSV, 1 LKPNB, 36 CMNH, 1 JZXPH, 20 DGJPN, 3 WDWB, 69 DXJKC, 3 WHJKH, 18 XSGP, 22 CGZL, 2 BNVB, 57 PNSD => 1 FUEL"),
+            Recipe::new("7 LCSV, 1 LKPNB, 36 CMNH, 1 JZXPH, 20 DGJPN, 3 WDWB, 69 DXJKC, 3 WHJKH, 18 XSGP, 22 CGZL, 2 BNVB, 57 PNSD => 1 FUEL").unwrap(),
             Recipe {inputs: vec![RecipeComponent { chemical: "LCSV".to_string(), quantity: 7 }, RecipeComponent { chemical: "LKPNB".to_string(), quantity: 1 }, RecipeComponent { chemical: "CMNH".to_string(), quantity: 36 }, RecipeComponent { chemical: "JZXPH".to_string(), quantity: 1 }, RecipeComponent { chemical: "DGJPN".to_string(), quantity: 20 }, RecipeComponent { chemical: "WDWB".to_string(), quantity: 3 }, RecipeComponent { chemical: "DXJKC".to_string(), quantity: 69 }, RecipeComponent { chemical: "WHJKH".to_string(), quantity: 3 }, RecipeComponent { chemical: "XSGP".to_string(), quantity: 18 }, RecipeComponent { chemical: "CGZL".to_string(), quantity: 22 }, RecipeComponent { chemical: "BNVB".to_string(), quantity: 2 }, RecipeComponent { chemical: "PNSD".to_string(), quantity: 57 }], output: RecipeComponent { chemical: "FUEL".to_string(), quantity: 1 }}
         );
     }
 
+    #[test]
+    fn test_recipe_errors() {
+        // Extra whitespace, a trailing newline, and blank lines are all fine.
+        assert_eq!(
+            parse_recipes("10 ORE  =>  10 A\r\n\n 7 A ,1 ORE=>1 FUEL \n\n").unwrap(),
+            parse_recipes("10 ORE => 10 A\n7 A, 1 ORE => 1 FUEL").unwrap()
+        );
+
+        assert_eq!(Recipe::new("7 A, 1 E"), Err(RecipeError::MissingArrow));
+        assert_eq!(
+            Recipe::new("7 A, 1E => 1 FUEL"),
+            Err(RecipeError::MalformedComponent("1E".to_string()))
+        );
+        assert_eq!(
+            Recipe::new("7 A => 0 FUEL"),
+            Err(RecipeError::MalformedComponent("0 FUEL".to_string()))
+        );
+
+        let error = parse_recipes("10 ORE => 10 A\n7 A, x B => 1 FUEL").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(
+            error.to_string(),
+            r#"line 2: "7 A, x B => 1 FUEL": "x B" isn't a quantity followed by a chemical, like "7 A""#
+        );
+    }
+
+    #[test]
+    fn test_missing_recipes() {
+        assert_eq!(
+            parse_recipes("10 ORE => 10 A\n7 A, 1 B => 1 FUEL"),
+            Err(ParseError::new(
+                2,
+                "7 A, 1 B => 1 FUEL",
+                "there's no recipe that makes B"
+            ))
+        );
+        assert_eq!(
+            parse_recipes("10 ORE => 10 A\n7 A => 1 B\n"),
+            Err(ParseError::new(2, "", "there's no recipe that makes FUEL"))
+        );
+        assert_eq!(
+            parse_recipes("").unwrap_err().to_string(),
+            r#"line 1: "": there's no recipe that makes FUEL"#
+        );
+    }
+
     #[test]
     fn test_cost_for_one_fuel() {
         let recipes = load_recipes("src/inputs/14_sample_1.txt");
@@ -164,12 +470,114 @@ mod tests {
         assert_eq!(ore_cost_for_fuel(&recipes, 1), 180697);
     }
 
+    #[test]
+    fn test_chemicals_in_dependency_order() {
+        let recipes = load_recipes("src/inputs/14_sample_1.txt");
+        let order = chemicals_in_dependency_order(&recipes);
+
+        assert_eq!(order.len(), recipes.len());
+        assert_eq!(order[0], "FUEL");
+
+        for (i, chemical) in order.iter().enumerate() {
+            for input in &recipes[*chemical].inputs {
+                if input.chemical != "ORE" {
+                    assert!(order.iter().position(|&c| c == input.chemical).unwrap() > i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let recipes =
+            parse_recipes("10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n2 A, 3 C => 1 FUEL")
+                .unwrap();
+
+        assert_eq!(
+            to_dot(&recipes),
+            r#"digraph reactions {
+    "ORE" [style=filled, fillcolor=gray];
+    "FUEL" [label="FUEL (makes 1)", style=filled, fillcolor=gold];
+    "A" -> "FUEL" [label=2];
+    "C" -> "FUEL" [label=3];
+    "C" [label="C (makes 1)"];
+    "A" -> "C" [label=7];
+    "B" -> "C" [label=1];
+    "B" [label="B (makes 1)"];
+    "ORE" -> "B" [label=1];
+    "A" [label="A (makes 10)"];
+    "ORE" -> "A" [label=10];
+}
+"#
+        );
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(fourteen_a(), 158482);
         assert_eq!(fourteen_b(), 7993831);
     }
 
+    #[test]
+    fn test_public_api() {
+        let recipes = "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 D\n7 A, 1 D => 1 E\n7 A, 1 E => 1 FUEL";
+
+        assert_eq!(ore_for_fuel(recipes, 1), Ok(31));
+        assert_eq!(ore_for_fuel(recipes, 0), Ok(0));
+        assert_eq!(max_fuel_for_ore(recipes, 30), Ok(0));
+        assert_eq!(max_fuel_for_ore(recipes, 31), Ok(1));
+        assert_eq!(
+            max_fuel_for_ore(&util::read("src/inputs/14_sample_2.txt"), ONE_TRILLION),
+            Ok(82892753)
+        );
+        assert!(ore_for_fuel("10 ORE -> 1 FUEL", 1).is_err());
+    }
+
+    #[test]
+    fn test_search_with_cheap_ore_and_flat_costs() {
+        // One ORE makes ten FUEL.
+        let cheap = "1 ORE => 10 A\n1 A => 1 FUEL";
+        assert_eq!(max_fuel_for_ore(cheap, 10), Ok(100));
+        // Ten ORE makes ten FUEL, so the cost of 1 through 10 FUEL is the same.
+        let flat = "10 ORE => 10 A\n1 A => 1 FUEL";
+        assert_eq!(max_fuel_for_ore(flat, 10), Ok(10));
+
+        for recipes in [cheap, flat, &util::read("src/inputs/14_sample_1.txt")].iter() {
+            let recipes = parse_recipes(recipes).unwrap();
+            for ore_budget in 0..300 {
+                let expected = (1..)
+                    .find(|&fuel| ore_cost_for_fuel(&recipes, fuel) > ore_budget)
+                    .unwrap()
+                    - 1;
+                assert_eq!(search_for_max_fuel(&recipes, ore_budget).0, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_trace() {
+        let (fuel, trace) = fourteen_b_with_trace();
+        assert_eq!(fuel, 7993831);
+
+        assert_eq!(trace[0].fuel, 1);
+        assert_eq!(trace[0].ore_cost, 158482);
+
+        // The search ends once it's seen that `fuel` is affordable and `fuel + 1` isn't.
+        let cost_of = |fuel| {
+            trace
+                .iter()
+                .find(|step| step.fuel == fuel)
+                .unwrap()
+                .ore_cost
+        };
+        assert!(cost_of(fuel) <= ONE_TRILLION);
+        assert!(cost_of(fuel + 1) > ONE_TRILLION);
+        assert_eq!(trace.len(), 5);
+        assert!(trace
+            .iter()
+            .all(|step| step.lower_bound <= step.fuel && step.fuel <= step.upper_bound + 1));
+    }
+
     #[test]
     fn test_one_trillion_ore() {
         let recipes = load_recipes("src/inputs/14_sample_2.txt");
@@ -0,0 +1,317 @@
+use crate::util;
+use std::error;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+/// Everything that can go wrong when parsing a Grid.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// There weren't any lines, or they were all empty.
+    Empty,
+    /// Line `line` (counting from 1) was `width` characters wide instead of `expected`.
+    Ragged {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the grid is empty"),
+            ParseError::Ragged {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "line {} is {} characters wide, but the first line is {}",
+                line, width, expected
+            ),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl ParseError {
+    /// Converts this into a util::ParseError that points at the line of `text` it's about,
+    /// where `text` is what was being parsed.
+    pub fn at_line_of(&self, text: &str) -> util::ParseError {
+        match *self {
+            ParseError::Empty => util::ParseError::new(1, "", self),
+            ParseError::Ragged {
+                line,
+                width,
+                expected,
+            } => util::ParseError::new(
+                line,
+                text.lines().nth(line - 1).unwrap_or(""),
+                format_args!(
+                    "expected {} characters, like the first line, but found {}",
+                    expected, width
+                ),
+            ),
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Parses `text`, one row per line, by calling `cell` with each character and its (x, y) position.
+    /// `cell` can also keep track of the positions of any interesting characters it sees.
+    /// Every line has to be the same width.
+    pub fn parse(
+        text: &str,
+        cell: impl FnMut(char, (usize, usize)) -> T,
+    ) -> Result<Self, ParseError> {
+        Grid::parse_lines(text, None, cell)
+    }
+
+    /// Like `parse()`, but for text whose lines have had their trailing whitespace trimmed off:
+    /// lines that are shorter than the longest one are padded out with `fill`.
+    pub fn parse_padded(
+        text: &str,
+        fill: char,
+        cell: impl FnMut(char, (usize, usize)) -> T,
+    ) -> Result<Self, ParseError> {
+        Grid::parse_lines(text, Some(fill), cell)
+    }
+
+    fn parse_lines(
+        text: &str,
+        fill: Option<char>,
+        mut cell: impl FnMut(char, (usize, usize)) -> T,
+    ) -> Result<Self, ParseError> {
+        let widths: Vec<usize> = text.lines().map(|line| line.chars().count()).collect();
+        let width = match fill {
+            Some(_) => widths.iter().copied().max(),
+            None => widths.first().copied(),
+        }
+        .filter(|&width| width > 0)
+        .ok_or(ParseError::Empty)?;
+
+        let mut cells = Vec::with_capacity(width * widths.len());
+        for (y, line) in text.lines().enumerate() {
+            if fill.is_none() && widths[y] != width {
+                return Err(ParseError::Ragged {
+                    line: y + 1,
+                    width: widths[y],
+                    expected: width,
+                });
+            }
+
+            let padding = std::iter::repeat(fill.unwrap_or(' '));
+            for (x, c) in line.chars().chain(padding).take(width).enumerate() {
+                cells.push(cell(c, (x, y)));
+            }
+        }
+
+        Ok(Grid {
+            cells,
+            width,
+            height: widths.len(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the cell at (x, y), or None if (x, y) is off the grid.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    /// Each row of cells, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    /// Each cell along with its (x, y) position, in reading order.
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// Returns a grid of the same size, made by calling `f` on each of this grid's cells.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Returns something that displays this grid one row per line, drawing each cell as `to_char(cell)`.
+    pub fn display<F: Fn(&T) -> char>(&self, to_char: F) -> Display<'_, T, F> {
+        Display {
+            grid: self,
+            to_char,
+        }
+    }
+
+    fn index_of(&self, (x, y): (usize, usize)) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is off of a {}x{} grid",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        y * self.width + x
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: (usize, usize)) -> &T {
+        &self.cells[self.index_of(position)]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, position: (usize, usize)) -> &mut T {
+        let index = self.index_of(position);
+        &mut self.cells[index]
+    }
+}
+
+/// Draws a Grid as text; see `Grid::display()`.
+pub struct Display<'a, T, F> {
+    grid: &'a Grid<T>,
+    to_char: F,
+}
+
+impl<T, F: Fn(&T) -> char> fmt::Display for Display<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.grid.rows() {
+            let line: String = row.iter().map(&self.to_char).collect();
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut hashes = vec![];
+        let grid = Grid::parse("#..\n.#.", |c, position| {
+            if c == '#' {
+                hashes.push(position);
+            }
+            c == '#'
+        })
+        .unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(hashes, vec![(0, 0), (1, 1)]);
+        assert_eq!(grid.get(0, 0), Some(&true));
+        assert_eq!(grid.get(2, 1), Some(&false));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+    }
+
+    #[test]
+    fn test_index_and_display() {
+        let mut grid = Grid::parse("#..\n.#.", |c, _| c == '#').unwrap();
+        assert!(grid[(1, 1)]);
+
+        grid[(2, 0)] = true;
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![&[true, false, true][..], &[false, true, false][..]]
+        );
+        assert_eq!(
+            grid.cells()
+                .filter(|(_, &cell)| cell)
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (2, 0), (1, 1)]
+        );
+
+        let drawing = grid.display(|&cell| if cell { '#' } else { '.' });
+        assert_eq!(drawing.to_string(), "#.#\n.#.\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "(3, 0) is off of a 3x2 grid")]
+    fn test_index_off_grid() {
+        let grid = Grid::parse("#..\n.#.", |c, _| c).unwrap();
+        let _ = grid[(3, 0)];
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Grid::parse("", |c, _| c), Err(ParseError::Empty));
+        assert_eq!(Grid::parse("\n\n", |c, _| c), Err(ParseError::Empty));
+
+        let error = Grid::parse("...\n..\n...", |c, _| c).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::Ragged {
+                line: 2,
+                width: 2,
+                expected: 3
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 2 is 2 characters wide, but the first line is 3"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_at_line_of() {
+        let text = "...\n..\n...";
+        let error = Grid::parse(text, |c, _| c).unwrap_err();
+        assert_eq!(
+            error.at_line_of(text),
+            util::ParseError::new(
+                2,
+                "..",
+                "expected 3 characters, like the first line, but found 2"
+            )
+        );
+
+        assert_eq!(
+            ParseError::Empty.at_line_of(""),
+            util::ParseError::new(1, "", "the grid is empty")
+        );
+    }
+
+    #[test]
+    fn test_parse_padded() {
+        let grid = Grid::parse_padded(" A\nB\n", ' ', |c, _| c).unwrap();
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(grid.get(1, 1), Some(&' '));
+        assert_eq!(grid.get(0, 1), Some(&'B'));
+    }
+}
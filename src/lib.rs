@@ -1,81 +1,152 @@
-mod computer;
-mod eight;
-mod eighteen;
-mod eleven;
-mod fifteen;
-mod five;
-mod four;
-mod fourteen;
-mod nine;
-mod nineteen;
-mod one;
-mod seven;
-mod seventeen;
-mod six;
-mod sixteen;
-mod ten;
-mod thirteen;
-mod three;
-mod twelve;
-mod twenty;
-mod twenty_five;
-mod twenty_four;
-mod twenty_one;
+pub mod automaton;
+pub mod computer;
+#[cfg(feature = "cli")]
+pub mod dashboard;
+pub mod eight;
+pub mod eighteen;
+pub mod eleven;
+pub mod fifteen;
+pub mod five;
+pub mod four;
+pub mod fourteen;
+pub mod grid;
+pub mod nine;
+pub mod nineteen;
+pub mod one;
+pub mod programs;
+pub mod render;
+pub mod report;
+pub mod samples;
+pub mod seven;
+pub mod seventeen;
+pub mod six;
+pub mod sixteen;
+pub mod solver;
+pub mod ten;
+pub mod thirteen;
+pub mod three;
+pub mod twelve;
+pub mod twenty;
+pub mod twenty_five;
+pub mod twenty_four;
+pub mod twenty_one;
 pub mod twenty_three;
-mod twenty_two;
-mod two;
-mod util;
+pub mod twenty_two;
+pub mod two;
+pub mod util;
+#[cfg(feature = "cli")]
+pub mod watch;
+
+use solver::Solver;
+use std::fmt;
+use tracing::info_span;
 
 pub fn run_all_solutions() {
-    println!("1a: {}", one::one_a());
-    println!("1b: {}", one::one_b());
-    println!("2a: {}", two::two_a());
-    println!("2b: {}", two::two_b());
-    println!("3a: {}", three::three_a());
-    println!("3b: {}", three::three_b());
-    println!("4a: {}", four::four_a());
-    println!("4b: {}", four::four_b());
-    println!("5a: {}", five::five_a());
-    println!("5b: {}", five::five_b());
-    println!("6a: {}", six::six_a());
-    println!("6b: {}", six::six_b());
-    println!("7a: {}", seven::seven_a());
-    println!("7b: {}", seven::seven_b());
-    println!("8a: {}", eight::eight_a());
-    println!("8b:\n{}", eight::eight_b());
-    println!("9a: {}", nine::nine_a());
-    println!("9b: {}", nine::nine_b());
-    println!("10a: {}", ten::ten_a());
-    println!("10b: {}", ten::ten_b());
-    println!("11a: {}", eleven::eleven_a());
-    println!("11b:\n{}", eleven::eleven_b());
-    println!("12a: {}", twelve::twelve_a());
-    println!("12b: {}", twelve::twelve_b());
-    println!("13a: {}", thirteen::thirteen_a());
-    println!("13b: {}", thirteen::thirteen_b());
-    println!("14a: {}", fourteen::fourteen_a());
-    println!("14b: {}", fourteen::fourteen_b());
-    println!("15a: {}", fifteen::fifteen_a());
-    println!("15b: {}", fifteen::fifteen_b());
-    println!("16a: {}", sixteen::sixteen_a());
-    println!("16b: {}", sixteen::sixteen_b());
-    println!("17a: {}", seventeen::seventeen_a());
-    println!("17b: {}", seventeen::seventeen_b());
-    println!("18a: {}", eighteen::eighteen_a());
-    println!("18b: {}", eighteen::eighteen_b());
-    println!("19a: {}", nineteen::nineteen_a());
-    println!("19b: {}", nineteen::nineteen_b());
-    println!("20a: {}", twenty::twenty_a());
-    println!("20b: {}", twenty::twenty_b());
-    println!("21a: {}", twenty_one::twenty_one_a());
-    println!("21b: {}", twenty_one::twenty_one_b());
-    println!("22a: {}", twenty_two::twenty_two_a());
-    println!("22b: {}", twenty_two::twenty_two_b());
-    println!("23a: {}", twenty_three::twenty_three_a());
-    println!("23b: {}", twenty_three::twenty_three_b());
-    println!("24a: {}", twenty_four::twenty_four_a());
-    println!("24b: {}", twenty_four::twenty_four_b());
-    println!("25a: {}", twenty_five::twenty_five_a());
+    for day in 1..=25 {
+        run_solution(day);
+    }
+}
+
+/// Like `run_all_solutions()`, but also returns a report on how each part went.
+pub fn run_all_solutions_with_reports() -> Vec<report::RunReport> {
+    report::run_days(1..=25, &mut |report| {
+        print_answer(report.day, report.part, &report.answer)
+    })
+}
+
+/// Prints both parts of `day`'s answer. Panics if there's no such day.
+pub fn run_solution(day: u32) {
+    solve(day, &mut |part, answer| print_answer(day, part, &answer));
+}
+
+/// Lists each day's module along with how to solve it, and builds `solve()` and `module_name()` from the list.
+/// A day is either `module(part_a, part_b)`, `module(part_a)` if it only has one part, or `module[Solver]`.
+macro_rules! days {
+    ($($day:literal => $module:ident $parts:tt,)*) => {
+        /// Computes both parts of `day`'s answer, calling `on_answer` with each part and its answer as soon as
+        /// that part is solved. Panics if there's no such day.
+        pub fn solve(day: u32, on_answer: &mut dyn FnMut(char, String)) {
+            match day {
+                $($day => days!(@solve $day, $module $parts, on_answer),)*
+                _ => panic!("there's no day {}", day),
+            }
+        }
+
+        /// The name of the module that solves `day`, like "twenty_two", or None if there's no such day.
+        pub fn module_name(day: u32) -> Option<&'static str> {
+            match day {
+                $($day => Some(stringify!($module)),)*
+                _ => None,
+            }
+        }
+    };
+
+    (@solve $day:literal, $module:ident ($part_a:ident, $part_b:ident), $on_answer:ident) => {{
+        solve_part($day, 'a', $module::$part_a, $on_answer);
+        solve_part($day, 'b', $module::$part_b, $on_answer);
+    }};
+    (@solve $day:literal, $module:ident ($part_a:ident), $on_answer:ident) => {
+        solve_part($day, 'a', $module::$part_a, $on_answer)
+    };
+    (@solve $day:literal, $module:ident [$solver:ident], $on_answer:ident) => {
+        solve_solver::<$module::$solver>($on_answer)
+    };
+}
+
+days! {
+    1 => one(one_a, one_b),
+    2 => two(two_a, two_b),
+    3 => three(three_a, three_b),
+    4 => four(four_a, four_b),
+    5 => five(five_a, five_b),
+    6 => six(six_a, six_b),
+    7 => seven(seven_a, seven_b),
+    8 => eight(eight_a, eight_b),
+    9 => nine(nine_a, nine_b),
+    10 => ten(ten_a, ten_b),
+    11 => eleven(eleven_a, eleven_b),
+    12 => twelve(twelve_a, twelve_b),
+    13 => thirteen(thirteen_a, thirteen_b),
+    14 => fourteen[Solution],
+    15 => fifteen[Solution],
+    16 => sixteen[Solution],
+    17 => seventeen(seventeen_a, seventeen_b),
+    18 => eighteen[Solution],
+    19 => nineteen(nineteen_a, nineteen_b),
+    20 => twenty[Solution],
+    21 => twenty_one(twenty_one_a, twenty_one_b),
+    22 => twenty_two[Solution],
+    23 => twenty_three(twenty_three_a, twenty_three_b),
+    24 => twenty_four(twenty_four_a, twenty_four_b),
+    25 => twenty_five(twenty_five_a),
+}
+
+/// Computes one part of one day's answer inside a span that records the day and part,
+/// and hands it to `on_answer`.
+fn solve_part<T: fmt::Display>(
+    day: u32,
+    part: char,
+    solve: impl FnOnce() -> T,
+    on_answer: &mut dyn FnMut(char, String),
+) {
+    let answer = info_span!("solution", day, %part).in_scope(solve);
+    on_answer(part, answer.to_string());
+}
+
+/// Computes both parts of a Solver's answer, after reading and parsing its input once inside its own span.
+fn solve_solver<S: Solver>(on_answer: &mut dyn FnMut(char, String)) {
+    let parsed = info_span!("parse", day = S::DAY).in_scope(S::load);
+
+    solve_part(S::DAY, 'a', || S::part_a(&parsed), on_answer);
+    solve_part(S::DAY, 'b', || S::part_b(&parsed), on_answer);
+}
+
+fn print_answer(day: u32, part: char, answer: &str) {
+    if answer.contains('\n') {
+        println!("{}{}:\n{}", day, part, answer);
+    } else {
+        println!("{}{}: {}", day, part, answer);
+    }
 }
 
 #[cfg(test)]
@@ -87,4 +158,13 @@ mod tests {
         // Make sure that run_all_solutions() doesn't crash.
         run_all_solutions()
     }
+
+    #[test]
+    fn test_module_name() {
+        assert_eq!(module_name(1), Some("one"));
+        assert_eq!(module_name(22), Some("twenty_two"));
+        assert_eq!(module_name(25), Some("twenty_five"));
+        assert_eq!(module_name(0), None);
+        assert_eq!(module_name(26), None);
+    }
 }
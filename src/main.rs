@@ -1,6 +1,90 @@
 #![warn(clippy::all, clippy::nursery)]
 
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+use std::time::Instant;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
 fn main() {
-    pretty_env_logger::init();
-    advent_2019::run_all_solutions();
+    // Verbosity is controlled with RUST_LOG, e.g. RUST_LOG=info shows how long each solution took,
+    // and RUST_LOG=advent_2019::fourteen=debug shows each step of day 14's search.
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => advent_2019::run_all_solutions(),
+        ["--all"] => run_all_with_report(DEFAULT_REPORT_PATH),
+        ["--all", "--report", path] => run_all_with_report(path),
+        ["--dashboard"] => {
+            if let Err(e) = advent_2019::dashboard::run((1..=25).collect()) {
+                eprintln!("couldn't run the dashboard: {}", e);
+                process::exit(1);
+            }
+        }
+        ["samples"] => check_samples(None),
+        ["samples", day] => check_samples(Some(parse_day(day))),
+        ["--watch", day] => {
+            if let Err(e) = advent_2019::watch::watch(parse_day(day)) {
+                eprintln!("couldn't watch day {}: {}", day, e);
+                process::exit(1);
+            }
+        }
+        [day] => {
+            let start = Instant::now();
+            advent_2019::run_solution(parse_day(day));
+            println!("Solved in {:.2?}", start.elapsed());
+        }
+        _ => usage(),
+    }
+}
+
+/// Where `--all` writes its report unless told otherwise.
+const DEFAULT_REPORT_PATH: &str = "run_report.json";
+
+/// Runs every day, then writes how long each part took to `path` as JSON.
+fn run_all_with_report(path: &str) {
+    let reports = advent_2019::run_all_solutions_with_reports();
+
+    let written = File::create(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            advent_2019::report::write_json(&reports, BufWriter::new(file))
+                .map_err(|e| e.to_string())
+        });
+    match written {
+        Ok(()) => println!("Wrote a report on {} answers to {}", reports.len(), path),
+        Err(e) => {
+            eprintln!("couldn't write {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Checks the puzzle descriptions' examples, and exits unsuccessfully if any of them came out wrong,
+/// or if there weren't any to check.
+fn check_samples(day: Option<u32>) {
+    if let Err(e) = advent_2019::samples::check_samples(day) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// Exits with a usage message if `day` isn't a number from 1 to 25.
+fn parse_day(day: &str) -> u32 {
+    match day.parse() {
+        Ok(day) if (1..=25).contains(&day) => day,
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: advent_2019 [day | --all [--report file] | samples [day] | --watch day | --dashboard]");
+    process::exit(2);
 }
@@ -1,26 +1,125 @@
-use crate::computer;
-use crate::computer::{Computer, HaltReason};
+use crate::computer::{Computer, HaltReason, Memory};
+use crate::programs;
 
 pub fn nine_a() -> i64 {
-    let memory = computer::load_program("src/inputs/9.txt");
-    let mut computer = Computer::new(memory);
-    computer.push_input(1);
-    computer.run(HaltReason::Exit);
-    computer.pop_output().unwrap()
+    let memory = programs::load("boost");
+    let report = run_boost_diagnostic(memory);
+
+    match report.keycode {
+        Some(keycode) => keycode,
+        None => panic!(
+            "BOOST reported malfunctioning opcodes: {:?}",
+            report.malfunctions
+        ),
+    }
 }
 
 pub fn nine_b() -> i64 {
-    let memory = computer::load_program("src/inputs/9.txt");
+    let memory = programs::load("boost");
     let mut computer = Computer::new(memory);
     computer.push_input(2);
     computer.run(HaltReason::Exit);
     computer.pop_output().unwrap()
 }
 
+/// The result of running BOOST in test mode.
+#[derive(Debug, PartialEq)]
+pub struct BoostReport {
+    /// "Finally output a BOOST keycode." Only present if no malfunctions were reported.
+    pub keycode: Option<i64>,
+    pub malfunctions: Vec<Malfunction>,
+}
+
+/// An instruction that BOOST says "seem[s] to be functioning incorrectly".
+#[derive(Debug, PartialEq)]
+pub struct Malfunction {
+    /// The instruction as BOOST output it, like `203`.
+    pub instruction: i64,
+    pub opcode: i64,
+    /// The instruction's parameter modes, starting with its first parameter's.
+    pub parameter_modes: Vec<i64>,
+}
+
+impl Malfunction {
+    fn new(instruction: i64) -> Self {
+        let mut parameter_modes = vec![];
+        let mut remaining_modes = instruction / 100;
+        while remaining_modes != 0 {
+            parameter_modes.push(remaining_modes % 10);
+            remaining_modes /= 10;
+        }
+
+        Malfunction {
+            instruction,
+            opcode: instruction % 100,
+            parameter_modes,
+        }
+    }
+}
+
+/// "The BOOST program will ask for a single input; run it in test mode by providing it the value 1.
+/// It will perform a series of checks on each opcode, output any opcodes (and the associated parameter modes)
+/// that seem to be functioning incorrectly, and finally output a BOOST keycode."
+pub fn run_boost_diagnostic(memory: Memory) -> BoostReport {
+    let mut computer = Computer::new(memory);
+    computer.push_input(1);
+    computer.run(HaltReason::Exit);
+
+    let outputs: Vec<i64> = std::iter::from_fn(|| computer.pop_output()).collect();
+
+    match outputs.as_slice() {
+        [keycode] => BoostReport {
+            keycode: Some(*keycode),
+            malfunctions: vec![],
+        },
+        _ => BoostReport {
+            keycode: None,
+            malfunctions: outputs
+                .iter()
+                .take(outputs.len().saturating_sub(1))
+                .map(|&instruction| Malfunction::new(instruction))
+                .collect(),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_run_boost_diagnostic() {
+        // A stand-in for BOOST that reports two malfunctions before its final output.
+        let report = run_boost_diagnostic(vec![104, 203, 104, 21102, 104, 0, 99]);
+
+        assert_eq!(
+            report,
+            BoostReport {
+                keycode: None,
+                malfunctions: vec![
+                    Malfunction {
+                        instruction: 203,
+                        opcode: 3,
+                        parameter_modes: vec![2],
+                    },
+                    Malfunction {
+                        instruction: 21102,
+                        opcode: 2,
+                        parameter_modes: vec![1, 1, 2],
+                    },
+                ],
+            }
+        );
+
+        assert_eq!(
+            run_boost_diagnostic(programs::load("boost")),
+            BoostReport {
+                keycode: Some(3280416268),
+                malfunctions: vec![],
+            }
+        );
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(nine_a(), 3280416268);
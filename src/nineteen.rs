@@ -1,136 +1,412 @@
 use crate::computer::load_program;
-use crate::computer::{Computer, HaltReason};
+use crate::computer::{Computer, Memory};
+use crate::programs;
+use crate::render::{self, RenderGrid, Rgb};
+use std::collections::{HashMap, VecDeque};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 struct Position(u32, u32);
 
-fn position_is_in_beam(x: u32, y: u32, computer: &mut Computer, original_memory: &[i64]) -> bool {
-    reset_computer(computer, original_memory);
-    computer.push_input(x as i64);
-    computer.push_input(y as i64);
-    computer.run(HaltReason::Output);
+/// How many empty rows in a row BeamEdges will look through before deciding that the beam has run out.
+const MAX_EMPTY_ROWS: u32 = 100;
 
-    match computer.pop_output().unwrap() {
-        0 => false,
-        1 => true,
-        _ => unreachable!(),
+/// How many rows before its estimate `find_topleft_of_first_bounding_box()` starts looking for the square.
+/// The beam's edges jitter by a point or so from row to row, so the square can fit a little earlier than
+/// straight edges would allow.
+const ROWS_BEFORE_ESTIMATE: u32 = 10;
+
+/// How far out, in x per row, `TractorBeam::edges()` will look for a beam whose slope it doesn't know yet.
+const MAX_SLOPE: u32 = 20;
+
+/// The tractor beam, as described by a drone program.
+///
+/// Remembers the answer to every query, since each one costs a full run of the drone program,
+/// and keeps track of how steeply the beam's edges slope away from the emitter so that it can
+/// guess where the beam will be in rows it hasn't looked at yet.
+pub struct TractorBeam {
+    computer: Computer,
+    original_memory: Vec<i64>,
+    cache: HashMap<(u32, u32), bool>,
+    // The edges of the beam in the farthest row we've measured, as (y, left_x, right_x).
+    farthest_edges: Option<(u32, u32, u32)>,
+    num_program_runs: usize,
+}
+
+impl TractorBeam {
+    pub fn new(filename: &str) -> Self {
+        TractorBeam::from_program(load_program(filename))
+    }
+
+    pub fn from_program(program: Memory) -> Self {
+        let computer = Computer::new(program);
+        let original_memory = computer.state.memory.clone();
+
+        TractorBeam {
+            computer,
+            original_memory,
+            cache: HashMap::new(),
+            farthest_edges: None,
+            num_program_runs: 0,
+        }
+    }
+
+    /// Returns true if (x, y) is being pulled by the tractor beam.
+    pub fn is_in_beam(&mut self, x: u32, y: u32) -> bool {
+        if let Some(&in_beam) = self.cache.get(&(x, y)) {
+            return in_beam;
+        }
+
+        self.computer
+            .state
+            .memory
+            .copy_from_slice(&self.original_memory);
+        self.computer.state.instruction_pointer = 0;
+        self.computer.state.relative_base = 0;
+
+        self.computer.push_input(x as i64);
+        self.computer.push_input(y as i64);
+        let output = self.computer.next_output();
+        self.num_program_runs += 1;
+
+        let in_beam = match output.unwrap() {
+            0 => false,
+            1 => true,
+            _ => unreachable!(),
+        };
+
+        self.cache.insert((x, y), in_beam);
+        in_beam
+    }
+
+    /// Returns the number of times the drone program has been run so far.
+    pub fn num_program_runs(&self) -> usize {
+        self.num_program_runs
+    }
+
+    /// Returns the beam's leftmost and rightmost x positions in row `y`, or None if we couldn't find the beam there.
+    /// Starts looking wherever the beam's slope says it should be, and walks outward from there,
+    /// as far right as x = MAX_SLOPE * y.
+    pub fn edges(&mut self, y: u32) -> Option<(u32, u32)> {
+        let (guess_left, guess_right) = self.estimate_edges(y);
+        let farthest_x = (y * MAX_SLOPE).max(guess_right + 2);
+
+        // Look for the beam between the guesses first, then to the left of them, then to the right.
+        let mut left = (guess_left..=guess_right + 2)
+            .chain((0..guess_left).rev())
+            .chain(guess_right + 3..=farthest_x)
+            .find(|&x| self.is_in_beam(x, y))?;
+        while left > 0 && self.is_in_beam(left - 1, y) {
+            left -= 1;
+        }
+
+        let mut right = left.max(guess_right);
+        while !self.is_in_beam(right, y) {
+            right -= 1;
+        }
+        while self.is_in_beam(right + 1, y) {
+            right += 1;
+        }
+
+        // Row 0 is just the emitter, which doesn't tell us anything about the slope.
+        let is_farthest_row = match self.farthest_edges {
+            Some((farthest_y, _, _)) => y > farthest_y,
+            None => y > 0,
+        };
+        if is_farthest_row {
+            self.farthest_edges = Some((y, left, right));
+        }
+
+        Some((left, right))
+    }
+
+    /// Returns a guess at the beam's (left, right) edges in row `y`, based on the farthest row we've measured.
+    fn estimate_edges(&self, y: u32) -> (u32, u32) {
+        match self.farthest_edges {
+            Some((farthest_y, left, right)) => {
+                let scale = y as f64 / farthest_y as f64;
+                (
+                    (left as f64 * scale) as u32,
+                    (right as f64 * scale).ceil() as u32,
+                )
+            }
+            None => (0, y),
+        }
+    }
+
+    /// Returns true if every point on the perimeter of the `size`x`size` square whose top-left corner
+    /// is `top_left` is in the beam. A square of size 0 has no points, so it never fits.
+    pub fn fits_square(&mut self, top_left: (u32, u32), size: u32) -> bool {
+        if size == 0 {
+            return false;
+        }
+
+        let (left, top) = top_left;
+        let (right, bottom) = (left + size - 1, top + size - 1);
+
+        (left..=right).all(|x| self.is_in_beam(x, top) && self.is_in_beam(x, bottom))
+            && (top..=bottom).all(|y| self.is_in_beam(left, y) && self.is_in_beam(right, y))
+    }
+
+    /// Returns the (left_slope, right_slope) of the beam's edges, measured as change in x per row.
+    fn slopes(&self) -> Option<(f64, f64)> {
+        self.farthest_edges
+            .map(|(y, left, right)| (left as f64 / y as f64, right as f64 / y as f64))
     }
 }
 
-fn reset_computer(computer: &mut Computer, original_memory: &[i64]) {
-    computer.state.memory.copy_from_slice(&original_memory);
-    computer.state.instruction_pointer = 0;
-    computer.state.relative_base = 0;
+/// Iterates over the rows of the beam from the emitter outward, yielding (y, left_x, right_x) for each
+/// row that the beam reaches. Each row's edges are found by walking outward from the previous row's.
+/// Ends once MAX_EMPTY_ROWS rows in a row come up empty.
+pub struct BeamEdges {
+    beam: TractorBeam,
+    y: u32,
+    num_empty_rows: u32,
 }
 
-pub fn nineteen_a() -> u32 {
-    let mut num_affected_points = 0;
-    let memory = load_program("src/inputs/19.txt");
-    let mut computer = Computer::new(memory);
-    let original_memory = computer.state.memory.clone();
-
-    for y in 0..50 {
-        for x in 0..50 {
-            if position_is_in_beam(x, y, &mut computer, &original_memory) {
-                num_affected_points += 1;
+impl Iterator for BeamEdges {
+    type Item = (u32, u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.num_empty_rows < MAX_EMPTY_ROWS {
+            let y = self.y;
+            self.y += 1;
+
+            // The beam doesn't become coherent/continuous for a bit, so some rows close to the emitter are empty.
+            if let Some((left, right)) = self.beam.edges(y) {
+                self.num_empty_rows = 0;
+                return Some((y, left, right));
             }
+            self.num_empty_rows += 1;
         }
-    }
 
-    num_affected_points
+        None
+    }
 }
 
-fn step_left_cursor(
-    position: Position,
-    computer: &mut Computer,
-    original_memory: &[i64],
-) -> Position {
-    let y = position.1 + 1;
-    let mut x = position.0;
+impl BeamEdges {
+    /// Jumps ahead (or back) to row `y` without measuring the rows in between.
+    /// The beam's slope so far is used to guess where to look for it in row `y`.
+    pub fn skip_to(&mut self, y: u32) {
+        self.y = y;
+        self.num_empty_rows = 0;
+    }
+}
 
-    while !position_is_in_beam(x, y, computer, original_memory) {
-        x += 1;
+/// Returns an Iterator over the edges of the beam described by the drone program `program`.
+pub fn beam_edges(program: Memory) -> BeamEdges {
+    BeamEdges {
+        beam: TractorBeam::from_program(program),
+        y: 0,
+        num_empty_rows: 0,
     }
+}
 
-    Position(x, y)
+/// "How many points are affected by the tractor beam in the 50x50 area closest to the emitter?"
+pub fn nineteen_a() -> u32 {
+    beam_edges(programs::load("drone"))
+        .take_while(|&(y, _, _)| y < 50)
+        .filter(|&(_, left, _)| left < 50)
+        .map(|(_, left, right)| right.min(49) - left + 1)
+        .sum()
 }
 
-fn step_right_cursor(
-    position: Position,
-    computer: &mut Computer,
-    original_memory: &[i64],
-) -> Position {
-    let y = position.1 + 1;
-    let mut x = position.0;
+/// Walks `edges` from row `start` and returns the top-left corner of the first `box_size`x`box_size` square
+/// that fits in the beam with its top row at or below `start`, or None if the beam runs out first.
+fn first_square_from(edges: &mut BeamEdges, start: u32, box_size: u32) -> Option<Position> {
+    edges.skip_to(start);
 
-    while !position_is_in_beam(x, y, computer, original_memory) {
-        x += 1;
-    }
+    // The (y, right_x) of the rows we've walked through that could still be the top of a square.
+    let mut rows = VecDeque::new();
+
+    while let Some((y, left, right)) = edges.next() {
+        rows.push_back((y, right));
 
-    while position_is_in_beam(x, y, computer, original_memory) {
-        x += 1;
+        // A square whose bottom row is `y` has its top row at `top`.
+        let top = match (y + 1).checked_sub(box_size) {
+            Some(top) if top >= start => top,
+            _ => continue,
+        };
+        while rows.front().is_some_and(|&(row, _)| row < top) {
+            rows.pop_front();
+        }
+
+        if let Some(&(row, top_right)) = rows.front() {
+            // The edges alone can be fooled by a jittery beam, so make sure the whole square really fits.
+            if row == top
+                && top_right >= left + box_size - 1
+                && edges.beam.fits_square((left, top), box_size)
+            {
+                return Some(Position(left, top));
+            }
+        }
     }
 
-    Position(x - 1, y)
+    None
 }
 
-fn find_topleft_of_first_bounding_box(box_size: u32, filename: &str) -> Position {
-    let memory = load_program(filename);
-    let mut computer = Computer::new(memory.to_vec());
-    let original_memory = computer.state.memory.clone();
+/// Guesses the top row of the first `box_size`x`box_size` square that fits in the beam, based on its slopes so far.
+fn estimate_first_square_row(beam: &TractorBeam, box_size: u32) -> u32 {
+    // If the edges are straight lines x = left_slope * y and x = right_slope * y, the square first fits when
+    // right_slope * y - left_slope * (y + box_size - 1) = box_size - 1.
+    match beam.slopes() {
+        Some((left_slope, right_slope)) if right_slope > left_slope => {
+            ((box_size - 1) as f64 * (1.0 + left_slope) / (right_slope - left_slope)) as u32
+        }
+        _ => 0,
+    }
+}
 
-    // Cursors that hug the left and right side of the beam.
-    let mut left_cursor = Position(0, 0);
-    let mut right_cursor = Position(0, 0);
+/// Returns the top-left corner of the first `box_size`x`box_size` square that fits in the beam,
+/// or None if the beam runs out before one does.
+fn find_topleft_of_first_bounding_box(box_size: u32, program: Memory) -> Option<Position> {
+    let mut edges = beam_edges(program);
 
     // The beam doesn't become coherent/continuous for a bit,
-    // so we do a full sweep over the area close to (0, 0) to calibrate our left and right cursor.
-    for y in 1..15 {
-        let mut beam_exists_at_this_y_position = false;
-        let mut farthest_left = 0;
-        let mut farthest_right = 0;
-
-        for x in 0..20 {
-            if position_is_in_beam(x, y, &mut computer, &original_memory) {
-                beam_exists_at_this_y_position = true;
-                if farthest_left == 0 {
-                    farthest_left = x;
-                }
-                farthest_right = farthest_right.max(x);
+    // so we measure the rows close to the emitter to get a rough idea of its slope.
+    for _ in edges.by_ref().take_while(|&(y, _, _)| y < 15) {}
+
+    // Our slopes get more accurate the farther out we've measured, so jump ahead to our estimate of the
+    // first row where the square fits, measure it, and estimate again until the estimate settles down.
+    let mut start = estimate_first_square_row(&edges.beam, box_size);
+    loop {
+        edges.skip_to(start);
+        edges.next();
+
+        let estimate = estimate_first_square_row(&edges.beam, box_size);
+        if estimate == start {
+            break;
+        }
+        start = estimate;
+    }
+    start = start.saturating_sub(ROWS_BEFORE_ESTIMATE);
+
+    loop {
+        let position = first_square_from(&mut edges, start, box_size)?;
+
+        // If the square fits in the first row we looked at, it might have fit in an earlier one too.
+        if position.1 > start || start == 0 {
+            break Some(position);
+        }
+        start = start.saturating_sub(box_size);
+    }
+}
+
+/// A rectangular scan of the tractor beam, with a square optionally outlined on top of it.
+struct BeamScan {
+    top_left: Position,
+    width: u32,
+    height: u32,
+    in_beam: Vec<bool>,
+    // Some((top_left_corner, size)) if a square should be outlined.
+    square: Option<(Position, u32)>,
+}
+
+impl BeamScan {
+    /// Scans the `width`x`height` rectangle whose top-left corner is `top_left`.
+    fn new(top_left: Position, width: u32, height: u32, beam: &mut TractorBeam) -> Self {
+        let mut in_beam = Vec::with_capacity((width * height) as usize);
+
+        for y in top_left.1..top_left.1 + height {
+            for x in top_left.0..top_left.0 + width {
+                in_beam.push(beam.is_in_beam(x, y));
             }
+        }
 
-            if beam_exists_at_this_y_position {
-                left_cursor = Position(farthest_left, y);
-                right_cursor = Position(farthest_right, y);
+        BeamScan {
+            top_left,
+            width,
+            height,
+            in_beam,
+            square: None,
+        }
+    }
+
+    /// Returns true if (x, y) is on the border of the outlined square, false otherwise.
+    fn is_on_square_outline(&self, x: u32, y: u32) -> bool {
+        match self.square {
+            Some((Position(left, top), size)) => {
+                let (right, bottom) = (left + size - 1, top + size - 1);
+                let in_square = x >= left && x <= right && y >= top && y <= bottom;
+                in_square && (x == left || x == right || y == top || y == bottom)
             }
+            None => false,
         }
     }
+}
 
-    // Step the left cursor forward so that it describes the bottom-left corner of a bounding box
-    // whose top-right corner is the right cursor.
-    for _ in 0..(box_size - 1) {
-        left_cursor = step_left_cursor(left_cursor, &mut computer, &original_memory);
+impl RenderGrid for BeamScan {
+    fn width(&self) -> usize {
+        self.width as usize
     }
 
-    loop {
-        left_cursor = step_left_cursor(left_cursor, &mut computer, &original_memory);
-        right_cursor = step_right_cursor(right_cursor, &mut computer, &original_memory);
+    fn height(&self) -> usize {
+        self.height as usize
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        if self.is_on_square_outline(self.top_left.0 + x as u32, self.top_left.1 + y as u32) {
+            'O'
+        } else if self.in_beam[y * self.width as usize + x] {
+            '#'
+        } else {
+            '.'
+        }
+    }
 
-        // We've found a bounding box of the right size!
-        if right_cursor.0 > left_cursor.0 && right_cursor.0 - left_cursor.0 >= box_size - 1 {
-            break Position(left_cursor.0, right_cursor.1);
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.cell_char(x, y) {
+            'O' => Rgb(240, 80, 60),
+            '#' => render::WHITE,
+            _ => render::BLACK,
         }
     }
 }
 
+/// Draws the `width`x`height` rectangle of the beam whose top-left corner is `top_left`, as described
+/// by the drone program in `filename`. The first `box_size`x`box_size` square that fits in the beam
+/// is outlined with Os, unless `box_size` is 0 or no such square fits.
+pub fn draw_beam(
+    filename: &str,
+    top_left: (u32, u32),
+    width: u32,
+    height: u32,
+    box_size: u32,
+) -> String {
+    let mut scan = BeamScan::new(
+        Position(top_left.0, top_left.1),
+        width,
+        height,
+        &mut TractorBeam::new(filename),
+    );
+    if box_size > 0 {
+        scan.square = find_topleft_of_first_bounding_box(box_size, load_program(filename))
+            .map(|top_left| (top_left, box_size));
+    }
+
+    render::to_text(&scan)
+}
+
 pub fn nineteen_b() -> u32 {
-    let position = find_topleft_of_first_bounding_box(100, "src/inputs/19.txt");
+    closest_square(programs::load("drone"), 100)
+}
+
+/// "Find the `box_size`x`box_size` square closest to the emitter that fits entirely within the tractor beam...
+/// What value do you get if you take that point's X coordinate, multiply it by 10000, then add the point's
+/// Y coordinate?"
+pub fn closest_square(program: Memory, box_size: u32) -> u32 {
+    let position = find_topleft_of_first_bounding_box(box_size, program).unwrap_or_else(|| {
+        panic!(
+            "the beam ran out before a {}x{} square fit in it",
+            box_size, box_size
+        )
+    });
     position.0 * 10000 + position.1
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::computer;
 
     #[test]
     fn test_solutions() {
@@ -138,11 +414,112 @@ mod tests {
         assert_eq!(nineteen_b(), 3790981);
     }
 
+    #[test]
+    fn test_draw_beam() {
+        let drawing = draw_beam("src/inputs/19_sample_1.txt", (22, 17), 16, 16, 10);
+
+        assert_eq!(
+            drawing,
+            "\
+########........
+#########.......
+###########.....
+###OOOOOOOOOO...
+###O########O#..
+###O########O###
+###O########O###
+###O########O###
+###O########O###
+###O########O###
+.##O########O###
+..#O########O###
+...OOOOOOOOOO###
+....############
+....############
+.....###########
+"
+        );
+
+        let drawing = draw_beam("src/inputs/19_sample_1.txt", (22, 17), 16, 16, 0);
+        assert!(!drawing.contains('O'));
+    }
+
+    #[test]
+    fn test_tractor_beam() {
+        let mut beam = TractorBeam::new("src/inputs/19_sample_1.txt");
+        assert!(beam.is_in_beam(0, 0));
+        assert!(!beam.is_in_beam(1, 0));
+        assert_eq!(beam.num_program_runs(), 2);
+
+        // Repeated queries are answered from the cache.
+        assert!(beam.is_in_beam(0, 0));
+        assert_eq!(beam.num_program_runs(), 2);
+
+        assert_eq!(beam.edges(20), Some((17, 34)));
+        assert_eq!(beam.edges(12), Some((11, 20)));
+    }
+
+    #[test]
+    fn test_fits_square() {
+        let mut beam = TractorBeam::new("src/inputs/19_sample_1.txt");
+        assert!(beam.fits_square((25, 20), 10));
+        assert!(!beam.fits_square((24, 20), 10));
+        assert!(!beam.fits_square((25, 19), 10));
+        assert!(!beam.fits_square((26, 20), 10));
+        assert!(beam.fits_square((0, 0), 1));
+        assert!(!beam.fits_square((0, 0), 0));
+        assert!(!beam.fits_square((25, 20), 0));
+    }
+
+    #[test]
+    fn test_beam_edges() {
+        let edges: Vec<_> = beam_edges(load_program("src/inputs/19_sample_1.txt"))
+            .take(6)
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (0, 0, 0),
+                (1, 1, 1),
+                (2, 2, 3),
+                (3, 3, 5),
+                (4, 4, 6),
+                (5, 5, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_beam_edges_when_the_beam_runs_out() {
+        // Only (0, 0) is in the beam: outputs whether x + y == 0.
+        let program = computer::parse_program("3,20,3,21,1,20,21,22,1008,22,0,23,4,23,99");
+        let edges: Vec<_> = beam_edges(program).collect();
+        assert_eq!(edges, vec![(0, 0, 0)]);
+    }
+
+    #[test]
+    fn test_steep_beam() {
+        // Outputs whether 5y <= x <= 6y, so the beam starts out well to the right of the diagonal.
+        let program = computer::parse_program(
+            "3,100,3,101,1002,101,5,102,1002,101,6,103,7,100,102,104,7,103,100,105,\
+             1,104,105,106,1007,106,1,107,4,107,99",
+        );
+        let edges: Vec<_> = beam_edges(program.clone()).take(4).collect();
+        assert_eq!(edges, vec![(0, 0, 0), (1, 5, 6), (2, 10, 12), (3, 15, 18)]);
+
+        // A 10x10 square whose top row is y fits once 6y >= 5(y + 9) + 9, i.e. y >= 54,
+        // and its left edge is the left edge of its bottom row.
+        assert_eq!(
+            find_topleft_of_first_bounding_box(10, program),
+            Some(Position(5 * 63, 54))
+        );
+    }
+
     #[test]
     fn test_sample() {
         assert_eq!(
-            find_topleft_of_first_bounding_box(10, "src/inputs/19_sample_1.txt"),
-            Position(25, 20)
+            find_topleft_of_first_bounding_box(10, load_program("src/inputs/19_sample_1.txt")),
+            Some(Position(25, 20))
         );
     }
 }
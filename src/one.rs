@@ -1,13 +1,18 @@
 use crate::util;
 
-pub fn one_a() -> i32 {
-    let masses = util::parse_lines_from_file("src/inputs/1.txt");
-    masses.iter().map(|x| fuel_for_module_one_step(*x)).sum()
+pub fn one_a() -> i64 {
+    let masses: Vec<i64> = util::load(util::input(1), util::parse_lines);
+    masses.into_iter().map(fuel_for_module_one_step).sum()
 }
 
-pub fn one_b() -> i32 {
-    let masses = util::parse_lines_from_file("src/inputs/1.txt");
-    masses.iter().map(|x| fuel_for_module(*x)).sum()
+pub fn one_b() -> i64 {
+    total_fuel(util::load(util::input(1), util::parse_lines))
+}
+
+/// Returns the total fuel required to launch modules with the given `masses`,
+/// taking into account the fuel needed to carry the fuel itself.
+pub fn total_fuel(masses: impl IntoIterator<Item = i64>) -> i64 {
+    masses.into_iter().map(fuel_for_module).sum()
 }
 
 /// Performs one step of the fuel calculation algorithm for a given mass.
@@ -15,9 +20,8 @@ pub fn one_b() -> i32 {
 /// "Fuel required to launch a given module is based on its mass. Specifically, to
 /// find the fuel required for a module, take its mass, divide by three, round
 /// down, and subtract 2."
-fn fuel_for_module_one_step(mass: i32) -> i32 {
-    let divided = mass as f32 / 3.0;
-    divided.trunc() as i32 - 2
+pub fn fuel_for_module_one_step(mass: i64) -> i64 {
+    mass / 3 - 2
 }
 
 /// Calculates fuel for a given mass.
@@ -25,7 +29,7 @@ fn fuel_for_module_one_step(mass: i32) -> i32 {
 /// "Fuel itself requires fuel just like a module - take its mass, divide by
 /// three, round down, and subtract 2. However, that fuel also requires fuel, and
 /// that fuel requires fuel, and so on."
-fn fuel_for_module(mass: i32) -> i32 {
+pub fn fuel_for_module(mass: i64) -> i64 {
     let step_output = fuel_for_module_one_step(mass);
 
     if step_output <= 0 {
@@ -54,6 +58,12 @@ mod test {
         assert_eq!(fuel_for_module(100756), 50346);
     }
 
+    #[test]
+    fn test_total_fuel() {
+        assert_eq!(total_fuel(vec![14, 1969, 100756]), 2 + 966 + 50346);
+        assert_eq!(total_fuel(vec![]), 0);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(one_a(), 3334297);
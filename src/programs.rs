@@ -0,0 +1,127 @@
+use crate::computer::{self, Memory};
+use crate::util::{self, LoadError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The Intcode programs from the puzzle inputs, by name and day.
+const MANIFEST: [(&str, u32); 12] = [
+    ("gravity_assist", 2),
+    ("diagnostic", 5),
+    ("amplifier", 7),
+    ("boost", 9),
+    ("hull_painting_robot", 11),
+    ("arcade", 13),
+    ("repair_droid", 15),
+    ("ascii", 17),
+    ("drone", 19),
+    ("springdroid", 21),
+    ("network_interface", 23),
+    ("cryostasis", 25),
+];
+
+/// A set of Intcode programs that can be loaded by name.
+#[derive(Debug, Default)]
+pub struct Registry {
+    paths: BTreeMap<String, PathBuf>,
+}
+
+impl Registry {
+    /// The programs from this year's puzzles.
+    pub fn embedded() -> Self {
+        // A relative inputs directory is relative to this crate, not to wherever it's being run from.
+        let inputs_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(util::inputs_dir());
+
+        let mut registry = Registry::default();
+        for &(name, day) in MANIFEST.iter() {
+            registry.register(name, inputs_dir.join(format!("{}.txt", day)));
+        }
+        registry
+    }
+
+    /// Every `.txt` or `.intcode` file in `directory`, named after the file without its extension.
+    pub fn from_directory(directory: impl AsRef<Path>) -> io::Result<Self> {
+        let mut registry = Registry::default();
+
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let is_program = matches!(
+                path.extension().and_then(|extension| extension.to_str()),
+                Some("txt") | Some("intcode")
+            );
+
+            if let (true, Some(name)) =
+                (is_program, path.file_stem().and_then(|stem| stem.to_str()))
+            {
+                registry.register(name, &path);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Makes the program in the file at `path` available as `name`, replacing any other program with that name.
+    pub fn register(&mut self, name: &str, path: impl AsRef<Path>) {
+        self.paths
+            .insert(name.to_string(), path.as_ref().to_path_buf());
+    }
+
+    /// The names of all of the registered programs, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.paths.keys().map(String::as_str)
+    }
+
+    /// Returns None if there's no program called `name`, or an error if its file can't be read
+    /// or doesn't hold an Intcode program.
+    pub fn load(&self, name: &str) -> Option<Result<Memory, LoadError>> {
+        self.paths.get(name).map(computer::try_load_program)
+    }
+}
+
+/// Loads one of this year's programs by name, like `load("boost")`.
+/// Panics if there's no program called `name`, or if it can't be loaded.
+pub fn load(name: &str) -> Memory {
+    Registry::embedded()
+        .load(name)
+        .unwrap_or_else(|| panic!("no Intcode program called {}", name))
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded() {
+        let registry = Registry::embedded();
+        assert_eq!(registry.names().count(), MANIFEST.len());
+        assert_eq!(
+            registry.load("gravity_assist").unwrap().unwrap(),
+            computer::load_program(util::input(2))
+        );
+        assert!(registry.load("nonexistent").is_none());
+
+        // The programs are found no matter what directory we're running in.
+        assert!(registry.paths.values().all(|path| path.is_absolute()));
+
+        for name in registry.names() {
+            assert!(!load(name).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_from_directory() {
+        let registry = Registry::from_directory("src/inputs").unwrap();
+        assert_eq!(registry.load("9").unwrap().unwrap(), load("boost"));
+        assert!(registry.load("19_sample_1").unwrap().is_ok());
+        assert!(registry.names().any(|name| name == "25"));
+
+        // Not every .txt file is an Intcode program.
+        let error = registry.load("6").unwrap().unwrap_err();
+        assert!(matches!(error, LoadError::Parse { .. }));
+        assert!(error.to_string().contains("6.txt: line 1: "));
+
+        assert!(Registry::from_directory("src/nonexistent").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+
+/// A color, as (red, green, blue).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+pub const BLACK: Rgb = Rgb(0, 0, 0);
+pub const WHITE: Rgb = Rgb(255, 255, 255);
+pub const GRAY: Rgb = Rgb(128, 128, 128);
+
+/// A rectangular grid of cells that knows how to draw itself.
+pub trait RenderGrid {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+
+    /// Returns the character used to draw the cell at (x, y).
+    fn cell_char(&self, x: usize, y: usize) -> char;
+
+    /// Returns the color used to draw the cell at (x, y).
+    /// By default, blank-looking cells are black and everything else is white.
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.cell_char(x, y) {
+            ' ' | '.' => BLACK,
+            _ => WHITE,
+        }
+    }
+}
+
+/// Draws `grid` as plain text, one line per row.
+pub fn to_text(grid: &impl RenderGrid) -> String {
+    let mut s = String::with_capacity((grid.width() + 1) * grid.height());
+
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            s.push(grid.cell_char(x, y));
+        }
+        s.push('\n');
+    }
+
+    s
+}
+
+/// Draws `grid` as text that's colored with 24-bit ANSI escape codes, for printing to a terminal.
+pub fn to_ansi(grid: &impl RenderGrid) -> String {
+    let mut s = String::new();
+
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let Rgb(r, g, b) = grid.cell_color(x, y);
+            write!(
+                &mut s,
+                "\x1B[38;2;{};{};{}m{}",
+                r,
+                g,
+                b,
+                grid.cell_char(x, y)
+            )
+            .unwrap();
+        }
+        writeln!(&mut s, "\x1B[0m").unwrap();
+    }
+
+    s
+}
+
+/// Prints `grid` to the terminal in color.
+#[cfg(not(tarpaulin_include))]
+pub fn print(grid: &impl RenderGrid) {
+    print!("{}", to_ansi(grid));
+}
+
+/// Draws `grid` as a binary PPM image, with each cell drawn as a `scale`x`scale` square of pixels.
+pub fn to_ppm(grid: &impl RenderGrid, scale: usize) -> Vec<u8> {
+    let (width, height) = (grid.width() * scale, grid.height() * scale);
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    image.reserve(width * height * 3);
+
+    for y in 0..height {
+        for x in 0..width {
+            let Rgb(r, g, b) = grid.cell_color(x / scale, y / scale);
+            image.extend_from_slice(&[r, g, b]);
+        }
+    }
+
+    image
+}
+
+/// Writes `grid` to `path` as a PPM image.
+pub fn write_ppm(grid: &impl RenderGrid, scale: usize, path: &str) -> io::Result<()> {
+    fs::write(path, to_ppm(grid, scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Checkerboard;
+
+    impl RenderGrid for Checkerboard {
+        fn width(&self) -> usize {
+            3
+        }
+
+        fn height(&self) -> usize {
+            2
+        }
+
+        fn cell_char(&self, x: usize, y: usize) -> char {
+            if (x + y) % 2 == 0 {
+                '#'
+            } else {
+                '.'
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(to_text(&Checkerboard), "#.#\n.#.\n");
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        assert_eq!(
+            to_ansi(&Checkerboard).lines().next().unwrap(),
+            "\x1B[38;2;255;255;255m#\x1B[38;2;0;0;0m.\x1B[38;2;255;255;255m#\x1B[0m"
+        );
+    }
+
+    #[test]
+    fn test_to_ppm() {
+        let image = to_ppm(&Checkerboard, 2);
+        let header = b"P6\n6 4\n255\n";
+
+        assert!(image.starts_with(header));
+        assert_eq!(image.len(), header.len() + 6 * 4 * 3);
+
+        // The first row of pixels is white, white, black, black, white, white.
+        assert_eq!(
+            &image[header.len()..header.len() + 18],
+            &[255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::util;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+/// How one part of one day's solution went, for keeping track of performance from run to run.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RunReport {
+    pub day: u32,
+    pub part: char,
+    pub answer: String,
+    /// How long it took to produce `answer`, counting from when the day's previous part was answered.
+    /// So part a's duration includes the time spent reading and parsing the input.
+    pub duration: Duration,
+    /// Which puzzle input the answer came from; see `input_hash()`.
+    pub input_hash: Option<String>,
+}
+
+/// A hash of `day`'s puzzle input, in hex, or None if the day's input is in its source.
+/// The hash is stable from one Rust release to the next, so reports from different runs can be compared.
+pub fn input_hash(day: u32) -> Option<String> {
+    util::read_input(u8::try_from(day).ok()?)
+        .ok()
+        .map(|input| format!("{:016x}", util::fnv1a(input.as_bytes())))
+}
+
+/// Solves each of `days` in order, calling `on_report` as soon as each part is answered.
+/// Returns every part's report.
+pub fn run_days(
+    days: impl IntoIterator<Item = u32>,
+    on_report: &mut dyn FnMut(&RunReport),
+) -> Vec<RunReport> {
+    let mut reports = vec![];
+
+    for day in days {
+        let input_hash = input_hash(day);
+        let mut start = Instant::now();
+
+        crate::solve(day, &mut |part, answer| {
+            let report = RunReport {
+                day,
+                part,
+                answer,
+                duration: start.elapsed(),
+                input_hash: input_hash.clone(),
+            };
+            on_report(&report);
+            reports.push(report);
+            start = Instant::now();
+        });
+    }
+
+    reports
+}
+
+/// Writes `reports` to `writer` as a JSON array.
+#[cfg(feature = "serde")]
+pub fn write_json(reports: &[RunReport], writer: impl std::io::Write) -> serde_json::Result<()> {
+    serde_json::to_writer_pretty(writer, reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_days() {
+        let mut seen = vec![];
+        let reports = run_days(vec![1, 4], &mut |report| seen.push(report.clone()));
+        assert_eq!(reports, seen);
+
+        let answers: Vec<_> = reports
+            .iter()
+            .map(|report| (report.day, report.part, report.answer.as_str()))
+            .collect();
+        assert_eq!(
+            answers,
+            vec![
+                (1, 'a', "3334297"),
+                (1, 'b', "4998565"),
+                (4, 'a', "931"),
+                (4, 'b', "609")
+            ]
+        );
+
+        assert_eq!(reports[0].input_hash, input_hash(1));
+        assert_eq!(reports[0].input_hash.as_ref().map(String::len), Some(16));
+        // Day 4's input is in its source.
+        assert_eq!(reports[2].input_hash, None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_write_json() {
+        let report = RunReport {
+            day: 6,
+            part: 'b',
+            answer: "4".to_string(),
+            duration: Duration::from_millis(1500),
+            input_hash: None,
+        };
+
+        let mut json = vec![];
+        write_json(&[report], &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "day": 6,
+                "part": "b",
+                "answer": "4",
+                "duration": {"secs": 1, "nanos": 500_000_000},
+                "input_hash": null
+            }])
+        );
+    }
+}
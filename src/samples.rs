@@ -0,0 +1,221 @@
+use crate::computer;
+use crate::solver::Solver;
+use crate::util;
+use crate::{eighteen, fourteen, nineteen, six, ten, twenty, twenty_four, twenty_two};
+use itertools::Itertools;
+use std::error;
+use std::fmt;
+
+/// One of the examples from a puzzle's description, along with the answer that the description gives for it.
+pub struct Sample {
+    pub day: u32,
+    pub part: char,
+    /// Lives in `util::inputs_dir()`.
+    pub file: &'static str,
+    pub expected: &'static str,
+    /// Computes the answer from the contents of `file`.
+    solve: fn(&str) -> String,
+}
+
+fn part_a<S: Solver>(input: &str) -> String {
+    S::part_a(&parse::<S>(input)).to_string()
+}
+
+fn part_b<S: Solver>(input: &str) -> String {
+    S::part_b(&parse::<S>(input)).to_string()
+}
+
+fn parse<S: Solver>(input: &str) -> S::Parsed {
+    S::parse(input).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn total_orbits(input: &str) -> String {
+    six::total_orbits(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .to_string()
+}
+
+fn transfers_from_you_to_santa(input: &str) -> String {
+    six::min_transfers(input, "YOU", "SAN")
+        .unwrap_or_else(|e| panic!("{}", e))
+        .expect("YOU and SAN should both be in orbit")
+        .to_string()
+}
+
+fn best_station_visible_count(input: &str) -> String {
+    ten::best_station(&ten::Grid::parse(input).unwrap_or_else(|e| panic!("{}", e)))
+        .visible_count
+        .to_string()
+}
+
+fn shortest_path_through_vault(input: &str) -> String {
+    eighteen::shortest_path(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .to_string()
+}
+
+/// "In the example above, this would be 250020."
+fn closest_ten_by_ten_square(input: &str) -> String {
+    nineteen::closest_square(computer::parse_program(input), 10).to_string()
+}
+
+/// "For example, if you deal a deck of 10 cards..., the resulting deck would be..."
+fn shuffled_deck_of_ten(input: &str) -> String {
+    twenty_two::shuffle(
+        10,
+        &twenty_two::parse_instructions(input).unwrap_or_else(|e| panic!("{}", e)),
+    )
+    .iter()
+    .join(" ")
+}
+
+/// "To calculate the biodiversity rating for this layout, consider each tile left-to-right in the top row..."
+/// A bitboard's bits are laid out in that order, so it's its own rating.
+fn biodiversity_rating(input: &str) -> String {
+    twenty_four::parse_bitboard(input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .to_string()
+}
+
+fn first_repeated_rating(input: &str) -> String {
+    twenty_four::bitboard::first_repeated_rating(
+        twenty_four::parse_bitboard(input).unwrap_or_else(|e| panic!("{}", e)),
+    )
+    .to_string()
+}
+
+/// "After 10 minutes, a total of 99 bugs are present."
+fn bugs_after_ten_minutes(input: &str) -> String {
+    twenty_four::bitboard::num_bugs_after(
+        twenty_four::parse_bitboard(input).unwrap_or_else(|e| panic!("{}", e)),
+        10,
+    )
+    .to_string()
+}
+
+macro_rules! samples {
+    ($(($day:expr, $part:expr, $file:expr, $expected:expr, $solve:expr)),* $(,)?) => {
+        &[$(Sample {
+            day: $day,
+            part: $part,
+            file: $file,
+            expected: $expected,
+            solve: $solve,
+        }),*]
+    };
+}
+
+#[rustfmt::skip]
+pub const SAMPLES: &[Sample] = samples![
+    (6, 'a', "6_sample.txt", "42", total_orbits),
+    (6, 'b', "6_sample_2.txt", "4", transfers_from_you_to_santa),
+    (10, 'a', "10_sample_small.txt", "8", best_station_visible_count),
+    (10, 'a', "10_sample_1.txt", "33", best_station_visible_count),
+    (14, 'a', "14_sample_1.txt", "31", part_a::<fourteen::Solution>),
+    (14, 'a', "14_sample_2.txt", "13312", part_a::<fourteen::Solution>),
+    (14, 'a', "14_sample_3.txt", "165", part_a::<fourteen::Solution>),
+    (14, 'a', "14_sample_4.txt", "180697", part_a::<fourteen::Solution>),
+    (14, 'b', "14_sample_2.txt", "82892753", part_b::<fourteen::Solution>),
+    (14, 'b', "14_sample_4.txt", "5586022", part_b::<fourteen::Solution>),
+    (18, 'a', "18_sample_1.txt", "8", shortest_path_through_vault),
+    (18, 'a', "18_sample_2.txt", "136", shortest_path_through_vault),
+    (18, 'a', "18_sample_3.txt", "86", shortest_path_through_vault),
+    (18, 'a', "18_sample_4.txt", "81", shortest_path_through_vault),
+    (19, 'b', "19_sample_1.txt", "250020", closest_ten_by_ten_square),
+    (20, 'a', "20_sample_1.txt", "23", part_a::<twenty::Solution>),
+    (20, 'a', "20_sample_2.txt", "58", part_a::<twenty::Solution>),
+    (20, 'b', "20_sample_1.txt", "26", part_b::<twenty::Solution>),
+    (20, 'b', "20_sample_3.txt", "396", part_b::<twenty::Solution>),
+    (22, 'a', "22_sample_1.txt", "0 3 6 9 2 5 8 1 4 7", shuffled_deck_of_ten),
+    (22, 'a', "22_sample_2.txt", "3 0 7 4 1 8 5 2 9 6", shuffled_deck_of_ten),
+    (22, 'a', "22_sample_3.txt", "6 3 0 7 4 1 8 5 2 9", shuffled_deck_of_ten),
+    (24, 'a', "24_sample_1.txt", "2129920", biodiversity_rating),
+    (24, 'a', "24_sample_2.txt", "2129920", first_repeated_rating),
+    (24, 'b', "24_sample_2.txt", "99", bugs_after_ten_minutes),
+];
+
+/// Why `check_samples()` didn't pass.
+#[derive(Debug, PartialEq)]
+pub enum CheckError {
+    /// There aren't any samples for the day that was asked for, so nothing was checked.
+    NoSamples(u32),
+    /// `failed` of the `checked` samples didn't produce their expected answers.
+    Failed { failed: usize, checked: usize },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::NoSamples(day) => write!(f, "day {} has no samples", day),
+            CheckError::Failed { failed, checked } => {
+                write!(f, "{} of {} samples failed", failed, checked)
+            }
+        }
+    }
+}
+
+impl error::Error for CheckError {}
+
+/// Runs every sample for `day`, or for every day if `day` is None, printing how each one went.
+/// Returns an error if any of them didn't produce its expected answer, or if there weren't any to run.
+pub fn check_samples(day: Option<u32>) -> Result<(), CheckError> {
+    let samples: Vec<&Sample> = SAMPLES
+        .iter()
+        .filter(|sample| day.is_none() || day == Some(sample.day))
+        .collect();
+    if let (Some(day), true) = (day, samples.is_empty()) {
+        return Err(CheckError::NoSamples(day));
+    }
+
+    let mut failed = 0;
+    for sample in &samples {
+        let input = match util::read_file(util::inputs_dir().join(sample.file)) {
+            Ok(input) => input,
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "{}{} {}: FAILED, {}",
+                    sample.day, sample.part, sample.file, e
+                );
+                continue;
+            }
+        };
+        let actual = (sample.solve)(&input);
+
+        if actual == sample.expected {
+            println!("{}{} {}: ok", sample.day, sample.part, sample.file);
+        } else {
+            failed += 1;
+            println!(
+                "{}{} {}: FAILED, expected {} but got {}",
+                sample.day, sample.part, sample.file, sample.expected, actual
+            );
+        }
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(CheckError::Failed {
+            failed,
+            checked: samples.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_samples() {
+        assert_eq!(check_samples(None), Ok(()));
+        assert_eq!(check_samples(Some(14)), Ok(()));
+        assert_eq!(check_samples(Some(19)), Ok(()));
+        assert_eq!(check_samples(Some(25)), Err(CheckError::NoSamples(25)));
+        assert_eq!(
+            CheckError::NoSamples(25).to_string(),
+            "day 25 has no samples"
+        );
+    }
+}
@@ -1,32 +1,116 @@
 use itertools::Itertools;
 use rayon::prelude::*;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
 
-use crate::computer;
 use crate::computer::{Computer, HaltReason, Memory};
+use crate::programs;
 
 pub fn seven_a() -> i64 {
-    let memory = computer::load_program("src/inputs/7.txt");
+    let memory = programs::load("amplifier");
     largest_output_for_program_one_shot(memory)
 }
 
 pub fn seven_b() -> i64 {
-    let memory = computer::load_program("src/inputs/7.txt");
+    let memory = programs::load("amplifier");
     largest_output_for_program_feedback(memory)
 }
 
+/// How the amplifiers' outputs are connected to their inputs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Wiring {
+    /// "The first amplifier's input value is 0, and the last amplifier's output
+    /// leads to your ship's thrusters."
+    OneShot,
+    /// "The output from amplifier E is now connected into amplifier A's input."
+    Feedback,
+}
+
+/// A series of amplifiers, each running its own copy of the same amplifier controller software.
+/// There's one amplifier per phase setting.
+pub struct AmplifierChain {
+    memory: Memory,
+    wiring: Wiring,
+}
+
+impl AmplifierChain {
+    pub fn new(memory: Memory, wiring: Wiring) -> Self {
+        AmplifierChain { memory, wiring }
+    }
+
+    /// Runs one amplifier per entry in `phase_settings`, and returns the signal sent to the thrusters.
+    pub fn run(&self, phase_settings: &[i64]) -> i64 {
+        match self.wiring {
+            Wiring::OneShot => {
+                run_amplifier_controller_software_one_shot(self.memory.clone(), phase_settings)
+            }
+            Wiring::Feedback => {
+                run_amplifier_controller_software_feedback(self.memory.clone(), phase_settings)
+            }
+        }
+    }
+
+    /// Like `run()`, but runs each amplifier on its own thread, with amplifiers passing signals
+    /// to one another over channels.
+    ///
+    /// Each amplifier waits for a signal, runs until it produces an output, and passes that
+    /// output along to the next amplifier, which is how the amplifier controller software behaves.
+    pub fn run_threaded(&self, phase_settings: &[i64]) -> i64 {
+        let num_amplifiers = phase_settings.len();
+        let (senders, receivers): (Vec<Sender<i64>>, Vec<Receiver<i64>>) =
+            (0..num_amplifiers).map(|_| mpsc::channel()).unzip();
+
+        let handles: Vec<_> = receivers
+            .into_iter()
+            .zip(phase_settings.iter())
+            .enumerate()
+            .map(|(i, (receiver, &phase_setting))| {
+                let sender = if i + 1 < num_amplifiers {
+                    senders[i + 1].clone()
+                } else if self.wiring == Wiring::Feedback {
+                    senders[0].clone()
+                } else {
+                    // The last amplifier's output goes to the thrusters, which we don't need to model.
+                    mpsc::channel().0
+                };
+
+                let memory = self.memory.clone();
+                thread::spawn(move || {
+                    run_amplifier_on_thread(memory, phase_setting, receiver, sender)
+                })
+            })
+            .collect();
+
+        // "To start the process, a 0 signal is sent to amplifier A's input exactly once."
+        senders[0].send(0).unwrap();
+        drop(senders);
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .last()
+            .unwrap()
+    }
+
+    /// Tries every ordering of `phase_settings`, and returns the largest signal that can be sent
+    /// to the thrusters along with the phase settings that produce it.
+    /// The orderings are tried in parallel, each with its own freshly-built amplifiers.
+    pub fn best_phase_settings(&self, phase_settings: &[i64]) -> (i64, Vec<i64>) {
+        permutations(phase_settings.to_vec())
+            .into_par_iter()
+            .map(|permutation| (self.run(&permutation), permutation))
+            .max_by_key(|&(signal, _)| signal)
+            .unwrap()
+    }
+}
+
 /// "Your job is to find the largest output signal that can be sent to the
 /// thrusters by trying every possible combination of phase settings on the
 /// amplifiers."
 fn largest_output_for_program_one_shot(memory: Memory) -> i64 {
-    let phase_setting_permutations = permutations(vec![0, 1, 2, 3, 4]);
-
-    phase_setting_permutations
-        .into_iter()
-        .map(|phase_settings| {
-            run_amplifier_controller_software_one_shot(memory.clone(), phase_settings)
-        })
-        .max()
-        .unwrap()
+    AmplifierChain::new(memory, Wiring::OneShot)
+        .best_phase_settings(&[0, 1, 2, 3, 4])
+        .0
 }
 
 /// "There are five amplifiers connected in series; each one receives an input
@@ -35,7 +119,7 @@ fn largest_output_for_program_one_shot(memory: Memory) -> i64 {
 /// amplifier's output leads to the third amplifier's input, and so on. The first
 /// amplifier's input value is 0, and the last amplifier's output leads to your
 /// ship's thrusters."
-fn run_amplifier_controller_software_one_shot(memory: Memory, phase_settings: Vec<i64>) -> i64 {
+fn run_amplifier_controller_software_one_shot(memory: Memory, phase_settings: &[i64]) -> i64 {
     phase_settings.iter().fold(0, |acc, &phase_setting| {
         let mut computer = Computer::new(memory.clone());
         computer.push_input(phase_setting);
@@ -49,22 +133,16 @@ fn run_amplifier_controller_software_one_shot(memory: Memory, phase_settings: Ve
 /// "Your job is to find the largest output signal that can be sent to the
 /// thrusters using the new phase settings and feedback loop arrangement."
 fn largest_output_for_program_feedback(memory: Memory) -> i64 {
-    let phase_setting_permutations = permutations(vec![5, 6, 7, 8, 9]);
-
-    phase_setting_permutations
-        .into_par_iter()
-        .map(|phase_settings| {
-            run_amplifier_controller_software_feedback(memory.clone(), phase_settings)
-        })
-        .max()
-        .unwrap()
+    AmplifierChain::new(memory, Wiring::Feedback)
+        .best_phase_settings(&[5, 6, 7, 8, 9])
+        .0
 }
 
 /// "Most of the amplifiers are connected as they were before; amplifier A's
 /// output is connected to amplifier B's input, and so on. However, the output
 /// from amplifier E is now connected into amplifier A's input. This creates the
 /// feedback loop: the signal will be sent through the amplifiers many times."
-fn run_amplifier_controller_software_feedback(memory: Memory, phase_settings: Vec<i64>) -> i64 {
+fn run_amplifier_controller_software_feedback(memory: Memory, phase_settings: &[i64]) -> i64 {
     let mut computers = phase_settings
         .iter()
         .map(|&phase_setting| {
@@ -105,6 +183,36 @@ fn run_amplifier_controller_software_feedback(memory: Memory, phase_settings: Ve
     }
 }
 
+/// Runs a single amplifier that receives its input signals from `receiver` and sends its output signals to `sender`.
+/// Returns the last signal the amplifier produced.
+fn run_amplifier_on_thread(
+    memory: Memory,
+    phase_setting: i64,
+    receiver: Receiver<i64>,
+    sender: Sender<i64>,
+) -> i64 {
+    let mut computer = Computer::new(memory);
+    computer.push_input(phase_setting);
+
+    let mut last_output = 0;
+
+    // If the previous amplifier has shut down, there won't be any more signals coming our way.
+    while let Ok(signal) = receiver.recv() {
+        computer.push_input(signal);
+
+        if computer.run(HaltReason::Output) == HaltReason::Exit {
+            break;
+        }
+
+        last_output = computer.pop_output().unwrap();
+
+        // The next amplifier may already have halted, in which case nobody's listening.
+        let _ = sender.send(last_output);
+    }
+
+    last_output
+}
+
 fn permutations(x: Vec<i64>) -> Vec<Vec<i64>> {
     let length = x.len();
     x.into_iter().permutations(length).collect()
@@ -173,6 +281,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_amplifier_chain() {
+        let memory = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+
+        let chain = AmplifierChain::new(memory, Wiring::OneShot);
+        assert_eq!(chain.run(&[4, 3, 2, 1, 0]), 43210);
+        assert_eq!(chain.run(&[1, 0]), 10);
+        assert_eq!(chain.best_phase_settings(&[0, 1, 2]), (210, vec![2, 1, 0]));
+    }
+
+    #[test]
+    fn test_run_threaded() {
+        let one_shot = AmplifierChain::new(
+            vec![
+                3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+            ],
+            Wiring::OneShot,
+        );
+        assert_eq!(one_shot.run_threaded(&[4, 3, 2, 1, 0]), 43210);
+
+        let feedback = AmplifierChain::new(
+            vec![
+                3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28,
+                -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+            ],
+            Wiring::Feedback,
+        );
+        assert_eq!(feedback.run_threaded(&[9, 8, 7, 6, 5]), 139629729);
+
+        let puzzle = AmplifierChain::new(programs::load("amplifier"), Wiring::Feedback);
+        for phase_settings in permutations(vec![5, 6, 7, 8, 9]) {
+            assert_eq!(
+                puzzle.run_threaded(&phase_settings),
+                puzzle.run(&phase_settings)
+            );
+        }
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(seven_a(), 117312);
@@ -1,28 +1,28 @@
-use crate::computer;
 use crate::computer::{Computer, HaltReason};
-use itertools::Itertools;
+use crate::grid::{self, Grid};
+use crate::programs;
+use crate::render::RenderGrid;
+use crate::util::Direction;
 use std::collections::{HashMap, HashSet};
-
-type Position = (i32, i32);
-type Path = Vec<(Option<Turn>, Position)>;
-type Segment = (Turn, usize);
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
-
-#[derive(Copy, Clone, PartialEq)]
-enum Spot {
+use std::error;
+use std::fmt;
+use std::io::{self, Write};
+use tracing::instrument;
+
+pub type Position = (i32, i32);
+/// Each step the robot takes along the scaffold, along with the turn it made before taking that step, if any.
+pub type Path = Vec<(Option<Turn>, Position)>;
+/// A turn followed by a number of steps forward, like (Right, 8).
+pub type Segment = (Turn, usize);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Spot {
     Scaffold,
     Empty,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-enum Turn {
+pub enum Turn {
     Left,
     Right,
 }
@@ -39,33 +39,19 @@ impl Robot {
         let mut turn_taken = None;
 
         if !ship.spot_is_on_ship(try_x, try_y)
-            || ship.get(try_x as usize, try_y as usize) == Spot::Empty
+            || ship.map[(try_x as usize, try_y as usize)] == Spot::Empty
         {
             // If we keep going forward, we'll fall off of a scaffold or off of the ship entirely. Time to turn.
             // Find the first direction that'll take us to a scaffold.
-            let directions_to_try: [(Turn, Direction); 2] = match self.direction {
-                Direction::North => [
-                    (Turn::Left, Direction::West),
-                    (Turn::Right, Direction::East),
-                ],
-                Direction::East => [
-                    (Turn::Left, Direction::North),
-                    (Turn::Right, Direction::South),
-                ],
-                Direction::South => [
-                    (Turn::Left, Direction::East),
-                    (Turn::Right, Direction::West),
-                ],
-                Direction::West => [
-                    (Turn::Left, Direction::South),
-                    (Turn::Right, Direction::North),
-                ],
-            };
+            let directions_to_try = [
+                (Turn::Left, self.direction.turn_left()),
+                (Turn::Right, self.direction.turn_right()),
+            ];
 
             for &(turn, direction) in directions_to_try.iter() {
                 let (new_x, new_y) = one_position_ahead(&direction, &self.position);
                 if ship.spot_is_on_ship(new_x, new_y)
-                    && ship.get(new_x as usize, new_y as usize) == Spot::Scaffold
+                    && ship.map[(new_x as usize, new_y as usize)] == Spot::Scaffold
                 {
                     self.direction = direction;
                     turn_taken = Some(turn);
@@ -82,123 +68,135 @@ impl Robot {
 
 /// Returns the Position that's one step ahead of `position` in `direction`.
 fn one_position_ahead(direction: &Direction, position: &Position) -> Position {
-    match direction {
-        Direction::North => (position.0, position.1 - 1),
-        Direction::East => (position.0 + 1, position.1),
-        Direction::South => (position.0, position.1 + 1),
-        Direction::West => (position.0 - 1, position.1),
-    }
+    let (dx, dy) = direction.offset();
+    (position.0 + dx, position.1 + dy)
 }
-struct ShipMap {
-    map: Vec<Spot>,
-    width: usize,
-    height: usize,
+
+/// The ship's scaffolding, as seen by the ASCII program's cameras.
+#[derive(Debug)]
+pub struct ShipMap {
+    map: Grid<Spot>,
 }
 
 impl ShipMap {
     /// Returns true if (x, y) is within the bounds of the ship, false otherwise.
     fn spot_is_on_ship(&self, x: i32, y: i32) -> bool {
-        x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32
+        x >= 0 && x < self.map.width() as i32 && y >= 0 && y < self.map.height() as i32
     }
+}
 
-    /// Returns an Iterator over each (Position, Spot) pair on the ship map.
-    fn walk_map<'a>(&'a self) -> impl Iterator<Item = (Position, Spot)> + 'a {
-        let width = self.width;
-        self.map
-            .iter()
-            .enumerate()
-            .map(move |(i, &spot)| (((i % width) as i32, (i / width) as i32), spot))
+impl RenderGrid for ShipMap {
+    fn width(&self) -> usize {
+        self.map.width()
     }
 
-    #[cfg(not(tarpaulin_include))]
-    /// Draws the ship and robot to the screen.
-    fn _draw(&self, robot: &Robot) {
-        for ((x, y), spot) in self.walk_map() {
-            if x == 0 {
-                println!();
-            }
-
-            if y == robot.position.1 && x == robot.position.0 {
-                print!("R");
-            } else {
-                print!(
-                    "{}",
-                    match spot {
-                        Spot::Scaffold => "#",
-                        Spot::Empty => ".",
-                    }
-                );
-            }
-        }
+    fn height(&self) -> usize {
+        self.map.height()
     }
 
-    /// Returns the Spot at (x, y).
-    fn get(&self, x: usize, y: usize) -> Spot {
-        self.map[y * self.width + x]
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        match self.map[(x, y)] {
+            Spot::Scaffold => '#',
+            Spot::Empty => '.',
+        }
     }
 }
 
+#[instrument(name = "parse", level = "debug", skip_all)]
 fn load_level() -> (ShipMap, Robot) {
-    let memory = computer::load_program("src/inputs/17.txt");
+    let memory = programs::load("ascii");
     let mut computer = Computer::new(memory);
     computer.run(HaltReason::Exit);
 
-    let mut x = 0;
-    let mut y = 0;
-    let mut width = 0;
-    let mut map = vec![];
-    let mut robot = None;
+    let camera_output: String = std::iter::from_fn(|| computer.pop_output())
+        .map(|output| output as u8 as char)
+        .collect();
 
-    while let Some(output) = computer.pop_output() {
-        match output as u8 as char {
-            '#' => map.push(Spot::Scaffold),
-            '.' => map.push(Spot::Empty),
-            '\n' => {
-                width = x.max(width);
-                x = 0;
-                y += 1;
-                continue;
-            }
-            '^' | '>' | 'v' | '<' => {
-                map.push(Spot::Scaffold);
-                robot = Some(Robot {
-                    position: (x, y),
-                    direction: match output as u8 as char {
-                        '^' => Direction::North,
-                        '>' => Direction::East,
-                        'v' => Direction::South,
-                        '<' => Direction::West,
-                        _ => unreachable!(),
-                    },
-                });
+    parse_camera_frame(&camera_output).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Everything that can go wrong when parsing a frame of the cameras' output.
+#[derive(Debug, PartialEq)]
+enum FrameError {
+    /// The frame is empty, or its lines aren't all the same width.
+    /// Line numbers count from the frame's first non-blank line.
+    Shape(grid::ParseError),
+    UnexpectedCharacter(char, Position),
+    MissingRobot,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Shape(e) => write!(f, "{}", e),
+            FrameError::UnexpectedCharacter(c, (x, y)) => {
+                write!(f, "unexpected character {:?} at ({}, {})", c, x, y)
             }
+            FrameError::MissingRobot => write!(f, "there's no robot in the frame"),
+        }
+    }
+}
 
-            _ => unreachable!(),
-        };
+impl error::Error for FrameError {}
+
+/// Parses a frame of camera output like
+///
+/// ```text
+/// ..#..........
+/// ..#..........
+/// #######...###
+/// #.#...#...#.#
+/// #############
+/// ..#...#...#..
+/// ..#####...^..
+/// ```
+///
+/// into a ShipMap and the robot on it. Blank lines before and after the frame are ignored,
+/// so it doesn't matter how many newlines the program outputs after it.
+fn parse_camera_frame(frame: &str) -> Result<(ShipMap, Robot), FrameError> {
+    let lines: Vec<&str> = frame.lines().collect();
+    let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(0);
+    let last = lines.iter().rposition(|line| !line.is_empty()).unwrap_or(0);
+    let frame = lines.get(first..=last).unwrap_or(&[]).join("\n");
+
+    let mut robot = None;
+    let mut unexpected_character = None;
+    let map = Grid::parse(&frame, |c, (x, y)| match c {
+        '#' => Spot::Scaffold,
+        '^' | '>' | 'v' | '<' => {
+            robot = Some(Robot {
+                position: (x as i32, y as i32),
+                direction: match c {
+                    '^' => Direction::North,
+                    '>' => Direction::East,
+                    'v' => Direction::South,
+                    _ => Direction::West,
+                },
+            });
+            Spot::Scaffold
+        }
+        '.' => Spot::Empty,
+        _ => {
+            unexpected_character.get_or_insert((c, (x as i32, y as i32)));
+            Spot::Empty
+        }
+    })
+    .map_err(FrameError::Shape)?;
 
-        x += 1;
+    if let Some((c, position)) = unexpected_character {
+        return Err(FrameError::UnexpectedCharacter(c, position));
     }
 
-    (
-        ShipMap {
-            map,
-            width: width as usize,
-            height: (y - 1) as usize,
-        },
-        robot.unwrap(),
-    )
+    let robot = robot.ok_or(FrameError::MissingRobot)?;
+    Ok((ShipMap { map }, robot))
 }
 
 fn find_path(ship: &ShipMap, mut robot: Robot) -> Path {
     let mut unvisited_scaffolds: HashSet<Position> = ship
-        .walk_map()
-        .filter_map(|(position, spot)| {
-            if spot == Spot::Scaffold {
-                Some(position)
-            } else {
-                None
-            }
-        })
+        .map
+        .cells()
+        .filter(|(_, &spot)| spot == Spot::Scaffold)
+        .map(|((x, y), _)| (x as i32, y as i32))
         .collect();
 
     unvisited_scaffolds.remove(&robot.position);
@@ -239,158 +237,219 @@ fn find_intersections(ship: &ShipMap, robot: Robot) -> Vec<Position> {
         .collect()
 }
 
+/// A place where two scaffold lines cross.
+#[derive(Debug, PartialEq)]
+pub struct Intersection {
+    pub position: Position,
+    /// "The alignment parameter for the scaffold intersection [...] is the distance
+    /// between its left edge and the left edge of the view multiplied by the distance
+    /// between its top edge and the top edge of the view."
+    pub alignment_parameter: i32,
+}
+
+/// Returns the parsed ship map, along with each of its scaffold intersections, sorted by position.
+pub fn intersections() -> (ShipMap, Vec<Intersection>) {
+    let (ship, robot) = load_level();
+
+    let mut intersections: Vec<Intersection> = find_intersections(&ship, robot)
+        .into_iter()
+        .map(|(x, y)| Intersection {
+            position: (x, y),
+            alignment_parameter: x * y,
+        })
+        .collect();
+    intersections.sort_by_key(|intersection| (intersection.position.1, intersection.position.0));
+
+    (ship, intersections)
+}
+
 /// "What is the sum of the alignment parameters for the scaffold intersections?"
 pub fn seventeen_a() -> i32 {
-    let (ship, robot) = load_level();
-    let intersections = find_intersections(&ship, robot);
-    intersections.iter().fold(0, |acc, &(x, y)| acc + x * y)
+    let (_, intersections) = intersections();
+    intersections
+        .iter()
+        .map(|intersection| intersection.alignment_parameter)
+        .sum()
 }
 
 /// Takes a path, returns a Vec of tuples like [(Right, 8), (Left, 4), ..]
-fn path_to_segments(path: &[(Option<Turn>, Position)]) -> Vec<Segment> {
+/// Returns None if the path is empty or doesn't start with a turn, since every movement the robot
+/// can be told to make starts with one.
+fn path_to_segments(path: &[(Option<Turn>, Position)]) -> Option<Vec<Segment>> {
     let mut segments = vec![];
-    let mut turn = None;
+    let (first_turn, _) = path.first()?;
+    let mut turn = (*first_turn)?;
     let mut distance = 0;
 
     for (maybe_turn, _) in path {
         if let Some(new_turn) = maybe_turn {
-            if let Some(turn) = turn {
+            if distance > 0 {
                 segments.push((turn, distance));
             }
 
-            turn = Some(*new_turn);
+            turn = *new_turn;
             distance = 1;
         } else {
             distance += 1;
         }
     }
 
-    segments.push((turn.unwrap(), distance));
+    segments.push((turn, distance));
 
-    segments
+    Some(segments)
 }
 
-/// Finds Returns a Vec of the top 20 most popular windows in `segments`.
-/// If `segments` looks like [(R, 8), (R, 4), (L, 12), (R, 5), (L, 20)],
-/// then a window can look like [(R, 4), (L, 12)], or [(L, 12), (R, 5), (L, 20)], etc.
-fn most_popular_segment_chunks(segments: &[Segment]) -> Vec<Vec<Segment>> {
-    let mut window_frequencies = HashMap::new();
+// "Each function can contain at most 20 characters, not counting the newline."
+// The same limit applies to the main movement routine.
+const MAX_ASCII_LENGTH: usize = 20;
 
-    for window_size in 2..5 {
-        for window in segments.windows(window_size) {
-            let entry = window_frequencies.entry(window.to_vec()).or_insert(0);
-            *entry += 1;
-        }
-    }
+// "The main routine may only call the movement functions: A, B, or C."
+const NUM_MOVEMENT_FUNCTIONS: usize = 3;
 
-    window_frequencies
-        .into_iter()
-        .sorted_by_key(|(window, count)| window.len() * count)
-        .map(|(chunk, _)| chunk)
-        .rev()
-        .take(20)
+/// Spells out a movement function the way the robot expects it, e.g. "R,8,L,4".
+pub fn function_to_ascii(function: &[Segment]) -> String {
+    function
+        .iter()
+        .map(|&(turn, distance)| {
+            let turn = if turn == Turn::Left { 'L' } else { 'R' };
+            format!("{},{}", turn, distance)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Spells out a main movement routine the way the robot expects it, e.g. "A,B,A,C".
+pub fn routine_to_ascii(routine: &[usize]) -> String {
+    routine
+        .iter()
+        .map(|&index| ((b'A' + index as u8) as char).to_string())
         .collect::<Vec<_>>()
+        .join(",")
 }
 
-/// Returns Some(segments_painted_by_chunks) if `chunks` can be used to fully paint the path
-/// described by `segments`, None otherwise.
-fn paint_segments_with_chunks(
+/// Tries to finish building a main routine that fully covers `segments`, given the movement
+/// functions and routine built so far. Returns true if it succeeded, false otherwise;
+/// on failure, `functions` and `routine` are left as they were.
+fn factorize_segments(
     segments: &[Segment],
-    chunks: &[Vec<Segment>],
-    painted_segments: &mut Vec<Vec<Segment>>,
-) -> Option<Vec<Vec<Segment>>> {
+    functions: &mut Vec<Vec<Segment>>,
+    routine: &mut Vec<usize>,
+) -> bool {
     if segments.is_empty() {
-        return Some(painted_segments.clone());
+        return true;
     }
 
-    for chunk in chunks {
-        if segments.starts_with(chunk) {
-            painted_segments.push(chunk.clone());
+    // Each call in the routine takes a letter and a comma, but there's no trailing comma.
+    if 2 * (routine.len() + 1) - 1 > MAX_ASCII_LENGTH {
+        return false;
+    }
 
-            if let Some(painted_path) =
-                paint_segments_with_chunks(&segments[chunk.len()..], chunks, painted_segments)
-            {
-                return Some(painted_path);
+    // Try each of the functions we've already got.
+    for i in 0..functions.len() {
+        if segments.starts_with(&functions[i]) {
+            routine.push(i);
+            if factorize_segments(&segments[functions[i].len()..], functions, routine) {
+                return true;
             }
+            routine.pop();
+        }
+    }
 
-            painted_segments.pop();
+    // Try each new function that starts here and fits within the length limit.
+    if functions.len() < NUM_MOVEMENT_FUNCTIONS {
+        for length in 1..=segments.len() {
+            let candidate = &segments[..length];
+            if function_to_ascii(candidate).len() > MAX_ASCII_LENGTH {
+                break;
+            }
+            if functions.iter().any(|function| function == candidate) {
+                continue;
+            }
+
+            functions.push(candidate.to_vec());
+            routine.push(functions.len() - 1);
+            if factorize_segments(&segments[length..], functions, routine) {
+                return true;
+            }
+            routine.pop();
+            functions.pop();
         }
     }
-    None
+
+    false
 }
 
-/// Returns a tuple of (vec_of_three_movement_functions, vec_of_indexes_into_first_vec).
-fn movement_functions_and_path(
-    segments: &[Segment],
-    chunks: Vec<Vec<Segment>>,
-) -> (Vec<Vec<Segment>>, Vec<usize>) {
-    let painted_path = chunks
-        .iter()
-        .cloned()
-        // We're making three movement functions: "A", "B", and "C".
-        .combinations(3)
-        .find_map(|chunks| paint_segments_with_chunks(segments, &chunks, &mut vec![]))
-        .unwrap();
-
-    let movement_functions: Vec<Vec<Segment>> = painted_path.iter().unique().cloned().collect();
-    let indexes_path = painted_path
-        .iter()
-        .map(|chunk| movement_functions.iter().position(|x| x == chunk).unwrap())
-        .collect();
+/// Compresses `path` into a tuple of (vec_of_up_to_three_movement_functions, main_routine),
+/// where the main routine is a Vec of indexes into the first Vec.
+/// Returns None if `path` can't be covered by movement functions and a main routine that
+/// fit within the robot's length limits, or if it doesn't start with a turn.
+pub fn compress_path(path: &[(Option<Turn>, Position)]) -> Option<(Vec<Vec<Segment>>, Vec<usize>)> {
+    let segments = path_to_segments(path)?;
+    let mut functions = vec![];
+    let mut routine = vec![];
+
+    if factorize_segments(&segments, &mut functions, &mut routine) {
+        Some((functions, routine))
+    } else {
+        None
+    }
+}
 
-    (movement_functions, indexes_path)
+/// Feeds `line` to `computer` as ASCII, followed by a newline.
+fn push_ascii_line(computer: &mut Computer, line: &str) {
+    for byte in line.bytes() {
+        computer.push_input(byte as i64);
+    }
+    computer.push_input(10);
 }
 
 pub fn seventeen_b() -> i64 {
+    run_vacuum_robot(None)
+}
+
+/// Like `seventeen_b()`, but turns on the robot's continuous video feed and shows it on stdout as it comes in.
+#[cfg(not(tarpaulin_include))]
+pub fn watch_vacuum_robot() -> i64 {
+    run_vacuum_robot(Some(Box::new(io::stdout())))
+}
+
+/// Walks the vacuum robot over every part of the scaffold and returns the amount of dust it collected.
+/// If `video_feed` is given, the robot's continuous video feed is written to it.
+fn run_vacuum_robot(video_feed: Option<Box<dyn Write>>) -> i64 {
     let (ship, robot) = load_level();
     let path = find_path(&ship, robot);
-    let segments = path_to_segments(&path);
-    let chunks = most_popular_segment_chunks(&segments);
-    let (movement_functions, main_routine) = movement_functions_and_path(&segments, chunks);
+    let (movement_functions, main_routine) = compress_path(&path).unwrap();
 
-    let mut memory = computer::load_program("src/inputs/17.txt");
+    let mut memory = programs::load("ascii");
     // "Force the vacuum robot to wake up by changing the value in your ASCII program at address 0 from 1 to 2."
     memory[0] = 2;
 
     let mut computer = Computer::new(memory);
+    let wants_video_feed = video_feed.is_some();
+    if let Some(sink) = video_feed {
+        computer.stream_ascii_output(sink);
+    }
 
     // "First, you will be prompted for the main movement routine. The main
     // routine may only call the movement functions: A, B, or C. Supply the
     // movement functions to use as ASCII text, separating them with commas (,
     // ASCII code 44), and ending the list with a newline (ASCII code 10)."
-    for (i, &index) in main_routine.iter().enumerate() {
-        computer.push_input(index as i64 + 65);
-        if i != main_routine.len() - 1 {
-            computer.push_input(44);
-        }
-    }
-    computer.push_input(10);
+    push_ascii_line(&mut computer, &routine_to_ascii(&main_routine));
 
     // "Then, you will be prompted for each movement function. Movement
     // functions may use L to turn left, R to turn right, or a number to move
     // forward that many units. Movement functions may not call other movement
     // functions. Again, separate the actions with commas and end the list with
     // a newline."
-    for function in movement_functions {
-        for (i, &(turn, distance)) in function.iter().enumerate() {
-            computer.push_input(if turn == Turn::Left { 76 } else { 82 });
-            computer.push_input(44);
-
-            for digit in distance.to_string().chars() {
-                computer.push_input(digit as i64);
-            }
-
-            if i != function.len() - 1 {
-                computer.push_input(44);
-            }
-        }
-        computer.push_input(10);
+    for i in 0..NUM_MOVEMENT_FUNCTIONS {
+        // The robot asks for all three functions, even if we didn't need them all.
+        let function = movement_functions.get(i).map_or(&[][..], |f| &f[..]);
+        push_ascii_line(&mut computer, &function_to_ascii(function));
     }
 
     // "Finally, you will be asked whether you want to see a continuous video
     // feed; provide either y or n and a newline."
-    computer.push_input(110);
-    computer.push_input(10);
+    push_ascii_line(&mut computer, if wants_video_feed { "y" } else { "n" });
 
     computer.run(HaltReason::Exit);
 
@@ -413,9 +472,174 @@ pub fn seventeen_b() -> i64 {
 mod tests {
     use super::*;
 
+    /// Builds a Path that follows `segments` like "R,8,L,4", ignoring positions.
+    fn path_from_str(segments: &str) -> Path {
+        let parts: Vec<&str> = segments.split(',').collect();
+        let mut path = vec![];
+
+        for pair in parts.chunks(2) {
+            let turn = if pair[0] == "L" {
+                Turn::Left
+            } else {
+                Turn::Right
+            };
+            let distance: usize = pair[1].parse().unwrap();
+
+            path.push((Some(turn), (0, 0)));
+            for _ in 1..distance {
+                path.push((None, (0, 0)));
+            }
+        }
+
+        path
+    }
+
+    /// Asserts that compressing `path` yields functions and a routine that fit within the robot's
+    /// limits and that spell out exactly the same movements as `path`.
+    fn assert_compresses(path: &[(Option<Turn>, Position)]) {
+        let (functions, routine) = compress_path(path).unwrap();
+
+        assert!(functions.len() <= NUM_MOVEMENT_FUNCTIONS);
+        assert!(functions
+            .iter()
+            .all(|function| function_to_ascii(function).len() <= MAX_ASCII_LENGTH));
+        assert!(routine_to_ascii(&routine).len() <= MAX_ASCII_LENGTH);
+
+        let expanded: Vec<Segment> = routine
+            .iter()
+            .flat_map(|&i| functions[i].iter().cloned())
+            .collect();
+        assert_eq!(Some(expanded), path_to_segments(path));
+    }
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(
+            function_to_ascii(&[(Turn::Right, 8), (Turn::Left, 10)]),
+            "R,8,L,10"
+        );
+        assert_eq!(function_to_ascii(&[]), "");
+        assert_eq!(routine_to_ascii(&[0, 1, 2, 1, 0, 2]), "A,B,C,B,A,C");
+    }
+
+    #[test]
+    fn test_parse_camera_frame() {
+        let frame = "..#..........\n..#..........\n#######...###\n#.#...#...#.#\n#############\n..#...#...#..\n..#####...^..";
+
+        // However many newlines the program outputs around the frame, the map comes out the same.
+        for text in [
+            frame.to_string(),
+            format!("{}\n", frame),
+            format!("{}\n\n", frame),
+            format!("\n{}\n\n\n", frame),
+        ]
+        .iter()
+        {
+            let (ship, robot) = parse_camera_frame(text).unwrap();
+            assert_eq!((ship.width(), ship.height()), (13, 7));
+            assert_eq!(ship.map[(2, 0)], Spot::Scaffold);
+            assert_eq!(ship.map[(0, 6)], Spot::Empty);
+            assert_eq!(robot.position, (10, 6));
+            assert_eq!(robot.direction, Direction::North);
+        }
+    }
+
+    #[test]
+    fn test_parse_camera_frame_errors() {
+        assert_eq!(
+            parse_camera_frame("\n\n").unwrap_err(),
+            FrameError::Shape(grid::ParseError::Empty)
+        );
+        assert_eq!(
+            parse_camera_frame("..#..\n.#.\n..^..\n").unwrap_err(),
+            FrameError::Shape(grid::ParseError::Ragged {
+                line: 2,
+                width: 3,
+                expected: 5
+            })
+        );
+        // "...the robot is tumbling through space uncontrollably..."
+        assert_eq!(
+            parse_camera_frame("..#..\n..X..\n").unwrap_err(),
+            FrameError::UnexpectedCharacter('X', (2, 1))
+        );
+        assert_eq!(
+            parse_camera_frame("..#..\n..#..\n").unwrap_err(),
+            FrameError::MissingRobot
+        );
+    }
+
+    #[test]
+    fn test_compress_path_example() {
+        // "For example, consider the following camera feed: [...] One way to do this is:
+        // Main routine: A,B,C,B,A,C; Function A: R,8,R,8; Function B: R,4,R,4,R,8; Function C: L,6,L,2"
+        let path = path_from_str("R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+        assert_compresses(&path);
+    }
+
+    #[test]
+    fn test_compress_path_real_input() {
+        let (ship, robot) = load_level();
+        assert_compresses(&find_path(&ship, robot));
+    }
+
+    #[test]
+    fn test_compress_path_too_long() {
+        // Fifteen different segments can't be covered by three functions of at most 20 characters.
+        let path = path_from_str(
+            "R,10,L,11,R,12,L,13,R,14,L,15,R,16,L,17,R,18,L,19,R,20,L,21,R,22,L,23,R,24",
+        );
+        assert_eq!(compress_path(&path), None);
+    }
+
+    #[test]
+    fn test_compress_path_without_a_turn() {
+        assert_eq!(compress_path(&[]), None);
+
+        // The robot can't be told to move forward before it's turned.
+        let mut path = vec![(None, (0, 0))];
+        path.extend(path_from_str("R,8,R,8"));
+        assert_eq!(path_to_segments(&path), None);
+        assert_eq!(compress_path(&path), None);
+
+        assert_eq!(
+            path_to_segments(&path_from_str("R,8,L,4")),
+            Some(vec![(Turn::Right, 8), (Turn::Left, 4)])
+        );
+    }
+
+    #[test]
+    fn test_intersections() {
+        let (ship, intersections) = intersections();
+
+        for intersection in &intersections {
+            let (x, y) = intersection.position;
+            assert_eq!(intersection.alignment_parameter, x * y);
+
+            // Each intersection is a scaffold with scaffolds on all four sides.
+            for &(dx, dy) in &[(0, 0), (0, -1), (1, 0), (0, 1), (-1, 0)] {
+                assert_eq!(
+                    ship.map[((x + dx) as usize, (y + dy) as usize)],
+                    Spot::Scaffold
+                );
+            }
+        }
+
+        assert!(intersections
+            .windows(2)
+            .all(|pair| (pair[0].position.1, pair[0].position.0)
+                < (pair[1].position.1, pair[1].position.0)));
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(seventeen_a(), 7816);
         assert_eq!(seventeen_b(), 952010);
     }
+
+    #[test]
+    fn test_video_feed() {
+        // The dust report comes after the video feed, so turning the feed on doesn't change it.
+        assert_eq!(run_vacuum_robot(Some(Box::new(io::sink()))), 952010);
+    }
 }
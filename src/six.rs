@@ -1,103 +1,247 @@
-use std::collections::HashMap;
+use crate::util::{self, ParseError};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
 use std::fs;
+use std::io;
+use tracing::instrument;
 
 type BodyToSatellites = HashMap<String, Vec<String>>;
 type SatelliteToBody = HashMap<String, String>;
 
 pub fn six_a() -> u32 {
-    let (body_to_satellites, _) = parse_orbits("src/inputs/6.txt");
-    num_orbits("COM", &body_to_satellites, 0)
+    util::load(util::input(6), total_orbits)
 }
 
 pub fn six_b() -> u32 {
-    let (body_to_satellites, satellite_to_body) = parse_orbits("src/inputs/6.txt");
-    find_minimum_orbital_transfers("SAN", "YOU", "YOU", &body_to_satellites, &satellite_to_body) - 2
+    util::load(util::input(6), |orbits| min_transfers(orbits, "YOU", "SAN"))
+        .expect("YOU and SAN should both be in orbit")
 }
 
-/// Returns the minimum number of orbital transfers needed to get from `origin` to `destination`.
-fn find_minimum_orbital_transfers(
-    destination: &str,
-    origin: &str,
-    previous_origin: &str,
-    body_to_satellites: &BodyToSatellites,
-    satellite_to_body: &SatelliteToBody,
-) -> u32 {
-    if let Some(distance) = find_path_to(
-        destination,
-        origin,
-        previous_origin,
-        body_to_satellites,
-        satellite_to_body,
-    ) {
-        distance
+/// Returns "the total number of direct and indirect orbits" in an orbit map with lines like `FOO)BAR`.
+#[instrument(name = "solve", level = "debug", skip_all)]
+pub fn total_orbits(orbits: &str) -> Result<u32, ParseError> {
+    let (body_to_satellites, _) = parse_orbit_map(orbits)?;
+    Ok(num_orbits("COM", &body_to_satellites))
+}
+
+/// Returns "the minimum number of orbital transfers required to move from the object `from` is orbiting
+/// to the object `to` is orbiting" in an orbit map with lines like `FOO)BAR`,
+/// or None if either of them isn't orbiting anything in it.
+#[instrument(name = "solve", level = "debug", skip_all)]
+pub fn min_transfers(orbits: &str, from: &str, to: &str) -> Result<Option<u32>, ParseError> {
+    let satellite_to_body = parse_orbits_into_satellite_to_body(orbits)?;
+    Ok(find_minimum_orbital_transfers(from, to, &satellite_to_body))
+}
+
+/// Returns a Graphviz DOT graph of the orbit map `orbits`, with an edge from each body to each of its satellites.
+/// If `transfer` is Some((from, to)), the path between `from` and `to` is highlighted in red.
+pub fn to_dot(orbits: &str, transfer: Option<(&str, &str)>) -> Result<String, ParseError> {
+    let tuples = split_orbits_into_tuples(orbits)?;
+
+    let highlighted_satellites: HashSet<String> = match transfer {
+        Some((from, to)) => {
+            let satellite_to_body = parse_orbits_into_satellite_to_body(orbits)?;
+            transfer_path(from, to, &satellite_to_body)
+        }
+        None => HashSet::new(),
+    };
+
+    let mut dot = String::from("digraph orbits {\n");
+
+    if let Some((from, to)) = transfer {
+        writeln!(&mut dot, "    \"{}\" [color=red];", from).unwrap();
+        writeln!(&mut dot, "    \"{}\" [color=red];", to).unwrap();
+    }
+
+    for (body, satellite) in tuples {
+        write!(&mut dot, "    \"{}\" -> \"{}\"", body, satellite).unwrap();
+        if highlighted_satellites.contains(&satellite) {
+            write!(&mut dot, " [color=red, penwidth=2]").unwrap();
+        }
+        writeln!(&mut dot, ";").unwrap();
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// Writes a DOT graph of the puzzle input's orbit map to `path`, highlighting the path from YOU to SAN.
+pub fn write_dot(path: &str) -> io::Result<()> {
+    let dot = util::load(util::input(6), |orbits| {
+        to_dot(orbits, Some(("YOU", "SAN")))
+    });
+    fs::write(path, dot)
+}
+
+/// Draws the orbit map `orbits` as an indented tree, like the `tree` command does with directories,
+/// with each body's satellites listed underneath it.
+/// If `subtree_containing` is Some((a, b)), only the subtree rooted at the closest body that
+/// both `a` and `b` are in orbit around is drawn, and None is returned if there isn't one.
+pub fn to_tree(
+    orbits: &str,
+    subtree_containing: Option<(&str, &str)>,
+) -> Result<Option<String>, ParseError> {
+    let (body_to_satellites, satellite_to_body) = parse_orbit_map(orbits)?;
+    let root = match subtree_containing {
+        Some((a, b)) => match common_ancestor(a, b, &satellite_to_body) {
+            Some(root) => root,
+            None => return Ok(None),
+        },
+        None => "COM".to_string(),
+    };
+
+    let mut tree = format!("{}\n", root);
+    // (body, the prefix drawn before it, whether it's the last of its siblings)
+    let mut stack: Vec<(&str, String, bool)> = vec![];
+    let push_satellites = |stack: &mut Vec<_>, body: &str, prefix: String| {
+        if let Some(satellites) = body_to_satellites.get(body) {
+            for (i, satellite) in satellites.iter().enumerate().rev() {
+                stack.push((
+                    satellite.as_str(),
+                    prefix.clone(),
+                    i == satellites.len() - 1,
+                ));
+            }
+        }
+    };
+
+    push_satellites(&mut stack, &root, String::new());
+    while let Some((body, prefix, is_last)) = stack.pop() {
+        let (branch, indent) = if is_last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        writeln!(&mut tree, "{}{}{}", prefix, branch, body).unwrap();
+        push_satellites(&mut stack, body, prefix + indent);
+    }
+
+    Ok(Some(tree))
+}
+
+/// Prints the puzzle input's orbit map as a tree; if `only_transfer` is true, only the part of it
+/// that contains YOU and SAN is printed.
+pub fn print_tree(only_transfer: bool) -> io::Result<()> {
+    let subtree_containing = if only_transfer {
+        Some(("YOU", "SAN"))
     } else {
-        // Head one step closer to the COM and try again.
-        1 + find_minimum_orbital_transfers(
-            destination,
-            &satellite_to_body[origin],
-            origin,
-            body_to_satellites,
-            satellite_to_body,
+        None
+    };
+
+    match util::load(util::input(6), |orbits| to_tree(orbits, subtree_containing)) {
+        Some(tree) => {
+            print!("{}", tree);
+            Ok(())
+        }
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "YOU and SAN aren't in orbit around a common body",
+        )),
+    }
+}
+
+/// Returns the closest body that both `a` and `b` are in orbit around, or None if they aren't
+/// both in orbit around the same body.
+fn common_ancestor(a: &str, b: &str, satellite_to_body: &SatelliteToBody) -> Option<String> {
+    let b_ancestors: HashSet<String> = ancestors(b, satellite_to_body).into_iter().collect();
+    ancestors(a, satellite_to_body)
+        .into_iter()
+        .skip(1)
+        .find(|body| b_ancestors.contains(body) && body != b)
+}
+
+/// Returns the satellites whose orbits make up the path between `from` and `to`, identified by
+/// their satellite so that each one names a single edge of the orbit tree.
+fn transfer_path(from: &str, to: &str, satellite_to_body: &SatelliteToBody) -> HashSet<String> {
+    let from_ancestors = ancestors(from, satellite_to_body);
+    let to_ancestors = ancestors(to, satellite_to_body);
+
+    let from_ancestor_set: HashSet<&String> = from_ancestors.iter().collect();
+    let to_ancestor_set: HashSet<&String> = to_ancestors.iter().collect();
+
+    from_ancestors
+        .iter()
+        .take_while(|body| !to_ancestor_set.contains(body))
+        .chain(
+            to_ancestors
+                .iter()
+                .take_while(|body| !from_ancestor_set.contains(body)),
         )
+        .cloned()
+        .collect()
+}
+
+/// Returns `body`, followed by the body it orbits, and so on, all the way out to the COM.
+fn ancestors(body: &str, satellite_to_body: &SatelliteToBody) -> Vec<String> {
+    let mut ret = vec![body.to_string()];
+    let mut current = body;
+
+    while let Some(parent) = satellite_to_body.get(current) {
+        ret.push(parent.clone());
+        current = parent;
     }
+
+    ret
 }
 
-/// Returns Some(num_orbital_transfers) if it's possible to get to `destination` by following `origin`'s satellites, None otherwise.
-fn find_path_to(
-    destination: &str,
-    origin: &str,
-    previous_origin: &str,
-    body_to_satellites: &BodyToSatellites,
+/// Returns the minimum number of orbital transfers needed to get from the body `from` orbits
+/// to the body `to` orbits, by way of their lowest common ancestor.
+/// Returns None if `from` or `to` isn't orbiting anything in `satellite_to_body`, or if the
+/// bodies they orbit aren't in the same orbit map.
+fn find_minimum_orbital_transfers(
+    from: &str,
+    to: &str,
     satellite_to_body: &SatelliteToBody,
 ) -> Option<u32> {
-    if satellite_to_body[origin] == satellite_to_body[destination] {
-        return Some(0);
-    }
-
-    if let Some(children) = body_to_satellites.get(origin) {
-        for child in children.iter().filter(|&x| x != previous_origin) {
-            if let Some(distance) = find_path_to(
-                destination,
-                child,
-                previous_origin,
-                body_to_satellites,
-                satellite_to_body,
-            ) {
-                return Some(1 + distance);
-            }
-        }
-    }
+    // Neither `from` nor `to` has to move to the body it's already orbiting, so the search
+    // starts from those bodies instead.
+    let from_ancestors = ancestors(satellite_to_body.get(from)?, satellite_to_body);
+    let to_ancestors = ancestors(satellite_to_body.get(to)?, satellite_to_body);
 
-    None
+    let from_depths: HashMap<&String, usize> = from_ancestors
+        .iter()
+        .enumerate()
+        .map(|(i, body)| (body, i))
+        .collect();
+
+    to_ancestors
+        .iter()
+        .enumerate()
+        .find_map(|(i, body)| from_depths.get(body).map(|&j| (i + j) as u32))
 }
 
-/// Starting from `body` at `depth`, calculates the number of "direct and indirect orbits" in `body_to_satellites`.
-fn num_orbits(body: &str, body_to_satellites: &BodyToSatellites, depth: u32) -> u32 {
-    match body_to_satellites.get(body) {
-        None => depth,
+/// Starting from `root`, calculates the number of "direct and indirect orbits" in `body_to_satellites`.
+fn num_orbits(root: &str, body_to_satellites: &BodyToSatellites) -> u32 {
+    let mut total = 0;
+    let mut stack = vec![(root, 0)];
+
+    while let Some((body, depth)) = stack.pop() {
+        total += depth;
 
-        Some(satellites) => {
-            depth
-                + satellites
+        if let Some(satellites) = body_to_satellites.get(body) {
+            stack.extend(
+                satellites
                     .iter()
-                    .map(|satellite| num_orbits(satellite, body_to_satellites, depth + 1))
-                    .sum::<u32>()
+                    .map(|satellite| (satellite.as_str(), depth + 1)),
+            );
         }
     }
+
+    total
 }
 
-/// Parses `path` into two hashmaps: one facing out, the other facing in.
-fn parse_orbits(path: &str) -> (BodyToSatellites, SatelliteToBody) {
-    let orbits = fs::read_to_string(path).unwrap();
-    (
-        parse_orbits_into_body_to_satellites(&orbits),
-        parse_orbits_into_satellite_to_body(&orbits),
-    )
+/// Parses `orbits` into two hashmaps: one facing out, the other facing in.
+#[instrument(name = "parse", level = "debug", skip_all)]
+fn parse_orbit_map(orbits: &str) -> Result<(BodyToSatellites, SatelliteToBody), ParseError> {
+    Ok((
+        parse_orbits_into_body_to_satellites(orbits)?,
+        parse_orbits_into_satellite_to_body(orbits)?,
+    ))
 }
 
 #[allow(clippy::or_fun_call)]
-fn parse_orbits_into_body_to_satellites(orbits: &str) -> BodyToSatellites {
-    let tuples = split_orbits_into_tuples(orbits);
+fn parse_orbits_into_body_to_satellites(orbits: &str) -> Result<BodyToSatellites, ParseError> {
+    let tuples = split_orbits_into_tuples(orbits)?;
     let mut body_to_satellites = HashMap::with_capacity(tuples.len());
 
     for (body, satellite) in tuples.into_iter() {
@@ -107,29 +251,34 @@ fn parse_orbits_into_body_to_satellites(orbits: &str) -> BodyToSatellites {
             .push(satellite);
     }
 
-    body_to_satellites
+    Ok(body_to_satellites)
 }
 
-fn parse_orbits_into_satellite_to_body(orbits: &str) -> SatelliteToBody {
-    let tuples = split_orbits_into_tuples(orbits);
+fn parse_orbits_into_satellite_to_body(orbits: &str) -> Result<SatelliteToBody, ParseError> {
+    let tuples = split_orbits_into_tuples(orbits)?;
     let mut satellite_to_body = HashMap::with_capacity(tuples.len());
 
     for (body, satellite) in tuples.into_iter() {
         satellite_to_body.insert(satellite, body);
     }
 
-    satellite_to_body
+    Ok(satellite_to_body)
 }
 
 /// Parses a file with lines like `FOO)BAR` into a Vec of tuples like ("FOO", "BAR").
-fn split_orbits_into_tuples(orbits: &str) -> Vec<(String, String)> {
+fn split_orbits_into_tuples(orbits: &str) -> Result<Vec<(String, String)>, ParseError> {
     orbits
         .lines()
-        .map(|line| {
-            let mut split_line = line.split(')');
-            let body = split_line.next().unwrap();
-            let satellite = split_line.next().unwrap();
-            (body.to_string(), satellite.to_string())
+        .enumerate()
+        .map(|(i, line)| match line.split_once(')') {
+            Some((body, satellite)) if !body.is_empty() && !satellite.is_empty() => {
+                Ok((body.to_string(), satellite.to_string()))
+            }
+            _ => Err(ParseError::new(
+                i + 1,
+                line,
+                "expected a body and its satellite, like FOO)BAR",
+            )),
         })
         .collect()
 }
@@ -138,9 +287,14 @@ fn split_orbits_into_tuples(orbits: &str) -> Vec<(String, String)> {
 mod tests {
     use super::*;
 
+    fn read(path: &str) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
     #[test]
     fn test_parse_orbits() {
-        let (body_to_satellites, satellite_to_body) = parse_orbits("src/inputs/6.txt");
+        let (body_to_satellites, satellite_to_body) =
+            parse_orbit_map(&util::read_input(6).unwrap()).unwrap();
         assert_eq!(body_to_satellites["COM"], vec!["PY1"]);
         assert_eq!(body_to_satellites["Q9V"], vec!["88G"]);
         assert_eq!(body_to_satellites["8PZ"], vec!["MSY", "TTS"]);
@@ -153,24 +307,162 @@ mod tests {
 
     #[test]
     fn test_num_orbits() {
-        let (body_to_satellites, _) = parse_orbits("src/inputs/6_sample.txt");
+        let (body_to_satellites, _) = parse_orbit_map(&read("src/inputs/6_sample.txt")).unwrap();
 
-        assert_eq!(num_orbits("COM", &body_to_satellites, 0), 42);
+        assert_eq!(num_orbits("COM", &body_to_satellites), 42);
     }
 
     #[test]
     fn test_find_minimum_orbital_transfers() {
-        let (body_to_satellites, satellite_to_body) = parse_orbits("src/inputs/6_sample_2.txt");
+        let satellite_to_body =
+            parse_orbits_into_satellite_to_body(&read("src/inputs/6_sample_2.txt")).unwrap();
+
+        assert_eq!(
+            find_minimum_orbital_transfers("YOU", "SAN", &satellite_to_body),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_transfers_between_adjacent_bodies() {
+        // SAN orbits YOU, so YOU's one hop from K to J puts it in orbit around the body SAN orbits.
+        let orbits = read("src/inputs/6_sample.txt").trim_end().to_string() + "\nK)YOU\nYOU)SAN";
+        assert_eq!(min_transfers(&orbits, "YOU", "SAN").unwrap(), Some(1));
+        assert_eq!(min_transfers(&orbits, "SAN", "YOU").unwrap(), Some(1));
+
+        assert_eq!(
+            min_transfers("COM)A\nA)YOU\nA)SAN", "YOU", "SAN").unwrap(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_transfers_with_missing_body() {
+        let orbits = read("src/inputs/6_sample_2.txt");
+        assert_eq!(min_transfers(&orbits, "YOU", "NOPE").unwrap(), None);
+        assert_eq!(min_transfers(&orbits, "NOPE", "SAN").unwrap(), None);
+        assert_eq!(min_transfers(&orbits, "COM", "SAN").unwrap(), None);
+        assert_eq!(
+            min_transfers("COM)YOU\nELSEWHERE)SAN", "YOU", "SAN").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            total_orbits("COM)A\nA-B\nA)C").unwrap_err(),
+            ParseError::new(2, "A-B", "expected a body and its satellite, like FOO)BAR")
+        );
+        assert_eq!(
+            min_transfers("COM)A\nA)", "YOU", "SAN").unwrap_err(),
+            ParseError::new(2, "A)", "expected a body and its satellite, like FOO)BAR")
+        );
+        assert_eq!(to_tree("COM)A\n\nA)B", None).unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        // A chain of 50,000 orbits leading out to YOU, with SAN orbiting the COM.
+        let mut orbits = String::from("COM)SAN\nCOM)0\n");
+        for i in 1..50_000 {
+            writeln!(&mut orbits, "{}){}", i - 1, i).unwrap();
+        }
+        orbits.push_str("49999)YOU\n");
+
+        assert_eq!(
+            total_orbits(&orbits).unwrap(),
+            1 + (1..=50_001).sum::<u32>()
+        );
+        assert_eq!(min_transfers(&orbits, "YOU", "SAN").unwrap(), Some(50_000));
+    }
+
+    #[test]
+    fn test_public_api() {
+        assert_eq!(total_orbits("COM)A\nA)B\nA)C").unwrap(), 5);
+        assert_eq!(total_orbits("COM)A\nA)B\nA)C\nB)YOU\nC)SAN").unwrap(), 11);
+        assert_eq!(
+            min_transfers("COM)A\nA)B\nA)C\nB)YOU\nC)SAN", "YOU", "SAN").unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            min_transfers("COM)A\nA)B\nA)C\nB)YOU\nC)SAN", "SAN", "YOU").unwrap(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let orbits = "COM)A\nA)B\nA)C\nB)YOU\nC)SAN";
+
+        assert_eq!(
+            to_dot(orbits, None).unwrap(),
+            r#"digraph orbits {
+    "COM" -> "A";
+    "A" -> "B";
+    "A" -> "C";
+    "B" -> "YOU";
+    "C" -> "SAN";
+}
+"#
+        );
+
+        assert_eq!(
+            to_dot(orbits, Some(("YOU", "SAN"))).unwrap(),
+            r#"digraph orbits {
+    "YOU" [color=red];
+    "SAN" [color=red];
+    "COM" -> "A";
+    "A" -> "B" [color=red, penwidth=2];
+    "A" -> "C" [color=red, penwidth=2];
+    "B" -> "YOU" [color=red, penwidth=2];
+    "C" -> "SAN" [color=red, penwidth=2];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_tree() {
+        let orbits = "COM)A\nA)B\nA)C\nB)YOU\nC)SAN\nCOM)D\nC)E";
+        let tree = |subtree_containing| to_tree(orbits, subtree_containing).unwrap().unwrap();
+
+        assert_eq!(
+            tree(None),
+            "COM
+├── A
+│   ├── B
+│   │   └── YOU
+│   └── C
+│       ├── SAN
+│       └── E
+└── D
+"
+        );
+
+        assert_eq!(
+            tree(Some(("YOU", "SAN"))),
+            "A
+├── B
+│   └── YOU
+└── C
+    ├── SAN
+    └── E
+"
+        );
+        assert_eq!(tree(Some(("SAN", "E"))), "C\n├── SAN\n└── E\n");
+
+        // Nothing contains a body that isn't in the map, or the COM, which doesn't orbit anything.
+        assert_eq!(to_tree(orbits, Some(("YOU", "NOPE"))), Ok(None));
+        assert_eq!(to_tree(orbits, Some(("NOPE", "SAN"))), Ok(None));
+        assert_eq!(to_tree(orbits, Some(("A", "COM"))), Ok(None));
 
+        let whole_map = to_tree(&util::read_input(6).unwrap(), None)
+            .unwrap()
+            .unwrap();
         assert_eq!(
-            find_minimum_orbital_transfers(
-                "SAN",
-                "YOU",
-                "YOU",
-                &body_to_satellites,
-                &satellite_to_body
-            ) - 2,
-            4
+            whole_map.lines().count(),
+            util::read_input(6).unwrap().lines().count() + 1
         );
     }
 
@@ -1,6 +1,10 @@
-use std::fs;
+use crate::solver::Solver;
+use crate::util::{self, digits_to_u64, ParseError};
+use rayon::prelude::*;
+use tracing::instrument;
 
 static BASE_PATTERN: [i32; 4] = [0, 1, 0, -1];
+const MESSAGE_LENGTH: usize = 8;
 
 /// "Repeat each value in the pattern a number of times equal to the position in
 /// the output list being considered. Repeat once for the first element, twice
@@ -27,35 +31,41 @@ fn indexes_and_pattern_pieces(position: usize) -> impl Iterator<Item = (usize, i
 /// element on the left and each value in the repeating pattern on the right of
 /// each multiplication). Then, only the ones digit is kept: 38 becomes 8, -17
 /// becomes 7, and so on."
-fn dft_one_phase(numbers: &[i32]) -> Vec<i32> {
-    (0..numbers.len())
-        .map(|i| {
-            let indexes_and_patterns = indexes_and_pattern_pieces(i);
-            indexes_and_patterns
-                .take_while(|&(j, _)| j < numbers.len())
-                .fold(0, |acc, (j, pattern_piece)| {
-                    acc + numbers[j] * pattern_piece
-                })
-                .abs()
-                % 10
-        })
-        .collect()
+///
+/// Writes the new list into `output`, which must be the same length as `numbers`.
+fn dft_one_phase(numbers: &[i32], output: &mut [i32]) {
+    output.par_iter_mut().enumerate().for_each(|(i, out)| {
+        let indexes_and_patterns = indexes_and_pattern_pieces(i);
+        *out = indexes_and_patterns
+            .take_while(|&(j, _)| j < numbers.len())
+            .fold(0, |acc, (j, pattern_piece)| {
+                acc + numbers[j] * pattern_piece
+            })
+            .abs()
+            % 10;
+    });
 }
 
 /// "FFT operates in repeated phases. In each phase, a new list is constructed
 /// with the same length as the input list. This new list is also used as the
 /// input for the next phase."
+#[instrument(name = "solve", level = "debug", skip_all)]
 fn run_dft(numbers: &[i32], num_times: usize) -> Vec<i32> {
-    let mut out = numbers.to_vec();
+    // Each phase reads from one buffer and writes to the other, and then they trade places.
+    let mut current = numbers.to_vec();
+    let mut next = vec![0; numbers.len()];
     for _ in 0..num_times {
-        out = dft_one_phase(&out);
+        dft_one_phase(&current, &mut next);
+        std::mem::swap(&mut current, &mut next);
     }
-    out
+    current
 }
 
 // got here from following the advice on https://www.reddit.com/r/adventofcode/comments/ebf5cy/2019_day_16_part_2_understanding_how_to_come_up/
 // i tried learning about the actual fft but i don't really have any background in math and lecture vids made my head spin
 // oh well!
+//
+// Each digit only depends on the digits after it, so walking backwards lets the phase happen in place.
 fn fft_one_phase(numbers: &mut [i32]) {
     let mut sum = 0;
 
@@ -71,48 +81,108 @@ fn run_fft(numbers: &mut [i32], num_times: usize) {
     }
 }
 
-fn parse_int_str(int_str: &str) -> Vec<i32> {
-    int_str
-        .chars()
-        .map(|x| x.to_digit(10).unwrap() as i32)
-        .collect()
-}
+/// Returns "the eight-digit message" (or what's left of it, near the end of the signal) at `offset` in the output of
+/// running `phases` phases of FFT on `signal` repeated `repeats` times.
+pub fn fft_decode(signal: &[i32], repeats: usize, offset: usize, phases: usize) -> Vec<i32> {
+    let length = signal.len() * repeats;
+    assert!(offset <= length);
+
+    // Each output digit only depends on the input digits at or after its own position,
+    // so we can ignore everything before `offset`.
+    let mut numbers: Vec<i32> = (offset..length).map(|i| signal[i % signal.len()]).collect();
 
-fn number_slice_into_number(numbers: &[i32]) -> u64 {
+    if offset * 2 >= length {
+        // In the back half of the signal, the pattern is all zeroes followed by all ones.
+        run_fft(&mut numbers, phases);
+    } else {
+        let mut prefix_sums = Vec::with_capacity(numbers.len() + 1);
+        for _ in 0..phases {
+            partial_sums_one_phase(&mut numbers, &mut prefix_sums, offset);
+        }
+    }
+
+    numbers.truncate(MESSAGE_LENGTH);
     numbers
-        .iter()
-        .rev()
-        .enumerate()
-        .fold(0, |acc, (i, &number)| {
-            acc + number as u64 * ((10.0 as f64).powi(i as i32) as u64)
-        })
 }
 
-pub fn sixteen_a() -> u64 {
-    let contents = fs::read_to_string("src/inputs/16.txt").unwrap();
-    let number_string = contents.lines().next().unwrap();
+/// Runs one phase of FFT on `numbers` in place, where `numbers` are the digits of a signal starting at position `offset`.
+///
+/// The pattern for each position is made of runs of 0s, 1s, and -1s, so rather than multiplying every digit
+/// by its part of the pattern, we add up each run of 1s and subtract each run of -1s using prefix sums.
+/// `prefix_sums` is scratch space, and is reused from one phase to the next.
+fn partial_sums_one_phase(numbers: &mut [i32], prefix_sums: &mut Vec<i64>, offset: usize) {
+    prefix_sums.clear();
+    prefix_sums.push(0);
+    for &number in numbers.iter() {
+        prefix_sums.push(prefix_sums.last().unwrap() + number as i64);
+    }
+
+    let length = numbers.len();
 
-    let mut numbers = parse_int_str(number_string);
-    numbers = run_dft(&numbers, 100);
+    // Returns the sum of the original numbers[start..end], clamped to the end of the signal.
+    let sum_between = |start: usize, end: usize| {
+        let start = start.min(length);
+        let end = end.min(length);
+        prefix_sums[end] - prefix_sums[start]
+    };
 
-    number_slice_into_number(&numbers[..8])
+    // Each output digit only reads from `prefix_sums`, so they can all be computed independently.
+    numbers.par_iter_mut().enumerate().for_each(|(i, number)| {
+        // The position's pattern starts with a run of 1s at its own position,
+        // and each run is as long as the position's 1-based index.
+        let run_length = offset + i + 1;
+        let mut total = 0;
+
+        let mut start = i;
+        while start < length {
+            total += sum_between(start, start + run_length);
+            total -= sum_between(start + 2 * run_length, start + 3 * run_length);
+            start += 4 * run_length;
+        }
+
+        *number = (total.abs() % 10) as i32;
+    });
+}
+
+pub fn sixteen_a() -> u64 {
+    Solution::part_a(&Solution::load())
 }
 
 pub fn sixteen_b() -> u64 {
-    let contents = fs::read_to_string("src/inputs/16.txt").unwrap();
-    let number_string = contents.lines().next().unwrap();
+    Solution::part_b(&Solution::load())
+}
+
+pub struct Solution;
 
-    let mut numbers = parse_int_str(&number_string.repeat(5000));
-    let offset = (number_slice_into_number(&numbers[..7]) as usize) - (5000 * number_string.len());
+impl Solver for Solution {
+    type Parsed = Vec<i32>;
+    type Error = ParseError;
+    type A = u64;
+    type B = u64;
 
-    run_fft(&mut numbers, 100);
+    const DAY: u32 = 16;
+
+    fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+        util::try_parse_digits(input.trim())
+    }
 
-    number_slice_into_number(&numbers[offset..offset + 8])
+    fn part_a(numbers: &Vec<i32>) -> u64 {
+        let numbers = run_dft(numbers, 100);
+        digits_to_u64(&numbers[..MESSAGE_LENGTH]).unwrap()
+    }
+
+    fn part_b(numbers: &Vec<i32>) -> u64 {
+        // "The first seven digits of your initial input signal also represent the message offset."
+        let offset = digits_to_u64(&numbers[..7]).unwrap() as usize;
+
+        digits_to_u64(&fft_decode(numbers, 10000, offset, 100)).unwrap()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::parse_digits;
 
     #[test]
     fn test_pattern_for_position() {
@@ -161,49 +231,49 @@ mod tests {
 
     #[test]
     fn test_dft_one_phase() {
-        let mut numbers = vec![1, 2, 3, 4, 5, 6, 7, 8];
-
-        numbers = dft_one_phase(&numbers);
-        assert_eq!(numbers, vec![4, 8, 2, 2, 6, 1, 5, 8]);
+        let numbers = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let mut output = vec![0; 8];
 
-        numbers = dft_one_phase(&numbers);
-        assert_eq!(numbers, vec![3, 4, 0, 4, 0, 4, 3, 8]);
+        dft_one_phase(&numbers, &mut output);
+        assert_eq!(output, vec![4, 8, 2, 2, 6, 1, 5, 8]);
 
-        numbers = dft_one_phase(&numbers);
-        assert_eq!(numbers, vec![0, 3, 4, 1, 5, 5, 1, 8]);
-
-        numbers = dft_one_phase(&numbers);
-        assert_eq!(numbers, vec![0, 1, 0, 2, 9, 4, 9, 8]);
-    }
-
-    #[test]
-    fn test_parse_int_str() {
-        assert_eq!(
-            parse_int_str("80871224585914546619083218645595"),
-            vec![
-                8, 0, 8, 7, 1, 2, 2, 4, 5, 8, 5, 9, 1, 4, 5, 4, 6, 6, 1, 9, 0, 8, 3, 2, 1, 8, 6, 4,
-                5, 5, 9, 5
-            ]
-        );
+        assert_eq!(run_dft(&numbers, 2), vec![3, 4, 0, 4, 0, 4, 3, 8]);
+        assert_eq!(run_dft(&numbers, 3), vec![0, 3, 4, 1, 5, 5, 1, 8]);
+        assert_eq!(run_dft(&numbers, 4), vec![0, 1, 0, 2, 9, 4, 9, 8]);
     }
 
     #[test]
     fn test_run_dft() {
-        let mut numbers = parse_int_str("80871224585914546619083218645595");
+        let mut numbers = parse_digits("80871224585914546619083218645595");
         numbers = run_dft(&numbers, 100);
         assert_eq!(&numbers[..8], [2, 4, 1, 7, 6, 1, 7, 6]);
 
-        let mut numbers = parse_int_str("69317163492948606335995924319873");
+        let mut numbers = parse_digits("69317163492948606335995924319873");
         numbers = run_dft(&numbers, 100);
         assert_eq!(&numbers[..8], [5, 2, 4, 3, 2, 1, 3, 3]);
     }
 
     #[test]
-    fn test_number_slice_into_number() {
+    fn test_fft_decode() {
+        // Offsets in the back half of the signal.
+        let signal = parse_digits("03036732577212944063491565474664");
         assert_eq!(
-            number_slice_into_number(&[5, 2, 4, 3, 2, 1, 3, 3]),
-            52432133
-        )
+            fft_decode(&signal, 10000, 303673, 100),
+            [8, 4, 4, 6, 2, 0, 2, 6]
+        );
+
+        // Offsets anywhere else.
+        let signal = parse_digits("80871224585914546619083218645595");
+        assert_eq!(fft_decode(&signal, 1, 0, 100), [2, 4, 1, 7, 6, 1, 7, 6]);
+
+        let signal = parse_digits("69317163492948606335995924319873");
+        let expected = run_dft(&signal.repeat(3), 10);
+        for &offset in &[0, 5, 31, 47, 90] {
+            assert_eq!(
+                fft_decode(&signal, 3, offset, 10),
+                expected[offset..(offset + 8).min(expected.len())]
+            );
+        }
     }
 
     #[test]
@@ -0,0 +1,25 @@
+use crate::util;
+use std::error;
+use std::fmt::Display;
+
+/// One day's puzzle, split into parsing the input and solving each part,
+/// so that the input only needs to be parsed once for both parts.
+pub trait Solver {
+    /// Everything that both parts need from the puzzle input.
+    type Parsed;
+    /// What can go wrong when parsing the puzzle input.
+    type Error: error::Error + Send + Sync + 'static;
+    type A: Display;
+    type B: Display;
+
+    const DAY: u32;
+
+    fn parse(input: &str) -> Result<Self::Parsed, Self::Error>;
+    fn part_a(parsed: &Self::Parsed) -> Self::A;
+    fn part_b(parsed: &Self::Parsed) -> Self::B;
+
+    /// Reads and parses the puzzle input, panicking with the file's path and what's wrong with it if that fails.
+    fn load() -> Self::Parsed {
+        util::load(util::input(Self::DAY as u8), Self::parse)
+    }
+}
@@ -1,19 +1,22 @@
+use crate::grid;
+use crate::util::{self, ParseError};
 use itertools::Itertools;
 use rayon::prelude::*;
+use std::path::Path;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::f64::consts::PI;
-use std::fs;
+use std::fmt;
+use tracing::instrument;
 
 pub fn ten_a() -> usize {
-    let grid = Grid::new("src/inputs/10.txt");
-    let (x, y) = best_location_for_monitoring_station(grid.clone());
-    grid.num_asteroids_visible_from_location(x, y)
+    let grid = Grid::new(util::input(10));
+    best_station(&grid).visible_count
 }
 
 pub fn ten_b() -> usize {
-    let grid = Grid::new("src/inputs/10.txt");
-    let two_hundredth_zapped = zap_order(grid, 20, 20)[199];
+    let grid = Grid::new(util::input(10));
+    let two_hundredth_zapped = nth_vaporized(&grid, (20, 20), 200).unwrap();
     two_hundredth_zapped.0 * 100 + two_hundredth_zapped.1
 }
 
@@ -26,22 +29,47 @@ pub fn ten_b() -> usize {
 /// asteroid makes another one detectable, the newly-detected asteroid won't be
 /// vaporized until the laser has returned to the same position by rotating a
 /// full 360 degrees."
-fn zap_order(grid: Grid, x: i32, y: i32) -> Vec<(usize, usize)> {
-    let mut grouped_positions = group_asteroids_by_angle(&grid.asteroid_positions, x, y);
+///
+/// Returns an iterator over the asteroids in the order they're vaporized by a laser at `station`.
+pub fn zap_iter(grid: &Grid, station: (usize, usize)) -> ZapIter {
+    ZapIter {
+        groups: group_asteroids_by_angle(
+            &grid.asteroid_positions,
+            station.0 as i32,
+            station.1 as i32,
+        )
+        .into_iter()
+        .collect(),
+    }
+}
+
+/// Returns the position of the `n`th asteroid to be vaporized by a laser at `station`, counting from 1.
+pub fn nth_vaporized(grid: &Grid, station: (usize, usize), n: usize) -> Option<(usize, usize)> {
+    zap_iter(grid, station).nth(n.checked_sub(1)?)
+}
+
+/// Yields asteroid positions in the order they're vaporized.
+pub struct ZapIter {
+    /// Buckets of asteroids that are in line with one another, closest first, in the order
+    /// that the laser will next point at them.
+    groups: VecDeque<VecDeque<(usize, usize)>>,
+}
+
+impl Iterator for ZapIter {
+    type Item = (usize, usize);
 
-    let mut order = Vec::with_capacity(grouped_positions.len() * 3);
+    fn next(&mut self) -> Option<Self::Item> {
+        // Zap the first asteroid in the next bucket, and then send that bucket to the back of the line
+        // unless it's now empty.
+        let mut group = self.groups.pop_front()?;
+        let zapped = group.pop_front().unwrap();
 
-    while !grouped_positions.is_empty() {
-        // Pop the first item off of each bucket.
-        for group in &mut grouped_positions {
-            order.push(group.pop_front().unwrap());
+        if !group.is_empty() {
+            self.groups.push_back(group);
         }
 
-        // Discard any now-empty buckets.
-        grouped_positions.retain(|x| !x.is_empty());
+        Some(zapped)
     }
-
-    order
 }
 
 /// Group `asteroid_positions` into VecDeque buckets based on their angle relative to (x, y).
@@ -56,9 +84,15 @@ fn group_asteroids_by_angle(
         .map(|&(xx, yy)| ((xx, yy), angle_between(x, y, xx as i32, yy as i32)))
         .collect();
 
-    // Sort by angle increasing.
-    positions_and_angles
-        .sort_by(|(_, angle_1), (_, angle_2)| (angle_1).partial_cmp(angle_2).unwrap());
+    // Sort by angle increasing, and then by distance from (x, y) so that each bucket
+    // has its closest asteroid at the front.
+    let distance = |&(xx, yy): &(usize, usize)| (xx as i32 - x).abs() + (yy as i32 - y).abs();
+    positions_and_angles.sort_by(|(position_1, angle_1), (position_2, angle_2)| {
+        angle_1
+            .partial_cmp(angle_2)
+            .unwrap()
+            .then_with(|| distance(position_1).cmp(&distance(position_2)))
+    });
 
     // Group the positions into buckets by angle.
     let mut grouped_positions: Vec<VecDeque<(usize, usize)>> = vec![];
@@ -91,43 +125,46 @@ enum Spot {
 }
 
 #[derive(Debug, Clone)]
-struct Grid {
-    width: usize,
-    height: usize,
-    map: Vec<Spot>,
+pub struct Grid {
+    map: grid::Grid<Spot>,
     asteroid_positions: Vec<(usize, usize)>,
 }
 
 impl Grid {
-    pub fn new(filename: &str) -> Self {
-        let contents = fs::read_to_string(filename).unwrap();
-
-        let height = contents.lines().count();
-        let width = contents.lines().next().unwrap().chars().count();
-
-        let map: Vec<Spot> = contents
-            .lines()
-            .flat_map(|line| {
-                line.chars().map(|c| match c {
-                    '.' => Spot::Empty,
-                    '#' => Spot::Asteroid,
-                    _ => panic!("unexpected char {}", c),
-                })
-            })
-            .collect();
+    pub fn new(filename: impl AsRef<Path>) -> Self {
+        util::load(filename, Grid::parse)
+    }
 
-        let asteroid_positions = map
-            .iter()
-            .positions(|spot| *spot == Spot::Asteroid)
-            .map(|i| (i % width, i / width))
-            .collect();
+    /// Parses an asteroid map like ".#..#\n.....\n#####".
+    #[instrument(name = "parse", level = "debug", skip_all)]
+    pub fn parse(contents: &str) -> Result<Self, ParseError> {
+        let mut asteroid_positions = vec![];
+        let mut unexpected_character = None;
+        let map = grid::Grid::parse(contents, |c, position| match c {
+            '.' => Spot::Empty,
+            '#' => {
+                asteroid_positions.push(position);
+                Spot::Asteroid
+            }
+            _ => {
+                unexpected_character.get_or_insert((c, position));
+                Spot::Empty
+            }
+        })
+        .map_err(|e| e.at_line_of(contents))?;
+
+        if let Some((c, (_, y))) = unexpected_character {
+            return Err(ParseError::new(
+                y + 1,
+                &c.to_string(),
+                "expected . or # for each spot",
+            ));
+        }
 
-        Grid {
+        Ok(Grid {
             map,
-            width,
-            height,
             asteroid_positions,
-        }
+        })
     }
 
     /// "A monitoring station can detect any asteroid to which it has direct line
@@ -148,15 +185,46 @@ impl Grid {
     }
 }
 
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let drawing = self.map.display(|spot| match spot {
+            Spot::Asteroid => '#',
+            Spot::Empty => '.',
+        });
+        write!(f, "{}", drawing)
+    }
+}
+
+/// The best location for a monitoring station, along with how it compares to the other candidates.
+#[derive(Debug, Clone)]
+pub struct Station {
+    pub position: (usize, usize),
+    /// The number of asteroids that can be detected from `position`.
+    pub visible_count: usize,
+    /// The number of asteroids that can be detected from each asteroid in the grid.
+    pub visibility_map: HashMap<(usize, usize), usize>,
+}
+
 /// "Your job is to figure out which asteroid would be the best place to build a
 /// new monitoring station. The best location is the asteroid that can
 /// detect the largest number of other asteroids."
-fn best_location_for_monitoring_station(grid: Grid) -> (usize, usize) {
-    *grid
+pub fn best_station(grid: &Grid) -> Station {
+    let visible_counts: Vec<((usize, usize), usize)> = grid
         .asteroid_positions
         .par_iter()
-        .max_by_key(|(x, y)| grid.num_asteroids_visible_from_location(*x, *y))
-        .unwrap()
+        .map(|&(x, y)| ((x, y), grid.num_asteroids_visible_from_location(x, y)))
+        .collect();
+
+    let (position, visible_count) = *visible_counts
+        .iter()
+        .max_by_key(|(_, count)| *count)
+        .unwrap();
+
+    Station {
+        position,
+        visible_count,
+        visibility_map: visible_counts.into_iter().collect(),
+    }
 }
 
 #[cfg(test)]
@@ -171,14 +239,73 @@ mod tests {
     fn test_sample_1() {
         let grid = Grid::new("src/inputs/10_sample_1.txt");
         assert_eq!(grid.num_asteroids_visible_from_location(5, 8), 33);
-        assert_eq!(best_location_for_monitoring_station(grid), (5, 8));
+        assert_eq!(best_station(&grid).position, (5, 8));
     }
 
     #[test]
     fn test_small_map() {
         let grid = Grid::new("src/inputs/10_sample_small.txt");
         assert_eq!(grid.num_asteroids_visible_from_location(3, 4), 8);
-        assert_eq!(best_location_for_monitoring_station(grid), (3, 4));
+        assert_eq!(best_station(&grid).position, (3, 4));
+    }
+
+    #[test]
+    fn test_best_station() {
+        let grid = Grid::parse(".#..#\n.....\n#####\n....#\n...##").unwrap();
+        let station = best_station(&grid);
+
+        assert_eq!(station.position, (3, 4));
+        assert_eq!(station.visible_count, 8);
+        assert_eq!(station.visibility_map.len(), 10);
+        assert_eq!(station.visibility_map[&(1, 0)], 7);
+        assert_eq!(station.visibility_map[&(4, 2)], 5);
+    }
+
+    #[test]
+    fn test_display() {
+        let contents = ".#..#\n.....\n#####\n....#\n...##\n";
+        assert_eq!(Grid::parse(contents).unwrap().to_string(), contents);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Grid::parse(".#..#\n..x..\n#####").unwrap_err(),
+            ParseError::new(2, "x", "expected . or # for each spot")
+        );
+        assert_eq!(Grid::parse(".#..#\n...\n#####").unwrap_err().line, 2);
+        assert_eq!(Grid::parse("").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_zap_iter() {
+        let grid = Grid::parse(
+            ".#....#####...#..\n##...##.#####..##\n##...#...#.#####.\n..#.....#...###..\n..#.#.....#....##",
+        )
+        .unwrap();
+
+        assert_eq!(
+            zap_iter(&grid, (8, 3)).take(9).collect::<Vec<_>>(),
+            vec![
+                (8, 1),
+                (9, 0),
+                (9, 1),
+                (10, 0),
+                (9, 2),
+                (11, 1),
+                (12, 1),
+                (11, 2),
+                (15, 1)
+            ]
+        );
+        assert_eq!(
+            zap_iter(&grid, (8, 3)).count(),
+            grid.asteroid_positions.len() - 1
+        );
+
+        assert_eq!(nth_vaporized(&grid, (8, 3), 1), Some((8, 1)));
+        assert_eq!(nth_vaporized(&grid, (8, 3), 0), None);
+        assert_eq!(nth_vaporized(&grid, (8, 3), 1000), None);
     }
 
     #[test]
@@ -197,7 +324,6 @@ mod tests {
 
     #[test]
     fn test_angle_between() {
-        dbg!(angle_between(8, 3, 8, 1));
         assert!(equal(angle_between(1, -4, 1, -8), 0.0));
         assert!(equal(angle_between(2, 2, 4, 2), 90.0));
         assert!(equal(angle_between(2, 5, 2, 10), 180.0));
@@ -1,54 +1,238 @@
-use crate::computer;
 use crate::computer::{Computer, HaltReason};
+use crate::programs;
+use crate::render::{self, RenderGrid, Rgb};
 use std::cmp::Ordering;
+use std::fmt::Write;
+use std::io;
 
-static WIDTH: usize = 43;
-static HEIGHT: usize = 21;
+/// What's currently on the arcade cabinet's screen.
+pub struct Screen {
+    tiles: Vec<Tile>,
+    width: usize,
+    height: usize,
+    pub score: i64,
+    /// The ball's (x, y) position.
+    pub ball: (i64, i64),
+    /// The paddle's (x, y) position.
+    pub paddle: (i64, i64),
+}
+
+impl Screen {
+    /// Draws `tile` at (x, y), growing the screen if (x, y) is outside of the area we've seen so far.
+    fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
+        if x >= self.width || y >= self.height {
+            let new_width = self.width.max(x + 1);
+            let new_height = self.height.max(y + 1);
+            let mut tiles = vec![Tile::Empty; new_width * new_height];
+
+            for (i, &old_tile) in self.tiles.iter().enumerate() {
+                tiles[(i / self.width) * new_width + i % self.width] = old_tile;
+            }
+
+            self.tiles = tiles;
+            self.width = new_width;
+            self.height = new_height;
+        }
+
+        self.tiles[y * self.width + x] = tile;
+    }
+
+    pub fn num_blocks(&self) -> usize {
+        self.tiles
+            .iter()
+            .filter(|&tile| tile == &Tile::Block)
+            .count()
+    }
+}
+
+/// Decides which way to tilt the joystick.
+pub trait Strategy {
+    /// "If the joystick is in the neutral position, provide 0.
+    /// If the joystick is tilted to the left, provide -1.
+    /// If the joystick is tilted to the right, provide 1."
+    fn joystick_input(&mut self, screen: &Screen) -> i64;
+}
+
+/// Moves the paddle toward wherever the ball currently is.
+pub struct FollowBall;
+
+impl Strategy for FollowBall {
+    fn joystick_input(&mut self, screen: &Screen) -> i64 {
+        tilt_toward(screen.paddle.0, screen.ball.0)
+    }
+}
+
+/// Moves the paddle toward where the ball is going to be when it comes back down to the paddle,
+/// taking into account bounces off of the side walls.
+#[derive(Default)]
+pub struct PredictBall {
+    previous_ball: Option<(i64, i64)>,
+}
+
+impl Strategy for PredictBall {
+    fn joystick_input(&mut self, screen: &Screen) -> i64 {
+        let (ball_x, ball_y) = screen.ball;
+        let previous_ball = self.previous_ball.replace(screen.ball);
+
+        let target_x = match previous_ball {
+            Some((previous_x, previous_y)) if ball_y > previous_y => {
+                let steps_until_paddle = screen.paddle.1 - 1 - ball_y;
+                let unbounded_x = ball_x + (ball_x - previous_x) * steps_until_paddle;
+
+                // The ball bounces back and forth between the walls at x = 0 and x = width - 1.
+                let (min_x, max_x) = (1, screen.width() as i64 - 2);
+                let period = 2 * (max_x - min_x);
+                let offset = (unbounded_x - min_x).rem_euclid(period);
+
+                if offset > max_x - min_x {
+                    min_x + period - offset
+                } else {
+                    min_x + offset
+                }
+            }
+            _ => ball_x,
+        };
+
+        tilt_toward(screen.paddle.0, target_x)
+    }
+}
 
-struct Game {
-    state: Vec<Tile>,
+/// Lets a person play the game by entering "a" to move left and "d" to move right.
+pub struct Human;
+
+impl Strategy for Human {
+    #[cfg(not(tarpaulin_include))]
+    fn joystick_input(&mut self, screen: &Screen) -> i64 {
+        render::print(screen);
+        println!(
+            "Score: {}. Move left (a), right (d), or stay put (enter)?",
+            screen.score
+        );
+
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).unwrap();
+
+        match line.trim() {
+            "a" => -1,
+            "d" => 1,
+            _ => 0,
+        }
+    }
+}
+
+fn tilt_toward(paddle_x: i64, target_x: i64) -> i64 {
+    match paddle_x.cmp(&target_x) {
+        Ordering::Less => 1,
+        Ordering::Equal => 0,
+        Ordering::Greater => -1,
+    }
+}
+
+/// A snapshot of the game, taken each time it asks for joystick input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    pub frame: usize,
+    pub ball: (i64, i64),
+    pub paddle: (i64, i64),
+    pub blocks_remaining: usize,
+    pub score: i64,
+}
+
+/// Serializes `frames` as CSV, one frame per line.
+pub fn serialize_replay(frames: &[Frame]) -> String {
+    let mut s = String::from("frame,ball_x,ball_y,paddle_x,paddle_y,blocks_remaining,score\n");
+
+    for frame in frames {
+        writeln!(
+            &mut s,
+            "{},{},{},{},{},{},{}",
+            frame.frame,
+            frame.ball.0,
+            frame.ball.1,
+            frame.paddle.0,
+            frame.paddle.1,
+            frame.blocks_remaining,
+            frame.score
+        )
+        .unwrap();
+    }
+
+    s
+}
+
+pub struct Game {
+    screen: Screen,
     computer: Computer,
-    score: i64,
-    initialized: bool,
-    ball_x: i64,
-    paddle_x: i64,
+    strategy: Box<dyn Strategy>,
+    finished: bool,
+    replay: Option<Vec<Frame>>,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let memory = computer::load_program("src/inputs/13.txt");
+    pub fn new(strategy: Box<dyn Strategy>) -> Game {
+        let memory = programs::load("arcade");
 
         Game {
-            state: vec![Tile::Empty; WIDTH * HEIGHT],
+            screen: Screen {
+                tiles: vec![],
+                width: 0,
+                height: 0,
+                score: 0,
+                ball: (0, 0),
+                paddle: (0, 0),
+            },
             computer: Computer::new(memory),
-            score: 0,
-            initialized: false,
-            ball_x: 0,
-            paddle_x: 0,
+            strategy,
+            finished: false,
+            replay: None,
         }
     }
 
+    /// Makes the game record a Frame each time it asks for joystick input.
+    pub fn record_replay(mut self) -> Self {
+        self.replay = Some(vec![]);
+        self
+    }
+
+    /// Returns the frames recorded so far, if the game is recording a replay.
+    pub fn replay(&self) -> Option<&[Frame]> {
+        self.replay.as_deref()
+    }
+
+    /// Returns the (width, height) of the screen, based on the tiles the game has drawn so far.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.screen.width, self.screen.height)
+    }
+
+    /// Runs the game until it exits or wants to know which way the joystick is tilted.
     pub fn update_state(&mut self) {
         loop {
             // "The software draws tiles to the screen with output instructions: every
             // three output instructions specify the x position (distance from the left), y
             // position (distance from the top), and tile id."
-            let halt_reason = self.computer.run(HaltReason::Output);
-            if halt_reason == HaltReason::Exit {
-                break;
+            match self.computer.run(HaltReason::NeedsInput) {
+                HaltReason::Exit => {
+                    self.finished = true;
+                    break;
+                }
+                HaltReason::NeedsInput => {
+                    // The frame is done, and the game is waiting on the joystick.
+                    self.computer.retry_input();
+                    self.record_frame();
+                    break;
+                }
+                HaltReason::Output => (),
             }
-            self.computer.run(HaltReason::Output);
-            self.computer.run(HaltReason::Output);
-
-            let x = self.computer.pop_output().unwrap();
-            let y = self.computer.pop_output().unwrap();
-            let score_or_tile_id = self.computer.pop_output().unwrap();
+            let (x, y, score_or_tile_id) = match self.computer.run_until_n_outputs(3)[..] {
+                [x, y, score_or_tile_id] => (x, y, score_or_tile_id),
+                _ => panic!("the game exited partway through drawing a tile"),
+            };
 
             if x == -1 && y == 0 {
                 // "When three output instructions specify X=-1, Y=0, the third
                 // output instruction is not a tile; the value instead specifies the
                 // new score to show in the segment display."
-                self.score = score_or_tile_id;
+                self.screen.score = score_or_tile_id;
             } else {
                 // It's a tile ID!
                 let tile = match score_or_tile_id {
@@ -56,50 +240,75 @@ impl Game {
                     1 => Tile::Wall,
                     2 => Tile::Block,
                     3 => {
-                        self.paddle_x = x;
+                        self.screen.paddle = (x, y);
                         Tile::Paddle
                     }
                     4 => {
-                        self.ball_x = x;
+                        self.screen.ball = (x, y);
                         Tile::Ball
                     }
                     _ => panic!("unexpected tile {}", score_or_tile_id),
                 };
 
-                self.state[y as usize * WIDTH + x as usize] = tile;
-
-                if self.initialized {
-                    // Once the game is in flight, it signals the end of a frame
-                    // by outputting the ball's location.
-                    if tile == Tile::Ball {
-                        break;
-                    }
-                } else if x as usize == WIDTH - 1 && y as usize == HEIGHT - 1 {
-                    // We've finished loading the game's initial state.
-                    self.initialized = true;
-                    break;
-                }
+                self.screen.set_tile(x as usize, y as usize, tile);
             }
         }
     }
 
-    #[cfg(not(tarpaulin_include))]
-    fn _draw_to_screen(&self) {
-        for (i, tile) in self.state.iter().enumerate() {
-            if i > 0 && i % WIDTH == 0 {
-                println!();
-            }
+    fn record_frame(&mut self) {
+        if let Some(replay) = &mut self.replay {
+            replay.push(Frame {
+                frame: replay.len(),
+                ball: self.screen.ball,
+                paddle: self.screen.paddle,
+                blocks_remaining: self.screen.num_blocks(),
+                score: self.screen.score,
+            });
+        }
+    }
 
-            print!(
-                "{}",
-                match tile {
-                    Tile::Empty => " ",
-                    Tile::Wall => "|",
-                    Tile::Block => "_",
-                    Tile::Paddle => "p",
-                    Tile::Ball => "O",
-                }
-            );
+    /// Plays the game until every block is broken or the program exits, and returns the final score.
+    pub fn play(&mut self) -> i64 {
+        // "Memory address 0 represents the number of quarters that have been inserted; set it to 2 to play for free."
+        self.computer.state.memory[0] = 2;
+        self.update_state();
+
+        while !self.finished && self.screen.num_blocks() > 0 {
+            let joystick_input = self.strategy.joystick_input(&self.screen);
+            self.computer.push_input(joystick_input);
+            self.update_state();
+        }
+
+        self.screen.score
+    }
+}
+
+impl RenderGrid for Screen {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn cell_char(&self, x: usize, y: usize) -> char {
+        match self.tiles[y * self.width + x] {
+            Tile::Empty => ' ',
+            Tile::Wall => '|',
+            Tile::Block => '_',
+            Tile::Paddle => 'p',
+            Tile::Ball => 'O',
+        }
+    }
+
+    fn cell_color(&self, x: usize, y: usize) -> Rgb {
+        match self.tiles[y * self.width + x] {
+            Tile::Empty => render::BLACK,
+            Tile::Wall => render::GRAY,
+            Tile::Block => Rgb(200, 80, 40),
+            Tile::Paddle => render::WHITE,
+            Tile::Ball => Rgb(240, 220, 60),
         }
     }
 }
@@ -120,43 +329,104 @@ enum Tile {
 
 /// "Start the game. How many block tiles are on the screen when the game exits?"
 pub fn thirteen_a() -> usize {
-    let mut game = Game::new();
+    let mut game = Game::new(Box::new(FollowBall));
     game.update_state();
-
-    game.state
-        .iter()
-        .filter(|&tile| tile == &Tile::Block)
-        .count()
+    game.screen.num_blocks()
 }
 
 /// "Beat the game by breaking all the blocks. What is your score after the last block is broken?"
 pub fn thirteen_b() -> i64 {
-    let mut game = Game::new();
+    Game::new(Box::new(FollowBall)).play()
+}
 
-    // "Memory address 0 represents the number of quarters that have been inserted; set it to 2 to play for free."
-    game.computer.state.memory[0] = 2;
-    game.update_state();
+/// Lets you play the game yourself.
+#[cfg(not(tarpaulin_include))]
+pub fn play_interactively() -> i64 {
+    Game::new(Box::new(Human)).play()
+}
 
-    while game.state.iter().any(|tile| tile == &Tile::Block) {
-        // "If the joystick is in the neutral position, provide 0.
-        // If the joystick is tilted to the left, provide -1.
-        // If the joystick is tilted to the right, provide 1."
-        let joystick_input = match game.paddle_x.cmp(&game.ball_x) {
-            Ordering::Less => 1,
-            Ordering::Equal => 0,
-            Ordering::Greater => -1,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer;
 
-        game.computer.push_input(joystick_input);
+    #[test]
+    fn test_dimensions() {
+        let mut game = Game::new(Box::new(FollowBall));
         game.update_state();
+        assert_eq!(game.dimensions(), (43, 21));
     }
 
-    game.score
-}
+    #[test]
+    fn test_screen_grows() {
+        let mut screen = Screen {
+            tiles: vec![],
+            width: 0,
+            height: 0,
+            score: 0,
+            ball: (0, 0),
+            paddle: (0, 0),
+        };
+        screen.set_tile(1, 0, Tile::Wall);
+        screen.set_tile(0, 1, Tile::Block);
+        screen.set_tile(2, 1, Tile::Ball);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(render::to_text(&screen), " | \n_ O\n");
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut game = Game::new(Box::new(FollowBall)).record_replay();
+        game.play();
+        let replay = game.replay().unwrap();
+
+        // Pin down how many frames it takes to win, so that changes to the VM that alter gameplay get noticed.
+        assert_eq!(replay.len(), 6994);
+        assert_eq!(replay[0].frame, 0);
+        assert_eq!(replay[0].blocks_remaining, 284);
+        assert_eq!(replay[0].score, 0);
+        assert!(replay
+            .windows(2)
+            .all(|pair| pair[1].frame == pair[0].frame + 1));
+
+        let serialized = serialize_replay(replay);
+        assert_eq!(serialized.lines().count(), replay.len() + 1);
+        assert_eq!(
+            serialized.lines().nth(1).unwrap(),
+            format!(
+                "0,{},{},{},{},284,0",
+                replay[0].ball.0, replay[0].ball.1, replay[0].paddle.0, replay[0].paddle.1
+            )
+        );
+
+        assert!(Game::new(Box::new(FollowBall)).replay().is_none());
+    }
+
+    #[test]
+    fn test_golden_trace() {
+        let mut memory = programs::load("arcade");
+        memory[0] = 2;
+
+        // The whole game's trace would be enormous, so this just covers the first screen and a few moves.
+        let mut game = Game::new(Box::new(FollowBall));
+        game.computer.record_io();
+        game.computer.state.memory[0] = 2;
+        game.update_state();
+        for _ in 0..20 {
+            let joystick_input = game.strategy.joystick_input(&game.screen);
+            game.computer.push_input(joystick_input);
+            game.update_state();
+        }
+
+        let golden = computer::load_trace("src/inputs/13_trace.txt");
+        assert_eq!(game.computer.trace(), Some(&golden));
+        assert_eq!(computer::replay(memory, &golden), Ok(()));
+    }
+
+    #[test]
+    fn test_predict_ball() {
+        assert_eq!(Game::new(Box::new(PredictBall::default())).play(), 13581);
+    }
 
     #[test]
     fn test_solutions() {
@@ -1,7 +1,9 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
+use crate::util::{self, ParseError};
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io;
+use tracing::instrument;
 
 type Wire = Vec<(i32, i32)>;
 
@@ -15,89 +17,235 @@ pub fn three_b() -> i32 {
     closest_intersection_by_steps(wire_1, wire_2)
 }
 
+/// A point where the two wires cross, along with the number of steps each wire takes to first reach it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Intersection {
+    pub position: (i32, i32),
+    pub wire_1_steps: i32,
+    pub wire_2_steps: i32,
+}
+
+impl Intersection {
+    pub fn manhattan_distance(&self) -> i32 {
+        self.position.0.abs() + self.position.1.abs()
+    }
+
+    /// "The fewest combined steps the wires must take to reach" this intersection.
+    pub fn combined_steps(&self) -> i32 {
+        self.wire_1_steps + self.wire_2_steps
+    }
+}
+
+/// Returns every intersection of the puzzle input's wires.
+pub fn find_intersections() -> Vec<Intersection> {
+    let (wire_1, wire_2) = load_wires();
+    wire_intersections(&wire_1, &wire_2)
+}
+
 /// Returns the Manhattan distance of the two wires' closest intersection to 0,0.
+#[instrument(name = "solve", level = "debug", skip_all)]
 fn closest_intersection_by_manhattan_distance(wire_1: Wire, wire_2: Wire) -> i32 {
-    let intersections = wire_intersections(&wire_1, &wire_2);
-
-    intersections
+    wire_intersections(&wire_1, &wire_2)
         .iter()
-        .map(|&(x, y)| x.abs() + y.abs())
+        .map(Intersection::manhattan_distance)
         .min()
         .unwrap()
 }
 
 /// Returns the combined number of steps taken by each wire between 0,0 and their closest intersection by number of steps.
+#[instrument(name = "solve", level = "debug", skip_all)]
 fn closest_intersection_by_steps(wire_1: Wire, wire_2: Wire) -> i32 {
-    let intersections = wire_intersections(&wire_1, &wire_2);
-
-    intersections
+    wire_intersections(&wire_1, &wire_2)
         .iter()
-        .map(|intersection| {
-            wire_1.iter().position(|elem| elem == intersection).unwrap() as i32
-                + wire_2.iter().position(|elem| elem == intersection).unwrap() as i32
-        })
+        .map(Intersection::combined_steps)
         .min()
         .unwrap()
 }
 
-fn wire_intersections(wire_1: &[(i32, i32)], wire_2: &[(i32, i32)]) -> Vec<(i32, i32)> {
-    let wire_1_positions = wire_1.iter().cloned().collect::<HashSet<(i32, i32)>>();
+/// Returns a map of each position in `wire` to the number of steps it takes the wire to first reach it.
+fn first_visit_steps(wire: &[(i32, i32)]) -> HashMap<(i32, i32), i32> {
+    let mut steps = HashMap::with_capacity(wire.len());
+
+    for (i, &position) in wire.iter().enumerate() {
+        steps.entry(position).or_insert(i as i32);
+    }
+
+    steps
+}
+
+/// Returns the wires' intersections, in the order that `wire_2` reaches them.
+fn wire_intersections(wire_1: &[(i32, i32)], wire_2: &[(i32, i32)]) -> Vec<Intersection> {
+    let wire_1_steps = first_visit_steps(wire_1);
+    let wire_2_steps = first_visit_steps(wire_2);
 
     wire_2
         .iter()
-        .filter(|&position| wire_1_positions.contains(position) && *position != (0, 0))
-        .cloned()
+        .enumerate()
+        .filter(|&(i, position)| *position != (0, 0) && wire_2_steps[position] == i as i32)
+        .filter_map(|(i, &position)| {
+            wire_1_steps.get(&position).map(|&steps| Intersection {
+                position,
+                wire_1_steps: steps,
+                wire_2_steps: i as i32,
+            })
+        })
         .collect()
 }
 
 /// Parses a wire string like "R8,U5,L5,D3" into a Vec of (x, y) positions.
-fn parse_wire(wire: String) -> Wire {
+/// Directions can be lowercase, and whitespace and empty segments are ignored.
+fn parse_wire(wire: &str) -> Result<Wire, ParseError> {
     let mut ret = vec![];
 
     let mut x = 0;
     let mut y = 0;
 
-    for movement in wire.trim().split(',').into_iter() {
-        let mut chars = movement.chars();
-        let direction = chars.next().unwrap();
-        let amount = chars.collect::<String>().parse::<i32>().unwrap();
+    for segment in wire.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut chars = segment.chars();
+        let (dx, dy) = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('U') => (0, 1),
+            Some('D') => (0, -1),
+            Some('L') => (-1, 0),
+            Some('R') => (1, 0),
+            _ => {
+                return Err(ParseError::new(
+                    1,
+                    segment,
+                    "expected a direction of U, D, L, or R",
+                ))
+            }
+        };
+        let amount: u32 = chars.as_str().trim().parse().map_err(|_| {
+            ParseError::new(1, segment, "expected a number of steps after the direction")
+        })?;
 
         for _ in 0..amount {
             ret.push((x, y));
-
-            match direction {
-                'U' => {
-                    y += 1;
-                }
-                'D' => {
-                    y -= 1;
-                }
-                'L' => {
-                    x -= 1;
-                }
-                'R' => {
-                    x += 1;
-                }
-                _ => panic!("unknown direction {}", direction),
-            }
+            x += dx;
+            y += dy;
         }
     }
 
     ret.push((x, y));
 
-    ret
+    Ok(ret)
 }
 
-fn load_wires() -> (Wire, Wire) {
-    let f = File::open("src/inputs/3.txt").unwrap();
-    let mut reader = BufReader::new(f);
+/// Parses the two wires in `contents`, one per line.
+fn parse_wires(contents: &str) -> Result<(Wire, Wire), ParseError> {
+    let lines: Vec<(usize, &str)> = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .collect();
+    let parse =
+        |&(i, line): &(usize, &str)| parse_wire(line).map_err(|e| ParseError { line: i + 1, ..e });
+
+    match lines.as_slice() {
+        [wire_1, wire_2] => Ok((parse(wire_1)?, parse(wire_2)?)),
+        [_, _, (i, extra), ..] => Err(ParseError::new(i + 1, extra, "expected only two wires")),
+        _ => Err(ParseError::new(
+            contents.lines().count() + 1,
+            "",
+            "expected two wires, one per line",
+        )),
+    }
+}
+
+/// Returns the positions in `wire` where it changes direction, along with its two endpoints.
+fn wire_corners(wire: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut corners = vec![wire[0]];
+
+    for window in wire.windows(3) {
+        let (a, b, c) = (window[0], window[1], window[2]);
+        if (b.0 - a.0, b.1 - a.1) != (c.0 - b.0, c.1 - b.1) {
+            corners.push(b);
+        }
+    }
+
+    corners.push(wire[wire.len() - 1]);
+    corners
+}
+
+/// Returns an SVG drawing of both wires, with a dot on each of their intersections.
+/// The intersection that's closest to 0,0 is highlighted in green, and the one that's
+/// closest by number of steps is highlighted in orange.
+fn wires_to_svg(wire_1: &[(i32, i32)], wire_2: &[(i32, i32)]) -> String {
+    let intersections = wire_intersections(wire_1, wire_2);
+    let closest_by_distance = intersections
+        .iter()
+        .min_by_key(|intersection| intersection.manhattan_distance());
+    let closest_by_steps = intersections
+        .iter()
+        .min_by_key(|intersection| intersection.combined_steps());
+
+    // SVG's y axis points down, so we flip our wires upside down when drawing them.
+    let all_positions = wire_1.iter().chain(wire_2.iter());
+    let min_x = all_positions.clone().map(|&(x, _)| x).min().unwrap();
+    let max_x = all_positions.clone().map(|&(x, _)| x).max().unwrap();
+    let min_y = all_positions.clone().map(|&(_, y)| -y).min().unwrap();
+    let max_y = all_positions.map(|&(_, y)| -y).max().unwrap();
+
+    let stroke_width = ((max_x - min_x).max(max_y - min_y) / 500).max(1);
+    let margin = stroke_width * 10;
+
+    let mut svg = String::new();
+    writeln!(
+        &mut svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min_x - margin,
+        min_y - margin,
+        max_x - min_x + 2 * margin,
+        max_y - min_y + 2 * margin
+    )
+    .unwrap();
+
+    for (wire, color) in [(wire_1, "red"), (wire_2, "blue")].iter() {
+        let points: Vec<String> = wire_corners(wire)
+            .iter()
+            .map(|&(x, y)| format!("{},{}", x, -y))
+            .collect();
+
+        writeln!(
+            &mut svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+            points.join(" "),
+            color,
+            stroke_width
+        )
+        .unwrap();
+    }
 
-    let mut line_1 = String::new();
-    reader.read_line(&mut line_1).unwrap();
-    let mut line_2 = String::new();
-    reader.read_line(&mut line_2).unwrap();
+    for intersection in &intersections {
+        let (color, radius) = if Some(intersection) == closest_by_distance {
+            ("green", stroke_width * 6)
+        } else if Some(intersection) == closest_by_steps {
+            ("orange", stroke_width * 6)
+        } else {
+            ("black", stroke_width * 3)
+        };
 
-    (parse_wire(line_1), parse_wire(line_2))
+        writeln!(
+            &mut svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            intersection.position.0, -intersection.position.1, radius, color
+        )
+        .unwrap();
+    }
+
+    writeln!(&mut svg, "</svg>").unwrap();
+    svg
+}
+
+/// Writes an SVG drawing of the puzzle input's wires to `path`.
+pub fn render_svg(path: &str) -> io::Result<()> {
+    let (wire_1, wire_2) = load_wires();
+    fs::write(path, wires_to_svg(&wire_1, &wire_2))
+}
+
+#[instrument(name = "parse", level = "debug", skip_all)]
+fn load_wires() -> (Wire, Wire) {
+    util::load(util::input(3), parse_wires)
 }
 
 #[cfg(test)]
@@ -107,7 +255,7 @@ mod tests {
     #[test]
     fn test_parse_wire() {
         assert_eq!(
-            parse_wire(String::from("R8,U5,L5,D3")),
+            parse_wire("R8,U5,L5,D3").unwrap(),
             vec![
                 (0, 0),
                 (1, 0),
@@ -135,19 +283,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_wire_leniently() {
+        let expected = parse_wire("R8,U5,L5,D3").unwrap();
+        assert_eq!(parse_wire("r8,u5,L5,d3").unwrap(), expected);
+        assert_eq!(parse_wire(" R8, U5 ,,L5,D3,\n").unwrap(), expected);
+        assert_eq!(parse_wire("").unwrap(), vec![(0, 0)]);
+
+        let (wire_1, wire_2) = parse_wires("\nR8,U5,L5,D3\r\nU7,R6,D4,L4\n\n").unwrap();
+        assert_eq!(wire_1, expected);
+        assert_eq!(wire_2, parse_wire("U7,R6,D4,L4").unwrap());
+    }
+
+    #[test]
+    fn test_parse_wire_errors() {
+        assert_eq!(
+            parse_wire("R8,X5").unwrap_err(),
+            ParseError::new(1, "X5", "expected a direction of U, D, L, or R")
+        );
+        for bad_amount in ["R", "R-3", "R8U5", "R 5 5"].iter() {
+            assert_eq!(
+                parse_wire(bad_amount).unwrap_err(),
+                ParseError::new(
+                    1,
+                    bad_amount,
+                    "expected a number of steps after the direction"
+                )
+            );
+        }
+
+        assert_eq!(
+            parse_wires("R8,U5\nU7,R6,Q4\n").unwrap_err().to_string(),
+            "line 2: \"Q4\": expected a direction of U, D, L, or R"
+        );
+        assert_eq!(
+            parse_wires("R8,U5\n").unwrap_err(),
+            ParseError::new(2, "", "expected two wires, one per line")
+        );
+        assert_eq!(
+            parse_wires("R8\nU5\n\nL5\n").unwrap_err(),
+            ParseError::new(4, "L5", "expected only two wires")
+        );
+    }
+
     #[test]
     fn test_closest_intersection_by_manhattan() {
         assert_eq!(
             closest_intersection_by_manhattan_distance(
-                parse_wire(String::from("R75,D30,R83,U83,L12,D49,R71,U7,L72")),
-                parse_wire(String::from("U62,R66,U55,R34,D71,R55,D58,R83"))
+                parse_wire("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap(),
+                parse_wire("U62,R66,U55,R34,D71,R55,D58,R83").unwrap()
             ),
             159
         );
         assert_eq!(
             closest_intersection_by_manhattan_distance(
-                parse_wire(String::from("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51")),
-                parse_wire(String::from("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"))
+                parse_wire("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap(),
+                parse_wire("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap()
             ),
             135
         );
@@ -157,20 +348,67 @@ mod tests {
     fn test_closest_intersection_by_steps() {
         assert_eq!(
             closest_intersection_by_steps(
-                parse_wire(String::from("R75,D30,R83,U83,L12,D49,R71,U7,L72")),
-                parse_wire(String::from("U62,R66,U55,R34,D71,R55,D58,R83"))
+                parse_wire("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap(),
+                parse_wire("U62,R66,U55,R34,D71,R55,D58,R83").unwrap()
             ),
             610
         );
         assert_eq!(
             closest_intersection_by_steps(
-                parse_wire(String::from("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51")),
-                parse_wire(String::from("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7"))
+                parse_wire("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap(),
+                parse_wire("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap()
             ),
             410
         );
     }
 
+    #[test]
+    fn test_wire_intersections() {
+        assert_eq!(
+            wire_intersections(
+                &parse_wire("R8,U5,L5,D3").unwrap(),
+                &parse_wire("U7,R6,D4,L4").unwrap()
+            ),
+            vec![
+                Intersection {
+                    position: (6, 5),
+                    wire_1_steps: 15,
+                    wire_2_steps: 15
+                },
+                Intersection {
+                    position: (3, 3),
+                    wire_1_steps: 20,
+                    wire_2_steps: 20
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wire_corners() {
+        assert_eq!(
+            wire_corners(&parse_wire("R8,U5,L5,D3").unwrap()),
+            vec![(0, 0), (8, 0), (8, 5), (3, 5), (3, 2)]
+        );
+    }
+
+    #[test]
+    fn test_wires_to_svg() {
+        assert_eq!(
+            wires_to_svg(
+                &parse_wire("R8,U5,L5,D3").unwrap(),
+                &parse_wire("U7,R6,D4,L4").unwrap()
+            ),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-10 -17 28 27">
+<polyline points="0,0 8,0 8,-5 3,-5 3,-2" fill="none" stroke="red" stroke-width="1"/>
+<polyline points="0,0 0,-7 6,-7 6,-3 2,-3" fill="none" stroke="blue" stroke-width="1"/>
+<circle cx="6" cy="-5" r="6" fill="orange"/>
+<circle cx="3" cy="-3" r="6" fill="green"/>
+</svg>
+"#
+        );
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(three_a(), 8015);
@@ -1,20 +1,31 @@
+use crate::util::{self, ParseError};
 use num::integer::Integer;
+use rayon::prelude::*;
 use regex::Regex;
-use std::cmp::Ordering;
-use std::fs;
+use smallvec::SmallVec;
+use std::convert::TryInto;
+use std::io::{self, Write};
+use tracing::instrument;
 
 #[derive(PartialEq, Debug, Clone, Copy, Hash, Eq)]
-struct Vector {
-    x: i32,
-    y: i32,
-    z: i32,
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl Vector {
+    /// "The sum of the absolute values of its x, y, and z" components.
+    fn absolute_sum(&self) -> i32 {
+        self.x.abs() + self.y.abs() + self.z.abs()
+    }
 }
 
 /// "Each moon has a 3-dimensional position (x, y, and z) and a 3-dimensional velocity.""""
 #[derive(PartialEq, Debug, Clone, Copy, Hash, Eq)]
-struct Moon {
-    position: Vector,
-    velocity: Vector,
+pub struct Moon {
+    pub position: Vector,
+    pub velocity: Vector,
 }
 
 impl Moon {
@@ -25,148 +36,301 @@ impl Moon {
             velocity: Vector { x: 0, y: 0, z: 0 },
         }
     }
+
+    /// "A moon's potential energy is the sum of the absolute values of its x, y, and z position coordinates."
+    pub fn potential_energy(&self) -> i32 {
+        self.position.absolute_sum()
+    }
+
+    /// "A moon's kinetic energy is the sum of the absolute values of its velocity coordinates."
+    pub fn kinetic_energy(&self) -> i32 {
+        self.velocity.absolute_sum()
+    }
+
+    /// "The total energy for a single moon is its potential energy multiplied by its kinetic energy."
+    pub fn total_energy(&self) -> i32 {
+        self.potential_energy() * self.kinetic_energy()
+    }
+}
+
+/// "The puzzle input is a scan of the four largest moons", so an Axis keeps up to this many moons in
+/// fixed-size arrays of its own, without allocating.
+const NUM_MOONS: usize = 4;
+
+/// The positions and velocities of every moon along a single axis.
+/// The axes don't affect one another, so each one can be simulated on its own.
+#[derive(PartialEq, Debug, Clone)]
+struct Axis {
+    positions: SmallVec<[i32; NUM_MOONS]>,
+    velocities: SmallVec<[i32; NUM_MOONS]>,
 }
 
-/// "To apply gravity, consider every pair of moons. On each axis (x, y, and z),
-/// the velocity of each moon changes by exactly +1 or -1 to pull the moons
-/// together. For example, if Ganymede has an x position of 3, and Callisto has a
-/// x position of 5, then Ganymede's x velocity changes by +1 (because 5 > 3) and
-/// Callisto's x velocity changes by -1 (because 3 < 5). However, if the
-/// positions on a given axis are the same, the velocity on that axis does not
-/// change for that pair of moons."
-fn apply_gravity(moons: &mut [Moon]) {
-    for i in 0..moons.len() {
-        let mut moon = moons[i];
-
-        for j in (0..moons.len()).filter(|&j| j != i) {
-            let position = moons[i].position;
-            let other_position = moons[j].position;
-
-            moon.velocity.x += calculate_gravity_for_axis(position.x, other_position.x);
-            moon.velocity.y += calculate_gravity_for_axis(position.y, other_position.y);
-            moon.velocity.z += calculate_gravity_for_axis(position.z, other_position.z);
+impl Axis {
+    /// "To apply gravity, consider every pair of moons. On each axis (x, y, and z),
+    /// the velocity of each moon changes by exactly +1 or -1 to pull the moons
+    /// together. For example, if Ganymede has an x position of 3, and Callisto has a
+    /// x position of 5, then Ganymede's x velocity changes by +1 (because 5 > 3) and
+    /// Callisto's x velocity changes by -1 (because 3 < 5). However, if the
+    /// positions on a given axis are the same, the velocity on that axis does not
+    /// change for that pair of moons."
+    fn apply_gravity(&mut self) {
+        let positions: Result<&[i32; NUM_MOONS], _> = self.positions[..].try_into();
+        let velocities: Result<&mut [i32; NUM_MOONS], _> = (&mut self.velocities[..]).try_into();
+
+        match (positions, velocities) {
+            (Ok(positions), Ok(velocities)) => apply_gravity_to_four_moons(positions, velocities),
+            _ => apply_gravity_to_any_moons(&self.positions, &mut self.velocities),
         }
+    }
 
-        moons[i] = moon;
+    /// "Once all gravity has been applied, apply velocity: simply add the velocity
+    /// of each moon to its own position. For example, if Europa has a position of
+    /// x=1, y=2, z=3 and a velocity of x=-2, y=0,z=3, then its new position would be
+    /// x=-1, y=2, z=6. This process does not modify the velocity of any moon."
+    fn apply_velocity(&mut self) {
+        for (position, &velocity) in self.positions.iter_mut().zip(self.velocities.iter()) {
+            *position += velocity;
+        }
     }
-}
 
-fn calculate_gravity_for_axis(self_axis_value: i32, other_axis_value: i32) -> i32 {
-    match self_axis_value.cmp(&other_axis_value) {
-        Ordering::Less => 1,
-        Ordering::Equal => 0,
-        Ordering::Greater => -1,
+    /// "Simulate the motion of the moons in time steps. Within each time step, first
+    /// update the velocity of every moon by applying gravity. Then, once all moons'
+    /// velocities have been updated, update the position of every moon by applying
+    /// velocity. Time progresses by one step once all of the positions are updated."
+    fn step(&mut self) {
+        self.apply_gravity();
+        self.apply_velocity();
     }
-}
 
-/// "Once all gravity has been applied, apply velocity: simply add the velocity
-/// of each moon to its own position. For example, if Europa has a position of
-/// x=1, y=2, z=3 and a velocity of x=-2, y=0,z=3, then its new position would be
-/// x=-1, y=2, z=6. This process does not modify the velocity of any moon."
-fn apply_velocity(moons: &mut [Moon]) {
-    for moon in moons {
-        moon.position.x += moon.velocity.x;
-        moon.position.y += moon.velocity.y;
-        moon.position.z += moon.velocity.z;
+    fn num_steps_until_repeat(&self) -> u64 {
+        let mut axis = self.clone();
+        let mut num_steps = 0;
+
+        loop {
+            num_steps += 1;
+            axis.step();
+
+            if axis == *self {
+                break num_steps;
+            }
+        }
     }
 }
 
-/// "Simulate the motion of the moons in time steps. Within each time step, first
-/// update the velocity of every moon by applying gravity. Then, once all moons'
-/// velocities have been updated, update the position of every moon by applying
-/// velocity. Time progresses by one step once all of the positions are updated."
-fn advance_time_one_step(moons: &mut [Moon]) {
-    apply_gravity(moons);
-    apply_velocity(moons);
+/// `Axis::apply_gravity()` for any number of moons.
+fn apply_gravity_to_any_moons(positions: &[i32], velocities: &mut [i32]) {
+    // Each pair pulls its two moons together by the same amount.
+    for i in 0..positions.len() {
+        for j in i + 1..positions.len() {
+            let pull = (positions[j] - positions[i]).signum();
+            velocities[i] += pull;
+            velocities[j] -= pull;
+        }
+    }
 }
 
-/// Parses our puzzle input into a Vec of Moons.
-fn parse_moons() -> Vec<Moon> {
-    let contents = fs::read_to_string("src/inputs/12.txt").unwrap();
-    let re = Regex::new(r"<x=(-?[0-9]\d*), y=(-?[0-9]\d*), z=(-?[0-9]\d*)>").unwrap();
-
-    contents
-        .lines()
-        .map(|line| {
-            let caps = re.captures(line).unwrap();
-            Moon::new(
-                caps[1].parse::<i32>().unwrap(),
-                caps[2].parse::<i32>().unwrap(),
-                caps[3].parse::<i32>().unwrap(),
-            )
-        })
-        .collect()
+/// `Axis::apply_gravity()` for the usual four moons, unrolled: each of the six pairs is compared once,
+/// and every moon's velocity changes by the sum of its three pulls without any branches or loops.
+fn apply_gravity_to_four_moons(positions: &[i32; NUM_MOONS], velocities: &mut [i32; NUM_MOONS]) {
+    let [a, b, c, d] = *positions;
+    let (ab, ac, ad) = ((b - a).signum(), (c - a).signum(), (d - a).signum());
+    let (bc, bd, cd) = ((c - b).signum(), (d - b).signum(), (d - c).signum());
+
+    velocities[0] += ab + ac + ad;
+    velocities[1] += bc + bd - ab;
+    velocities[2] += cd - ac - bc;
+    velocities[3] -= ad + bd + cd;
 }
 
-fn compute_energy_for_vector(v: Vector) -> i32 {
-    v.x.abs() + v.y.abs() + v.z.abs()
+/// A set of moons that pull on one another, stored as one Axis per dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct System {
+    axes: [Axis; 3],
 }
 
-fn compute_energy_for_moons(moons: &[Moon]) -> i32 {
-    moons.iter().fold(0, |acc, moon| {
-        acc + (compute_energy_for_vector(moon.position) * compute_energy_for_vector(moon.velocity))
-    })
-}
+impl System {
+    /// Parses a scan with lines like "<x=-1, y=0, z=2>" into a System.
+    #[instrument(name = "parse", level = "debug", skip_all)]
+    pub fn parse(contents: &str) -> Result<Self, ParseError> {
+        Ok(System::from_moons(&parse_moons(contents)?))
+    }
 
-pub fn twelve_a() -> i32 {
-    let mut moons = parse_moons();
-    for _ in 0..1000 {
-        advance_time_one_step(&mut moons);
+    /// Returns a System of stationary moons at `positions`.
+    pub fn from_positions(positions: &[(i32, i32, i32)]) -> Self {
+        let moons: Vec<Moon> = positions
+            .iter()
+            .map(|&(x, y, z)| Moon::new(x, y, z))
+            .collect();
+
+        System::from_moons(&moons)
     }
-    compute_energy_for_moons(&moons)
-}
 
-fn num_steps_until_axis_repeats(mut positions: Vec<i32>, mut velocities: Vec<i32>) -> u64 {
-    assert!(positions.len() == velocities.len());
+    fn from_moons(moons: &[Moon]) -> Self {
+        let axis = |get: fn(&Vector) -> i32| Axis {
+            positions: moons.iter().map(|moon| get(&moon.position)).collect(),
+            velocities: moons.iter().map(|moon| get(&moon.velocity)).collect(),
+        };
 
-    let mut num_steps = 0;
-    let original_positions = positions.clone();
-    let original_velocities = velocities.clone();
+        System {
+            axes: [axis(|v| v.x), axis(|v| v.y), axis(|v| v.z)],
+        }
+    }
 
-    loop {
-        num_steps += 1;
+    /// Returns the current state of each moon in the system.
+    pub fn moons(&self) -> Vec<Moon> {
+        let [x, y, z] = &self.axes;
 
-        // Update velocities based on gravity.
-        for i in 0..positions.len() {
-            for j in (0..positions.len()).filter(|&j| j != i) {
-                let position = positions[i];
-                let other_position = positions[j];
+        (0..x.positions.len())
+            .map(|i| Moon {
+                position: Vector {
+                    x: x.positions[i],
+                    y: y.positions[i],
+                    z: z.positions[i],
+                },
+                velocity: Vector {
+                    x: x.velocities[i],
+                    y: y.velocities[i],
+                    z: z.velocities[i],
+                },
+            })
+            .collect()
+    }
 
-                velocities[i] += calculate_gravity_for_axis(position, other_position);
+    /// Advances the simulation by `n` time steps.
+    pub fn step(&mut self, n: usize) {
+        for axis in &mut self.axes {
+            for _ in 0..n {
+                axis.step();
             }
         }
+    }
 
-        // Update positions based on velocities.
-        for i in 0..positions.len() {
-            positions[i] += velocities[i];
+    /// Advances the simulation by `n` time steps, one at a time, calling `observe` after each one
+    /// with the number of steps taken so far and the system's new state.
+    pub fn simulate(&mut self, n: usize, mut observe: impl FnMut(usize, &System)) {
+        for step in 1..=n {
+            self.step(1);
+            observe(step, self);
         }
+    }
+
+    /// Advances the simulation by `n` time steps, and returns the system's total energy after each one.
+    pub fn energy_history(&mut self, n: usize) -> Vec<i32> {
+        let mut energies = Vec::with_capacity(n);
+        self.simulate(n, |_, system| energies.push(system.total_energy()));
+        energies
+    }
 
-        if positions == original_positions && velocities == original_velocities {
-            break num_steps;
+    /// Advances the simulation by `n` time steps, writing the state of every moon to `writer` as CSV,
+    /// starting with their state before the first step.
+    /// Each row has a step number, a moon's index, its position and velocity, and its energies.
+    pub fn write_trajectories(&mut self, n: usize, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "step,moon,x,y,z,vx,vy,vz,potential,kinetic,total")?;
+        write_trajectory_rows(&mut writer, 0, self)?;
+
+        let mut result = Ok(());
+        self.simulate(n, |step, system| {
+            if result.is_ok() {
+                result = write_trajectory_rows(&mut writer, step, system);
+            }
+        });
+        result
+    }
+
+    /// "The total energy for a single moon is its potential energy multiplied by its kinetic energy."
+    /// Returns the sum of the total energy of every moon in the system.
+    pub fn total_energy(&self) -> i32 {
+        let num_moons = self.axes[0].positions.len();
+        let mut potential_energies = vec![0; num_moons];
+        let mut kinetic_energies = vec![0; num_moons];
+
+        for axis in &self.axes {
+            for i in 0..num_moons {
+                potential_energies[i] += axis.positions[i].abs();
+                kinetic_energies[i] += axis.velocities[i].abs();
+            }
         }
+
+        potential_energies
+            .iter()
+            .zip(kinetic_energies.iter())
+            .map(|(potential, kinetic)| potential * kinetic)
+            .sum()
     }
+
+    /// Returns the number of steps that it takes for the system to get back to its current state.
+    /// Each axis repeats on its own cycle, so we find those cycles in parallel and combine them.
+    #[instrument(name = "solve", level = "debug", skip_all)]
+    pub fn steps_until_repeat(&self) -> u64 {
+        self.axes
+            .par_iter()
+            .map(Axis::num_steps_until_repeat)
+            .reduce(|| 1, |a, b| a.lcm(&b))
+    }
+}
+
+fn write_trajectory_rows(writer: &mut impl Write, step: usize, system: &System) -> io::Result<()> {
+    for (i, moon) in system.moons().iter().enumerate() {
+        let Moon { position, velocity } = moon;
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            step,
+            i,
+            position.x,
+            position.y,
+            position.z,
+            velocity.x,
+            velocity.y,
+            velocity.z,
+            moon.potential_energy(),
+            moon.kinetic_energy(),
+            moon.total_energy()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Parses a scan with lines like "<x=-1, y=0, z=2>" into a Vec of Moons.
+fn parse_moons(contents: &str) -> Result<Vec<Moon>, ParseError> {
+    let re = Regex::new(r"<x=(-?[0-9]\d*), y=(-?[0-9]\d*), z=(-?[0-9]\d*)>").unwrap();
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let caps = re.captures(line).ok_or_else(|| {
+                ParseError::new(i + 1, line, "expected a position like <x=-1, y=0, z=2>")
+            })?;
+            Ok(Moon::new(
+                util::parse_token(&caps[1], i + 1)?,
+                util::parse_token(&caps[2], i + 1)?,
+                util::parse_token(&caps[3], i + 1)?,
+            ))
+        })
+        .collect()
 }
 
-fn num_steps_until_original_state_repeats(moons: &[Moon]) -> u64 {
-    let x_steps = num_steps_until_axis_repeats(
-        moons.iter().map(|moon| moon.position.x).collect(),
-        moons.iter().map(|moon| moon.velocity.x).collect(),
-    );
-    let y_steps = num_steps_until_axis_repeats(
-        moons.iter().map(|moon| moon.position.y).collect(),
-        moons.iter().map(|moon| moon.velocity.y).collect(),
-    );
-    let z_steps = num_steps_until_axis_repeats(
-        moons.iter().map(|moon| moon.position.z).collect(),
-        moons.iter().map(|moon| moon.velocity.z).collect(),
-    );
-
-    x_steps.lcm(&y_steps).lcm(&z_steps)
+pub fn twelve_a() -> i32 {
+    let mut system = load_system();
+    system.step(1000);
+    system.total_energy()
 }
 
 pub fn twelve_b() -> u64 {
-    let moons = parse_moons();
-    num_steps_until_original_state_repeats(&moons)
+    load_system().steps_until_repeat()
+}
+
+/// Writes the first `steps` steps of the puzzle input's moons' trajectories to `writer` as CSV,
+/// so that their orbits can be plotted.
+pub fn export_trajectories(steps: usize, writer: impl Write) -> io::Result<()> {
+    load_system().write_trajectories(steps, writer)
+}
+
+fn load_system() -> System {
+    util::load(util::input(12), System::parse)
 }
 
 #[cfg(test)]
@@ -176,7 +340,7 @@ mod tests {
     #[test]
     fn test_parse_moons() {
         assert_eq!(
-            parse_moons(),
+            load_system().moons(),
             vec![
                 Moon::new(17, -7, -11),
                 Moon::new(1, 4, -1),
@@ -188,15 +352,13 @@ mod tests {
 
     #[test]
     fn test_advance_gravity_one_step() {
-        let mut moons = vec![
-            Moon::new(-1, 0, 2),
-            Moon::new(2, -10, -7),
-            Moon::new(4, -8, 8),
-            Moon::new(3, 5, -1),
-        ];
-        apply_gravity(&mut moons);
+        let mut system =
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)]);
+        for axis in &mut system.axes {
+            axis.apply_gravity();
+        }
         assert_eq!(
-            moons,
+            system.moons(),
             vec![
                 Moon {
                     position: Vector { x: -1, y: 0, z: 2 },
@@ -223,20 +385,42 @@ mod tests {
     }
 
     #[test]
-    fn test_advance_time() {
-        let mut moons = vec![
-            Moon::new(-1, 0, 2),
-            Moon::new(2, -10, -7),
-            Moon::new(4, -8, 8),
-            Moon::new(3, 5, -1),
-        ];
-
-        for _ in 0..10 {
-            advance_time_one_step(&mut moons);
+    fn test_apply_gravity_to_four_moons() {
+        for positions in [[-1, 2, 4, 3], [0, -10, -8, 5], [2, -7, 8, -1], [3, 3, 3, 1]].iter() {
+            let mut unrolled = [1, 2, 3, 4];
+            let mut looped = unrolled;
+            apply_gravity_to_four_moons(positions, &mut unrolled);
+            apply_gravity_to_any_moons(positions, &mut looped);
+            assert_eq!(unrolled, looped);
         }
+    }
+
+    #[test]
+    fn test_other_numbers_of_moons() {
+        let mut system = System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8)]);
+        system.step(10);
+        assert_eq!(system.moons().len(), 3);
+
+        // Every pull has an equal and opposite pull, so the moons' velocities always cancel out.
+        let mut system =
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1), (0, 0, 0)]);
+        system.step(10);
+        let velocities = system
+            .moons()
+            .iter()
+            .map(|moon| moon.velocity.x)
+            .sum::<i32>();
+        assert_eq!(velocities, 0);
+    }
+
+    #[test]
+    fn test_advance_time() {
+        let mut system =
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)]);
+        system.step(10);
 
         assert_eq!(
-            moons,
+            system.moons(),
             vec![
                 Moon {
                     position: Vector { x: 2, y: 1, z: -3 },
@@ -260,46 +444,88 @@ mod tests {
 
     #[test]
     fn test_compute_energy_1() {
-        let mut moons = vec![
-            Moon::new(-1, 0, 2),
-            Moon::new(2, -10, -7),
-            Moon::new(4, -8, 8),
-            Moon::new(3, 5, -1),
-        ];
-
-        for _ in 0..10 {
-            advance_time_one_step(&mut moons);
-        }
+        let mut system =
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)]);
+        system.step(10);
+
+        assert_eq!(system.total_energy(), 179);
+    }
 
-        assert_eq!(compute_energy_for_moons(&moons), 179);
+    #[test]
+    fn test_write_trajectories() {
+        let mut system =
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)]);
+        let mut csv = vec![];
+        system.write_trajectories(10, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 11 * 4);
+        assert_eq!(lines[0], "step,moon,x,y,z,vx,vy,vz,potential,kinetic,total");
+        assert_eq!(lines[1], "0,0,-1,0,2,0,0,0,3,0,0");
+        // "Energy after 10 steps: pot: 2 + 1 + 3 =  6;   kin: 3 + 2 + 1 = 6;   total:  6 * 6 = 36"
+        assert_eq!(lines[41], "10,0,2,1,-3,-3,-2,1,6,6,36");
+        assert_eq!(lines[44], "10,3,2,0,4,1,-1,-1,6,3,18");
+        assert_eq!(system.total_energy(), 179);
+    }
+
+    #[test]
+    fn test_export_trajectories() {
+        let mut csv = vec![];
+        export_trajectories(1000, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let total: i32 = csv
+            .lines()
+            .filter(|line| line.starts_with("1000,"))
+            .map(|line| line.rsplit(',').next().unwrap().parse::<i32>().unwrap())
+            .sum();
+        assert_eq!(total, twelve_a());
     }
 
     #[test]
     fn test_compute_energy_2() {
-        let mut moons = vec![
-            Moon::new(-8, -10, 0),
-            Moon::new(5, 5, 10),
-            Moon::new(2, -7, 3),
-            Moon::new(9, -8, -3),
-        ];
-
-        for _ in 0..100 {
-            advance_time_one_step(&mut moons);
-        }
+        let mut system =
+            System::from_positions(&[(-8, -10, 0), (5, 5, 10), (2, -7, 3), (9, -8, -3)]);
+        system.step(100);
 
-        assert_eq!(compute_energy_for_moons(&moons), 1940);
+        assert_eq!(system.total_energy(), 1940);
     }
 
     #[test]
     fn test_num_steps_til_repeat() {
-        let moons = vec![
-            Moon::new(-1, 0, 2),
-            Moon::new(2, -10, -7),
-            Moon::new(4, -8, 8),
-            Moon::new(3, 5, -1),
-        ];
-
-        assert_eq!(num_steps_until_original_state_repeats(&moons), 2772);
+        let system = System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)]);
+
+        assert_eq!(system.steps_until_repeat(), 2772);
+    }
+
+    #[test]
+    fn test_system() {
+        let mut system = System::parse(
+            "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>\n",
+        )
+        .unwrap();
+        assert_eq!(
+            system,
+            System::from_positions(&[(-1, 0, 2), (2, -10, -7), (4, -8, 8), (3, 5, -1)])
+        );
+        assert_eq!(system.steps_until_repeat(), 2772);
+
+        let history = system.clone().energy_history(10);
+        assert_eq!(history.len(), 10);
+        assert_eq!(history[9], 179);
+
+        system.step(10);
+        assert_eq!(system.total_energy(), 179);
+
+        assert_eq!(
+            System::parse("<x=-1, y=0, z=2>\n<x=2, y=-10>\n").unwrap_err(),
+            ParseError::new(
+                2,
+                "<x=2, y=-10>",
+                "expected a position like <x=-1, y=0, z=2>"
+            )
+        );
     }
 
     #[test]
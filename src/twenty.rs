@@ -1,33 +1,77 @@
+use crate::grid::Grid;
+use crate::render::RenderGrid;
+use crate::solver::Solver;
+use crate::util::search::MinScored;
+use crate::util::{self, FastHashMap, FastHashSet, Point};
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs;
+use std::collections::{BinaryHeap, VecDeque};
+use std::error;
+use std::fmt;
+use tracing::instrument;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub struct Position(usize, usize);
-
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position(pub usize, pub usize);
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Space {
     Empty,   // '.'
     Wall,    // '#'
     Nowhere, // ' '
 }
 
-mod cave {
-    use super::*;
+/// Everything that can go wrong when parsing a DonutCave.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Empty,
+    UnexpectedCharacter(char, Position),
+    /// A portal letter that isn't next to another letter.
+    LoneLetter(char, Position),
+    /// A portal label that isn't next to an open tile.
+    UnconnectedLabel(String),
+    /// A portal label that appears some number of times other than twice,
+    /// or whose two ends are both on the inside or both on the outside of the donut.
+    MismatchedPortal(String),
+    MissingStart,
+    MissingFinish,
+}
 
-    /// A half-parsed Portal.
-    #[derive(Copy, Clone, Debug)]
-    struct PartialPortal {
-        position: Position,
-        letter: char,
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the maze is empty"),
+            ParseError::UnexpectedCharacter(c, Position(x, y)) => {
+                write!(f, "unexpected character {:?} at ({}, {})", c, x, y)
+            }
+            ParseError::LoneLetter(c, Position(x, y)) => {
+                write!(
+                    f,
+                    "letter {:?} at ({}, {}) isn't part of a portal label",
+                    c, x, y
+                )
+            }
+            ParseError::UnconnectedLabel(label) => {
+                write!(f, "portal {} isn't next to an open tile", label)
+            }
+            ParseError::MismatchedPortal(label) => {
+                write!(
+                    f,
+                    "portal {} doesn't have one inner and one outer end",
+                    label
+                )
+            }
+            ParseError::MissingStart => write!(f, "the maze has no AA"),
+            ParseError::MissingFinish => write!(f, "the maze has no ZZ"),
+        }
     }
+}
+
+impl error::Error for ParseError {}
+
+pub mod cave {
+    use super::*;
+    use crate::render::{self, RenderGrid, Rgb};
 
     #[derive(Debug, PartialEq)]
     enum PortalKind {
@@ -43,499 +87,676 @@ mod cave {
         kind: PortalKind,
     }
 
-    #[derive(Debug)]
+    #[derive(Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct DonutCave {
-        pub spaces: Vec<Space>,
-        pub inner_portals: HashMap<Position, Position>,
-        pub outer_portals: HashMap<Position, Position>,
+        pub spaces: Grid<Space>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::util::pairs"))]
+        pub inner_portals: FastHashMap<Position, Position>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::util::pairs"))]
+        pub outer_portals: FastHashMap<Position, Position>,
         pub start: Position,
         pub finish: Position,
-        pub width: usize,
-    }
-
-    /// Returns Some(a_portal) if `partial_portal.position` and `other_position` are neighbors, None otherwise.
-    /// NOTE: Assumes that `partial_portal` precedes `(other_position, other_letter)` in the input maze file.
-    fn try_to_make_portal_from_partial(
-        partial_portal: &PartialPortal,
-        other_position: Position,
-        other_letter: char,
-        width: usize,
-        height: usize,
-    ) -> Option<Portal> {
-        let label = format!("{}{}", partial_portal.letter, other_letter);
-
-        if partial_portal.position.0 == other_position.0
-            && partial_portal.position.1 + 1 == other_position.1
-        {
-            // We've found a portal, and partial_portal is above other_position.
-
-            let (position, kind) = match (
-                other_position.1 <= height / 4,
-                other_position.1 <= height / 2,
-                other_position.1 <= 3 * height / 4,
-            ) {
-                (true, true, true) => {
-                    // This portal affects the position _below_ other_position.
-                    // P
-                    // O
-                    // . <-- target
-                    (
-                        Position(other_position.0, other_position.1 + 1),
-                        PortalKind::Outer,
-                    )
-                }
+        /// The label of each portal end, plus AA and ZZ, keyed by the open tile it's attached to.
+        #[cfg_attr(feature = "serde", serde(with = "crate::util::pairs"))]
+        pub labels: FastHashMap<Position, String>,
+    }
 
-                (false, false, true) => {
-                    // Same here, but we're inside the donut.
-                    (
-                        Position(other_position.0, other_position.1 + 1),
-                        PortalKind::Inner,
-                    )
-                }
-                (false, true, true) => {
-                    // This portal affects the position _above_ partial_portal.position.
-                    // . <- target
-                    // P
-                    // O
-                    (
-                        Position(other_position.0, partial_portal.position.1 - 1),
-                        PortalKind::Inner,
-                    )
-                }
-                (false, false, false) => {
-                    // Same here, but we're outside the donut.
-                    (
-                        Position(other_position.0, partial_portal.position.1 - 1),
-                        PortalKind::Outer,
-                    )
-                }
-                _ => unreachable!(),
-            };
+    #[cfg(feature = "serde")]
+    impl DonutCave {
+        /// Returns this cave as JSON. Maps keyed by position are written as lists of [position, value] pairs.
+        pub fn to_json(&self) -> String {
+            serde_json::to_string(self).unwrap()
+        }
 
-            Some(Portal {
-                label,
-                kind,
-                position,
-            })
-        } else if partial_portal.position.0 + 1 == other_position.0
-            && partial_portal.position.1 == other_position.1
-        {
-            // We've found a portal, and partial_portal is to the left of other_position.
-
-            let (position, kind) = match (
-                other_position.0 <= width / 5,
-                other_position.0 <= width / 2,
-                other_position.0 <= 4 * width / 5,
-            ) {
-                (true, true, true) => {
-                    // This portal affects the position to the right of other_position.
-                    // PO.
-                    //   ^ target
-                    (
-                        Position(other_position.0 + 1, other_position.1),
-                        PortalKind::Outer,
-                    )
-                }
-                (false, false, true) => {
-                    // Same here, but we're inside the donut.
-                    (
-                        Position(other_position.0 + 1, other_position.1),
-                        PortalKind::Inner,
-                    )
+        pub fn from_json(json: &str) -> serde_json::Result<Self> {
+            serde_json::from_str(json)
+        }
+    }
+
+    /// Finds each portal label in `grid`, along with the open tile that it's attached to.
+    /// Returns a Vec of (label, position_of_open_tile) pairs, in reading order.
+    fn find_labels(grid: &Grid<char>) -> Result<Vec<(String, Position)>, ParseError> {
+        let char_at = |x: usize, y: usize| grid.get(x, y).copied();
+        let is_letter = |x: usize, y: usize| matches!(char_at(x, y), Some('A'..='Z'));
+
+        let mut labels = vec![];
+        let mut second_letters = FastHashSet::default();
+
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let c = char_at(x, y).unwrap();
+                if !c.is_ascii_uppercase() || second_letters.contains(&Position(x, y)) {
+                    continue;
                 }
-                (false, true, true) => {
-                    // This portal affects the position to the left of partial_portal.position.
-                    // .PO
-                    // ^ target
+
+                // Labels read left-to-right or top-to-bottom, so the first letter we see is the first letter of the label.
+                // The open tile is either right after the label or right before it.
+                let (second_letter, candidates) = if is_letter(x + 1, y) {
                     (
-                        Position(partial_portal.position.0 - 1, other_position.1),
-                        PortalKind::Inner,
+                        Position(x + 1, y),
+                        [
+                            Some(Position(x + 2, y)),
+                            x.checked_sub(1).map(|x| Position(x, y)),
+                        ],
                     )
-                }
-                (false, false, false) => {
-                    // Same here, but we're outside the donut.
+                } else if is_letter(x, y + 1) {
                     (
-                        Position(partial_portal.position.0 - 1, other_position.1),
-                        PortalKind::Outer,
+                        Position(x, y + 1),
+                        [
+                            Some(Position(x, y + 2)),
+                            y.checked_sub(1).map(|y| Position(x, y)),
+                        ],
                     )
-                }
-                _ => unreachable!(),
-            };
+                } else {
+                    return Err(ParseError::LoneLetter(c, Position(x, y)));
+                };
 
-            Some(Portal {
-                label,
-                position,
-                kind,
-            })
-        } else {
-            None
+                second_letters.insert(second_letter);
+                let label = format!(
+                    "{}{}",
+                    c,
+                    char_at(second_letter.0, second_letter.1).unwrap()
+                );
+
+                let position = candidates
+                    .iter()
+                    .flatten()
+                    .find(|position| char_at(position.0, position.1) == Some('.'))
+                    .copied()
+                    .ok_or_else(|| ParseError::UnconnectedLabel(label.clone()))?;
+
+                labels.push((label, position));
+            }
         }
+
+        Ok(labels)
     }
 
-    /// Returns Some((index_of_relevant_partial_portal, portal)) if (position, letter) can be successfully combined
-    /// with any of the entries in `partial_portals`, None otherwise.
-    fn try_to_make_portal(
-        partial_portals: &[PartialPortal],
-        position: Position,
-        letter: char,
-        width: usize,
-        height: usize,
-    ) -> Option<(usize, Portal)> {
-        partial_portals.iter().enumerate().find_map(
-            |(i, partial_portal): (usize, &PartialPortal)| {
-                let possible_portal = try_to_make_portal_from_partial(
-                    &partial_portal,
-                    position,
-                    letter,
-                    width,
-                    height,
-                );
+    /// Returns Inner if `position` is on the edge of the donut's hole, or Outer if it's on the donut's outer edge.
+    /// `bounds` is the (top_left, bottom_right) corners of the donut itself, walls included.
+    fn classify_portal(position: Position, bounds: (Position, Position)) -> PortalKind {
+        let (Position(left, top), Position(right, bottom)) = bounds;
 
-                if let Some(portal) = possible_portal {
-                    Some((i, portal))
-                } else {
-                    None
-                }
-            },
-        )
+        if position.0 == left || position.0 == right || position.1 == top || position.1 == bottom {
+            PortalKind::Outer
+        } else {
+            PortalKind::Inner
+        }
     }
 
+    type PortalMaps = (
+        FastHashMap<Position, Position>,
+        FastHashMap<Position, Position>,
+    );
+
     /// Merges a slice of Portals into a tuple of (inner_portals, outer_portals).
-    fn merge_portals(
-        portals: &[Portal],
-    ) -> (HashMap<Position, Position>, HashMap<Position, Position>) {
-        let mut inner_portals = HashMap::new();
-        let mut outer_portals = HashMap::new();
+    fn merge_portals(portals: &[Portal]) -> Result<PortalMaps, ParseError> {
+        let mut inner_portals = FastHashMap::default();
+        let mut outer_portals = FastHashMap::default();
 
-        for (_, mut pair) in &portals
+        for (label, pair) in &portals
             .iter()
             .sorted_by_key(|portal| &portal.label)
             .group_by(|portal| &portal.label)
         {
-            let first_half = pair.next().unwrap();
-            let second_half = pair.next().unwrap();
-
-            assert!(pair.next().is_none());
+            let (inner, outer) = match pair.collect::<Vec<_>>()[..] {
+                [a, b] if a.kind == PortalKind::Inner && b.kind == PortalKind::Outer => (a, b),
+                [a, b] if a.kind == PortalKind::Outer && b.kind == PortalKind::Inner => (b, a),
+                _ => return Err(ParseError::MismatchedPortal(label.clone())),
+            };
 
-            match first_half.kind {
-                PortalKind::Inner => {
-                    assert_eq!(second_half.kind, PortalKind::Outer);
-                    inner_portals.insert(first_half.position, second_half.position);
-                    outer_portals.insert(second_half.position, first_half.position);
-                }
-                PortalKind::Outer => {
-                    assert_eq!(second_half.kind, PortalKind::Inner);
-                    outer_portals.insert(first_half.position, second_half.position);
-                    inner_portals.insert(second_half.position, first_half.position);
-                }
-            }
+            inner_portals.insert(inner.position, outer.position);
+            outer_portals.insert(outer.position, inner.position);
         }
 
-        (inner_portals, outer_portals)
+        Ok((inner_portals, outer_portals))
     }
 
     impl DonutCave {
         pub fn new(filename: &str) -> Self {
-            let mut spaces = Vec::new();
-            let mut partial_portals = Vec::new();
-            let mut portals = Vec::new();
+            util::load(filename, DonutCave::parse)
+        }
 
-            let contents = fs::read_to_string(filename).unwrap();
-            let width = contents.lines().next().unwrap().len();
-            let height = contents.lines().count();
+        /// Parses a maze like
+        ///
+        /// ```text
+        ///          A
+        ///          A
+        ///   #######.#########
+        ///   #######.........#
+        ///   #######.#######.#
+        ///   #######.#######.#
+        ///   #######.#######.#
+        ///   #####  B    ###.#
+        /// BC...##  C    ###.#
+        ///   ##.##       ###.#
+        ///   ##...DE  F  ###.#
+        ///   #####    G  ###.#
+        ///   #########.#####.#
+        /// DE..#######...###.#
+        ///   #.#########.###.#
+        /// FG..#########.....#
+        ///   ###########.#####
+        ///              Z
+        ///              Z
+        /// ```
+        ///
+        /// into a DonutCave. Lines can have trailing spaces trimmed off.
+        #[instrument(name = "parse", level = "debug", skip_all)]
+        pub fn parse(contents: &str) -> Result<Self, ParseError> {
+            let mut unexpected_character = None;
+            // The donut is the part of the map that's covered in walls and open tiles.
+            let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+
+            let grid = Grid::parse_padded(contents, ' ', |c, (x, y)| {
+                match c {
+                    '#' | '.' => {
+                        left = left.min(x);
+                        top = top.min(y);
+                        right = right.max(x);
+                        bottom = bottom.max(y);
+                    }
+                    // Letters are only used for labeling portals.
+                    ' ' | 'A'..='Z' => (),
+                    _ => {
+                        unexpected_character.get_or_insert((c, Position(x, y)));
+                    }
+                }
+
+                c
+            })
+            .map_err(|_| ParseError::Empty)?;
+
+            if let Some((c, position)) = unexpected_character {
+                return Err(ParseError::UnexpectedCharacter(c, position));
+            }
+
+            let spaces = grid.map(|&c| match c {
+                '#' => Space::Wall,
+                '.' => Space::Empty,
+                _ => Space::Nowhere,
+            });
+            let bounds = (Position(left, top), Position(right, bottom));
 
             let mut start = None;
             let mut finish = None;
-
-            for (y, line) in contents.lines().enumerate() {
-                for (x, c) in line.chars().enumerate() {
-                    spaces.push(match c {
-                        '#' => Space::Wall,
-                        '.' => Space::Empty,
-                        ' ' => Space::Nowhere,
-                        _ => {
-                            let possible_portal_and_index = try_to_make_portal(
-                                &partial_portals,
-                                Position(x, y),
-                                c,
-                                width,
-                                height,
-                            );
-
-                            if let Some((i, portal)) = possible_portal_and_index {
-                                // `(x, y)` was the second half of a partially-processed portal!
-                                // We turned the two halves into a Portal; now let's use it.
-                                partial_portals.remove(i);
-
-                                // AA and ZZ are special markers -
-                                // they're not portals, they're the start and end of the maze.
-                                if portal.label == "AA" {
-                                    start = Some(portal.position);
-                                } else if portal.label == "ZZ" {
-                                    finish = Some(portal.position);
-                                } else {
-                                    portals.push(portal);
-                                }
-                            } else {
-                                partial_portals.push(PartialPortal {
-                                    position: Position(x, y),
-                                    letter: c,
-                                });
-                            }
-
-                            Space::Nowhere
-                        }
-                    });
+            let mut portals = Vec::new();
+            let mut labels = FastHashMap::default();
+
+            for (label, position) in find_labels(&grid)? {
+                labels.insert(position, label.clone());
+
+                // AA and ZZ are special markers -
+                // they're not portals, they're the start and end of the maze.
+                match label.as_str() {
+                    "AA" => start = Some(position),
+                    "ZZ" => finish = Some(position),
+                    _ => portals.push(Portal {
+                        kind: classify_portal(position, bounds),
+                        label,
+                        position,
+                    }),
                 }
             }
 
-            let (inner_portals, outer_portals) = merge_portals(&portals);
+            let (inner_portals, outer_portals) = merge_portals(&portals)?;
 
-            DonutCave {
+            Ok(DonutCave {
                 spaces,
                 inner_portals,
                 outer_portals,
-                start: start.unwrap(),
-                finish: finish.unwrap(),
-                width,
+                start: start.ok_or(ParseError::MissingStart)?,
+                finish: finish.ok_or(ParseError::MissingFinish)?,
+                labels,
+            })
+        }
+    }
+
+    impl RenderGrid for DonutCave {
+        fn width(&self) -> usize {
+            self.spaces.width()
+        }
+
+        fn height(&self) -> usize {
+            self.spaces.height()
+        }
+
+        /// Draws the start and finish as A and Z, and both ends of each portal as O.
+        fn cell_char(&self, x: usize, y: usize) -> char {
+            let position = Position(x, y);
+
+            if position == self.start {
+                'A'
+            } else if position == self.finish {
+                'Z'
+            } else if self.inner_portals.contains_key(&position)
+                || self.outer_portals.contains_key(&position)
+            {
+                'O'
+            } else {
+                match self.spaces[(x, y)] {
+                    Space::Empty => '.',
+                    Space::Wall => '#',
+                    Space::Nowhere => ' ',
+                }
             }
         }
 
-        /// Returns the Space at (x, y).
-        pub fn get(&self, x: usize, y: usize) -> Space {
-            self.spaces[y * self.width + x]
+        fn cell_color(&self, x: usize, y: usize) -> Rgb {
+            match self.cell_char(x, y) {
+                'A' | 'Z' => Rgb(240, 220, 60),
+                'O' => Rgb(80, 160, 255),
+                '#' => render::GRAY,
+                _ => render::BLACK,
+            }
         }
     }
 }
 
-/// Returns the Position that's one step ahead of `position` in `direction`.
-fn one_position_ahead(direction: &Direction, position: &Position) -> Position {
-    match direction {
-        Direction::North => Position(position.0, position.1 - 1),
-        Direction::East => Position(position.0 + 1, position.1),
-        Direction::South => Position(position.0, position.1 + 1),
-        Direction::West => Position(position.0 - 1, position.1),
-    }
+/// One step along a path through a DonutCave.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStep {
+    pub position: Position,
+    /// How many levels deep into a recursive cave this step is. Always 0 in part A's caves.
+    pub level: i32,
+    /// The label of the portal that was taken to get to this step, if any.
+    pub portal: Option<String>,
 }
 
-/// A BFS search implemented for the cave described by part A.
-pub mod search_a {
-    use super::*;
-
-    struct SearchNode {
-        distance: u32,
-        position: Position,
-    }
+/// Follows the parent pointers in `steps` from `last_step` back to the start of a search,
+/// and returns the path in the order it was walked.
+/// `steps` holds the (parent_index, position, level) of each node that a search has visited.
+fn follow_path(
+    cave: &cave::DonutCave,
+    steps: &[(Option<usize>, Position, i32)],
+    last_step: usize,
+) -> Vec<PathStep> {
+    let mut path = vec![];
+    let mut current_step = Some(last_step);
+
+    while let Some(index) = current_step {
+        let (parent, position, level) = steps[index];
+
+        // Walking only ever changes one coordinate by one; anything else was a trip through a portal.
+        let portal = parent.and_then(|parent| {
+            let (_, previous_position, _) = steps[parent];
+            let distance = (position.0 as i64 - previous_position.0 as i64).abs()
+                + (position.1 as i64 - previous_position.1 as i64).abs();
+            if distance == 1 {
+                None
+            } else {
+                Some(cave.labels[&previous_position].clone())
+            }
+        });
 
-    pub fn shortest_path_through_cave(cave: &cave::DonutCave) -> u32 {
-        let mut frontier = VecDeque::new();
-        frontier.push_back(SearchNode {
-            distance: 0,
-            position: cave.start,
+        path.push(PathStep {
+            position,
+            level,
+            portal,
         });
+        current_step = parent;
+    }
 
-        let mut seen = HashSet::new();
-        seen.insert(cave.start);
+    path.reverse();
+    path
+}
 
-        let mut shortest_path = 0;
-        while !frontier.is_empty() {
-            let node = frontier.pop_front().expect("frontier is non-empty");
+/// How portals behave.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Mode {
+    /// Portals simply connect two spaces, as in part A.
+    Flat,
+    /// "When you enter the maze, you are at the outermost level; when at any other level,
+    /// entering an outer labeled tile also counts as moving up one level. Each inner labeled
+    /// tile is connected to an outer labeled tile one level deeper", as in part B.
+    Recursive,
+}
 
-            if node.position == cave.finish {
-                shortest_path = node.distance;
-                break;
-            }
+/// Keeps track of which (level, position) pairs a search has seen.
+struct PositionTracker {
+    seen_vecs: Vec<Vec<bool>>,
+    cave_width: usize,
+    num_spaces: usize,
+}
 
-            // Walk into adjacent empty spaces.
-            for direction in [
-                Direction::North,
-                Direction::East,
-                Direction::South,
-                Direction::West,
-            ]
-            .iter()
-            {
-                let next_position = one_position_ahead(direction, &node.position);
+impl PositionTracker {
+    fn new(cave_width: usize, num_spaces: usize) -> Self {
+        PositionTracker {
+            seen_vecs: vec![],
+            cave_width,
+            num_spaces,
+        }
+    }
 
-                if seen.contains(&next_position) {
-                    continue;
-                }
+    /// Tracks `(level, position)`.
+    fn insert(&mut self, level: i32, position: Position) {
+        while level as usize >= self.seen_vecs.len() {
+            self.seen_vecs.push(vec![false; self.num_spaces]);
+        }
 
-                if cave.get(next_position.0, next_position.1) == Space::Empty {
-                    frontier.push_back(SearchNode {
-                        position: next_position,
-                        distance: node.distance + 1,
-                    });
-                    seen.insert(next_position);
-                }
-            }
+        let index = self.position_to_index(position);
+        self.seen_vecs[level as usize][index] = true;
+    }
 
-            // If we're at a portal, step through it.
-            for portals in [&cave.inner_portals, &cave.outer_portals].iter() {
-                if let Some(portal_position) = portals.get(&node.position) {
-                    if !seen.contains(portal_position) {
-                        frontier.push_back(SearchNode {
-                            position: *portal_position,
-                            distance: node.distance + 1,
-                        });
-                        seen.insert(*portal_position);
-                    }
-                }
-            }
+    /// Returns true if `(level, position)` has been seen, false otherwise.
+    fn contains(&self, level: i32, position: Position) -> bool {
+        if level as usize >= self.seen_vecs.len() {
+            return false;
         }
 
-        shortest_path
+        self.seen_vecs[level as usize][self.position_to_index(position)]
     }
-}
 
-pub fn twenty_a() -> u32 {
-    let cave = cave::DonutCave::new("src/inputs/20.txt");
-    search_a::shortest_path_through_cave(&cave)
+    fn position_to_index(&self, position: Position) -> usize {
+        position.1 * self.cave_width + position.0
+    }
 }
 
-/// A BFS search implemented for the "recursive" caves described by part B.
-mod search_b {
-    use super::*;
-
-    #[derive(Debug, Copy, Clone)]
-    struct SearchNode {
-        distance: u32,
-        position: Position,
-        level: i32,
+/// Returns the (position, level) on the other side of the portal at `position` on `level`,
+/// or None if there's no portal there that can be taken.
+fn portal_exit(
+    cave: &cave::DonutCave,
+    mode: Mode,
+    position: Position,
+    level: i32,
+) -> Option<(Position, i32)> {
+    // Inner portals are always accessible.
+    if let Some(&portal_position) = cave.inner_portals.get(&position) {
+        return Some(match mode {
+            Mode::Flat => (portal_position, level),
+            Mode::Recursive => (portal_position, level + 1),
+        });
     }
 
-    struct PositionTracker {
-        seen_vecs: Vec<Vec<bool>>,
-        cave_width: usize,
-        num_spaces: usize,
+    // In recursive caves, outer portals are only accessible if you're down at least one level.
+    match (cave.outer_portals.get(&position), mode) {
+        (Some(&portal_position), Mode::Flat) => Some((portal_position, level)),
+        (Some(&portal_position), Mode::Recursive) if level > 0 => {
+            Some((portal_position, level - 1))
+        }
+        _ => None,
     }
+}
 
-    impl PositionTracker {
-        /// Tracks `(node.level, node.position)`.
-        fn insert(&mut self, node: SearchNode) {
-            if node.level as usize >= self.seen_vecs.len() {
-                let mut vec = vec![false; self.num_spaces];
-                vec[self.position_to_index(node.position)] = true;
-                self.seen_vecs.push(vec);
-            } else {
-                let index = self.position_to_index(node.position);
-                self.seen_vecs[node.level as usize][index] = true;
-            }
-        }
+/// Returns each empty space next to `position`.
+fn adjacent_spaces(
+    cave: &cave::DonutCave,
+    position: Position,
+) -> impl Iterator<Item = Position> + '_ {
+    Point::from((position.0, position.1))
+        .neighbors4_within(cave.width(), cave.height())
+        .map(|next_position| {
+            let (x, y) = next_position.to_usize();
+            Position(x, y)
+        })
+        .filter(move |next_position| {
+            cave.spaces[(next_position.0, next_position.1)] == Space::Empty
+        })
+}
 
-        /// Returns true if `(node.level, node.position)` has been seen, false otherwise.
-        fn contains(&self, node: &SearchNode) -> bool {
-            if node.level as usize >= self.seen_vecs.len() {
-                return false;
-            }
+/// Returns each (position, level) that can be reached in one step from `position` on `level`.
+fn neighbors(
+    cave: &cave::DonutCave,
+    mode: Mode,
+    position: Position,
+    level: i32,
+) -> Vec<(Position, i32)> {
+    // Walk into adjacent empty spaces, or step through a portal if we're at one.
+    adjacent_spaces(cave, position)
+        .map(|next_position| (next_position, level))
+        .chain(portal_exit(cave, mode, position, level))
+        .collect()
+}
+
+/// The deepest level that searches of a recursive cave go down to unless they're told otherwise.
+/// Without a limit, a cave with no way through would keep a search going deeper forever.
+///
+/// This is a heuristic: one level per portal is deep enough for the puzzle's examples and input (see
+/// `test_default_max_level`), but nothing guarantees that a shortest path never needs to go deeper.
+/// If it does, searches that use this limit report a longer path or no path at all; the `_within`
+/// versions of the searches take a limit of their own.
+pub fn default_max_level(cave: &cave::DonutCave) -> i32 {
+    cave.inner_portals.len() as i32
+}
+
+/// Returns the number of steps it takes to get from AA to ZZ (on the outermost level, for recursive caves),
+/// or None if there's no way through that stays within `default_max_level()`.
+#[instrument(name = "solve", level = "debug", skip_all)]
+pub fn shortest_path(cave: &cave::DonutCave, mode: Mode) -> Option<u32> {
+    shortest_route(cave, mode).map(|path| path.len() as u32 - 1)
+}
+
+/// Returns each step of the shortest path from AA to ZZ (on the outermost level, for recursive caves),
+/// including both ends, or None if there's no way through that stays within `default_max_level()`.
+pub fn shortest_route(cave: &cave::DonutCave, mode: Mode) -> Option<Vec<PathStep>> {
+    shortest_route_within(cave, mode, default_max_level(cave))
+}
 
-            self.seen_vecs[node.level as usize][self.position_to_index(node.position)]
+/// Like `shortest_route()`, but never goes deeper than `max_level`.
+///
+/// This is a BFS over (position, level) pairs.
+pub fn shortest_route_within(
+    cave: &cave::DonutCave,
+    mode: Mode,
+    max_level: i32,
+) -> Option<Vec<PathStep>> {
+    // The (parent_index, position, level) of every node we've queued.
+    let mut steps = vec![(None, cave.start, 0)];
+
+    // Indexes into `steps`.
+    let mut frontier = VecDeque::new();
+    frontier.push_back(0);
+
+    let mut tracker = PositionTracker::new(cave.width(), cave.width() * cave.height());
+    tracker.insert(0, cave.start);
+
+    while let Some(step) = frontier.pop_front() {
+        let (_, position, level) = steps[step];
+
+        if position == cave.finish && level == 0 {
+            return Some(follow_path(cave, &steps, step));
         }
 
-        fn new(cave_width: usize, num_spaces: usize) -> Self {
-            PositionTracker {
-                seen_vecs: vec![],
-                cave_width,
-                num_spaces,
+        for (next_position, next_level) in neighbors(cave, mode, position, level) {
+            if next_level <= max_level && !tracker.contains(next_level, next_position) {
+                // We haven't been here before! Let's go there!
+                tracker.insert(next_level, next_position);
+                steps.push((Some(step), next_position, next_level));
+                frontier.push_back(steps.len() - 1);
             }
         }
+    }
+
+    None
+}
 
-        fn position_to_index(&self, position: Position) -> usize {
-            position.1 * self.cave_width + position.0
+/// Returns the number of steps from `source` to every space, or None for spaces that can't be reached.
+/// If `walk_only` is true, no portals are taken; otherwise, portals are taken as if the cave were flat.
+fn distances_from(cave: &cave::DonutCave, source: Position, walk_only: bool) -> Grid<Option<u32>> {
+    let mut distances = cave.spaces.map(|_| None);
+    distances[(source.0, source.1)] = Some(0);
+    let mut frontier = VecDeque::new();
+    frontier.push_back(source);
+
+    while let Some(position) = frontier.pop_front() {
+        let distance = distances[(position.0, position.1)].unwrap();
+        let through_portal = portal_exit(cave, Mode::Flat, position, 0)
+            .map(|(next_position, _)| next_position)
+            .filter(|_| !walk_only);
+        for next_position in adjacent_spaces(cave, position).chain(through_portal) {
+            let next_distance = &mut distances[(next_position.0, next_position.1)];
+            if next_distance.is_none() {
+                *next_distance = Some(distance + 1);
+                frontier.push_back(next_position);
+            }
         }
     }
 
-    pub fn shortest_path_through_cave(cave: &cave::DonutCave) -> u32 {
-        let starting_node = SearchNode {
-            distance: 0,
-            position: cave.start,
-            level: 0,
-        };
+    distances
+}
 
-        let mut frontier = VecDeque::new();
-        frontier.push_back(starting_node);
+/// A DonutCave boiled down to its interesting spaces - AA, ZZ, and both ends of every portal -
+/// along with how many steps it takes to walk between them without taking any portals.
+struct PortalGraph {
+    walks: FastHashMap<Position, Vec<(Position, u32)>>,
+    /// The shortest walk between any two interesting spaces.
+    shortest_walk: u32,
+    /// How far each interesting space is from ZZ, if portals didn't change levels.
+    to_finish: FastHashMap<Position, u32>,
+}
 
-        let mut tracker = PositionTracker::new(cave.width, cave.spaces.len());
-        tracker.insert(starting_node);
+impl PortalGraph {
+    fn new(cave: &cave::DonutCave) -> Self {
+        let spaces: Vec<Position> = cave.labels.keys().copied().collect();
 
-        let mut shortest_path = 0;
-        while !frontier.is_empty() {
-            let node = frontier.pop_front().expect("frontier is non-empty");
+        let walks: FastHashMap<_, Vec<_>> = spaces
+            .iter()
+            .map(|&from| {
+                let distances = distances_from(cave, from, true);
+                let walks = spaces
+                    .iter()
+                    .filter(|&&to| to != from)
+                    .filter_map(|&to| distances[(to.0, to.1)].map(|distance| (to, distance)))
+                    .collect();
+                (from, walks)
+            })
+            .collect();
+
+        let to_finish = distances_from(cave, cave.finish, false);
+
+        PortalGraph {
+            shortest_walk: walks
+                .values()
+                .flatten()
+                .map(|&(_, distance)| distance)
+                .min()
+                .unwrap_or(0),
+            to_finish: spaces
+                .iter()
+                .filter_map(|&space| {
+                    to_finish[(space.0, space.1)].map(|distance| (space, distance))
+                })
+                .collect(),
+            walks,
+        }
+    }
 
-            if node.position == cave.finish && node.level == 0 {
-                shortest_path = node.distance;
-                break;
-            }
+    /// A lower bound on the number of steps from `position` on `level` to ZZ on the outermost level,
+    /// or None if ZZ can't be reached from `position` at all.
+    ///
+    /// Ignoring levels can only make a path shorter, so the flat distance to ZZ is one bound.
+    /// For the other: getting up from `level` takes at least `level` trips through portals, and a shortest path
+    /// never steps straight back through the portal it just came out of, so each trip is followed by a walk.
+    fn estimate(&self, position: Position, level: i32) -> Option<u32> {
+        let to_finish = *self.to_finish.get(&position)?;
+        Some(to_finish.max(level as u32 * (1 + self.shortest_walk)))
+    }
+}
 
-            // Walk into adjacent empty spaces.
-            for direction in [
-                Direction::North,
-                Direction::East,
-                Direction::South,
-                Direction::West,
-            ]
-            .iter()
-            {
-                let next_position = one_position_ahead(direction, &node.position);
-                let next_node = SearchNode {
-                    position: next_position,
-                    distance: node.distance + 1,
-                    level: node.level,
-                };
+/// Like `shortest_path()`, but an A* search over a PortalGraph, which has far fewer nodes than the cave has spaces.
+/// `shortest_path()` is kept as the reference implementation.
+/// Like `shortest_path()`, it never goes deeper than `default_max_level()`.
+#[instrument(name = "solve", level = "debug", skip_all)]
+pub fn shortest_path_astar(cave: &cave::DonutCave, mode: Mode) -> Option<u32> {
+    shortest_path_astar_within(cave, mode, default_max_level(cave))
+}
 
-                if !tracker.contains(&next_node)
-                    && cave.get(next_position.0, next_position.1) == Space::Empty
-                {
-                    // We haven't been to this space before, and it's walkable! Let's go there!
-                    frontier.push_back(next_node);
-                    tracker.insert(next_node);
-                }
-            }
+/// Like `shortest_path_astar()`, but never goes deeper than `max_level`.
+pub fn shortest_path_astar_within(
+    cave: &cave::DonutCave,
+    mode: Mode,
+    max_level: i32,
+) -> Option<u32> {
+    let graph = PortalGraph::new(cave);
+
+    let mut best_distances = FastHashMap::default();
+    best_distances.insert((cave.start, 0), 0);
+
+    // Ordered by estimated total distance.
+    let mut queue = BinaryHeap::new();
+    queue.push(MinScored(
+        graph.estimate(cave.start, 0)?,
+        (0, cave.start, 0),
+    ));
+
+    while let Some(MinScored(_, (distance, position, level))) = queue.pop() {
+        if best_distances[&(position, level)] < distance {
+            // We've found a shorter way here since this entry was queued.
+            continue;
+        }
+
+        if position == cave.finish && level == 0 {
+            return Some(distance);
+        }
 
-            // Now that we're done walking normally: if we're at a portal, step through it.
+        let walks = graph.walks[&position]
+            .iter()
+            .map(|&(next_position, steps)| (next_position, level, steps));
+        let through_portal = portal_exit(cave, mode, position, level)
+            .map(|(next_position, next_level)| (next_position, next_level, 1));
 
-            // Inner portals are always accessible.
-            if let Some(portal_position) = cave.inner_portals.get(&node.position) {
-                let node_through_portal = SearchNode {
-                    position: *portal_position,
-                    distance: node.distance + 1,
-                    level: node.level + 1,
-                };
+        for (next_position, next_level, steps) in walks.chain(through_portal) {
+            if next_level > max_level {
+                continue;
+            }
 
-                if !tracker.contains(&node_through_portal) {
-                    frontier.push_back(node_through_portal);
-                    tracker.insert(node_through_portal);
-                }
+            let next_distance = distance + steps;
+            let best_distance = best_distances
+                .entry((next_position, next_level))
+                .or_insert(u32::MAX);
+            if next_distance >= *best_distance {
+                continue;
             }
 
-            // Outer portals are only accessible if you're down at least one level.
-            if node.level > 0 {
-                if let Some(portal_position) = cave.outer_portals.get(&node.position) {
-                    let node_through_portal = SearchNode {
-                        position: *portal_position,
-                        distance: node.distance + 1,
-                        level: node.level - 1,
-                    };
-                    if !tracker.contains(&node_through_portal) {
-                        frontier.push_back(node_through_portal);
-                        tracker.insert(node_through_portal);
-                    }
-                }
+            if let Some(estimate) = graph.estimate(next_position, next_level) {
+                *best_distance = next_distance;
+                queue.push(MinScored(
+                    next_distance + estimate,
+                    (next_distance, next_position, next_level),
+                ));
             }
         }
+    }
+
+    None
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = cave::DonutCave;
+    type Error = ParseError;
+    type A = u32;
+    type B = u32;
+
+    const DAY: u32 = 20;
+
+    fn parse(input: &str) -> Result<cave::DonutCave, ParseError> {
+        cave::DonutCave::parse(input)
+    }
 
-        shortest_path
+    fn part_a(cave: &cave::DonutCave) -> u32 {
+        shortest_path(cave, Mode::Flat).unwrap()
+    }
+
+    fn part_b(cave: &cave::DonutCave) -> u32 {
+        shortest_path_astar(cave, Mode::Recursive).unwrap()
     }
 }
 
+pub fn twenty_a() -> u32 {
+    Solution::part_a(&Solution::load())
+}
+
 pub fn twenty_b() -> u32 {
-    let cave = cave::DonutCave::new("src/inputs/20.txt");
-    search_b::shortest_path_through_cave(&cave)
+    Solution::part_b(&Solution::load())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::render;
 
     #[test]
     fn test_solutions() {
@@ -543,21 +764,235 @@ mod tests {
         assert_eq!(twenty_b(), 7976);
     }
 
+    #[test]
+    fn test_render_cave() {
+        let cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
+        let drawing = render::to_text(&cave);
+
+        assert_eq!(
+            drawing.lines().nth(2).unwrap().trim_end(),
+            "  #######A#########"
+        );
+        assert_eq!(
+            drawing.lines().nth(8).unwrap().trim_end(),
+            "  O..##       ###.#"
+        );
+        assert_eq!(
+            drawing.lines().nth(16).unwrap().trim_end(),
+            "  ###########Z#####"
+        );
+    }
+
     #[test]
     fn test_samples() {
         let cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
-        assert_eq!(search_a::shortest_path_through_cave(&cave), 23);
+        assert_eq!(shortest_path(&cave, Mode::Flat), Some(23));
+
+        let cave = cave::DonutCave::new("src/inputs/20_sample_2.txt");
+        assert_eq!(shortest_path(&cave, Mode::Flat), Some(58));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
+        let json = cave.to_json();
+        assert!(json.contains(r#""start":[9,2],"finish":[13,16]"#));
+        assert!(json.contains(r#"[[9,6],"BC"]"#));
+
+        assert_eq!(cave::DonutCave::from_json(&json).unwrap(), cave);
+        assert!(cave::DonutCave::from_json(r#"{"start":[9,2]}"#).is_err());
+    }
 
+    #[test]
+    fn test_no_way_through() {
+        // "In the second example above, there is no path that brings you to ZZ at the outermost level."
         let cave = cave::DonutCave::new("src/inputs/20_sample_2.txt");
-        assert_eq!(search_a::shortest_path_through_cave(&cave), 58);
+        assert_eq!(shortest_route(&cave, Mode::Recursive), None);
+        assert_eq!(shortest_path(&cave, Mode::Recursive), None);
+        assert_eq!(shortest_path_astar(&cave, Mode::Recursive), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let contents = util::read("src/inputs/20_sample_1.txt");
+
+        // Trailing whitespace doesn't matter.
+        let trimmed = contents
+            .lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cave = cave::DonutCave::parse(&trimmed).unwrap();
+        assert_eq!(shortest_path(&cave, Mode::Flat), Some(23));
+
+        assert_eq!(cave::DonutCave::parse("").unwrap_err(), ParseError::Empty);
+        assert_eq!(
+            cave::DonutCave::parse(&contents.replace("ZZ", "  ").replace("Z", " ")).unwrap_err(),
+            ParseError::MissingFinish
+        );
+        assert_eq!(
+            cave::DonutCave::parse(&contents.replacen('.', "?", 1)).unwrap_err(),
+            ParseError::UnexpectedCharacter('?', Position(9, 2))
+        );
+        assert_eq!(
+            cave::DonutCave::parse(&contents.replacen("BC", " C", 1)).unwrap_err(),
+            ParseError::LoneLetter('C', Position(1, 8))
+        );
+    }
+
+    #[test]
+    fn test_portal_classification() {
+        // A short, wide donut, where the old width/height fraction heuristics would have gotten confused.
+        let contents = "         A
+         A
+  #######.####################
+  #.#...#...#...#...#...#....#
+  #..........................#
+  #....     B            ....#
+  #....     C            ....#
+BC.....                  ....#
+  #....DE                .....DE
+  #....                  ....#
+  #..........................#
+  #..#....#....#....#....#...#
+  #########.##################
+           Z
+           Z
+";
+
+        let cave = cave::DonutCave::parse(contents).unwrap();
+        assert_eq!(cave.start, Position(9, 2));
+        assert_eq!(cave.finish, Position(11, 12));
+
+        assert_eq!(cave.inner_portals.len(), 2);
+        assert_eq!(cave.inner_portals[&Position(12, 4)], Position(2, 7));
+        assert_eq!(cave.outer_portals[&Position(2, 7)], Position(12, 4));
+        assert_eq!(cave.inner_portals[&Position(6, 8)], Position(29, 8));
+        assert_eq!(cave.outer_portals[&Position(29, 8)], Position(6, 8));
+    }
+
+    #[test]
+    fn test_shortest_route() {
+        let cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
+        let path = shortest_route(&cave, Mode::Flat).unwrap();
+
+        assert_eq!(path.len(), 24);
+        assert_eq!(path[0].position, cave.start);
+        assert_eq!(path[23].position, cave.finish);
+        assert!(path.iter().all(|step| step.level == 0));
+
+        // "One path through the maze doesn't require any teleporters [...] but it's possible to
+        // go through BC, DE, and FG to get there faster."
+        let portals: Vec<_> = path.iter().filter_map(|step| step.portal.clone()).collect();
+        assert_eq!(portals, vec!["BC", "DE", "FG"]);
+    }
+
+    #[test]
+    fn test_shortest_route_part_b() {
+        let cave = cave::DonutCave::new("src/inputs/20_sample_3.txt");
+        let path = shortest_route(&cave, Mode::Recursive).unwrap();
+        assert_eq!(path.len(), 397);
+
+        // "Walk from AA to XF (16 steps)
+        // Recurse into level 1 through XF (1 step)
+        // Walk from XF to CK (10 steps)
+        // Recurse into level 2 through CK (1 step) [...]"
+        let portals: Vec<_> = path
+            .iter()
+            .filter_map(|step| {
+                step.portal
+                    .as_ref()
+                    .map(|label| (label.as_str(), step.level))
+            })
+            .collect();
+        assert_eq!(
+            &portals[..10],
+            [
+                ("XF", 1),
+                ("CK", 2),
+                ("ZH", 3),
+                ("WB", 4),
+                ("IC", 5),
+                ("RF", 6),
+                ("NM", 7),
+                ("LP", 8),
+                ("FD", 9),
+                ("XQ", 10)
+            ]
+        );
+        assert_eq!(path.iter().position(|step| step.level == 1), Some(17));
+        assert_eq!(path.last().unwrap().level, 0);
+    }
+
+    #[test]
+    fn test_astar_agrees_with_bfs() {
+        let caves = [
+            (
+                cave::DonutCave::new("src/inputs/20_sample_1.txt"),
+                Mode::Flat,
+            ),
+            (
+                cave::DonutCave::new("src/inputs/20_sample_2.txt"),
+                Mode::Flat,
+            ),
+            (
+                cave::DonutCave::new("src/inputs/20_sample_1.txt"),
+                Mode::Recursive,
+            ),
+            (
+                cave::DonutCave::new("src/inputs/20_sample_3.txt"),
+                Mode::Recursive,
+            ),
+            (Solution::load(), Mode::Flat),
+            (Solution::load(), Mode::Recursive),
+        ];
+
+        for (cave, mode) in caves.iter() {
+            assert_eq!(shortest_path_astar(cave, *mode), shortest_path(cave, *mode));
+        }
+
+        // A* gives up right away if ZZ can't be reached even through flat portals.
+        let mut cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
+        cave.finish = Position(0, 0);
+        assert_eq!(shortest_path_astar(&cave, Mode::Recursive), None);
+        assert_eq!(shortest_path(&cave, Mode::Flat), None);
     }
 
     #[test]
     fn test_samples_part_b() {
         let cave = cave::DonutCave::new("src/inputs/20_sample_1.txt");
-        assert_eq!(search_b::shortest_path_through_cave(&cave), 26);
+        assert_eq!(shortest_path(&cave, Mode::Recursive), Some(26));
+
+        let cave = cave::DonutCave::new("src/inputs/20_sample_3.txt");
+        assert_eq!(shortest_path(&cave, Mode::Recursive), Some(396));
+    }
+
+    #[test]
+    fn test_default_max_level() {
+        // Letting the search go far deeper than the default limit doesn't turn up any shorter paths.
+        for cave in [
+            cave::DonutCave::new("src/inputs/20_sample_1.txt"),
+            cave::DonutCave::new("src/inputs/20_sample_3.txt"),
+            Solution::load(),
+        ]
+        .iter()
+        {
+            let max_level = default_max_level(cave);
+            assert_eq!(
+                shortest_path_astar_within(cave, Mode::Recursive, 10 * max_level),
+                shortest_path_astar(cave, Mode::Recursive)
+            );
+        }
 
+        // The example's shortest path goes down to level 10, so a lower limit cuts it off.
         let cave = cave::DonutCave::new("src/inputs/20_sample_3.txt");
-        assert_eq!(search_b::shortest_path_through_cave(&cave), 396);
+        assert!(default_max_level(&cave) >= 10);
+        assert_eq!(shortest_route_within(&cave, Mode::Recursive, 9), None);
+        assert_eq!(shortest_path_astar_within(&cave, Mode::Recursive, 9), None);
+        assert_eq!(
+            shortest_path_astar_within(&cave, Mode::Recursive, 10),
+            Some(396)
+        );
     }
 }
@@ -1,32 +1,62 @@
-use crate::computer::{self, Computer, HaltReason};
+use crate::computer::{Computer, HaltReason, Memory, Snapshot};
+use crate::programs;
+use once_cell::sync::Lazy;
+use regex::Regex;
+#[cfg(feature = "serde")]
+use std::collections::BTreeMap;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
 use std::io::{self, Write};
 
-fn run_computer_until_ready_to_take_input(computer: &mut Computer) -> String {
-    while computer.run(HaltReason::NeedsInput) != HaltReason::NeedsInput {}
+static PASSWORD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"typing (\d+) on the keypad at the main airlock").unwrap());
+
+/// Runs the game until it's waiting for a command or has ended, and returns everything it printed along the way.
+fn run_computer_until_ready_to_take_input(computer: &mut Computer) -> (String, HaltReason) {
+    let halt_reason = loop {
+        match computer.run(HaltReason::NeedsInput) {
+            HaltReason::Output => (),
+            halt_reason => break halt_reason,
+        }
+    };
 
     let mut output_chars = vec![];
     while let Some(c) = computer.pop_output() {
         output_chars.push(c);
     }
 
-    output_chars.into_iter().map(|x| x as u8 as char).collect()
+    (
+        output_chars.into_iter().map(|x| x as u8 as char).collect(),
+        halt_reason,
+    )
 }
 
+/// Lets a human play the game in the terminal, reading commands from stdin.
+/// If `script_filename` is given, its commands are run first; see `Game::run_script()`.
 #[cfg(not(tarpaulin_include))]
-fn _play_game_interactively(mut computer: Computer) {
-    loop {
-        let output = run_computer_until_ready_to_take_input(&mut computer);
-        println!("{}", output);
+pub fn play_interactively(script_filename: Option<&str>) {
+    // The game's own output is streamed straight to stdout as it's printed,
+    // so only the responses to `!` commands need printing here.
+    let memory = programs::load("cryostasis");
+    let mut game = Game::with_output_sink(memory, io::stdout());
+
+    if let Some(filename) = script_filename {
+        game.run_script_file(filename).unwrap();
+    }
 
+    while !game.is_over() {
         // Prompt the user for input.
         print!(">>> ");
         io::stdout().flush().unwrap();
 
         let mut buffer = String::new();
-        io::stdin().read_line(&mut buffer).unwrap();
+        if io::stdin().read_line(&mut buffer).unwrap() == 0 {
+            break;
+        }
 
-        for c in buffer.chars() {
-            computer.push_input(c as i64);
+        let response = game.execute(&buffer);
+        if buffer.trim_start().starts_with('!') {
+            print!("{}", response);
         }
     }
 }
@@ -39,82 +69,709 @@ fn input_command(computer: &mut Computer, command: &str) {
     computer.push_input(10);
 }
 
-pub fn twenty_five_a() -> u32 {
-    let memory = computer::load_program("src/inputs/25.txt");
-    let mut computer = Computer::new(memory);
-
-    let commands_until_checkpoint = [
-        "east",
-        "take antenna",
-        "east",
-        "take ornament",
-        "north",
-        "west",
-        "take fixed point",
-        "east",
-        "south",
-        "west",
-        "north",
-        "north",
-        "take asterisk",
-        "south",
-        "west",
-        "west",
-        "take astronaut ice cream",
-        "east",
-        "south",
-        "take hologram",
-        "north",
-        "east",
-        "south",
-        "west",
-        "south",
-        "south",
-        "south",
-        "take dark matter",
-        "north",
-        "west",
-        "north",
-        "take monolith",
-        "north",
-        "north",
-    ];
-
-    for command in commands_until_checkpoint.iter() {
-        run_computer_until_ready_to_take_input(&mut computer);
-        input_command(&mut computer, command);
-    }
-
-    let items_to_drop = ["monolith", "antenna", "hologram", "dark matter"];
-
-    for item in items_to_drop.iter() {
-        run_computer_until_ready_to_take_input(&mut computer);
-        input_command(&mut computer, &format!("drop {}", item));
-    }
-
-    run_computer_until_ready_to_take_input(&mut computer);
-    input_command(&mut computer, "east");
-
-    let mut output = String::new();
-
-    while !output.contains("main airlock") {
-        computer.run(HaltReason::Output);
-        let c = computer.pop_output().unwrap() as u8 as char;
-        output.push(c);
-    }
-
-    let output_chars: Vec<char> = output.chars().collect();
-
-    output_chars[335..344]
+/// A session of the game, which keeps a transcript of everything that's been said
+/// and can save and restore the whole game at named checkpoints.
+///
+/// Besides the game's own commands, `execute()` understands:
+/// * `!save NAME`, which saves the game as NAME
+/// * `!restore NAME`, which puts the game back how it was when NAME was saved
+pub struct Game {
+    computer: Computer,
+    transcript: String,
+    saves: HashMap<String, Snapshot>,
+    is_over: bool,
+}
+
+impl Game {
+    /// Boots the game and runs it until it asks for the first command.
+    pub fn new(memory: Memory) -> Self {
+        Game::boot(Computer::new(memory))
+    }
+
+    /// Like `new()`, but everything the game prints is also written to `sink` as it's printed.
+    pub fn with_output_sink(memory: Memory, sink: impl Write + 'static) -> Self {
+        let mut computer = Computer::new(memory);
+        computer.stream_ascii_output(sink);
+        Game::boot(computer)
+    }
+
+    fn boot(computer: Computer) -> Self {
+        let mut game = Game {
+            computer,
+            transcript: String::new(),
+            saves: HashMap::new(),
+            is_over: false,
+        };
+
+        game.run_until_ready();
+        game
+    }
+
+    fn run_until_ready(&mut self) -> String {
+        let (output, halt_reason) = run_computer_until_ready_to_take_input(&mut self.computer);
+        self.transcript.push_str(&output);
+        self.is_over = halt_reason == HaltReason::Exit;
+        output
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.is_over
+    }
+
+    /// Everything the game has printed so far, along with the commands that were typed into it.
+    pub fn transcript(&self) -> &str {
+        &self.transcript
+    }
+
+    /// Sends `command` to the game and returns its response.
+    pub fn send(&mut self, command: &str) -> String {
+        self.transcript.push_str(command);
+        self.transcript.push('\n');
+
+        input_command(&mut self.computer, command);
+        self.run_until_ready()
+    }
+
+    pub fn save(&mut self, name: &str) {
+        self.saves
+            .insert(name.to_string(), self.computer.snapshot());
+    }
+
+    /// Returns false if there's no save called `name`.
+    pub fn restore(&mut self, name: &str) -> bool {
+        match self.saves.get(name) {
+            Some(snapshot) => {
+                self.computer.restore(snapshot);
+                self.is_over = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs a single line of input, which is either a game command or one of the `!` commands described above.
+    pub fn execute(&mut self, line: &str) -> String {
+        let line = line.trim();
+
+        let response = if let Some(name) = line.strip_prefix("!save ") {
+            self.save(name);
+            format!("Saved {}.\n", name)
+        } else if let Some(name) = line.strip_prefix("!restore ") {
+            if self.restore(name) {
+                format!("Restored {}.\n", name)
+            } else {
+                format!("There's no save called {}.\n", name)
+            }
+        } else {
+            return self.send(line);
+        };
+
+        self.transcript.push_str(line);
+        self.transcript.push('\n');
+        self.transcript.push_str(&response);
+        response
+    }
+
+    /// Runs each line of `script`, skipping blank lines and lines that start with #.
+    /// Stops early if the game ends. Returns the response to the last line that was run.
+    pub fn run_script(&mut self, script: &str) -> String {
+        let mut response = String::new();
+
+        for line in script.lines().map(str::trim) {
+            if self.is_over {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            response = self.execute(line);
+        }
+
+        response
+    }
+
+    /// Runs the script in `filename`; see `run_script()`.
+    pub fn run_script_file(&mut self, filename: &str) -> io::Result<String> {
+        let script = fs::read_to_string(filename)?;
+        Ok(self.run_script(&script))
+    }
+
+    /// Writes the transcript to `filename`.
+    pub fn write_transcript(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, &self.transcript)
+    }
+}
+
+// "== Security Checkpoint ==": the only way past it is onto the pressure-sensitive floor.
+const SECURITY_CHECKPOINT: &str = "Security Checkpoint";
+
+// Items that end the game or get the droid stuck if you pick them up.
+const TRAPS: [&str; 5] = [
+    "escape pod",
+    "giant electromagnet",
+    "infinite loop",
+    "molten lava",
+    "photons",
+];
+
+/// A room, as described by the game.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Room {
+    pub name: String,
+    pub doors: Vec<String>,
+    pub items: Vec<String>,
+}
+
+/// Parses the last room description in `output`, like:
+///
+/// ```text
+/// == Hull Breach ==
+/// You got in through a hole in the floor here.
+///
+/// Doors here lead:
+/// - north
+/// - south
+///
+/// Items here:
+/// - mug
+/// ```
+pub fn parse_room(output: &str) -> Option<Room> {
+    let lines: Vec<&str> = output.lines().collect();
+    let start = lines
         .iter()
-        .collect::<String>()
-        .parse::<u32>()
+        .rposition(|line| line.starts_with("== ") && line.ends_with(" =="))?;
+
+    let name = lines[start]
+        .trim_start_matches("== ")
+        .trim_end_matches(" ==")
+        .to_string();
+    let mut room = Room {
+        name,
+        doors: vec![],
+        items: vec![],
+    };
+
+    let mut current_list = None;
+    for line in &lines[start + 1..] {
+        match *line {
+            "Doors here lead:" => current_list = Some(&mut room.doors),
+            "Items here:" => current_list = Some(&mut room.items),
+            _ => match (line.strip_prefix("- "), current_list.as_mut()) {
+                (Some(entry), Some(list)) => list.push(entry.to_string()),
+                _ => current_list = None,
+            },
+        }
+    }
+
+    Some(room)
+}
+
+fn opposite(direction: &str) -> &'static str {
+    match direction {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("unknown direction {}", direction),
+    }
+}
+
+/// Everything the droid has learned about the ship so far.
+#[derive(Debug, Default)]
+pub struct ShipMap {
+    /// The name of the room that the droid started in.
+    pub start: String,
+    pub rooms: HashMap<String, Room>,
+    /// (room name, direction) -> the name of the room that the door in that direction leads to.
+    pub exits: HashMap<(String, String), String>,
+}
+
+impl ShipMap {
+    /// Returns the directions to walk in to get from `from` to `to`, or None if there's no known way there.
+    pub fn route(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut came_from: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut route = vec![];
+                let mut current = room;
+                while let Some(&(previous, direction)) = came_from.get(current) {
+                    route.push(direction.to_string());
+                    current = previous;
+                }
+                route.reverse();
+                return Some(route);
+            }
+
+            for direction in &self.rooms[room].doors {
+                if let Some(next) = self.exits.get(&(room.to_string(), direction.clone())) {
+                    if next != from && !came_from.contains_key(next.as_str()) {
+                        came_from.insert(next, (room, direction));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the name of the room that the door in `direction` leads to, or None if nobody's been through it.
+    fn exit(&self, room: &str, direction: &str) -> Option<&String> {
+        self.exits.get(&(room.to_string(), direction.to_string()))
+    }
+
+    fn sorted_rooms(&self) -> Vec<&Room> {
+        let mut rooms: Vec<&Room> = self.rooms.values().collect();
+        rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        rooms
+    }
+
+    /// Draws the map as a Graphviz graph, with a node for each room and an edge for each door.
+    /// The starting room and the security checkpoint are highlighted, traps are marked,
+    /// and doors that nobody has been through lead to dashed "?" nodes.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph ship {\n");
+
+        for room in self.sorted_rooms() {
+            let mut label = room.name.clone();
+            for item in &room.items {
+                label.push_str("\\n");
+                label.push_str(item);
+                if TRAPS.contains(&item.as_str()) {
+                    label.push_str(" (trap)");
+                }
+            }
+
+            let style = if room.name == SECURITY_CHECKPOINT {
+                ", style=filled, fillcolor=gold"
+            } else if room.name == self.start {
+                ", style=filled, fillcolor=lightblue"
+            } else {
+                ""
+            };
+
+            dot.push_str(&format!(
+                "    {} [label={}{}];\n",
+                dot_string(&room.name),
+                dot_string(&label),
+                style
+            ));
+        }
+
+        for room in self.sorted_rooms() {
+            for direction in &room.doors {
+                match self.exit(&room.name, direction) {
+                    // Each door shows up in both of the rooms it connects, so only draw it from one side.
+                    Some(next) if room.name < *next => dot.push_str(&format!(
+                        "    {} -- {} [taillabel={}, headlabel={}];\n",
+                        dot_string(&room.name),
+                        dot_string(next),
+                        dot_string(direction),
+                        dot_string(opposite(direction))
+                    )),
+                    Some(_) => (),
+                    None => {
+                        let unexplored = dot_string(&format!("{} {}", room.name, direction));
+                        dot.push_str(&format!(
+                            "    {} [label=\"?\", style=dashed];\n    {} -- {} [taillabel={}, style=dashed];\n",
+                            unexplored,
+                            dot_string(&room.name),
+                            unexplored,
+                            dot_string(direction)
+                        ));
+                    }
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the map as JSON, like
+    /// `{"start":"Hull Breach","rooms":[{"name":"Hull Breach","doors":{"north":"Kitchen","south":null},"items":["mug"],"checkpoint":false}]}`.
+    /// Rooms are sorted by name and doors by direction. Doors that nobody has been through lead to null.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let rooms = self
+            .sorted_rooms()
+            .into_iter()
+            .map(|room| RoomJson {
+                name: &room.name,
+                doors: room
+                    .doors
+                    .iter()
+                    .map(|direction| {
+                        let destination = self.exit(&room.name, direction).map(String::as_str);
+                        (direction.as_str(), destination)
+                    })
+                    .collect(),
+                items: &room.items,
+                checkpoint: room.name == SECURITY_CHECKPOINT,
+            })
+            .collect();
+
+        serde_json::to_string(&ShipMapJson {
+            start: &self.start,
+            rooms,
+        })
         .unwrap()
+    }
+}
+
+/// A ShipMap as it's written out in JSON.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct ShipMapJson<'a> {
+    start: &'a str,
+    rooms: Vec<RoomJson<'a>>,
+}
+
+/// A Room as it's written out in JSON, with the name of the room behind each door, if it's known.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct RoomJson<'a> {
+    name: &'a str,
+    doors: BTreeMap<&'a str, Option<&'a str>>,
+    items: &'a [String],
+    checkpoint: bool,
+}
+
+/// Quotes `s` for use as a Graphviz ID.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+/// Drives the droid around the ship.
+struct Droid {
+    game: Game,
+    inventory: Vec<String>,
+}
+
+impl Droid {
+    /// Sends `command` and returns the game's response.
+    fn send(&mut self, command: &str) -> String {
+        self.game.send(command)
+    }
+
+    fn go(&mut self, direction: &str) -> Room {
+        parse_room(&self.send(direction)).unwrap()
+    }
+
+    fn take(&mut self, item: &str) {
+        self.send(&format!("take {}", item));
+        self.inventory.push(item.to_string());
+    }
+
+    fn drop(&mut self, item: &str) {
+        self.send(&format!("drop {}", item));
+        self.inventory.retain(|held| held != item);
+    }
+
+    /// Walks through every door reachable from `room` and back, mapping the ship and picking up every safe item.
+    /// Doesn't step onto the pressure-sensitive floor, since that would just throw us back to the checkpoint.
+    fn explore(&mut self, room: Room, came_from: Option<&str>, map: &mut ShipMap) {
+        for item in &room.items {
+            if !TRAPS.contains(&item.as_str()) {
+                self.take(item);
+            }
+        }
+
+        map.rooms.insert(room.name.clone(), room.clone());
+
+        for direction in &room.doors {
+            if Some(direction.as_str()) == came_from || room.name == SECURITY_CHECKPOINT {
+                continue;
+            }
+
+            let next_room = self.go(direction);
+            map.exits.insert(
+                (room.name.clone(), direction.clone()),
+                next_room.name.clone(),
+            );
+            map.exits.insert(
+                (next_room.name.clone(), opposite(direction).to_string()),
+                room.name.clone(),
+            );
+
+            self.explore(next_room, Some(opposite(direction)), map);
+            self.go(opposite(direction));
+        }
+    }
+
+    /// Stands at the security checkpoint, trying every combination of held items on the pressure-sensitive floor
+    /// in the direction `floor`. Returns the game's response once the floor lets us through.
+    fn find_correct_weight(&mut self, floor: &str) -> Option<String> {
+        let items = self.inventory.clone();
+
+        // Visit every combination of items in Gray code order, so that each step only picks up or drops one item.
+        for step in 0..1u32 << items.len() {
+            if step > 0 {
+                let item = &items[step.trailing_zeros() as usize];
+                if self.inventory.contains(item) {
+                    self.drop(item);
+                } else {
+                    self.take(item);
+                }
+            }
+
+            let output = self.send(floor);
+            if parse_password(&output).is_some() {
+                return Some(output);
+            }
+        }
+
+        None
+    }
+}
+
+/// Boots the game and walks the droid everywhere it can go, picking up every safe item on the way.
+/// Returns the droid, back in the room it started in, and the map that it made.
+fn explore_ship(memory: Memory) -> Option<(Droid, ShipMap)> {
+    let mut droid = Droid {
+        game: Game::new(memory),
+        inventory: vec![],
+    };
+
+    let start = parse_room(droid.game.transcript())?;
+    let mut map = ShipMap {
+        start: start.name.clone(),
+        ..ShipMap::default()
+    };
+    droid.explore(start, None, &mut map);
+
+    Some((droid, map))
+}
+
+/// Explores the ship and returns a map of it.
+pub fn map_ship(memory: Memory) -> Option<ShipMap> {
+    explore_ship(memory).map(|(_, map)| map)
+}
+
+/// Explores the ship, collects all of the safe items, and gets past the pressure-sensitive floor.
+/// Returns the password for the main airlock.
+pub fn find_password(memory: Memory) -> Option<u32> {
+    let (mut droid, map) = explore_ship(memory)?;
+
+    for direction in map.route(&map.start, SECURITY_CHECKPOINT)? {
+        droid.go(&direction);
+    }
+
+    // The checkpoint's only unexplored door leads to the floor.
+    let checkpoint = &map.rooms[SECURITY_CHECKPOINT];
+    let floor = checkpoint
+        .doors
+        .iter()
+        .find(|&direction| map.exit(SECURITY_CHECKPOINT, direction).is_none())?
+        .clone();
+
+    parse_password(&droid.find_correct_weight(&floor)?)
+}
+
+pub fn twenty_five_a() -> u32 {
+    let memory = programs::load("cryostasis");
+    find_password(memory).unwrap()
+}
+
+/// Finds the password in "You should be able to get in by typing 134227456 on the keypad at the main airlock."
+fn parse_password(output: &str) -> Option<u32> {
+    PASSWORD_RE
+        .captures(output)
+        .and_then(|captures| captures[1].parse().ok())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::computer;
+
+    #[test]
+    fn test_parse_password() {
+        assert_eq!(
+            parse_password(
+                "\"Oh, hello! You should be able to get in by typing 12345 on the keypad at the main airlock.\"\n"
+            ),
+            Some(12345)
+        );
+        assert_eq!(
+            parse_password("A loud, robotic voice says \"Alert! Droids on this ship are heavier than the detected value!\""),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_room() {
+        let output = "
+
+== Hull Breach ==
+You got in through a hole in the floor here. To keep your ship from also freezing, the hole has been sealed.
+
+Doors here lead:
+- north
+- east
+- south
+
+Items here:
+- mug
+- giant electromagnet
+
+Command?
+";
+        assert_eq!(
+            parse_room(output),
+            Some(Room {
+                name: "Hull Breach".to_string(),
+                doors: vec!["north".to_string(), "east".to_string(), "south".to_string()],
+                items: vec!["mug".to_string(), "giant electromagnet".to_string()],
+            })
+        );
+
+        assert_eq!(parse_room("Command?\n"), None);
+    }
+
+    #[test]
+    fn test_route() {
+        let memory = programs::load("cryostasis");
+        let mut droid = Droid {
+            game: Game::new(memory),
+            inventory: vec![],
+        };
+
+        let start = parse_room(droid.game.transcript()).unwrap();
+        let start_name = start.name.clone();
+        let mut map = ShipMap::default();
+        droid.explore(start, None, &mut map);
+
+        assert!(droid
+            .inventory
+            .iter()
+            .all(|item| !TRAPS.contains(&item.as_str())));
+
+        let route = map.route(&start_name, SECURITY_CHECKPOINT).unwrap();
+        let mut room = start_name;
+        for direction in &route {
+            room = droid.go(direction).name;
+        }
+        assert_eq!(room, SECURITY_CHECKPOINT);
+
+        assert_eq!(
+            map.route(SECURITY_CHECKPOINT, SECURITY_CHECKPOINT),
+            Some(vec![])
+        );
+    }
+
+    fn small_map() -> ShipMap {
+        let room = |name: &str, doors: &[&str], items: &[&str]| Room {
+            name: name.to_string(),
+            doors: doors.iter().map(|door| door.to_string()).collect(),
+            items: items.iter().map(|item| item.to_string()).collect(),
+        };
+
+        let mut map = ShipMap {
+            start: "Hull Breach".to_string(),
+            ..ShipMap::default()
+        };
+        for room in vec![
+            room("Hull Breach", &["north"], &["mug"]),
+            room(SECURITY_CHECKPOINT, &["south", "west"], &["molten lava"]),
+        ] {
+            map.rooms.insert(room.name.clone(), room);
+        }
+        map.exits.insert(
+            ("Hull Breach".to_string(), "north".to_string()),
+            SECURITY_CHECKPOINT.to_string(),
+        );
+        map.exits.insert(
+            (SECURITY_CHECKPOINT.to_string(), "south".to_string()),
+            "Hull Breach".to_string(),
+        );
+
+        map
+    }
+
+    #[test]
+    fn test_to_dot() {
+        assert_eq!(
+            small_map().to_dot(),
+            r#"graph ship {
+    "Hull Breach" [label="Hull Breach\nmug", style=filled, fillcolor=lightblue];
+    "Security Checkpoint" [label="Security Checkpoint\nmolten lava (trap)", style=filled, fillcolor=gold];
+    "Hull Breach" -- "Security Checkpoint" [taillabel="north", headlabel="south"];
+    "Security Checkpoint west" [label="?", style=dashed];
+    "Security Checkpoint" -- "Security Checkpoint west" [taillabel="west", style=dashed];
+}
+"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_to_json() {
+        assert_eq!(
+            small_map().to_json(),
+            r#"{"start":"Hull Breach","rooms":[{"name":"Hull Breach","doors":{"north":"Security Checkpoint"},"items":["mug"],"checkpoint":false},{"name":"Security Checkpoint","doors":{"south":"Hull Breach","west":null},"items":["molten lava"],"checkpoint":true}]}"#
+        );
+
+        let map = map_ship(programs::load("cryostasis")).unwrap();
+        let json = map.to_json();
+        assert_eq!(json.matches("\"name\":").count(), map.rooms.len());
+        assert_eq!(json.matches("\"checkpoint\":true").count(), 1);
+        assert_eq!(json.matches(":null").count(), 1);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["start"], "Hull Breach");
+        assert_eq!(value["rooms"].as_array().unwrap().len(), map.rooms.len());
+    }
+
+    #[test]
+    fn test_game() {
+        let memory = programs::load("cryostasis");
+        let mut game = Game::new(memory);
+        assert_eq!(parse_room(game.transcript()).unwrap().name, "Hull Breach");
+
+        let response = game
+            .run_script_file("src/inputs/25_sample_script.txt")
+            .unwrap();
+        assert!(response.contains("antenna"));
+        assert_eq!(
+            game.execute("!restore nowhere"),
+            "There's no save called nowhere.\n"
+        );
+
+        let transcript = game.transcript();
+        assert!(transcript.starts_with("\n\n\n== Hull Breach =="));
+        assert!(transcript.contains("Saved before.\neast\n"));
+        assert!(transcript.contains("!save antenna\nSaved antenna.\n"));
+        assert!(!transcript.contains("# "));
+
+        // Restoring "antenna" put the droid back in the antenna's room with the antenna in hand.
+        assert_eq!(parse_room(&game.send("west")).unwrap().name, "Hull Breach");
+
+        // Restoring "before" puts the antenna back where it was.
+        assert!(!game.run_script("!restore before\ninv").contains("antenna"));
+        let antenna_room = parse_room(&game.send("east")).unwrap();
+        assert_eq!(antenna_room.items, vec!["antenna".to_string()]);
+    }
+
+    #[test]
+    fn test_golden_trace() {
+        let memory = programs::load("cryostasis");
+        let mut computer = Computer::new(memory.clone());
+        computer.record_io();
+
+        // Restoring a save rewinds the trace too, so it only has what led up to the game's current state.
+        let mut game = Game::boot(computer);
+        game.run_script_file("src/inputs/25_sample_script.txt")
+            .unwrap();
+
+        let golden = computer::load_trace("src/inputs/25_trace.txt");
+        assert_eq!(game.computer.trace(), Some(&golden));
+        assert_eq!(computer::replay(memory, &golden), Ok(()));
+    }
 
     #[test]
     fn test_solutions() {
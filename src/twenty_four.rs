@@ -1,406 +1,498 @@
-use std::collections::HashSet;
+use crate::grid::Grid;
+use crate::util::{self, ParseError};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use tracing::instrument;
+
+const ANIMATION_LEVELS_PER_ROW: usize = 8;
+const ANIMATION_MILLIS_PER_FRAME: u64 = 150;
+
+/// "A bug dies (becoming an empty space) unless there is exactly one bug adjacent to it.
+/// An empty space becomes infested with a bug if exactly one or two bugs are adjacent to it."
+fn bug_rule(alive: bool, num_alive_neighbors: usize) -> bool {
+    if alive {
+        num_alive_neighbors == 1
+    } else {
+        num_alive_neighbors == 1 || num_alive_neighbors == 2
+    }
+}
+
+/// A grid's width, its height, and the (x, y) position of each bug on it.
+type Bugs = (usize, usize, Vec<(usize, usize)>);
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Cell {
-    Alive,
-    Dead,
+/// Reads the grid in `filename`, returning its width, its height, and the (x, y) position of each bug.
+fn load_bugs(filename: impl AsRef<Path>) -> Bugs {
+    util::load(filename, parse_bugs)
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Position {
-    x: i32,
-    y: i32,
+#[instrument(name = "parse", level = "debug", skip_all)]
+fn parse_bugs(contents: &str) -> Result<Bugs, ParseError> {
+    let mut bugs = vec![];
+    let mut unexpected_character = None;
+    let grid = Grid::parse(contents, |c, position| match c {
+        '.' => (),
+        '#' => bugs.push(position),
+        _ => {
+            unexpected_character.get_or_insert((c, position));
+        }
+    })
+    .map_err(|e| e.at_line_of(contents))?;
+
+    if let Some((c, (_, y))) = unexpected_character {
+        return Err(ParseError::new(
+            y + 1,
+            &c.to_string(),
+            "expected . or # for each tile",
+        ));
+    }
+
+    Ok((grid.width(), grid.height(), bugs))
 }
 
-mod regular_grid {
-    use super::{Cell, Position};
-    use std::fs;
+pub mod regular_grid {
+    use super::{bug_rule, load_bugs};
+    use crate::automaton::{Automaton, FlatGrid};
+    use crate::render::RenderGrid;
+    use std::path::Path;
 
-    #[derive(Debug)]
     pub struct Grid {
-        cells: Vec<Cell>,
-        width: usize,
-        height: usize,
+        automaton: Automaton<FlatGrid>,
     }
 
     impl Grid {
-        pub fn new(filename: &str) -> Self {
-            let contents = fs::read_to_string(filename).unwrap();
-            let width = contents.lines().next().unwrap().len();
-            let height = contents.lines().count();
-
-            let mut cells = vec![];
-            for line in contents.lines() {
-                for c in line.chars() {
-                    match c {
-                        '.' => cells.push(Cell::Dead),
-                        '#' => cells.push(Cell::Alive),
-                        _ => unreachable!(),
-                    }
-                }
-            }
-
-            Grid {
-                cells,
+        pub fn new(filename: impl AsRef<Path>) -> Self {
+            let (width, height, bugs) = load_bugs(filename);
+            let topology = FlatGrid {
                 width,
                 height,
+                wraps: false,
+            };
+
+            Grid {
+                automaton: Automaton::new(topology, bugs, bug_rule),
             }
         }
 
-        fn get(&self, position: Position) -> Cell {
-            self.cells[(position.x + self.width as i32 * position.y) as usize]
+        pub fn tick(&self) -> Grid {
+            Grid {
+                automaton: self.automaton.tick(),
+            }
         }
+    }
 
-        fn position_is_in_bounds(&self, Position { x, y }: Position) -> bool {
-            x >= 0 && (x as usize) < self.width && y >= 0 && (y as usize) < self.height
+    impl RenderGrid for Grid {
+        fn width(&self) -> usize {
+            self.automaton.topology().width
         }
 
-        fn num_alive_neighbors(&self, position: Position) -> usize {
-            [
-                Position {
-                    x: position.x - 1,
-                    y: position.y,
-                },
-                Position {
-                    x: position.x + 1,
-                    y: position.y,
-                },
-                Position {
-                    x: position.x,
-                    y: position.y - 1,
-                },
-                Position {
-                    x: position.x,
-                    y: position.y + 1,
-                },
-            ]
-            .iter()
-            .filter(|&&pos| self.position_is_in_bounds(pos))
-            .filter(|&&pos| self.get(pos) == Cell::Alive)
-            .count()
+        fn height(&self) -> usize {
+            self.automaton.topology().height
         }
 
-        pub fn tick(&self) -> Grid {
-            let mut new_cells = Vec::with_capacity(self.cells.len());
-
-            for y in 0..self.height {
-                for x in 0..self.width {
-                    let position = Position {
-                        x: x as i32,
-                        y: y as i32,
-                    };
-                    let cell = self.get(position);
-                    let alive_neighbors = self.num_alive_neighbors(position);
-
-                    if cell == Cell::Alive && alive_neighbors != 1 {
-                        // "A bug dies (becoming an empty space) unless there is exactly one bug adjacent to it."
-                        new_cells.push(Cell::Dead);
-                    } else if cell == Cell::Dead && (alive_neighbors == 1 || alive_neighbors == 2) {
-                        // "An empty space becomes infested with a bug if exactly one or two bugs are adjacent to it."
-                        new_cells.push(Cell::Alive);
-                    } else {
-                        new_cells.push(cell);
-                    }
-                }
-            }
-
-            Grid {
-                width: self.width,
-                height: self.height,
-                cells: new_cells,
+        fn cell_char(&self, x: usize, y: usize) -> char {
+            if self.automaton.is_alive((x, y)) {
+                '#'
+            } else {
+                '.'
             }
         }
     }
 
     pub fn biodiversity_rating(grid: &Grid) -> u64 {
-        grid.cells
-            .iter()
-            .enumerate()
-            .map(|(i, cell)| match cell {
-                Cell::Alive => 1 << i,
-                Cell::Dead => 0,
-            })
+        let width = grid.automaton.topology().width;
+
+        grid.automaton
+            .alive()
+            .map(|&(x, y)| 1 << (y * width + x))
             .sum()
     }
 }
 
-mod infinite_grid {
-    use super::{Cell, Position};
-    use std::fs;
+pub mod infinite_grid {
+    use super::{bug_rule, load_bugs};
+    use crate::automaton::{Automaton, LevelPosition, RecursiveGrid};
+    use crate::render::{self, RenderGrid};
+    use std::fmt::Write;
+    use std::ops::RangeInclusive;
+    use std::path::Path;
 
-    #[derive(Debug)]
     pub struct Grid {
-        levels: Vec<Level>,
-        width: usize,
-        height: usize,
+        automaton: Automaton<RecursiveGrid>,
+        // The depths of every level that has had bugs on it or next to it,
+        // relative to the level that was loaded from the input file.
+        depths: RangeInclusive<i32>,
     }
 
-    #[derive(Debug)]
-    struct Level {
-        cells: Vec<Cell>,
-        width: usize,
-        height: usize,
+    /// A single level of a Grid.
+    struct Level<'a> {
+        grid: &'a Grid,
+        depth: i32,
     }
 
-    impl Level {
-        fn get(&self, position: Position) -> Cell {
-            self.cells[(position.x + self.width as i32 * position.y) as usize]
-        }
-
-        fn num_alive_cells_in_row(&self, y: usize) -> usize {
-            (0..self.width)
-                .map(|x| {
-                    self.get(Position {
-                        x: x as i32,
-                        y: y as i32,
-                    })
-                })
-                .filter(|cell| *cell == Cell::Alive)
-                .count()
+    impl RenderGrid for Level<'_> {
+        fn width(&self) -> usize {
+            self.grid.automaton.topology().size
         }
 
-        fn num_alive_cells_in_column(&self, x: usize) -> usize {
-            (0..self.height)
-                .map(|y| {
-                    self.get(Position {
-                        x: x as i32,
-                        y: y as i32,
-                    })
-                })
-                .filter(|cell| *cell == Cell::Alive)
-                .count()
+        fn height(&self) -> usize {
+            self.grid.automaton.topology().size
         }
 
-        fn num_alive_neighbors(&self, position: Position, outer: &Level, inner: &Level) -> usize {
-            let mut num_alive = 0;
-
-            let cardinal_direction_neighbors = [
-                Position {
-                    x: position.x - 1,
-                    y: position.y,
-                },
-                Position {
-                    x: position.x + 1,
-                    y: position.y,
-                },
-                Position {
-                    x: position.x,
-                    y: position.y - 1,
-                },
-                Position {
-                    x: position.x,
-                    y: position.y + 1,
-                },
-            ];
-
-            let count_cell = |cell| match cell {
-                Cell::Alive => 1,
-                Cell::Dead => 0,
-            };
-
-            for neighbor in cardinal_direction_neighbors.iter() {
-                // 1: Handle positions that are off of the grid, i.e. part of the "outer" level.
-                if neighbor.x < 0 {
-                    num_alive += count_cell(outer.get(Position { x: 1, y: 2 }));
-                } else if neighbor.x > 4 {
-                    num_alive += count_cell(outer.get(Position { x: 3, y: 2 }));
-                } else if neighbor.y < 0 {
-                    num_alive += count_cell(outer.get(Position { x: 2, y: 1 }));
-                } else if neighbor.y > 4 {
-                    num_alive += count_cell(outer.get(Position { x: 2, y: 3 }));
-                } else if neighbor.x == 2 && neighbor.y == 2 {
-                    // 2: Handle the (2, 2) neighbor position, which refers to the "inner" level.
-                    num_alive += match (position.x, position.y) {
-                        (1, _) => inner.num_alive_cells_in_column(0),
-                        (3, _) => inner.num_alive_cells_in_column(4),
-                        (_, 1) => inner.num_alive_cells_in_row(0),
-                        (_, 3) => inner.num_alive_cells_in_row(4),
-                        _ => unreachable!(),
-                    }
-                } else {
-                    // 3: All other positions refer to cells on _this_ level.
-                    num_alive += count_cell(self.get(*neighbor));
-                }
-            }
-
-            num_alive
-        }
-        fn tick(&self, outer: &Level, inner: &Level) -> Level {
-            let mut new_cells = Vec::with_capacity(self.cells.len());
-
-            for y in 0..self.height {
-                for x in 0..self.width {
-                    if x == 2 && y == 2 {
-                        // Skip the middle cell; it contains another level inside of it.
-                        new_cells.push(Cell::Dead);
-                        continue;
-                    }
-
-                    let position = Position {
-                        x: x as i32,
-                        y: y as i32,
-                    };
-                    let cell = self.get(position);
-                    let alive_neighbors = self.num_alive_neighbors(position, outer, inner);
-
-                    if cell == Cell::Alive && alive_neighbors != 1 {
-                        // "A bug dies (becoming an empty space) unless there is exactly one bug adjacent to it."
-                        new_cells.push(Cell::Dead);
-                    } else if cell == Cell::Dead && (alive_neighbors == 1 || alive_neighbors == 2) {
-                        // "An empty space becomes infested with a bug if exactly one or two bugs are adjacent to it."
-                        new_cells.push(Cell::Alive);
-                    } else {
-                        new_cells.push(cell);
-                    }
-                }
+        /// The middle cell of each level contains the next level down, so it's drawn as a ?.
+        fn cell_char(&self, x: usize, y: usize) -> char {
+            let middle = self.width() / 2;
+            if x == middle && y == middle {
+                return '?';
             }
 
-            Level {
-                cells: new_cells,
-                width: self.width,
-                height: self.height,
+            let position = LevelPosition {
+                depth: self.depth,
+                x,
+                y,
+            };
+            if self.grid.automaton.is_alive(position) {
+                '#'
+            } else {
+                '.'
             }
         }
     }
 
     impl Grid {
-        pub fn new(filename: &str) -> Self {
-            let contents = fs::read_to_string(filename).unwrap();
-            let width = contents.lines().next().unwrap().len();
-            let height = contents.lines().count();
-
-            let mut cells = vec![];
-            for line in contents.lines() {
-                for c in line.chars() {
-                    match c {
-                        '.' => cells.push(Cell::Dead),
-                        '#' => cells.push(Cell::Alive),
-                        _ => unreachable!(),
-                    }
-                }
-            }
+        pub fn new(filename: impl AsRef<Path>) -> Self {
+            let (width, height, bugs) = load_bugs(filename);
+            assert_eq!(width, height);
+
+            let bugs = bugs
+                .into_iter()
+                .map(|(x, y)| LevelPosition { depth: 0, x, y });
+            let automaton = Automaton::new(RecursiveGrid { size: width }, bugs, bug_rule);
 
             Grid {
-                levels: vec![
-                    Level {
-                        cells: vec![Cell::Dead; 25],
-                        width,
-                        height,
-                    },
-                    Level {
-                        cells,
-                        width,
-                        height,
-                    },
-                    Level {
-                        cells: vec![Cell::Dead; 25],
-                        width,
-                        height,
-                    },
-                ],
-                width,
-                height,
+                automaton,
+                depths: -1..=1,
             }
         }
 
-        // TODO consider making levels a vecdeque
         pub fn tick(&self) -> Grid {
-            let mut new_levels = Vec::with_capacity(self.levels.len() + 2);
-
-            // Iterate over overlapping windows of three levels at a time.
-            for i in 0..self.levels.len() {
-                // If we're on the innermost or outermost level, treat it as its own inner or outer level,
-                // because innermost and outermost levels are guaranteed to be empty.
-                let window_indexes = if i == 0 {
-                    (0, 0, 1)
-                } else if i == self.levels.len() - 1 {
-                    (
-                        self.levels.len() - 2,
-                        self.levels.len() - 1,
-                        self.levels.len() - 1,
-                    )
-                } else {
-                    (i - 1, i, i + 1)
-                };
-
-                // Make a new Level by calling middle_level.tick().
-                new_levels.push(self.levels[window_indexes.1].tick(
-                    &self.levels[window_indexes.0],
-                    &self.levels[window_indexes.2],
-                ));
+            let automaton = self.automaton.tick();
+
+            // Bugs can only ever spread one level further in either direction each tick,
+            // so make sure there's an empty level on either side of the outermost and innermost bugs.
+            let (mut outermost, mut innermost) = (*self.depths.start(), *self.depths.end());
+            for position in automaton.alive() {
+                outermost = outermost.min(position.depth - 1);
+                innermost = innermost.max(position.depth + 1);
             }
 
-            // If the leftmost level now has any alive cells, push a new level on the far left side.
-            if new_levels[0].cells.iter().any(|cell| *cell == Cell::Alive) {
-                new_levels.insert(
-                    0,
-                    Level {
-                        cells: vec![Cell::Dead; 25],
-                        width: self.width,
-                        height: self.height,
-                    },
-                );
+            Grid {
+                automaton,
+                depths: outermost..=innermost,
             }
+        }
 
-            // If the rightmost level now has any alive cells, push a new level on the far right side.
-            if new_levels[new_levels.len() - 1]
-                .cells
-                .iter()
-                .any(|cell| *cell == Cell::Alive)
-            {
-                new_levels.push(Level {
-                    cells: vec![Cell::Dead; 25],
-                    width: self.width,
-                    height: self.height,
-                });
-            }
+        /// Draws the grid's levels side by side, outermost level first, `levels_per_row` levels at a time.
+        pub fn draw(&self, levels_per_row: usize) -> String {
+            let mut s = String::new();
+            let size = self.automaton.topology().size;
 
-            Grid {
-                width: self.width,
-                height: self.height,
-                levels: new_levels,
+            let levels: Vec<Level> = self
+                .depths
+                .clone()
+                .map(|depth| Level { grid: self, depth })
+                .collect();
+
+            for (row_index, row) in levels.chunks(levels_per_row).enumerate() {
+                if row_index > 0 {
+                    writeln!(&mut s).unwrap();
+                }
+
+                let labels: Vec<String> = row
+                    .iter()
+                    .map(|level| format!("Depth {}", level.depth))
+                    .collect();
+                let column_width = labels
+                    .iter()
+                    .map(|label| label.len())
+                    .max()
+                    .unwrap()
+                    .max(size);
+
+                let mut lines = vec![labels
+                    .iter()
+                    .map(|label| format!("{:<width$}", label, width = column_width))
+                    .collect::<Vec<_>>()
+                    .join("  ")];
+
+                let drawn_levels: Vec<String> = row.iter().map(render::to_text).collect();
+                for y in 0..size {
+                    lines.push(
+                        drawn_levels
+                            .iter()
+                            .map(|level| {
+                                let cells = level.lines().nth(y).unwrap();
+                                format!("{:<width$}", cells, width = column_width)
+                            })
+                            .collect::<Vec<_>>()
+                            .join("  "),
+                    );
+                }
+
+                for line in lines {
+                    writeln!(&mut s, "{}", line.trim_end()).unwrap();
+                }
             }
+
+            s
         }
     }
 
     pub fn num_alive_cells_in_grid(grid: &Grid) -> usize {
-        grid.levels
-            .iter()
-            .flat_map(|level| &level.cells)
-            .filter(|cell| **cell == Cell::Alive)
-            .count()
+        grid.automaton.num_alive()
     }
 }
 
-pub fn twenty_four_a() -> u64 {
-    let mut grid = regular_grid::Grid::new("src/inputs/24.txt");
-    let mut seen_ratings = HashSet::new();
+/// Fast versions of both parts, which pack each 5x5 level into the low 25 bits of a u32:
+/// bit `y * 5 + x` is set if there's a bug at (x, y).
+/// Conveniently, that means a level's biodiversity rating is just its value.
+pub mod bitboard {
+    use super::bug_rule;
+    use std::collections::VecDeque;
+
+    pub const SIZE: usize = 5;
+    const NUM_CELLS: usize = SIZE * SIZE;
+    const MIDDLE: usize = NUM_CELLS / 2;
 
-    loop {
-        let rating = regular_grid::biodiversity_rating(&grid);
-        if seen_ratings.contains(&rating) {
-            break rating;
+    /// For each cell, the cells above, below, and to either side of it.
+    const FLAT_NEIGHBORS: [u32; NUM_CELLS] = flat_neighbor_masks();
+
+    /// For each cell, its neighbors on the same level of a recursive grid, where the middle cell is another level.
+    const SAME_LEVEL_NEIGHBORS: [u32; NUM_CELLS] = same_level_neighbor_masks();
+
+    /// For each cell, its neighbors on the level outside this one.
+    const OUTER_NEIGHBORS: [u32; NUM_CELLS] = outer_neighbor_masks();
+
+    /// For each cell, its neighbors on the level inside this one.
+    const INNER_NEIGHBORS: [u32; NUM_CELLS] = inner_neighbor_masks();
+
+    const fn flat_neighbor_masks() -> [u32; NUM_CELLS] {
+        let mut masks = [0; NUM_CELLS];
+        let mut i = 0;
+
+        while i < NUM_CELLS {
+            let (x, y) = (i % SIZE, i / SIZE);
+            if x > 0 {
+                masks[i] |= 1 << (i - 1);
+            }
+            if x < SIZE - 1 {
+                masks[i] |= 1 << (i + 1);
+            }
+            if y > 0 {
+                masks[i] |= 1 << (i - SIZE);
+            }
+            if y < SIZE - 1 {
+                masks[i] |= 1 << (i + SIZE);
+            }
+            i += 1;
         }
 
-        seen_ratings.insert(rating);
+        masks
+    }
 
-        grid = grid.tick();
+    const fn same_level_neighbor_masks() -> [u32; NUM_CELLS] {
+        let mut masks = flat_neighbor_masks();
+        let mut i = 0;
+
+        while i < NUM_CELLS {
+            masks[i] &= !(1 << MIDDLE);
+            i += 1;
+        }
+        masks[MIDDLE] = 0;
+
+        masks
+    }
+
+    const fn outer_neighbor_masks() -> [u32; NUM_CELLS] {
+        let mut masks = [0; NUM_CELLS];
+        let mut i = 0;
+
+        while i < NUM_CELLS {
+            let (x, y) = (i % SIZE, i / SIZE);
+            if x == 0 {
+                masks[i] |= 1 << (MIDDLE - 1);
+            }
+            if x == SIZE - 1 {
+                masks[i] |= 1 << (MIDDLE + 1);
+            }
+            if y == 0 {
+                masks[i] |= 1 << (MIDDLE - SIZE);
+            }
+            if y == SIZE - 1 {
+                masks[i] |= 1 << (MIDDLE + SIZE);
+            }
+            i += 1;
+        }
+
+        masks
+    }
+
+    const fn inner_neighbor_masks() -> [u32; NUM_CELLS] {
+        let mut masks = [0; NUM_CELLS];
+        let mut i = 0;
+
+        while i < SIZE {
+            // The cell above the middle borders the inner level's top row, and so on.
+            masks[MIDDLE - SIZE] |= 1 << i;
+            masks[MIDDLE + SIZE] |= 1 << (NUM_CELLS - SIZE + i);
+            masks[MIDDLE - 1] |= 1 << (i * SIZE);
+            masks[MIDDLE + 1] |= 1 << (i * SIZE + SIZE - 1);
+            i += 1;
+        }
+
+        masks
+    }
+
+    /// Packs (x, y) bug positions into a bitboard. Panics if any of them is off of a 5x5 grid.
+    pub fn from_bugs(bugs: &[(usize, usize)]) -> u32 {
+        bugs.iter()
+            .map(|&(x, y)| {
+                assert!(x < SIZE && y < SIZE);
+                1 << (y * SIZE + x)
+            })
+            .fold(0, |board, bit| board | bit)
+    }
+
+    fn is_alive(board: u32, i: usize) -> bool {
+        board & (1 << i) != 0
+    }
+
+    pub fn tick(board: u32) -> u32 {
+        (0..NUM_CELLS)
+            .filter(|&i| {
+                let num_alive_neighbors = (board & FLAT_NEIGHBORS[i]).count_ones() as usize;
+                bug_rule(is_alive(board, i), num_alive_neighbors)
+            })
+            .fold(0, |next, i| next | 1 << i)
+    }
+
+    /// Returns the biodiversity rating of the first layout that appears twice.
+    pub fn first_repeated_rating(mut board: u32) -> u32 {
+        // One bit for every possible layout.
+        let mut seen = vec![0u64; (1 << NUM_CELLS) / 64];
+
+        loop {
+            let (word, bit) = (board as usize / 64, board % 64);
+            if seen[word] & (1 << bit) != 0 {
+                break board;
+            }
+
+            seen[word] |= 1 << bit;
+            board = tick(board);
+        }
+    }
+
+    fn tick_level(level: u32, outer: u32, inner: u32) -> u32 {
+        (0..NUM_CELLS)
+            .filter(|&i| i != MIDDLE)
+            .filter(|&i| {
+                let num_alive_neighbors = (level & SAME_LEVEL_NEIGHBORS[i]).count_ones()
+                    + (outer & OUTER_NEIGHBORS[i]).count_ones()
+                    + (inner & INNER_NEIGHBORS[i]).count_ones();
+                bug_rule(is_alive(level, i), num_alive_neighbors as usize)
+            })
+            .fold(0, |next, i| next | 1 << i)
+    }
+
+    /// Returns the number of bugs on all of the recursive levels after `num_minutes` minutes,
+    /// starting with `board` as the only level with bugs on it.
+    pub fn num_bugs_after(board: u32, num_minutes: usize) -> u32 {
+        // Outermost level first, with an empty level on each end.
+        let mut levels: VecDeque<u32> = vec![0, board & !(1 << MIDDLE), 0].into();
+
+        for _ in 0..num_minutes {
+            let mut old_outer = 0;
+            for i in 0..levels.len() {
+                let old_level = levels[i];
+                let inner = levels.get(i + 1).copied().unwrap_or(0);
+                levels[i] = tick_level(old_level, old_outer, inner);
+                old_outer = old_level;
+            }
+
+            if levels[0] != 0 {
+                levels.push_front(0);
+            }
+            if levels[levels.len() - 1] != 0 {
+                levels.push_back(0);
+            }
+        }
+
+        levels.iter().map(|level| level.count_ones()).sum()
     }
 }
 
+/// Reads the 5x5 grid in `filename` into a bitboard.
+fn load_bitboard(filename: impl AsRef<Path>) -> u32 {
+    util::load(filename, parse_bitboard)
+}
+
+/// Reads a 5x5 grid like `#..#.` into a bitboard. Returns an error if the grid is any other size.
+pub fn parse_bitboard(contents: &str) -> Result<u32, ParseError> {
+    let (width, height, bugs) = parse_bugs(contents)?;
+    let line = |y: usize| contents.lines().nth(y).unwrap_or("");
+
+    if width != bitboard::SIZE {
+        return Err(ParseError::new(
+            1,
+            line(0),
+            format_args!("expected {} tiles per row, found {}", bitboard::SIZE, width),
+        ));
+    }
+    if height != bitboard::SIZE {
+        // Point at the first extra row, or just past the last one if there are too few.
+        return Err(ParseError::new(
+            height.min(bitboard::SIZE) + 1,
+            line(bitboard::SIZE),
+            format_args!("expected {} rows, found {}", bitboard::SIZE, height),
+        ));
+    }
+
+    Ok(bitboard::from_bugs(&bugs))
+}
+
+pub fn twenty_four_a() -> u64 {
+    bitboard::first_repeated_rating(load_bitboard(util::input(24))) as u64
+}
+
 pub fn twenty_four_b() -> usize {
-    let mut grid = infinite_grid::Grid::new("src/inputs/24.txt");
+    bitboard::num_bugs_after(load_bitboard(util::input(24)), 200) as usize
+}
 
-    for _ in 0..200 {
+/// Animates `num_minutes` minutes of bugs spreading through the recursive levels of the grid in `filename`,
+/// redrawing the terminal once per minute.
+#[cfg(not(tarpaulin_include))]
+pub fn animate_b(filename: &str, num_minutes: usize) {
+    let mut grid = infinite_grid::Grid::new(filename);
+
+    for minute in 0..=num_minutes {
+        // Clear the screen and move the cursor back to the top-left corner.
+        print!("\x1B[2J\x1B[H");
+        println!(
+            "After {} minutes: {} bugs\n",
+            minute,
+            infinite_grid::num_alive_cells_in_grid(&grid)
+        );
+        println!("{}", grid.draw(ANIMATION_LEVELS_PER_ROW));
+
+        thread::sleep(Duration::from_millis(ANIMATION_MILLIS_PER_FRAME));
         grid = grid.tick();
     }
-
-    infinite_grid::num_alive_cells_in_grid(&grid)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
 
     #[test]
     fn test_biodiversity_rating() {
@@ -408,6 +500,78 @@ mod tests {
         assert_eq!(regular_grid::biodiversity_rating(&grid), 2129920);
     }
 
+    #[test]
+    fn test_bitboard() {
+        let grid = regular_grid::Grid::new("src/inputs/24_sample_1.txt");
+        let board = load_bitboard("src/inputs/24_sample_1.txt");
+        assert_eq!(board as u64, regular_grid::biodiversity_rating(&grid));
+
+        let mut grid = regular_grid::Grid::new(util::input(24));
+        let mut board = load_bitboard(util::input(24));
+        for _ in 0..20 {
+            assert_eq!(board as u64, regular_grid::biodiversity_rating(&grid));
+            grid = grid.tick();
+            board = bitboard::tick(board);
+        }
+
+        let board = load_bitboard("src/inputs/24_sample_2.txt");
+        assert_eq!(bitboard::first_repeated_rating(board), 2129920);
+        assert_eq!(bitboard::num_bugs_after(board, 10), 99);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_bitboard("....#\n#..#.\n#..b#\n..#..\n#...."),
+            Err(ParseError::new(3, "b", "expected . or # for each tile"))
+        );
+        assert_eq!(
+            parse_bitboard("....#\n#..#.\n#..#\n..#..\n#....")
+                .unwrap_err()
+                .line,
+            3
+        );
+
+        assert_eq!(
+            parse_bitboard("#....#\n#..#..\n#..##.\n..#...\n#.....\n"),
+            Err(ParseError::new(
+                1,
+                "#....#",
+                "expected 5 tiles per row, found 6"
+            ))
+        );
+        assert_eq!(
+            parse_bitboard("#...\n#..#\n#..#\n..#.\n#...\n")
+                .unwrap_err()
+                .message,
+            "expected 5 tiles per row, found 4"
+        );
+        assert_eq!(
+            parse_bitboard("....#\n#..#.\n#..##\n..#..\n#....\n#...."),
+            Err(ParseError::new(6, "#....", "expected 5 rows, found 6"))
+        );
+        assert_eq!(
+            parse_bitboard("....#\n#..#.\n#..##\n"),
+            Err(ParseError::new(4, "", "expected 5 rows, found 3"))
+        );
+    }
+
+    #[test]
+    fn test_automaton_solutions() {
+        let mut grid = regular_grid::Grid::new(util::input(24));
+        let mut seen_ratings = HashSet::new();
+        while seen_ratings.insert(regular_grid::biodiversity_rating(&grid)) {
+            grid = grid.tick();
+        }
+        assert_eq!(regular_grid::biodiversity_rating(&grid), 18375063);
+
+        let mut grid = infinite_grid::Grid::new(util::input(24));
+        for _ in 0..200 {
+            grid = grid.tick();
+        }
+        assert_eq!(infinite_grid::num_alive_cells_in_grid(&grid), 1959);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(twenty_four_a(), 18375063);
@@ -423,4 +587,32 @@ mod tests {
 
         assert_eq!(infinite_grid::num_alive_cells_in_grid(&grid), 99);
     }
+
+    #[test]
+    fn test_draw_infinite_grid() {
+        let grid = infinite_grid::Grid::new("src/inputs/24_sample_2.txt");
+        assert_eq!(
+            grid.draw(2),
+            "Depth -1  Depth 0
+.....     ....#
+.....     #..#.
+..?..     #.?##
+.....     ..#..
+.....     #....
+
+Depth 1
+.....
+.....
+..?..
+.....
+.....
+"
+        );
+
+        let grid = grid.tick();
+        assert_eq!(
+            grid.draw(5).lines().next().unwrap(),
+            "Depth -2  Depth -1  Depth 0   Depth 1   Depth 2"
+        );
+    }
 }
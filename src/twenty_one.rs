@@ -1,5 +1,8 @@
-use crate::computer;
 use crate::computer::{Computer, HaltReason};
+use crate::programs;
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 #[derive(Debug)]
 enum DroidOutcome {
@@ -15,7 +18,7 @@ fn input_line(computer: &mut Computer, line: &str) {
 }
 
 fn run_droid(program: &str, run_command: &str) -> DroidOutcome {
-    let memory = computer::load_program("src/inputs/21.txt");
+    let memory = programs::load("springdroid");
     let mut computer = Computer::new(memory);
 
     // Program the droid.
@@ -91,10 +94,381 @@ pub fn twenty_one_b() -> i64 {
     }
 }
 
+/// "There are only three instructions available in springscript: AND, OR, and NOT."
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    And,
+    Or,
+    Not,
+}
+
+/// One of the droid's registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Register {
+    /// A read-only sensor: Sensor(0) is A, "ground one tile away", Sensor(1) is B, and so on.
+    Sensor(u8),
+    /// "the temporary value register"
+    T,
+    /// "the jump register"
+    J,
+}
+
+/// A single springscript instruction, like `AND A J`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub operation: Operation,
+    pub source: Register,
+    /// Must be T or J.
+    pub target: Register,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let register_name = |register| match register {
+            Register::Sensor(i) => (b'A' + i) as char,
+            Register::T => 'T',
+            Register::J => 'J',
+        };
+
+        let operation = match self.operation {
+            Operation::And => "AND",
+            Operation::Or => "OR",
+            Operation::Not => "NOT",
+        };
+
+        write!(
+            f,
+            "{} {} {}",
+            operation,
+            register_name(self.source),
+            register_name(self.target)
+        )
+    }
+}
+
+/// Formats `program` as springscript, one instruction per line.
+pub fn to_springscript(program: &[Instruction]) -> String {
+    program
+        .iter()
+        .map(|instruction| instruction.to_string())
+        .join("\n")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The droid can see four tiles ahead.
+    Walk,
+    /// "the springdroid can see five additional tiles"
+    Run,
+}
+
+impl Mode {
+    fn command(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        }
+    }
+
+    fn num_sensors(self) -> usize {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+}
+
+// "the springdroid can only remember at most 15 springscript instructions"
+const MAX_INSTRUCTIONS: usize = 15;
+
+// "If the springdroid jumps, it moves forward four tiles."
+const JUMP_DISTANCE: usize = 4;
+
+/// Runs `program` on the droid's sensor readings, where `sensors[0]` is A, and returns the final (T, J).
+fn run_springscript(program: &[Instruction], sensors: &[bool]) -> (bool, bool) {
+    let (mut t, mut j) = (false, false);
+
+    for instruction in program {
+        let source = match instruction.source {
+            Register::Sensor(i) => sensors[i as usize],
+            Register::T => t,
+            Register::J => j,
+        };
+        let target = match instruction.target {
+            Register::T => &mut t,
+            Register::J => &mut j,
+            Register::Sensor(_) => panic!("sensors are read-only"),
+        };
+
+        *target = match instruction.operation {
+            Operation::And => source && *target,
+            Operation::Or => source || *target,
+            Operation::Not => !source,
+        };
+    }
+
+    (t, j)
+}
+
+/// Returns true if the droid jumps when running `program` with these sensor readings.
+pub fn simulate(program: &[Instruction], sensors: &[bool]) -> bool {
+    run_springscript(program, sensors).1
+}
+
+/// Parses a hull like "#####.#..########", where # is ground and . is a hole.
+pub fn parse_hull(hull: &str) -> Vec<bool> {
+    hull.chars().map(|c| c == '#').collect()
+}
+
+/// Returns the droid's sensor readings when it's standing at `position` on `hull`.
+/// Everything past the end of `hull` is assumed to be ground.
+fn sensor_readings(hull: &[bool], position: usize, mode: Mode) -> Vec<bool> {
+    (1..=mode.num_sensors())
+        .map(|distance| *hull.get(position + distance).unwrap_or(&true))
+        .collect()
+}
+
+/// Walks a droid across `hull`, asking `should_jump` what to do at each tile.
+/// Returns Ok(()) if the droid made it across, or Err(position) if it fell into a hole at `position`.
+fn walk_hull(
+    hull: &[bool],
+    mode: Mode,
+    mut should_jump: impl FnMut(&[bool]) -> bool,
+) -> Result<(), usize> {
+    let mut position = 0;
+
+    while position < hull.len() {
+        if !hull[position] {
+            return Err(position);
+        }
+
+        position += if should_jump(&sensor_readings(hull, position, mode)) {
+            JUMP_DISTANCE
+        } else {
+            1
+        };
+    }
+
+    Ok(())
+}
+
+/// Returns true if a droid running `program` in `mode` makes it across `hull`.
+pub fn survives(program: &[Instruction], mode: Mode, hull: &[bool]) -> bool {
+    walk_hull(hull, mode, |sensors| simulate(program, sensors)).is_ok()
+}
+
+/// Every instruction that can be written in `mode`.
+fn all_instructions(mode: Mode) -> Vec<Instruction> {
+    let sources = (0..mode.num_sensors() as u8)
+        .map(Register::Sensor)
+        .chain(vec![Register::T, Register::J]);
+
+    sources
+        .cartesian_product(vec![Register::T, Register::J])
+        .cartesian_product(vec![Operation::And, Operation::Or, Operation::Not])
+        .map(|((source, target), operation)| Instruction {
+            operation,
+            source,
+            target,
+        })
+        .collect()
+}
+
+/// Scores a program by how many of `hulls` it survives, and then by how far it gets on the others.
+/// `behavior` holds the program's final (T, J) for each of `all_readings`, and `decide` turns that into a jump.
+fn score(
+    mode: Mode,
+    hulls: &[Vec<bool>],
+    all_readings: &[Vec<bool>],
+    behavior: &[(bool, bool)],
+    decide: impl Fn((bool, bool)) -> bool,
+) -> (usize, usize) {
+    let decisions: HashMap<&[bool], bool> = all_readings
+        .iter()
+        .map(|sensors| &sensors[..])
+        .zip(behavior.iter().map(|&registers| decide(registers)))
+        .collect();
+
+    let mut num_survived = 0;
+    let mut total_progress = 0;
+    for hull in hulls {
+        match walk_hull(hull, mode, |sensors| decisions[sensors]) {
+            Ok(()) => {
+                num_survived += 1;
+                total_progress += hull.len();
+            }
+            Err(position) => total_progress += position,
+        }
+    }
+
+    (num_survived, total_progress)
+}
+
+// How many candidate programs are kept around after each round of the search.
+const BEAM_WIDTH: usize = 200;
+
+/// Searches for a springscript program with at most `max_instructions` instructions that gets
+/// the droid across every one of `hulls`. Returns None if the search couldn't find one.
+///
+/// This is a beam search: each round, every program in the beam is extended by every possible instruction,
+/// and the ones that get the droid the farthest are kept for the next round.
+pub fn search_for_program(
+    mode: Mode,
+    hulls: &[Vec<bool>],
+    max_instructions: usize,
+) -> Option<Vec<Instruction>> {
+    // Programs only ever see the sensor readings that can occur on these hulls,
+    // so two programs that leave T and J the same for all of them behave identically from here on out.
+    let all_readings: Vec<Vec<bool>> = hulls
+        .iter()
+        .flat_map(|hull| (0..hull.len()).map(move |position| sensor_readings(hull, position, mode)))
+        .unique()
+        .collect();
+
+    let instructions = all_instructions(mode);
+    let mut beam: Vec<Vec<Instruction>> = vec![vec![]];
+
+    for _ in 0..max_instructions.min(MAX_INSTRUCTIONS) {
+        let mut seen_behaviors = HashSet::new();
+        let mut candidates = vec![];
+
+        for program in &beam {
+            for &instruction in &instructions {
+                let mut candidate = program.clone();
+                candidate.push(instruction);
+
+                let behavior: Vec<(bool, bool)> = all_readings
+                    .iter()
+                    .map(|sensors| run_springscript(&candidate, sensors))
+                    .collect();
+                if !seen_behaviors.insert(behavior.clone()) {
+                    continue;
+                }
+
+                let (num_survived, _) = score(mode, hulls, &all_readings, &behavior, |(_, j)| j);
+                if num_survived == hulls.len() {
+                    return Some(candidate);
+                }
+
+                // Programs often build up something useful in T before folding it into J,
+                // so rank each candidate by the best that one more instruction could do with it.
+                let best_score = [
+                    score(mode, hulls, &all_readings, &behavior, |(_, j)| j),
+                    score(mode, hulls, &all_readings, &behavior, |(t, j)| t || j),
+                    score(mode, hulls, &all_readings, &behavior, |(t, j)| t && j),
+                ]
+                .iter()
+                .copied()
+                .max()
+                .unwrap();
+
+                candidates.push((best_score, candidate));
+            }
+        }
+
+        candidates.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        beam = candidates
+            .into_iter()
+            .take(BEAM_WIDTH)
+            .map(|(_, program)| program)
+            .collect();
+    }
+
+    None
+}
+
+/// Pulls the hull out of the replay that the droid shows when it falls into space.
+fn hull_from_replay(replay: &str) -> Vec<bool> {
+    let hull_line = replay
+        .lines()
+        .find(|line| line.contains('#') && line.chars().all(|c| c == '#' || c == '.'))
+        .expect("replay includes the hull");
+    parse_hull(hull_line)
+}
+
+/// Finds a springscript program that gets the droid across the hull in `mode`,
+/// and returns it along with the amount of hull damage that the droid reports.
+///
+/// Starts out knowing nothing about the hull: each time a candidate program fails on the real Intcode droid,
+/// the hull that it fell through is added to the set of hulls that the next program must survive.
+pub fn find_program(mode: Mode, max_rounds: usize) -> Option<(Vec<Instruction>, i64)> {
+    let mut hulls = vec![];
+
+    for _ in 0..max_rounds {
+        let program = search_for_program(mode, &hulls, MAX_INSTRUCTIONS)?;
+
+        match run_droid(&to_springscript(&program), mode.command()) {
+            DroidOutcome::Success(hull_damage) => return Some((program, hull_damage)),
+            DroidOutcome::Death(replay) => hulls.push(hull_from_replay(&replay)),
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_simulate() {
+        let program: Vec<Instruction> = vec![
+            Instruction {
+                operation: Operation::Not,
+                source: Register::Sensor(0),
+                target: Register::J,
+            },
+            Instruction {
+                operation: Operation::And,
+                source: Register::Sensor(3),
+                target: Register::J,
+            },
+        ];
+        assert_eq!(to_springscript(&program), "NOT A J\nAND D J");
+
+        assert!(simulate(&program, &[false, true, true, true]));
+        assert!(!simulate(&program, &[true, true, true, true]));
+        assert!(!simulate(&program, &[false, true, true, false]));
+
+        assert!(survives(
+            &program,
+            Mode::Walk,
+            &parse_hull("#####.###########")
+        ));
+        assert!(!survives(
+            &program,
+            Mode::Walk,
+            &parse_hull("#####..#.########")
+        ));
+    }
+
+    #[test]
+    fn test_search_for_program() {
+        let hulls: Vec<Vec<bool>> = [
+            "#####.###########",
+            "#####..#.########",
+            "#####...#########",
+        ]
+        .iter()
+        .map(|hull| parse_hull(hull))
+        .collect();
+
+        let program = search_for_program(Mode::Walk, &hulls, 6).unwrap();
+        assert!(hulls
+            .iter()
+            .all(|hull| survives(&program, Mode::Walk, hull)));
+    }
+
+    #[test]
+    fn test_find_program() {
+        let (_, hull_damage) = find_program(Mode::Walk, 20).unwrap();
+        assert_eq!(hull_damage, twenty_one_a());
+
+        let (program, hull_damage) = find_program(Mode::Run, 20).unwrap();
+        assert!(program.len() <= MAX_INSTRUCTIONS);
+        assert_eq!(hull_damage, twenty_one_b());
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(twenty_one_a(), 19352493);
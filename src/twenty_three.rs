@@ -1,44 +1,155 @@
-use crate::computer::{load_program, Computer, HaltReason};
+use crate::computer::{Computer, HaltReason, Memory};
+use crate::programs;
 use std::collections::VecDeque;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
 
-#[derive(Copy, Clone, Debug)]
-struct Message {
-    x: i64,
-    y: i64,
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Message {
+    pub x: i64,
+    pub y: i64,
 }
 
-struct Network {
+/// A packet that was sent over a `Network`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Packet {
+    /// The tick during which the packet was sent.
+    pub tick: usize,
+    pub from: usize,
+    pub to: usize,
+    pub message: Message,
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[tick {}] {} -> {}: X={}, Y={}",
+            self.tick, self.from, self.to, self.message.x, self.message.y
+        )
+    }
+}
+
+/// Reads the (destination address, X, Y) triple of the packet that `computer` has started sending.
+fn read_packet(computer: &mut Computer) -> (usize, Message) {
+    match computer.run_until_n_outputs(3)[..] {
+        [to, x, y] => (to as usize, Message { x, y }),
+        _ => panic!("a computer exited partway through sending a packet"),
+    }
+}
+
+type PacketObserver = Box<dyn FnMut(usize, usize, Message)>;
+
+/// Decides what the NAT does when the network goes idle.
+pub trait NatPolicy {
+    /// Called when the network is idle, with every packet the NAT has received so far, oldest first.
+    /// Returns the address and contents of the packet that the NAT should send, if any.
+    fn on_idle(&mut self, received: &[Message]) -> Option<(usize, Message)>;
+}
+
+/// The puzzle's NAT, which holds on to the last packet it received and sends it to address 0 when the network is idle.
+pub struct ForwardLastToZero;
+
+impl NatPolicy for ForwardLastToZero {
+    fn on_idle(&mut self, received: &[Message]) -> Option<(usize, Message)> {
+        received.last().map(|&message| (0, message))
+    }
+}
+
+pub struct Network {
     computers: Vec<Computer>,
     mailbox: Vec<VecDeque<Message>>,
+    nat_address: usize,
+    nat_policy: Box<dyn NatPolicy>,
     nat_mailbox: Vec<Message>,
+    num_ticks: usize,
+    observers: Vec<PacketObserver>,
+    packet_log: Option<Vec<Packet>>,
 }
 
 impl Network {
-    pub fn new(memory: &[i64]) -> Self {
+    /// Boots `num_computers` copies of `memory`, with network addresses 0 through `num_computers - 1`.
+    /// Packets sent to `nat_address` go to a NAT that follows `nat_policy`.
+    pub fn new(
+        memory: &[i64],
+        num_computers: usize,
+        nat_address: usize,
+        nat_policy: impl NatPolicy + 'static,
+    ) -> Self {
+        assert!(
+            nat_address >= num_computers,
+            "the NAT can't share an address with a computer"
+        );
+
         let mut computers = Vec::new();
-        for i in 0..50 {
+        for i in 0..num_computers {
             let mut computer = Computer::new(memory.to_vec());
-            computer.push_input(i);
+            computer.push_input(i as i64);
             computers.push(computer);
         }
 
-        let mailbox: Vec<VecDeque<Message>> = vec![VecDeque::new(); 50];
+        let mailbox: Vec<VecDeque<Message>> = vec![VecDeque::new(); num_computers];
 
         Network {
             computers,
             mailbox,
+            nat_address,
+            nat_policy: Box::new(nat_policy),
             nat_mailbox: vec![],
+            num_ticks: 0,
+            observers: vec![],
+            packet_log: None,
         }
     }
 
+    /// Calls `observer` with (from, to, message) every time a packet is sent, including by the NAT.
+    pub fn on_packet(&mut self, observer: impl FnMut(usize, usize, Message) + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Starts keeping a log of every packet sent over the network from here on out.
+    pub fn record_packets(&mut self) {
+        self.packet_log.get_or_insert_with(Vec::new);
+    }
+
+    /// Returns every packet sent since `record_packets()` was called, or None if it wasn't.
+    pub fn packet_log(&self) -> Option<&[Packet]> {
+        self.packet_log.as_deref()
+    }
+
+    fn send(&mut self, from: usize, to: usize, message: Message) {
+        for observer in self.observers.iter_mut() {
+            observer(from, to, message);
+        }
+
+        if let Some(log) = self.packet_log.as_mut() {
+            log.push(Packet {
+                tick: self.num_ticks,
+                from,
+                to,
+                message,
+            });
+        }
+
+        if to == self.nat_address {
+            self.nat_mailbox.push(message);
+        } else if let Some(mailbox) = self.mailbox.get_mut(to) {
+            mailbox.push_back(message);
+        }
+        // Packets sent to addresses that nobody has are dropped.
+    }
+
     /// Advances the network one tick.
     /// Returns true if all of the computers are waiting for input, false otherwise.
     pub fn tick(&mut self) -> bool {
         let mut all_machines_waiting_on_input = true;
 
-        for (i, computer) in self.computers.iter_mut().enumerate() {
+        for i in 0..self.computers.len() {
+            let computer = &mut self.computers[i];
+
             // Check our own mail to see if we have any messages.
-            if let Some(message) = self.mailbox[i as usize].pop_front() {
+            if let Some(message) = self.mailbox[i].pop_front() {
                 computer.push_input(message.x);
                 computer.push_input(message.y);
             }
@@ -49,30 +160,248 @@ impl Network {
 
                 // This computer has produced a message!
                 // Let's turn it into a Message and stuff it in the mailbox.
-                computer.run(HaltReason::Output);
-                computer.run(HaltReason::Output);
-
-                let message_address = computer.pop_output().unwrap() as usize;
-                let message = Message {
-                    x: computer.pop_output().unwrap(),
-                    y: computer.pop_output().unwrap(),
-                };
-
-                if message_address == 255 {
-                    self.nat_mailbox.push(message);
-                } else {
-                    self.mailbox[message_address].push_back(message);
-                }
+                let (message_address, message) = read_packet(computer);
+
+                self.send(i, message_address, message);
             }
         }
 
+        self.num_ticks += 1;
         all_machines_waiting_on_input
     }
+
+    /// Lets the NAT react to the network being idle.
+    /// Returns the packet that the NAT sent, or None if its policy decided not to send anything.
+    pub fn wake_up(&mut self) -> Option<Message> {
+        let (to, message) = self.nat_policy.on_idle(&self.nat_mailbox)?;
+        self.send(self.nat_address, to, message);
+        Some(message)
+    }
+}
+
+// "Packets sent to address 255 are handled by a device called a NAT"
+const NAT_ADDRESS: usize = 255;
+const NUM_COMPUTERS: usize = 50;
+
+// How many times in a row a computer has to come up empty when it reads its mailbox
+// before it tells the supervisor that it's idle.
+const NUM_EMPTY_READS_BEFORE_IDLE: usize = 2;
+
+/// Something a computer tells the supervisor.
+enum Event {
+    /// The computer at address `from` sent `message` to address `to`.
+    Packet {
+        from: usize,
+        to: usize,
+        message: Message,
+    },
+    /// The computer at `address` has run out of mail,
+    /// after having received `num_received` packets in total.
+    Idle { address: usize, num_received: usize },
+}
+
+/// When a `ThreadedNetwork` should stop running.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StopCondition {
+    FirstNatPacket,
+    RepeatedNatDelivery,
+}
+
+/// A network where each computer runs on its own thread.
+///
+/// Computers don't talk to each other directly: every packet goes through a supervisor,
+/// which routes it to the right computer's mailbox and plays the part of the NAT.
+pub struct ThreadedNetwork {
+    memory: Memory,
+}
+
+impl ThreadedNetwork {
+    pub fn new(memory: &[i64]) -> Self {
+        ThreadedNetwork {
+            memory: memory.to_vec(),
+        }
+    }
+
+    /// Returns the first packet sent to the NAT, or None if every computer exits first.
+    pub fn first_nat_packet(&self) -> Option<Message> {
+        self.run(StopCondition::FirstNatPacket)
+    }
+
+    /// Returns the first packet that the NAT delivers to address 0 twice in a row,
+    /// or None if every computer exits first.
+    pub fn first_repeated_nat_delivery(&self) -> Option<Message> {
+        self.run(StopCondition::RepeatedNatDelivery)
+    }
+
+    fn run(&self, stop_condition: StopCondition) -> Option<Message> {
+        let (event_sender, events) = mpsc::channel();
+        let mut mailboxes = Vec::new();
+        let mut threads = Vec::new();
+
+        for address in 0..NUM_COMPUTERS {
+            let (mailbox, inbox) = mpsc::channel();
+            mailboxes.push(mailbox);
+
+            let memory = self.memory.clone();
+            let event_sender = event_sender.clone();
+            threads.push(thread::spawn(move || {
+                run_computer(address, memory, inbox, event_sender)
+            }));
+        }
+        // Only the computers hold on to senders, so the events run dry once they've all exited.
+        drop(event_sender);
+
+        let result = supervise(&mailboxes, &events, stop_condition);
+
+        // Hanging up the mailboxes tells each computer to shut down.
+        drop(mailboxes);
+        drop(events);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        result
+    }
+}
+
+/// Runs the computer at `address` until its mailbox is hung up.
+fn run_computer(address: usize, memory: Memory, inbox: Receiver<Message>, events: Sender<Event>) {
+    let mut computer = Computer::new(memory);
+    computer.push_input(address as i64);
+
+    let mut num_received = 0;
+    let mut num_empty_reads = 0;
+
+    loop {
+        let mail = if num_empty_reads < NUM_EMPTY_READS_BEFORE_IDLE {
+            match inbox.try_recv() {
+                Ok(message) => Some(message),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        } else {
+            // We've already told the supervisor that we're idle, so there's no point in
+            // spinning until someone sends us something.
+            match inbox.recv() {
+                Ok(message) => Some(message),
+                Err(_) => return,
+            }
+        };
+
+        match mail {
+            Some(message) => {
+                num_received += 1;
+                num_empty_reads = 0;
+                computer.push_input(message.x);
+                computer.push_input(message.y);
+            }
+            None => num_empty_reads += 1,
+        }
+
+        match computer.run(HaltReason::NeedsInput) {
+            HaltReason::Output => {
+                num_empty_reads = 0;
+
+                let (to, message) = read_packet(&mut computer);
+
+                if events
+                    .send(Event::Packet {
+                        from: address,
+                        to,
+                        message,
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            HaltReason::NeedsInput => {
+                if num_empty_reads == NUM_EMPTY_READS_BEFORE_IDLE
+                    && events
+                        .send(Event::Idle {
+                            address,
+                            num_received,
+                        })
+                        .is_err()
+                {
+                    return;
+                }
+            }
+            HaltReason::Exit => return,
+        }
+    }
+}
+
+/// Routes packets between computers and runs the NAT until `stop_condition` is met.
+/// Returns None if every computer exits before then.
+fn supervise(
+    mailboxes: &[Sender<Message>],
+    events: &Receiver<Event>,
+    stop_condition: StopCondition,
+) -> Option<Message> {
+    let mut num_delivered = vec![0; mailboxes.len()];
+    let mut idle = vec![false; mailboxes.len()];
+    let mut nat_packet = None;
+    let mut last_nat_delivery = None;
+
+    for event in events.iter() {
+        match event {
+            Event::Packet { from, to, message } => {
+                idle[from] = false;
+
+                if to == NAT_ADDRESS {
+                    if stop_condition == StopCondition::FirstNatPacket {
+                        return Some(message);
+                    }
+                    nat_packet = Some(message);
+                } else if let Some(mailbox) = mailboxes.get(to) {
+                    // A computer that has exited can't read its mail, so it has nothing to wake up.
+                    if mailbox.send(message).is_ok() {
+                        idle[to] = false;
+                        num_delivered[to] += 1;
+                    }
+                }
+                // Packets sent to addresses that nobody has are dropped.
+            }
+            Event::Idle {
+                address,
+                num_received,
+            } => {
+                // A computer might report that it's idle just as we're sending it a packet,
+                // in which case it isn't actually done yet.
+                // Everything it sent before going idle has already reached us, because channels are FIFO.
+                if num_received == num_delivered[address] {
+                    idle[address] = true;
+                }
+            }
+        }
+
+        // "If all computers have empty incoming packet queues and are continuously
+        // trying to receive packets without sending packets, the network is considered idle."
+        if idle.iter().all(|&is_idle| is_idle) {
+            if let Some(message) = nat_packet {
+                if last_nat_delivery == Some(message.y) {
+                    return Some(message);
+                }
+
+                // "Once the network is idle, the NAT sends only the last packet it
+                // received to address 0"
+                if mailboxes[0].send(message).is_err() {
+                    return None;
+                }
+                idle[0] = false;
+                num_delivered[0] += 1;
+                last_nat_delivery = Some(message.y);
+            }
+        }
+    }
+
+    None
 }
 
 pub fn twenty_three_a() -> i64 {
-    let memory = load_program("src/inputs/23.txt");
-    let mut network = Network::new(&memory);
+    let memory = programs::load("network_interface");
+    let mut network = Network::new(&memory, NUM_COMPUTERS, NAT_ADDRESS, ForwardLastToZero);
 
     while network.nat_mailbox.is_empty() {
         network.tick();
@@ -82,8 +411,8 @@ pub fn twenty_three_a() -> i64 {
 }
 
 pub fn twenty_three_b() -> i64 {
-    let memory = load_program("src/inputs/23.txt");
-    let mut network = Network::new(&memory);
+    let memory = programs::load("network_interface");
+    let mut network = Network::new(&memory, NUM_COMPUTERS, NAT_ADDRESS, ForwardLastToZero);
 
     let mut last_restart_message = Message { x: 0, y: 0 };
 
@@ -101,7 +430,7 @@ pub fn twenty_three_b() -> i64 {
             // "Once the network is idle, the NAT sends only the last packet it
             // received to address 0; this will cause the computers on the
             // network to resume activity."
-            let restart_message = *network.nat_mailbox.last().unwrap();
+            let restart_message = network.wake_up().unwrap();
 
             if restart_message.y == last_restart_message.y {
                 // "Monitor packets released to the computer at address 0 by the
@@ -110,7 +439,6 @@ pub fn twenty_three_b() -> i64 {
                 break;
             }
 
-            network.mailbox[0].push_back(restart_message);
             last_restart_message = restart_message;
         }
     }
@@ -121,6 +449,119 @@ pub fn twenty_three_b() -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::computer;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_packet_tracing() {
+        let memory = programs::load("network_interface");
+        let mut network = Network::new(&memory, NUM_COMPUTERS, NAT_ADDRESS, ForwardLastToZero);
+        network.record_packets();
+
+        let observed = Rc::new(RefCell::new(vec![]));
+        let observed_clone = Rc::clone(&observed);
+        network.on_packet(move |from, to, message| {
+            observed_clone.borrow_mut().push((from, to, message))
+        });
+
+        while network.nat_mailbox.is_empty() {
+            network.tick();
+        }
+        while !network.tick() {}
+        let restart_message = network.wake_up().unwrap();
+
+        let log = network.packet_log().unwrap();
+        assert_eq!(log.len(), observed.borrow().len());
+        assert!(log.iter().zip(observed.borrow().iter()).all(
+            |(packet, &(from, to, message))| packet.from == from
+                && packet.to == to
+                && packet.message == message
+        ));
+        assert!(log.windows(2).all(|pair| pair[0].tick <= pair[1].tick));
+
+        let first_nat_packet = log.iter().find(|packet| packet.to == NAT_ADDRESS).unwrap();
+        assert_eq!(first_nat_packet.message.y, twenty_three_a());
+
+        let last_packet = log.last().unwrap();
+        assert_eq!((last_packet.from, last_packet.to), (NAT_ADDRESS, 0));
+        assert_eq!(last_packet.message, restart_message);
+        assert_eq!(
+            last_packet.to_string(),
+            format!(
+                "[tick {}] 255 -> 0: X={}, Y={}",
+                last_packet.tick, restart_message.x, restart_message.y
+            )
+        );
+    }
+
+    /// Sends the first packet it ever received to the last computer, once.
+    struct ForwardFirstOnce {
+        to: usize,
+        sent: bool,
+    }
+
+    impl NatPolicy for ForwardFirstOnce {
+        fn on_idle(&mut self, received: &[Message]) -> Option<(usize, Message)> {
+            if self.sent {
+                return None;
+            }
+
+            let message = *received.first()?;
+            self.sent = true;
+            Some((self.to, message))
+        }
+    }
+
+    #[test]
+    fn test_custom_network() {
+        let memory = programs::load("network_interface");
+        let policy = ForwardFirstOnce {
+            to: 49,
+            sent: false,
+        };
+        let mut network = Network::new(&memory, NUM_COMPUTERS, NAT_ADDRESS, policy);
+        network.record_packets();
+
+        while network.nat_mailbox.is_empty() {
+            network.tick();
+        }
+        while !network.tick() {}
+
+        let first_nat_message = network.nat_mailbox[0];
+        assert_eq!(network.wake_up(), Some(first_nat_message));
+        assert_eq!(network.wake_up(), None);
+
+        let log = network.packet_log().unwrap();
+        let last_packet = log.last().unwrap();
+        assert_eq!((last_packet.from, last_packet.to), (NAT_ADDRESS, 49));
+        assert_eq!(last_packet.message.y, twenty_three_a());
+    }
+
+    #[test]
+    fn test_threaded_network() {
+        let memory = programs::load("network_interface");
+        let network = ThreadedNetwork::new(&memory);
+
+        assert_eq!(network.first_nat_packet().unwrap().y, twenty_three_a());
+        assert_eq!(
+            network.first_repeated_nat_delivery().unwrap().y,
+            twenty_three_b()
+        );
+    }
+
+    #[test]
+    fn test_threaded_network_shutdown() {
+        // Reads its address, sends 1, 2 to address 77, which nobody has, then 3, 4 to the NAT, and exits.
+        let program = computer::parse_program("3,100,104,77,104,1,104,2,104,255,104,3,104,4,99");
+        let network = ThreadedNetwork::new(&program);
+        assert_eq!(network.first_nat_packet(), Some(Message { x: 3, y: 4 }));
+        // Every computer exits before the network can go idle.
+        assert_eq!(network.first_repeated_nat_delivery(), None);
+
+        let network = ThreadedNetwork::new(&computer::parse_program("99"));
+        assert_eq!(network.first_nat_packet(), None);
+    }
 
     #[test]
     fn test_solutions() {
@@ -1,44 +1,44 @@
-use std::fs;
+use crate::solver::Solver;
+use crate::util::{self, ParseError};
+use std::path::Path;
+use tracing::instrument;
 
-#[derive(Debug, PartialEq)]
-enum Instruction {
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
     DealIntoNewStack,
     Cut(i32),
     DealWithIncrement(usize),
 }
 
-fn parse_instructions(filename: &str) -> Vec<Instruction> {
-    let contents = fs::read_to_string(filename).unwrap();
+pub fn load_instructions(filename: impl AsRef<Path>) -> Vec<Instruction> {
+    util::load(filename, parse_instructions)
+}
 
+#[instrument(name = "parse", level = "debug", skip_all)]
+pub fn parse_instructions(contents: &str) -> Result<Vec<Instruction>, ParseError> {
     contents
         .lines()
-        .map(|line| {
-            if line.starts_with("deal with increment ") {
-                Instruction::DealWithIncrement(
-                    line.chars()
-                        .skip("deal with increment ".len())
-                        .collect::<String>()
-                        .parse::<usize>()
-                        .unwrap(),
-                )
-            } else if line.starts_with("deal into new stack") {
-                Instruction::DealIntoNewStack
-            } else if line.starts_with("cut ") {
-                Instruction::Cut(
-                    line.chars()
-                        .skip("cut ".len())
-                        .collect::<String>()
-                        .parse::<i32>()
-                        .unwrap(),
-                )
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            if let Some(increment) = line.strip_prefix("deal with increment ") {
+                Ok(Instruction::DealWithIncrement(util::parse_token(
+                    increment,
+                    i + 1,
+                )?))
+            } else if line == "deal into new stack" {
+                Ok(Instruction::DealIntoNewStack)
+            } else if let Some(n) = line.strip_prefix("cut ") {
+                Ok(Instruction::Cut(util::parse_token(n, i + 1)?))
             } else {
-                unreachable!()
+                Err(ParseError::new(i + 1, line, "unknown technique"))
             }
         })
         .collect()
 }
 
-fn shuffle(num_cards: usize, instructions: &[Instruction]) -> Vec<usize> {
+pub fn shuffle(num_cards: usize, instructions: &[Instruction]) -> Vec<usize> {
     let mut deck: Vec<usize> = (0..num_cards).collect();
 
     for instruction in instructions {
@@ -75,79 +75,176 @@ fn shuffle(num_cards: usize, instructions: &[Instruction]) -> Vec<usize> {
     deck
 }
 
+/// Returns the position that `card` ends up at after performing `instructions` on a deck in factory order.
+/// Only follows the one card around, so it doesn't need to build the whole deck like `shuffle()` does.
+pub fn track_card(num_cards: usize, instructions: &[Instruction], card: usize) -> usize {
+    instructions
+        .iter()
+        .fold(card, |position, instruction| match *instruction {
+            Instruction::DealIntoNewStack => num_cards - 1 - position,
+            Instruction::Cut(offset) => {
+                (position as i64 - offset as i64).rem_euclid(num_cards as i64) as usize
+            }
+            Instruction::DealWithIncrement(step) => {
+                (position as u128 * step as u128 % num_cards as u128) as usize
+            }
+        })
+}
+
 pub fn twenty_two_a() -> usize {
-    let instructions = parse_instructions("src/inputs/22.txt");
-    let deck = shuffle(10007, &instructions);
-    deck.iter().position(|&x| x == 2019).unwrap()
+    Solution::part_a(&Solution::load())
 }
 
 fn modulus(n: i128, m: i128) -> i128 {
     ((n % m) + m) % m
 }
 
-// Ripped straight from https://rob.co.bb/posts/2019-02-10-modular-exponentiation-in-rust/ .
-fn mod_pow(mut base: i128, mut exp: i128, m: i128) -> i128 {
-    if m == 1 {
-        return 0;
+/// Returns the x for which `n * x` is 1 mod `m`, via the extended Euclidean algorithm.
+/// Panics if `n` and `m` aren't coprime.
+fn modular_inverse(n: i128, m: i128) -> i128 {
+    let (mut old_r, mut r) = (modulus(n, m), m);
+    let (mut old_s, mut s) = (1, 0);
+
+    while r != 0 {
+        let quotient = old_r / r;
+        let (next_r, next_s) = (old_r - quotient * r, old_s - quotient * s);
+        old_r = r;
+        r = next_r;
+        old_s = s;
+        s = next_s;
     }
 
-    let mut result = 1;
-    base = modulus(base, m);
+    assert_eq!(old_r, 1, "{} has no inverse mod {}", n, m);
+    modulus(old_s, m)
+}
 
-    while exp > 0 {
-        if exp % 2 == 1 {
-            result = result * base % m;
+/// A shuffle of an `m`-card deck, which moves the card at position `p` to position `(a * p + b) mod m`.
+///
+/// Every technique in the puzzle is a function of this form, so any sequence of them
+/// (and any number of repetitions of that sequence) is too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shuffle {
+    pub a: i128,
+    pub b: i128,
+    pub m: i128,
+}
+
+impl Shuffle {
+    /// The shuffle that leaves every card where it is.
+    pub fn identity(num_cards: i128) -> Shuffle {
+        Shuffle {
+            a: 1,
+            b: 0,
+            m: num_cards,
         }
-        exp >>= 1;
-        base = base * base % m
     }
-    result
-}
 
-fn modular_inverse(n: i128, m: i128) -> i128 {
-    mod_pow(n, m - 2, m)
-}
+    /// The shuffle that performs a single technique.
+    pub fn from_instruction(instruction: &Instruction, num_cards: i128) -> Shuffle {
+        let (a, b) = match *instruction {
+            // The card at position p ends up at position m - 1 - p.
+            Instruction::DealIntoNewStack => (-1, -1),
+            Instruction::Cut(n) => (1, -(n as i128)),
+            Instruction::DealWithIncrement(n) => (n as i128, 0),
+        };
 
-pub fn twenty_two_b() -> i128 {
-    let num_cards: i128 = 119315717514047;
-    let num_shuffles: i128 = 101741582076661;
+        Shuffle {
+            a: modulus(a, num_cards),
+            b: modulus(b, num_cards),
+            m: num_cards,
+        }
+    }
 
-    // this approach taken _straight_ from https://www.reddit.com/r/adventofcode/comments/ee0rqi/2019_day_22_solutions/fbnkaju/
-    let mut offset: i128 = 0;
-    let mut increment: i128 = 1;
-    let instructions = parse_instructions("src/inputs/22.txt");
+    /// The shuffle that performs each of `instructions` in order.
+    pub fn new(instructions: &[Instruction], num_cards: i128) -> Shuffle {
+        instructions
+            .iter()
+            .fold(Shuffle::identity(num_cards), |shuffle, instruction| {
+                shuffle.compose(&Shuffle::from_instruction(instruction, num_cards))
+            })
+    }
 
-    for instruction in instructions {
-        match instruction {
-            Instruction::DealIntoNewStack => {
-                increment *= -1;
-                increment = modulus(increment, num_cards);
-                offset += increment;
-                offset = modulus(offset, num_cards);
-            }
-            Instruction::Cut(n) => {
-                offset += increment * n as i128;
-                offset = modulus(offset, num_cards);
-            }
-            Instruction::DealWithIncrement(n) => {
-                increment *= modular_inverse(n as i128, num_cards);
-                increment = modulus(increment, num_cards);
+    /// Returns the shuffle that performs `self` and then `other`.
+    pub fn compose(&self, other: &Shuffle) -> Shuffle {
+        assert_eq!(self.m, other.m);
+
+        Shuffle {
+            a: other.a * self.a % self.m,
+            b: modulus(other.a * self.b + other.b, self.m),
+            m: self.m,
+        }
+    }
+
+    /// Returns the shuffle that puts every card back where `self` found it.
+    pub fn invert(&self) -> Shuffle {
+        let a_inverse = modular_inverse(self.a, self.m);
+
+        Shuffle {
+            a: a_inverse,
+            b: modulus(-self.b * a_inverse, self.m),
+            m: self.m,
+        }
+    }
+
+    /// Returns the shuffle that performs `self` `times` times in a row.
+    pub fn pow(&self, mut times: u64) -> Shuffle {
+        let mut result = Shuffle::identity(self.m);
+        let mut base = *self;
+
+        while times > 0 {
+            if times % 2 == 1 {
+                result = result.compose(&base);
             }
+            base = base.compose(&base);
+            times >>= 1;
         }
+
+        result
+    }
+
+    /// Returns the position that `card` ends up at, starting from a deck in factory order.
+    pub fn apply_to_card(&self, card: i128) -> i128 {
+        modulus(self.a * card + self.b, self.m)
     }
 
-    // THIS NEXT PART IS TAKEN STRAIGHT FROM https://github.com/AxlLind/AdventOfCode2019/blob/master/src/bin/22.rs
-    // I DID NOT WRITE IT
-    // 22B CAN TAKE A LONG WALK OFF A SHORT PIER
-    // LIFE IS TOO SHORT
-    // THANK YOU AXLLIND FOR FREEING ME
+    /// Returns the number of the card that ends up at `position`, starting from a deck in factory order.
+    pub fn card_at_position(&self, position: i128) -> i128 {
+        self.invert().apply_to_card(position)
+    }
+}
+
+pub fn twenty_two_b() -> i128 {
+    Solution::part_b(&Solution::load())
+}
+
+pub struct Solution;
+
+impl Solver for Solution {
+    type Parsed = Vec<Instruction>;
+    type Error = ParseError;
+    type A = usize;
+    type B = i128;
+
+    const DAY: u32 = 22;
+
+    fn parse(input: &str) -> Result<Vec<Instruction>, ParseError> {
+        parse_instructions(input)
+    }
+
+    fn part_a(instructions: &Vec<Instruction>) -> usize {
+        track_card(10007, instructions, 2019)
+    }
 
-    let term1 = 2020 * mod_pow(increment, num_shuffles, num_cards) % num_cards;
-    let tmp = (mod_pow(increment, num_shuffles, num_cards) - 1)
-        * mod_pow(increment - 1, num_cards - 2, num_cards)
-        % num_cards;
-    let term2 = offset * tmp % num_cards;
-    (term1 + term2) % num_cards
+    fn part_b(instructions: &Vec<Instruction>) -> i128 {
+        let num_cards: i128 = 119315717514047;
+        let num_shuffles: u64 = 101741582076661;
+
+        // "After shuffling your new, giant, factory order deck that many times,
+        // what number is on the card that ends up in position 2020?"
+        Shuffle::new(instructions, num_cards)
+            .pow(num_shuffles)
+            .card_at_position(2020)
+    }
 }
 
 #[cfg(test)]
@@ -155,9 +252,9 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_instructions() {
+    fn test_load_instructions() {
         assert_eq!(
-            parse_instructions("src/inputs/22_sample_1.txt"),
+            load_instructions("src/inputs/22_sample_1.txt"),
             vec![
                 Instruction::DealWithIncrement(7),
                 Instruction::DealIntoNewStack,
@@ -166,7 +263,7 @@ mod tests {
         );
 
         assert_eq!(
-            parse_instructions("src/inputs/22_sample_2.txt"),
+            load_instructions("src/inputs/22_sample_2.txt"),
             vec![
                 Instruction::Cut(6),
                 Instruction::DealWithIncrement(7),
@@ -175,30 +272,96 @@ mod tests {
         );
 
         assert_eq!(
-            parse_instructions("src/inputs/22_sample_3.txt"),
+            load_instructions("src/inputs/22_sample_3.txt"),
             vec![
                 Instruction::DealWithIncrement(7),
                 Instruction::DealWithIncrement(9),
                 Instruction::Cut(-2),
             ]
         );
+
+        assert_eq!(
+            parse_instructions("cut 3\ncut three").unwrap_err(),
+            ParseError::new(2, "three", "invalid digit found in string")
+        );
+        assert_eq!(
+            parse_instructions("deal with a smile").unwrap_err(),
+            ParseError::new(1, "deal with a smile", "unknown technique")
+        );
     }
 
     #[test]
     fn test_shuffle() {
-        let instructions = parse_instructions("src/inputs/22_sample_1.txt");
+        let instructions = load_instructions("src/inputs/22_sample_1.txt");
         let deck = shuffle(10, &instructions);
         assert_eq!(deck, vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7,]);
 
-        let instructions = parse_instructions("src/inputs/22_sample_2.txt");
+        let instructions = load_instructions("src/inputs/22_sample_2.txt");
         let deck = shuffle(10, &instructions);
         assert_eq!(deck, vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6]);
 
-        let instructions = parse_instructions("src/inputs/22_sample_3.txt");
+        let instructions = load_instructions("src/inputs/22_sample_3.txt");
         let deck = shuffle(10, &instructions);
         assert_eq!(deck, vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9]);
     }
 
+    #[test]
+    fn test_track_card() {
+        for sample in &[
+            "src/inputs/22_sample_1.txt",
+            "src/inputs/22_sample_2.txt",
+            "src/inputs/22_sample_3.txt",
+        ] {
+            let instructions = load_instructions(sample);
+            let deck = shuffle(10, &instructions);
+
+            for (position, &card) in deck.iter().enumerate() {
+                assert_eq!(track_card(10, &instructions, card), position);
+            }
+        }
+
+        let instructions = load_instructions(util::input(22));
+        let deck = shuffle(10007, &instructions);
+        assert_eq!(
+            track_card(10007, &instructions, 2019),
+            deck.iter().position(|&x| x == 2019).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shuffle_transform() {
+        for sample in &[
+            "src/inputs/22_sample_1.txt",
+            "src/inputs/22_sample_2.txt",
+            "src/inputs/22_sample_3.txt",
+        ] {
+            let instructions = load_instructions(sample);
+            let deck = shuffle(10, &instructions);
+            let twice = shuffle(10, &[instructions.clone(), instructions.clone()].concat());
+            let transform = Shuffle::new(&instructions, 10);
+
+            for (position, &card) in deck.iter().enumerate() {
+                assert_eq!(transform.apply_to_card(card as i128), position as i128);
+                assert_eq!(transform.card_at_position(position as i128), card as i128);
+                assert_eq!(
+                    transform.pow(2).card_at_position(position as i128),
+                    twice[position] as i128
+                );
+            }
+
+            assert_eq!(transform.compose(&transform), transform.pow(2));
+            assert_eq!(
+                transform.compose(&transform.invert()),
+                Shuffle::identity(10)
+            );
+            assert_eq!(transform.pow(0), Shuffle::identity(10));
+        }
+
+        let instructions = load_instructions(util::input(22));
+        let transform = Shuffle::new(&instructions, 10007);
+        assert_eq!(transform.apply_to_card(2019), 7860);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(twenty_two_a(), 7860);
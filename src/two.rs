@@ -1,49 +1,163 @@
-use crate::computer;
-use crate::computer::{Computer, HaltReason};
-use rayon::prelude::*;
+use crate::computer::{self, Computer, HaltReason};
+use crate::programs;
 
 pub fn two_a() -> i64 {
-    let mut memory = computer::load_program("src/inputs/2.txt");
+    let memory = programs::load("gravity_assist");
 
     // Before running the program, replace position 1 with the value 12
     // and replace position 2 with the value 2.
-    memory[1] = 12;
-    memory[2] = 2;
-
     // What value is left at position 0 after the program halts?
-    let mut computer = Computer::new(memory);
-    computer.run(HaltReason::Exit);
-    computer.state.memory[0]
+    run_with_noun_and_verb(&mut Computer::new(memory), 12, 2)
 }
 
 pub fn two_b() -> i64 {
-    let baseline_memory = computer::load_program("src/inputs/2.txt");
+    let (noun, verb) = find_inputs_for_output(19690720).unwrap();
+    100 * noun + verb
+}
+
+/// "Determine what pair of inputs produces the output `target`."
+/// Returns None if no noun and verb in 0..=99 produce that output.
+pub fn find_inputs_for_output(target: i64) -> Option<(i64, i64)> {
+    find_inputs(&programs::load("gravity_assist"), target)
+}
+
+/// Solves for the noun and verb directly if `program`'s output turns out to be an affine function of them,
+/// and otherwise tries every noun and verb.
+fn find_inputs(program: &[i64], target: i64) -> Option<(i64, i64)> {
+    if let Some((noun, verb)) = Affine::fit(program).and_then(|affine| affine.solve(target)) {
+        if output(program, noun, verb) == target {
+            return Some((noun, verb));
+        }
+    }
 
-    let nouns_and_verbs: Vec<_> = (0..100)
-        .flat_map(|noun| (0..100).map(move |verb| (noun, verb)))
-        .collect();
+    // Either the fit found no answer, or its answer didn't check out. Both can happen when the four
+    // sample points line up even though the program isn't really affine, so it can't be trusted.
+    try_all_inputs(program, target)
+}
+
+/// Runs `program` with every noun and verb and returns the first pair that produces `target`.
+/// Each noun's verbs are tried in parallel, and no more nouns are tried once a match is found.
+fn try_all_inputs(program: &[i64], target: i64) -> Option<(i64, i64)> {
+    (0..100).find_map(|noun| {
+        let results = computer::batch_map(program, (0..100).collect(), |computer, verb| {
+            run_with_noun_and_verb(computer, noun, verb)
+        });
 
-    let (noun, verb) = nouns_and_verbs
-        .par_iter()
-        .find_any(|(noun, verb)| {
-            let mut memory = baseline_memory.clone();
-            memory[1] = *noun;
-            memory[2] = *verb;
+        (0..100)
+            .zip(results)
+            .find(|&(_, result)| result == target)
+            .map(|(verb, _)| (noun, verb))
+    })
+}
+
+/// A program's output as a function of its noun and verb, of the form
+/// `constant + noun_coefficient * noun + verb_coefficient * verb`.
+/// The gravity assist program only ever adds and multiplies by constants, so its output has this form.
+#[derive(Debug, PartialEq)]
+struct Affine {
+    constant: i64,
+    noun_coefficient: i64,
+    verb_coefficient: i64,
+}
 
-            let mut computer = Computer::new(memory);
-            computer.run(HaltReason::Exit);
+impl Affine {
+    /// Recovers the coefficients by running `program` three times, then checks them against a fourth run.
+    /// Returns None if `program` doesn't fit.
+    fn fit(program: &[i64]) -> Option<Affine> {
+        let constant = output(program, 0, 0);
+        let affine = Affine {
+            constant,
+            noun_coefficient: output(program, 1, 0) - constant,
+            verb_coefficient: output(program, 0, 1) - constant,
+        };
+
+        if affine.at(99, 99) == output(program, 99, 99) {
+            Some(affine)
+        } else {
+            None
+        }
+    }
+
+    fn at(&self, noun: i64, verb: i64) -> i64 {
+        self.constant + self.noun_coefficient * noun + self.verb_coefficient * verb
+    }
 
-            computer.state.memory[0] == 19690720
+    /// Returns the first noun and verb in 0..=99, in the same order as `try_all_inputs()`, that give `target`.
+    fn solve(&self, target: i64) -> Option<(i64, i64)> {
+        (0..100).find_map(|noun| {
+            let remainder = target - self.constant - self.noun_coefficient * noun;
+            let verb = match self.verb_coefficient {
+                0 if remainder == 0 => 0,
+                0 => return None,
+                coefficient if remainder % coefficient == 0 => remainder / coefficient,
+                _ => return None,
+            };
+            Some((noun, verb)).filter(|_| (0..100).contains(&verb))
         })
-        .unwrap();
+    }
+}
 
-    100 * noun + verb
+/// Runs a fresh copy of `program` with `noun` and `verb`, and returns the value left at address 0.
+fn output(program: &[i64], noun: i64, verb: i64) -> i64 {
+    run_with_noun_and_verb(&mut Computer::new(program.to_vec()), noun, verb)
+}
+
+/// Runs the program with `noun` and `verb` written to addresses 1 and 2,
+/// and returns the value left at address 0.
+fn run_with_noun_and_verb(computer: &mut Computer, noun: i64, verb: i64) -> i64 {
+    computer.state.memory[1] = noun;
+    computer.state.memory[2] = verb;
+
+    computer.run(HaltReason::Exit);
+    computer.state.memory[0]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_find_inputs_for_output() {
+        assert_eq!(find_inputs_for_output(4714701), Some((12, 2)));
+        assert_eq!(find_inputs_for_output(-1), None);
+    }
+
+    #[test]
+    fn test_affine() {
+        let program = programs::load("gravity_assist");
+        let affine = Affine::fit(&program).unwrap();
+        assert_eq!(affine.at(12, 2), 4714701);
+        assert_eq!(affine.solve(19690720), Some((51, 21)));
+        assert_eq!(
+            find_inputs(&program, 19690720),
+            try_all_inputs(&program, 19690720)
+        );
+
+        // Adds the values at addresses `noun` and `verb`, which isn't affine in the noun and verb themselves.
+        let program = computer::parse_program("1,0,0,0,99");
+        assert_eq!(Affine::fit(&program), None);
+        // With a noun of 2, address 2 holds the verb itself, and address 10 holds 0.
+        assert_eq!(find_inputs(&program, 10), Some((2, 10)));
+    }
+
+    #[test]
+    fn test_find_inputs_when_the_fit_misses() {
+        // Outputs noun * verb * (verb - 99), which is 0 at all four of the points that Affine::fit() samples,
+        // so it fits the constant 0, but that fit has no answer for -98.
+        let program =
+            computer::parse_program("1102,0,0,20,1001,2,-99,21,2,20,21,0,99,0,0,0,0,0,0,0,0,0");
+        assert_eq!(
+            Affine::fit(&program),
+            Some(Affine {
+                constant: 0,
+                noun_coefficient: 0,
+                verb_coefficient: 0
+            })
+        );
+        assert_eq!(find_inputs(&program, -98), Some((1, 1)));
+        assert_eq!(find_inputs(&program, 1), None);
+    }
+
     #[test]
     fn test_solutions() {
         assert_eq!(two_a(), 4714701);
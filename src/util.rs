@@ -1,15 +1,509 @@
+#[cfg(feature = "serde")]
+pub mod pairs;
+pub mod search;
+
+use std::env;
+use std::error;
+use std::fmt;
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use tracing::instrument;
+
+/// The HashMap used by the hottest searches, in days 15, 18, and 20. With the `fxhash` feature it hashes with
+/// FxHasher, which is much quicker than std's SipHash on small keys like positions, but makes no attempt
+/// to resist HashDoS. Make these with `default()` rather than `new()`, which only exists for SipHash maps.
+#[cfg(feature = "fxhash")]
+pub type FastHashMap<K, V> = rustc_hash::FxHashMap<K, V>;
+#[cfg(not(feature = "fxhash"))]
+pub type FastHashMap<K, V> = std::collections::HashMap<K, V>;
+
+/// The HashSet counterpart of FastHashMap.
+#[cfg(feature = "fxhash")]
+pub type FastHashSet<T> = rustc_hash::FxHashSet<T>;
+#[cfg(not(feature = "fxhash"))]
+pub type FastHashSet<T> = std::collections::HashSet<T>;
+
+/// Setting this environment variable reads puzzle inputs from the directory it names instead of src/inputs.
+pub const INPUTS_DIR_VAR: &str = "ADVENT_2019_INPUTS";
+
+/// The directory that puzzle inputs are read from.
+pub fn inputs_dir() -> PathBuf {
+    env::var_os(INPUTS_DIR_VAR).map_or_else(|| PathBuf::from("src/inputs"), PathBuf::from)
+}
 
-pub fn parse_lines_from_file<T: FromStr>(filename: &str) -> Vec<T> {
-    let contents = fs::read_to_string(filename).unwrap();
+/// The file that `day`'s puzzle input lives in, like src/inputs/6.txt.
+pub fn input(day: u8) -> PathBuf {
+    inputs_dir().join(format!("{}.txt", day))
+}
+
+/// Reads `day`'s puzzle input.
+pub fn read_input(day: u8) -> io::Result<String> {
+    fs::read_to_string(input(day))
+}
+
+/// Setting this environment variable lets slow solutions save expensive precomputations in the directory it names,
+/// and reuse them on later runs.
+pub const CACHE_DIR_VAR: &str = "ADVENT_2019_CACHE";
+
+/// The directory that precomputations are cached in, if caching is turned on.
+pub fn cache_dir() -> Option<PathBuf> {
+    env::var_os(CACHE_DIR_VAR).map(PathBuf::from)
+}
+
+/// The 64-bit FNV-1a hash of `bytes`. Unlike std's DefaultHasher, this doesn't change from one
+/// Rust release to the next, so it's safe to save to disk.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Something in a puzzle input that couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line it's on, counting from 1.
+    pub line: usize,
+    /// The text that couldn't be parsed.
+    pub token: String,
+    /// What's wrong with `token`.
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, token: &str, message: impl fmt::Display) -> Self {
+        ParseError {
+            line,
+            token: token.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {:?}: {}", self.line, self.token, self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parses `token`, which is on line `line` (counting from 1), with its FromStr implementation.
+pub fn parse_token<T>(token: &str, line: usize) -> Result<T, ParseError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    token.parse().map_err(|e| ParseError::new(line, token, e))
+}
 
+/// Everything that can go wrong when loading a file of puzzle input.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file at `path` couldn't be read.
+    Io { path: PathBuf, source: io::Error },
+    /// The file at `path` was read, but its contents couldn't be parsed.
+    Parse {
+        path: PathBuf,
+        source: Box<dyn error::Error + Send + Sync>,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => {
+                write!(f, "couldn't read {}: {}", path.display(), source)
+            }
+            LoadError::Parse { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LoadError::Io { source, .. } => Some(source),
+            LoadError::Parse { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+/// Reads the file at `path`.
+pub fn read_file(path: impl AsRef<Path>) -> Result<String, LoadError> {
+    let path = path.as_ref();
+    fs::read_to_string(path).map_err(|source| LoadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Like `read_file()`, but panics with a description of what went wrong, like `load()`.
+pub fn read(path: impl AsRef<Path>) -> String {
+    read_file(path).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Reads the file at `path` and parses its contents with `parse`.
+pub fn try_load<T, E>(
+    path: impl AsRef<Path>,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<T, LoadError>
+where
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    let path = path.as_ref();
+    parse(&read_file(path)?).map_err(|e| LoadError::Parse {
+        path: path.to_path_buf(),
+        source: e.into(),
+    })
+}
+
+/// Like `try_load()`, but panics with a description of what went wrong, for the solutions,
+/// which have no way to report an error.
+pub fn load<T, E>(path: impl AsRef<Path>, parse: impl FnOnce(&str) -> Result<T, E>) -> T
+where
+    E: Into<Box<dyn error::Error + Send + Sync>>,
+{
+    try_load(path, parse).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Parses each line of `contents` with its FromStr implementation.
+#[instrument(name = "parse", level = "debug", skip_all)]
+pub fn parse_lines<T>(contents: &str) -> Result<Vec<T>, ParseError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
     contents
         .lines()
-        .map(|line| {
-            line.parse::<T>()
-                .map_err(|_| format!("unable to parse {:?}", line))
-                .unwrap()
+        .enumerate()
+        .map(|(i, line)| parse_token(line.trim(), i + 1))
+        .collect()
+}
+
+pub fn parse_lines_from_file<T>(filename: impl AsRef<Path>) -> Result<Vec<T>, LoadError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    try_load(filename, parse_lines)
+}
+
+/// One of the four directions on a grid. North is toward the top of a puzzle input, so it's toward lower y values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// All four directions, clockwise from north.
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn turn_left(self) -> Direction {
+        match self {
+            Direction::North => Direction::West,
+            Direction::East => Direction::North,
+            Direction::South => Direction::East,
+            Direction::West => Direction::South,
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// The (dx, dy) of one step in this direction.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    /// Day 15's encoding: "Only four movement commands are understood: north (1), south (2), west (3), and east (4)."
+    pub fn movement_command(self) -> i64 {
+        match self {
+            Direction::North => 1,
+            Direction::South => 2,
+            Direction::West => 3,
+            Direction::East => 4,
+        }
+    }
+}
+
+/// A position on a grid, with y increasing downward like `Direction::offset()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The offsets of all eight surrounding points, clockwise from north.
+const EIGHT_OFFSETS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+impl Point {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+
+    /// Returns the Point that's one step away in `direction`.
+    pub fn step(self, direction: Direction) -> Point {
+        let (dx, dy) = direction.offset();
+        Point::new(self.x + dx, self.y + dy)
+    }
+
+    /// The four points directly above, below, and to either side of this one, clockwise from north.
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        Direction::ALL
+            .iter()
+            .map(move |&direction| self.step(direction))
+    }
+
+    /// `neighbors4()`, plus the four diagonal neighbors.
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        EIGHT_OFFSETS
+            .iter()
+            .map(move |&(dx, dy)| Point::new(self.x + dx, self.y + dy))
+    }
+
+    /// Like `neighbors4()`, but leaves out neighbors that aren't on a `width` by `height` grid.
+    pub fn neighbors4_within(self, width: usize, height: usize) -> impl Iterator<Item = Point> {
+        self.neighbors4()
+            .filter(move |point| point.is_within(width, height))
+    }
+
+    /// Like `neighbors8()`, but leaves out neighbors that aren't on a `width` by `height` grid.
+    pub fn neighbors8_within(self, width: usize, height: usize) -> impl Iterator<Item = Point> {
+        self.neighbors8()
+            .filter(move |point| point.is_within(width, height))
+    }
+
+    /// Returns true if this point is on a `width` by `height` grid whose top left corner is (0, 0).
+    pub fn is_within(self, width: usize, height: usize) -> bool {
+        self.x >= 0 && self.y >= 0 && (self.x as usize) < width && (self.y as usize) < height
+    }
+
+    /// Returns (x, y) as grid indexes. Panics if either coordinate is negative.
+    pub fn to_usize(self) -> (usize, usize) {
+        assert!(self.x >= 0 && self.y >= 0, "{:?} is off the grid", self);
+        (self.x as usize, self.y as usize)
+    }
+}
+
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Self {
+        Point::new(x as i32, y as i32)
+    }
+}
+
+/// Parses a string of decimal digits like "12345" into a Vec of its digits.
+pub fn parse_digits(digit_str: &str) -> Vec<i32> {
+    try_parse_digits(digit_str).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Like `parse_digits()`, but returns an error naming the first character that isn't a digit.
+pub fn try_parse_digits(digit_str: &str) -> Result<Vec<i32>, ParseError> {
+    digit_str
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            c.to_digit(10).map(|digit| digit as i32).ok_or_else(|| {
+                ParseError::new(1, &c.to_string(), format!("character {} isn't a digit", i))
+            })
         })
         .collect()
 }
+
+/// Combines a slice of decimal digits like [1, 2, 3] into the number 123.
+/// Leading zeros are fine; returns None if the number doesn't fit in a u64.
+pub fn digits_to_u64(digits: &[i32]) -> Option<u64> {
+    digits.iter().try_fold(0u64, |acc, &digit| {
+        debug_assert!((0..10).contains(&digit));
+        acc.checked_mul(10)?.checked_add(digit as u64)
+    })
+}
+
+/// Like `digits_to_u64`, but for numbers of up to 39 digits.
+pub fn digits_to_u128(digits: &[i32]) -> Option<u128> {
+    digits.iter().try_fold(0u128, |acc, &digit| {
+        debug_assert!((0..10).contains(&digit));
+        acc.checked_mul(10)?.checked_add(digit as u128)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_input() {
+        assert_eq!(input(22), inputs_dir().join("22.txt"));
+        assert!(read_input(1).unwrap().starts_with("88623\n"));
+
+        // Day 4's input is in its source.
+        assert_eq!(read_input(4).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(parse_lines::<i64>("12\n 34 \n"), Ok(vec![12, 34]));
+        let error = parse_lines::<i64>("12\n3x4\n").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::new(2, "3x4", "invalid digit found in string")
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 2: \"3x4\": invalid digit found in string"
+        );
+
+        let error = parse_lines_from_file::<i64>(inputs_dir().join("4.txt")).unwrap_err();
+        assert!(matches!(error, LoadError::Io { .. }));
+        assert!(error.to_string().starts_with("couldn't read "));
+
+        // Day 3's input is wires, not numbers.
+        let error = parse_lines_from_file::<i64>(input(3)).unwrap_err();
+        assert!(matches!(error, LoadError::Parse { .. }));
+        assert!(error
+            .to_string()
+            .starts_with(&format!("{}: line 1: \"R", input(3).display())));
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_direction() {
+        for &direction in Direction::ALL.iter() {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.turn_right().turn_right(), direction.reverse());
+            assert_eq!(direction.reverse().reverse(), direction);
+
+            let (dx, dy) = direction.offset();
+            let (reverse_dx, reverse_dy) = direction.reverse().offset();
+            assert_eq!((dx + reverse_dx, dy + reverse_dy), (0, 0));
+        }
+
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::North.offset(), (0, -1));
+        assert_eq!(Direction::East.offset(), (1, 0));
+        assert_eq!(Direction::West.movement_command(), 3);
+    }
+
+    #[test]
+    fn test_point_neighbors() {
+        let point = Point::new(0, 0);
+        assert_eq!(
+            point.neighbors4().collect::<Vec<_>>(),
+            vec![
+                Point::new(0, -1),
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(-1, 0)
+            ]
+        );
+        assert_eq!(point.neighbors8().count(), 8);
+        assert!(point
+            .neighbors4()
+            .all(|neighbor| point.neighbors8().any(|other| other == neighbor)));
+
+        assert_eq!(
+            point.neighbors4_within(3, 3).collect::<Vec<_>>(),
+            vec![Point::new(1, 0), Point::new(0, 1)]
+        );
+        assert_eq!(point.neighbors8_within(3, 3).count(), 3);
+        assert_eq!(Point::new(1, 1).neighbors8_within(3, 3).count(), 8);
+        assert_eq!(Point::new(2, 1).neighbors4_within(3, 2).count(), 2);
+
+        assert_eq!(Point::from((2, 3)).step(Direction::North), Point::new(2, 2));
+        assert_eq!(Point::new(2, 3).to_usize(), (2, 3));
+    }
+
+    #[test]
+    fn test_parse_digits() {
+        assert_eq!(parse_digits("0803"), vec![0, 8, 0, 3]);
+        assert_eq!(parse_digits(""), Vec::<i32>::new());
+        assert_eq!(
+            try_parse_digits("08x3").unwrap_err().to_string(),
+            "line 1: \"x\": character 2 isn't a digit"
+        );
+    }
+
+    #[test]
+    fn test_digits_to_u64() {
+        assert_eq!(digits_to_u64(&[]), Some(0));
+        assert_eq!(digits_to_u64(&[5, 2, 4, 3, 2, 1, 3, 3]), Some(52432133));
+        assert_eq!(digits_to_u64(&[0, 0, 4, 2]), Some(42));
+
+        // 2^53 + 1 is the first integer that an f64 can't represent.
+        assert_eq!(
+            digits_to_u64(&parse_digits("9007199254740993")),
+            Some(9_007_199_254_740_993)
+        );
+
+        assert_eq!(
+            digits_to_u64(&parse_digits("18446744073709551615")),
+            Some(u64::MAX)
+        );
+        assert_eq!(digits_to_u64(&parse_digits("18446744073709551616")), None);
+        assert_eq!(
+            digits_to_u64(&parse_digits("000000000018446744073709551615")),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_digits_to_u128() {
+        assert_eq!(
+            digits_to_u128(&parse_digits("18446744073709551616")),
+            Some(u64::MAX as u128 + 1)
+        );
+        assert_eq!(
+            digits_to_u128(&parse_digits("340282366920938463463374607431768211455")),
+            Some(u128::MAX)
+        );
+        assert_eq!(
+            digits_to_u128(&parse_digits("340282366920938463463374607431768211456")),
+            None
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::cmp::Ordering;
+
+/// A `score` paired with an item, ordered by score alone and in reverse, so that a BinaryHeap of
+/// MinScoreds pops the item with the lowest score first. This is the usual priority queue entry
+/// for Dijkstra's algorithm and A*; the item doesn't need to implement Ord itself.
+#[derive(Debug, Clone, Copy)]
+pub struct MinScored<K, T>(pub K, pub T);
+
+impl<K: Ord, T> Ord for MinScored<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl<K: Ord, T> PartialOrd for MinScored<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> PartialEq for MinScored<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Ord, T> Eq for MinScored<K, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn test_min_scored() {
+        // Vecs of f64s aren't Ord, but that's fine, since only the scores are compared.
+        let mut heap: BinaryHeap<_> = vec![
+            MinScored(3, vec![3.0]),
+            MinScored(1, vec![1.0]),
+            MinScored(2, vec![2.0]),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(heap.peek().map(|entry| entry.0), Some(1));
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop())
+            .map(|MinScored(_, item)| item[0])
+            .collect();
+        assert_eq!(popped, vec![1.0, 2.0, 3.0]);
+
+        assert_eq!(MinScored(1, "a"), MinScored(1, "b"));
+        assert!(MinScored(1, "a") > MinScored(2, "a"));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::util;
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Editors tend to touch a file several times when saving it, so changes that arrive
+/// within this long of each other only trigger one run.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The files that `day`'s solution is read from: its source file, and its puzzle input if it has one.
+/// Relative paths are resolved against the crate's directory, since that's where `run()` runs the solution.
+pub fn watched_paths(day: u32) -> Vec<PathBuf> {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let module = crate::module_name(day).unwrap_or_else(|| panic!("there's no day {}", day));
+
+    vec![
+        crate_dir.join("src").join(format!("{}.rs", module)),
+        // Every day has a module, so it's small enough to fit.
+        crate_dir.join(util::input(day as u8)),
+    ]
+    .into_iter()
+    .filter(|path| path.exists())
+    .collect()
+}
+
+/// The directories that `paths` are in, without repeats.
+/// Editors often save a file by writing a new one and renaming it over the old one, which a watch on
+/// the file itself wouldn't survive, so it's the directories that get watched.
+fn parent_directories(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut directories: Vec<_> = paths
+        .iter()
+        .filter_map(|path| path.parent())
+        .map(Path::to_path_buf)
+        .collect();
+    directories.sort();
+    directories.dedup();
+    directories
+}
+
+/// Rebuilds and reruns `day` every time its source or input changes, until interrupted.
+#[cfg(not(tarpaulin_include))]
+pub fn watch(day: u32) -> notify::Result<()> {
+    let paths = watched_paths(day);
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for directory in parent_directories(&paths) {
+        watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+    }
+
+    run(day);
+    loop {
+        // The channel only closes if the watcher is dropped, which it isn't until we return.
+        let event = receiver.recv().unwrap()?;
+        if event.kind.is_access() || !event.paths.iter().any(|path| paths.contains(path)) {
+            continue;
+        }
+
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+        run(day);
+    }
+}
+
+/// Runs `day` in a freshly built copy of this program, so that it picks up any changes to the source.
+#[cfg(not(tarpaulin_include))]
+fn run(day: u32) {
+    println!("\nRunning day {}...", day);
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["run", "--release", "--quiet", "--"])
+        .arg(day.to_string())
+        .status();
+
+    match status {
+        Ok(status) if status.success() => (),
+        Ok(status) => println!("Day {} failed: {}", day, status),
+        Err(e) => println!("Couldn't run cargo: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_paths() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            watched_paths(22),
+            vec![
+                crate_dir.join("src/twenty_two.rs"),
+                crate_dir.join("src/inputs/22.txt")
+            ]
+        );
+
+        // Day 4's input is in its source.
+        assert_eq!(watched_paths(4), vec![crate_dir.join("src/four.rs")]);
+    }
+
+    #[test]
+    fn test_parent_directories() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            parent_directories(&watched_paths(22)),
+            vec![crate_dir.join("src"), crate_dir.join("src/inputs")]
+        );
+        assert_eq!(
+            parent_directories(&[PathBuf::from("a/b.rs"), PathBuf::from("a/c.rs")]),
+            vec![PathBuf::from("a")]
+        );
+    }
+}